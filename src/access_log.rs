@@ -0,0 +1,340 @@
+//! Append-only log of accessed documentation, used to warm the cache on start.
+//!
+//! Every document requested through [`DocFetcher`] is recorded as one JSON line
+//! holding its [`DocsRsParams`]. On the next start the log is read back, the
+//! most frequently requested entries are determined, and those missing from
+//! the cache are fetched ahead of the first client request.
+//!
+//! Records are appended by a writer thread, so that recording never blocks
+//! the request being served. Once the log reaches its size limit it is
+//! rotated: the current file is moved aside, replacing the previous rotated
+//! file, and both are read back on start.
+//!
+//! [`DocFetcher`]: crate::mcp::DocFetcher
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Sender};
+use std::thread::JoinHandle;
+
+use crate::cache::Cache;
+use crate::cache_key::CacheKey;
+use crate::docs_parser::DocsRsParams;
+use crate::mcp::DocFetcher;
+
+/// Size from which the access log is rotated, by default.
+pub const DEFAULT_MAX_LOG_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Returns the location the log at `path` is moved to when rotated.
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".1");
+    PathBuf::from(rotated)
+}
+
+/// Append-only access log writer.
+#[derive(Debug)]
+pub struct AccessLog {
+    /// Location of the log file
+    path: PathBuf,
+    /// Lines passed to the writer thread, `None` once the log is dropped
+    lines: Mutex<Option<Sender<String>>>,
+    /// Thread appending the lines to the file
+    writer: Option<JoinHandle<()>>,
+}
+
+/// Appends lines to a log file, rotating it once it exceeds `max_bytes`.
+struct LogWriter {
+    path: PathBuf,
+    max_bytes: u64,
+    file: BufWriter<File>,
+    /// Bytes in the current file
+    len: u64,
+}
+
+impl LogWriter {
+    fn open(path: PathBuf, max_bytes: u64) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let len = file.metadata()?.len();
+        Ok(Self { path, max_bytes, file: BufWriter::new(file), len })
+    }
+
+    fn append(&mut self, line: &str) -> io::Result<()> {
+        if self.len > 0 && self.len + line.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.len += line.len() as u64;
+        Ok(())
+    }
+
+    /// Moves the current file aside and starts a new one.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        std::fs::rename(&self.path, rotated_path(&self.path))?;
+        let file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.file = BufWriter::new(file);
+        self.len = 0;
+        Ok(())
+    }
+
+    /// Writes lines until the sending side is dropped, flushing whenever no
+    /// more lines are waiting.
+    fn run(mut self, lines: mpsc::Receiver<String>) {
+        while let Ok(line) = lines.recv() {
+            let mut next = Some(line);
+            while let Some(line) = next {
+                if let Err(e) = self.append(&line) {
+                    tracing::warn!("Failed to append to access log {:?}: {}", self.path, e);
+                }
+                next = lines.try_recv().ok();
+            }
+            if let Err(e) = self.file.flush() {
+                tracing::warn!("Failed to flush access log {:?}: {}", self.path, e);
+            }
+        }
+    }
+}
+
+impl AccessLog {
+    /// Opens (or creates) the access log at `path` for appending, rotated
+    /// from [`DEFAULT_MAX_LOG_BYTES`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error if the file cannot be opened.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        Self::open_with_max_bytes(path, DEFAULT_MAX_LOG_BYTES)
+    }
+
+    /// Opens (or creates) the access log at `path` for appending, rotated
+    /// once it would grow past `max_bytes`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error if the file cannot be opened or the writer thread
+    /// cannot be started.
+    pub fn open_with_max_bytes(path: impl Into<PathBuf>, max_bytes: u64) -> io::Result<Self> {
+        let path = path.into();
+        let writer = LogWriter::open(path.clone(), max_bytes)?;
+        let (sender, receiver) = mpsc::channel();
+        let writer = std::thread::Builder::new()
+            .name("access-log".to_string())
+            .spawn(move || writer.run(receiver))?;
+        Ok(Self { path, lines: Mutex::new(Some(sender)), writer: Some(writer) })
+    }
+
+    /// Returns the location of the log file.
+    #[allow(dead_code)]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends one access to the log.
+    ///
+    /// The record is handed to the writer thread, which writes whole lines
+    /// so that concurrent records never interleave. Failures are logged and
+    /// otherwise ignored, since the log is only a warm-up hint.
+    pub fn record(&self, params: &DocsRsParams) {
+        let mut line = match serde_json::to_string(params) {
+            Ok(line) => line,
+            Err(e) => {
+                tracing::warn!("Failed to serialize access log entry {:?}: {}", params, e);
+                return;
+            }
+        };
+        line.push('\n');
+
+        let lines = match self.lines.lock() {
+            Ok(lines) => lines,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if lines.as_ref().is_none_or(|lines| lines.send(line).is_err()) {
+            tracing::warn!("Access log {:?} is closed, dropping entry {:?}", self.path, params);
+        }
+    }
+}
+
+impl Drop for AccessLog {
+    /// Waits for the records still queued to be written.
+    fn drop(&mut self) {
+        match self.lines.lock() {
+            Ok(mut lines) => lines.take(),
+            Err(poisoned) => poisoned.into_inner().take(),
+        };
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// Reads an access log, including its rotated part, and returns up to
+/// `limit` entries, most frequently accessed first. Ties keep the order in
+/// which entries first appeared.
+///
+/// Malformed lines, e.g. a line truncated by a crash, are skipped.
+///
+/// # Errors
+///
+/// Returns an IO error if the file cannot be read.
+pub fn most_frequent(path: &Path, limit: usize) -> io::Result<Vec<DocsRsParams>> {
+    let mut lines = Vec::new();
+    match File::open(rotated_path(path)) {
+        Ok(rotated) => lines.extend(BufReader::new(rotated).lines()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    lines.extend(BufReader::new(File::open(path)?).lines());
+    let mut counts: HashMap<DocsRsParams, (usize, usize)> = HashMap::new();
+
+    for (line_no, line) in lines.into_iter().enumerate() {
+        let line = line?;
+        match serde_json::from_str::<DocsRsParams>(&line) {
+            Ok(params) => counts.entry(params).or_insert((0, line_no)).0 += 1,
+            Err(e) => tracing::debug!("Skipping malformed access log line {}: {}", line_no + 1, e),
+        }
+    }
+
+    let mut ranked: Vec<_> = counts.into_iter().collect();
+    ranked.sort_by(|(_, (count_a, first_a)), (_, (count_b, first_b))| {
+        count_b.cmp(count_a).then(first_a.cmp(first_b))
+    });
+    Ok(ranked.into_iter().take(limit).map(|(params, _)| params).collect())
+}
+
+/// Preloads the most frequently accessed documents of a previous session.
+///
+/// Entries already present in the cache are skipped. Fetch failures are
+/// logged and do not stop the warm-up.
+///
+/// # Returns
+///
+/// The number of documents fetched into the cache.
+pub async fn preload(fetcher: &DocFetcher, cache: &dyn Cache, path: &Path, limit: usize) -> usize {
+    let candidates = match most_frequent(path, limit) {
+        Ok(candidates) => candidates,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            tracing::info!("No access log at {:?}, skipping cache preload.", path);
+            return 0;
+        }
+        Err(e) => {
+            tracing::error!("Failed to read access log {:?}: {}. Skipping cache preload.", path, e);
+            return 0;
+        }
+    };
+
+    let mut preloaded = 0;
+    for params in candidates {
+        if cache.contains_key(&CacheKey::from(&params)).await {
+            continue;
+        }
+        match fetcher.fetch(params.clone()).await {
+            Ok(_) => preloaded += 1,
+            Err(e) => tracing::warn!("Failed to preload {:?}: {}", params, e),
+        }
+    }
+
+    tracing::info!("Preloaded {} documents from access log {:?}.", preloaded, path);
+    preloaded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::InMemoryCache;
+    use crate::mcp::FetcherConfig;
+    use mockito::Server;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    fn params(path: &str) -> DocsRsParams {
+        DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            path: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_most_frequent_orders_by_count() {
+        let dir = tempdir().unwrap();
+        let log = AccessLog::open(dir.path().join("access.log")).unwrap();
+        for path in ["a.html", "b.html", "b.html", "c.html", "b.html", "c.html"] {
+            log.record(&params(path));
+        }
+        let path = log.path().to_path_buf();
+        drop(log);
+
+        let top = most_frequent(&path, 2).unwrap();
+        assert_eq!(top, vec![params("b.html"), params("c.html")]);
+    }
+
+    #[test]
+    fn test_log_is_rotated_at_size_limit() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("access.log");
+        let line_len = serde_json::to_string(&params("a.html")).unwrap().len() as u64 + 1;
+        let log = AccessLog::open_with_max_bytes(&path, 3 * line_len).unwrap();
+        for path in ["a.html", "a.html", "a.html", "b.html", "b.html", "c.html", "c.html", "c.html"] {
+            log.record(&params(path));
+        }
+        drop(log);
+
+        // Neither file exceeds the limit, and only the last rotation is kept
+        for file in [path.clone(), rotated_path(&path)] {
+            assert!(std::fs::metadata(&file).unwrap().len() <= 3 * line_len, "{:?} exceeds the limit", file);
+        }
+        let top = most_frequent(&path, 10).unwrap();
+        assert_eq!(top, vec![params("c.html"), params("b.html")]);
+    }
+
+    #[test]
+    fn test_most_frequent_skips_malformed_lines() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("access.log");
+        let valid = serde_json::to_string(&params("a.html")).unwrap();
+        std::fs::write(&path, format!("{}\n{{\"crate_na\n", valid)).unwrap();
+
+        assert_eq!(most_frequent(&path, 10).unwrap(), vec![params("a.html")]);
+    }
+
+    #[tokio::test]
+    async fn test_preload_fetches_logged_entries() {
+        let dir = tempdir().unwrap();
+        let log_path = dir.path().join("access.log");
+        {
+            let log = AccessLog::open(&log_path).unwrap();
+            log.record(&params("demo/struct.Hot.html"));
+            log.record(&params("demo/struct.Hot.html"));
+            log.record(&params("demo/struct.Cold.html"));
+        }
+
+        let mut server = Server::new_async().await;
+        let hot = server.mock("GET", "/demo/1.0.0/demo/struct.Hot.html")
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper">Hot docs</div>"#)
+            .expect(1)
+            .create();
+        let cold = server.mock("GET", "/demo/1.0.0/demo/struct.Cold.html")
+            .expect(0)
+            .create();
+
+        let cache = Arc::new(InMemoryCache::new(dir.path().join("cache")));
+        let config = FetcherConfig {
+            docs_base_url: server.url(),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache.clone(), config);
+
+        let preloaded = preload(&fetcher, cache.as_ref(), &log_path, 1).await;
+
+        hot.assert();
+        cold.assert();
+        assert_eq!(preloaded, 1);
+        let content = cache.get(&params("demo/struct.Hot.html").into()).await.unwrap();
+        assert!(content.content.contains("Hot docs"));
+    }
+}
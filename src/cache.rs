@@ -7,19 +7,44 @@
 //! - Efficient per-crate storage organization
 //! - Thread-safe concurrent access
 //!
-//! The cache is organized by crate, with each crate's documentation stored in a
-//! separate file for efficient loading and saving. The cache supports concurrent
-//! access through RwLocks and provides atomic operations.
+//! By default the cache is organized by crate, with each crate's documentation
+//! stored in a separate file for efficient loading and saving; other on-disk
+//! layouts can be plugged in through [`CacheLayout`]. The cache supports
+//! concurrent access through RwLocks and provides atomic operations.
+//!
+//! When saving keeps failing, e.g. because the disk is full or the cache
+//! volume was unmounted, the cache switches to an ephemeral mode: it goes
+//! on serving from memory and warns that nothing is persisted, see
+//! [`InMemoryCache::is_ephemeral`].
+//!
+//! Large crate files can be memory-mapped on load instead of read, and are
+//! then only decoded once an entry of their crate is needed; see
+//! [`InMemoryCache::with_mmap_threshold`].
+//!
+//! Entries record when they were fetched and can be given a time to live
+//! after which they are no longer served, see [`InMemoryCache::with_ttl`].
+//! The number of entries can be bounded, evicting the least recently used
+//! ones, see [`InMemoryCache::with_capacity`].
+//!
+//! For caches too large to keep in memory, see
+//! [`FileCache`](crate::file_cache::FileCache). [`NoopCache`] stores
+//! nothing at all.
 
-use crate::docs_parser::{DocContent, DocsRsParams};
+use crate::cache_layout::{CacheLayout, PerCrateLayout};
+use crate::cache_key::CacheKey;
+use crate::docs_parser::DocContent;
 use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, io};
-use std::sync::Arc;
+use std::{collections::{BTreeMap, HashMap}, io};
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
 use tokio::fs;
+use tokio::io::AsyncWriteExt;
 use tokio::sync::RwLock;
-use itertools::Itertools; // Added for grouping
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Trait defining the interface for a documentation cache.
 ///
@@ -32,17 +57,45 @@ use std::path::PathBuf;
 /// Implementations must be both Send and Sync to support concurrent access.
 #[async_trait]
 pub trait Cache: Send + Sync {
-    /// Retrieves documentation for the given parameters if it exists in the cache.
-    async fn get(&self, key: &DocsRsParams) -> Option<DocContent>;
+    /// Retrieves documentation for the given key if it exists in the cache.
+    async fn get(&self, key: &CacheKey) -> Option<DocContent>;
+
+    /// Retrieves documentation for the given key even if it expired, to
+    /// serve stale content while the documentation server is failing.
+    /// Lookups are not counted in the cache statistics. Caches without a
+    /// time to live return what [`Self::get`] returns.
+    async fn get_stale(&self, key: &CacheKey) -> Option<DocContent> {
+        self.get(key).await
+    }
+
+    /// Returns the versions of the crate `crate_name` with cached entries,
+    /// expired ones included, in no particular order. Caches that cannot
+    /// tell without reading every entry return none.
+    async fn cached_versions(&self, _crate_name: &str) -> Vec<String> {
+        Vec::new()
+    }
 
-    /// Stores documentation in the cache with the given parameters as the key.
-    async fn insert(&self, key: DocsRsParams, value: DocContent);
+    /// Stores documentation in the cache under the given key.
+    async fn insert(&self, key: CacheKey, value: DocContent);
 
-    /// Checks if documentation for the given parameters exists in the cache.
-    async fn contains_key(&self, key: &DocsRsParams) -> bool;
+    /// Checks if documentation for the given key exists in the cache.
+    async fn contains_key(&self, key: &CacheKey) -> bool;
 
-    /// Removes all entries from the cache.
-    async fn clear(&self);
+    /// Removes all entries from the cache, returning how many were removed.
+    async fn clear(&self) -> usize;
+
+    /// Removes the entries of the crate `crate_name`, returning how many
+    /// were removed.
+    async fn clear_crate(&self, crate_name: &str) -> usize;
+
+    /// Streams all entries of the cache.
+    ///
+    /// Implementations must not hold locks while the consumer processes an
+    /// item, so a slow consumer (e.g. an export writing to disk) does not
+    /// block concurrent readers and writers. Entries inserted or removed
+    /// while streaming may or may not be observed.
+    #[allow(dead_code)]
+    fn entries(&self) -> BoxStream<'_, (CacheKey, DocContent)>;
 
     /// Saves the current cache state to persistent storage.
     ///
@@ -57,57 +110,488 @@ pub trait Cache: Send + Sync {
     ///
     /// Returns an IO error if the load operation fails.
     async fn load(&self) -> Result<(), io::Error>;
+
+    /// Returns whether saving keeps failing, so that entries only live in
+    /// memory and are lost on shutdown.
+    fn is_ephemeral(&self) -> bool {
+        false
+    }
+
+    /// Returns whether entries were added or removed since the last
+    /// successful save, so that periodic saves can skip unchanged caches.
+    /// Caches that cannot tell report `true`.
+    fn has_unsaved_changes(&self) -> bool {
+        true
+    }
+
+    /// Returns the hit, miss and insert counters of the cache.
+    fn stats(&self) -> CacheStats;
+
+    /// Returns the number of stored entries, including expired ones not
+    /// evicted yet.
+    async fn entry_count(&self) -> usize;
+
+    /// Returns the approximate size in bytes of the stored documentation,
+    /// for capacity planning. How it is measured depends on the backend.
+    async fn size_bytes(&self) -> u64;
 }
 
-/// Cache data for a single crate, mapping version+path to content.
-///
-/// The key is a normalized string in the format "{version}::{path}".
-type CrateCacheData = HashMap<String, DocContent>;
+/// Counters, entry count and stored size of a cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CacheUsage {
+    #[serde(flatten)]
+    pub stats: CacheStats,
+    /// Lookups in total, hits and misses
+    pub gets: u64,
+    /// Number of stored entries, see [`Cache::entry_count`]
+    pub entries: usize,
+    /// Approximate size of the stored documentation, see [`Cache::size_bytes`]
+    pub size_bytes: u64,
+}
+
+impl CacheUsage {
+    /// Reads the counters, entry count and size of `cache`.
+    pub async fn of(cache: &dyn Cache) -> Self {
+        let stats = cache.stats();
+        Self {
+            stats,
+            gets: stats.gets(),
+            entries: cache.entry_count().await,
+            size_bytes: cache.size_bytes().await,
+        }
+    }
+}
+
+/// Returns the current time in seconds since the Unix epoch.
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// A cached document with the time it was fetched.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct CacheEntry {
+    /// The cached document
+    #[serde(flatten)]
+    pub value: DocContent,
+    /// Seconds since the Unix epoch at which the document was fetched.
+    /// Entries persisted before fetch times were recorded count as fetched
+    /// when they are loaded.
+    #[serde(default = "unix_now")]
+    pub fetched_at: u64,
+}
+
+impl CacheEntry {
+    /// Wraps a document fetched just now.
+    pub fn new(value: DocContent) -> Self {
+        Self { value, fetched_at: unix_now() }
+    }
+
+    /// Returns whether the entry is older than `ttl` at `now`, in seconds
+    /// since the Unix epoch. Entries never expire without a TTL.
+    pub(crate) fn is_expired(&self, ttl: Option<Duration>, now: u64) -> bool {
+        ttl.is_some_and(|ttl| now.saturating_sub(self.fetched_at) >= ttl.as_secs())
+    }
+}
 
 /// In-memory representation of the entire cache.
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Default)]
 struct CacheData {
-    /// Maps documentation parameters to their content
-    data: HashMap<DocsRsParams, DocContent>,
+    /// Maps documentation parameters to their content. Entries are shared,
+    /// so that snapshots taken under the lock are cheap, see
+    /// [`InMemoryCache::save_files`].
+    data: HashMap<CacheKey, Arc<CacheEntry>>,
+    /// Mapped cache files not decoded yet, keyed by the crate they hold
+    mapped: HashMap<String, Arc<MappedFile>>,
 }
 
-/// Normalizes documentation parameters into a string key.
-///
-/// Creates a unique string key from version and path, excluding crate name
-/// which is handled separately for file organization.
-///
-/// # Arguments
-///
-/// * `params` - The documentation parameters to normalize
-///
-/// # Returns
-///
-/// A string in the format "{version}::{path}"
-fn normalize_key(params: &DocsRsParams) -> String {
-    format!("{}::{}", params.version, params.path)
+impl CacheData {
+    /// Decodes the mapped file of `crate_name`, if any, into `data` and
+    /// unmaps it, leaving out empty entries if `drop_empty` is set. Entries
+    /// already in memory are newer than the file and are kept.
+    fn decode_mapped(&mut self, crate_name: &str, layout: &dyn CacheLayout, drop_empty: bool) {
+        if let Some(mapped) = self.mapped.remove(crate_name) {
+            let mut items = mapped.decode(layout).unwrap_or_default();
+            if drop_empty {
+                drop_empty_entries(&mut items, &mapped.path);
+            }
+            for (key, value) in items {
+                self.data.entry(key).or_insert_with(|| Arc::new(value));
+            }
+        }
+    }
+
+    /// Decodes and unmaps all mapped files.
+    fn decode_all_mapped(&mut self, layout: &dyn CacheLayout, drop_empty: bool) {
+        let crates: Vec<String> = self.mapped.keys().cloned().collect();
+        for crate_name in crates {
+            self.decode_mapped(&crate_name, layout, drop_empty);
+        }
+    }
 }
 
-/// Reconstructs documentation parameters from a normalized key and crate name.
+/// Order in which cache entries were last used, for LRU eviction.
 ///
-/// # Arguments
+/// Every key gets a tick: using a key gives it a tick newer than all
+/// others, while entries added without being used, e.g. by loading, get
+/// ticks older than all others.
+#[derive(Debug)]
+struct LruOrder {
+    /// Tick of the most recently used key
+    newest: u64,
+    /// Tick of the least recently used key
+    oldest: u64,
+    /// Tick of each tracked key
+    ticks: HashMap<CacheKey, u64>,
+    /// Tracked keys by tick
+    order: BTreeMap<u64, CacheKey>,
+}
+
+impl Default for LruOrder {
+    fn default() -> Self {
+        Self {
+            newest: u64::MAX / 2,
+            oldest: u64::MAX / 2,
+            ticks: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+}
+
+impl LruOrder {
+    /// Marks `key` as the most recently used.
+    fn touch(&mut self, key: &CacheKey) {
+        self.newest += 1;
+        self.set(key, self.newest);
+    }
+
+    /// Marks `key` as the least recently used, unless it is tracked already.
+    fn track_as_oldest(&mut self, key: &CacheKey) {
+        if !self.ticks.contains_key(key) {
+            self.oldest -= 1;
+            self.set(key, self.oldest);
+        }
+    }
+
+    fn set(&mut self, key: &CacheKey, tick: u64) {
+        if let Some(previous) = self.ticks.insert(key.clone(), tick) {
+            self.order.remove(&previous);
+        }
+        self.order.insert(tick, key.clone());
+    }
+
+    /// Stops tracking `key`.
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(tick) = self.ticks.remove(key) {
+            self.order.remove(&tick);
+        }
+    }
+
+    /// Stops tracking the least recently used key and returns it.
+    fn pop_oldest(&mut self) -> Option<CacheKey> {
+        let (_, key) = self.order.pop_first()?;
+        self.ticks.remove(&key);
+        Some(key)
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Removes the entries whose content is empty or only whitespace, such as
+/// placeholders cached by earlier versions, from the entries read from the
+/// cache file at `path`. Returns the number of entries removed.
+fn drop_empty_entries(items: &mut Vec<(CacheKey, CacheEntry)>, path: &Path) -> usize {
+    let before = items.len();
+    items.retain(|(_, entry)| !entry.value.content.trim().is_empty());
+    let dropped = before - items.len();
+    if dropped > 0 {
+        tracing::warn!("Dropped {} empty entries of cache file {:?}.", dropped, path);
+    }
+    dropped
+}
+
+/// A cache file mapped into memory, see [`InMemoryCache::with_mmap_threshold`].
+#[derive(Debug)]
+struct MappedFile {
+    /// Location of the file
+    path: PathBuf,
+    /// Name of the file within the cache directory
+    file_name: String,
+    /// Contents of the file
+    map: memmap2::Mmap,
+    /// Number of entries of the file, once counted
+    entries: OnceLock<usize>,
+}
+
+impl MappedFile {
+    /// Maps the cache file at `path` into memory.
+    fn open(path: &Path, file_name: &str) -> io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: the cache never writes a file while it is mapped: mapped
+        // files are decoded and unmapped before saving, and dropped without
+        // being read when reloaded. A file still mapped while being counted
+        // is replaced by renaming, which leaves the mapped contents intact.
+        // Other processes must not modify the file either, as documented on
+        // `InMemoryCache::with_mmap_threshold`.
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(Self { path: path.to_path_buf(), file_name: file_name.to_string(), map, entries: OnceLock::new() })
+    }
+
+    /// Counts the entries of the file without decoding them, see
+    /// [`CacheLayout::count`]. The count is only computed once.
+    fn count(&self, layout: &dyn CacheLayout) -> usize {
+        *self.entries.get_or_init(|| {
+            let content = match std::str::from_utf8(&self.map) {
+                Ok(content) if !content.trim().is_empty() => content,
+                _ => return 0,
+            };
+            layout.count(&self.file_name, content).unwrap_or_else(|e| {
+                tracing::error!("Failed to count entries of cache file {:?}: {}.", self.path, e);
+                0
+            })
+        })
+    }
+
+    /// Decodes the entries of the file like [`read_cache_file`].
+    fn decode(&self, layout: &dyn CacheLayout) -> Option<Vec<(CacheKey, CacheEntry)>> {
+        match std::str::from_utf8(&self.map) {
+            Ok(content) => decode_cache_file(&self.path, &self.file_name, content, layout),
+            Err(e) => {
+                tracing::error!("Cache file {:?} is not valid UTF-8: {}. Skipping file.", self.path, e);
+                None
+            }
+        }
+    }
+}
+
+/// Consecutive failed saves after which the cache switches to ephemeral
+/// mode by default.
+pub const DEFAULT_EPHEMERAL_AFTER_FAILURES: u32 = 3;
+
+/// Number of cache files read and decoded at once while loading, by default.
+pub const DEFAULT_LOAD_CONCURRENCY: usize = 8;
+
+/// Name of the file in the cache directory holding the hit/miss counters.
+/// It has no `.json` extension so it is never taken for a crate file.
+const STATS_FILE: &str = ".stats";
+
+/// Extension of the temporary files cache files are written to before
+/// being renamed over the previous version.
+const TEMP_EXTENSION: &str = "tmp";
+
+/// Time since a temporary file of another process was last written after
+/// which it is taken as left behind by an interrupted save. Younger files
+/// may belong to a save of another server sharing the cache directory.
+const LEFTOVER_TEMP_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Number of temporary files written by this process, distinguishing the
+/// files of concurrent saves.
+static TEMP_WRITES: AtomicU64 = AtomicU64::new(0);
+
+/// Writes `contents` to the file at `path` through a temporary file in the
+/// same directory, flushed to disk and then renamed over `path`, so that an
+/// interrupted save or a crash leaves either the previous or the new
+/// version of the file and never a partly written one.
+pub(crate) async fn write_atomically(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let write = TEMP_WRITES.fetch_add(1, Ordering::Relaxed);
+    let temp_path = path.with_file_name(format!(".{}.{}.{}.{}", file_name, std::process::id(), write, TEMP_EXTENSION));
+    let written = async {
+        let mut file = fs::File::create(&temp_path).await?;
+        file.write_all(contents.as_ref()).await?;
+        file.sync_all().await?;
+        fs::rename(&temp_path, path).await
+    };
+    if let Err(e) = written.await {
+        let _ = fs::remove_file(&temp_path).await;
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Returns whether `file_name` is a temporary file left behind by a save of
+/// another process that was interrupted before renaming it, judging by its
+/// name and `age`, the time since it was last written. Temporary files of
+/// this process may belong to a save still in progress.
+fn is_leftover_temp_file(file_name: &str, age: Duration) -> bool {
+    let Some(stem) = file_name.strip_prefix('.').and_then(|name| name.strip_suffix(&format!(".{}", TEMP_EXTENSION))) else {
+        return false;
+    };
+    let mut parts = stem.rsplitn(3, '.');
+    let (Some(_write), Some(pid)) = (parts.next(), parts.next()) else {
+        return false;
+    };
+    pid.parse::<u32>().is_ok_and(|pid| pid != std::process::id()) && age >= LEFTOVER_TEMP_AGE
+}
+
+/// Returns the time since the file of `entry` was last written, zero if it
+/// cannot be told.
+async fn modified_age(entry: &fs::DirEntry) -> Duration {
+    let modified = entry.metadata().await.and_then(|metadata| metadata.modified());
+    modified.ok().and_then(|modified| modified.elapsed().ok()).unwrap_or_default()
+}
+
+/// Hit, miss and insert counters of a cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheStats {
+    /// Lookups answered from the cache
+    pub hits: u64,
+    /// Lookups of entries not in the cache
+    pub misses: u64,
+    /// Documents stored in the cache, missing from stats files written
+    /// before it was counted
+    #[serde(default)]
+    pub inserts: u64,
+}
+
+impl CacheStats {
+    /// Returns the number of lookups, hits and misses together.
+    pub fn gets(&self) -> u64 {
+        self.hits + self.misses
+    }
+}
+
+/// Live counters behind [`InMemoryCache::stats`].
+#[derive(Debug, Default)]
+struct StatsCounters {
+    /// Counters restored from the stats file of earlier server lifetimes
+    restored: std::sync::Mutex<CacheStats>,
+    /// Hits since the server started
+    hits: AtomicU64,
+    /// Misses since the server started
+    misses: AtomicU64,
+    /// Inserts since the server started
+    inserts: AtomicU64,
+}
+
+impl StatsCounters {
+    fn restored(&self) -> std::sync::MutexGuard<'_, CacheStats> {
+        self.restored.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Reads the entries of a single cache file.
 ///
-/// * `crate_name` - Name of the crate
-/// * `normalized_key` - The normalized key string
+/// Problems are logged and the file (or entry) is skipped: `None` is returned
+/// if the file cannot be read, is empty or cannot be decoded by `layout`, and
+/// entries the layout cannot decode are left out. Transient read errors are
+/// retried according to `retry` first. Decoding runs on a blocking thread,
+/// so that several files can be decoded in parallel.
 ///
-/// # Returns
+/// # Arguments
 ///
-/// * `Ok(DocsRsParams)` - Successfully reconstructed parameters
-/// * `Err(String)` - Error message if the key format is invalid
-fn denormalize_key(crate_name: &str, normalized_key: &str) -> Result<DocsRsParams, String> {
-    let parts: Vec<&str> = normalized_key.splitn(2, "::").collect();
-    if parts.len() == 2 {
-        Ok(DocsRsParams {
-            crate_name: crate_name.to_string(),
-            version: parts[0].to_string(),
-            path: parts[1].to_string(),
-        })
-    } else {
-        Err(format!("Invalid normalized key format: {}", normalized_key))
+/// * `path` - Location of the cache file
+/// * `file_name` - Name of the file within the cache directory
+/// * `layout` - Layout the file is decoded with
+/// * `retry` - Retry policy for transient read errors
+async fn read_cache_file(
+    path: &Path,
+    file_name: &str,
+    layout: Arc<dyn CacheLayout>,
+    retry: LoadRetry,
+) -> Option<Vec<(CacheKey, CacheEntry)>> {
+    tracing::debug!("Attempting to load cache file {:?}", file_name);
+    let content = match read_with_retry(path, retry, || fs::read_to_string(path)).await {
+        Ok(content) => content,
+        Err(e) => {
+            tracing::error!("Failed to read cache file {:?}: {}. Skipping file.", path, e);
+            return None;
+        }
+    };
+    let (decode_path, file_name) = (path.to_path_buf(), file_name.to_string());
+    let decode = move || decode_cache_file(&decode_path, &file_name, &content, layout.as_ref());
+    match tokio::task::spawn_blocking(decode).await {
+        Ok(items) => items,
+        Err(e) => {
+            tracing::error!("Failed to decode cache file {:?}: {}. Skipping file.", path, e);
+            None
+        }
+    }
+}
+
+/// Decodes the `content` of a cache file with `layout`, logging problems
+/// like [`read_cache_file`].
+fn decode_cache_file(
+    path: &Path,
+    file_name: &str,
+    content: &str,
+    layout: &dyn CacheLayout,
+) -> Option<Vec<(CacheKey, CacheEntry)>> {
+    if content.trim().is_empty() {
+        tracing::warn!("Cache file {:?} is empty, skipping.", path);
+        return None;
+    }
+    match layout.decode(file_name, content) {
+        Ok(items) => Some(items),
+        Err(e) => {
+            tracing::error!("Failed to deserialize cache file {:?}: {}. Skipping file.", path, e);
+            None
+        }
+    }
+}
+
+/// A cache file read while loading the cache.
+enum LoadedFile {
+    /// Decoded entries of the file, with its location
+    Entries(Vec<(CacheKey, CacheEntry)>, PathBuf),
+    /// File of a crate mapped for lazy decoding, with the crate's name
+    Mapped(String, MappedFile),
+}
+
+/// Retry policy for reading cache files on transient IO errors, such as a
+/// hiccup of a networked filesystem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadRetry {
+    /// Number of retries after the first failed read
+    pub retries: u32,
+    /// Delay between attempts
+    pub delay: Duration,
+}
+
+impl Default for LoadRetry {
+    fn default() -> Self {
+        Self { retries: 2, delay: Duration::from_millis(100) }
+    }
+}
+
+/// Returns whether an IO error may go away when the operation is repeated.
+fn is_transient(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::Interrupted
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::ResourceBusy
+            | io::ErrorKind::StaleNetworkFileHandle
+            | io::ErrorKind::NetworkDown
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+    )
+}
+
+/// Runs `read`, repeating it after transient errors as allowed by `retry`.
+async fn read_with_retry<T, F, Fut>(path: &Path, retry: LoadRetry, read: F) -> io::Result<T>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = io::Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match read().await {
+            Err(e) if attempt < retry.retries && is_transient(&e) => {
+                attempt += 1;
+                tracing::warn!(
+                    "Transient error reading cache file {:?}: {}. Retrying ({}/{}).",
+                    path, e, attempt, retry.retries
+                );
+                tokio::time::sleep(retry.delay).await;
+            }
+            result => return result,
+        }
     }
 }
 
@@ -124,6 +608,36 @@ pub struct InMemoryCache {
     cache: Arc<RwLock<CacheData>>,
     /// Directory where cache files are stored
     cache_dir: PathBuf,
+    /// Retry policy for reading cache files
+    load_retry: LoadRetry,
+    /// Hit and miss counters, persisted next to the crate files
+    stats: Arc<StatsCounters>,
+    /// How entries are spread over files of the cache directory
+    layout: Arc<dyn CacheLayout>,
+    /// Fail loading when the cache directory does not exist
+    require_dir: bool,
+    /// Size from which cache files are memory-mapped on load
+    mmap_threshold: Option<u64>,
+    /// Maximum number of cache files read at once while loading
+    load_concurrency: usize,
+    /// Leave out entries with empty or whitespace-only content when loading
+    drop_empty_on_load: bool,
+    /// Time entries are served after being fetched, `None` to keep them
+    /// forever
+    ttl: Option<Duration>,
+    /// Maximum number of entries in memory, `None` for no limit
+    max_entries: Option<usize>,
+    /// Order in which entries were used, only tracked with a limit
+    lru: Arc<std::sync::Mutex<LruOrder>>,
+    /// Consecutive failed saves after which the cache is ephemeral, `None`
+    /// to never switch
+    ephemeral_after: Option<u32>,
+    /// Saves failed since the last successful one
+    save_failures: Arc<AtomicU32>,
+    /// Whether saving keeps failing and the cache only lives in memory
+    ephemeral: Arc<AtomicBool>,
+    /// Whether entries changed since the last successful save
+    dirty: Arc<AtomicBool>,
 }
 
 impl InMemoryCache {
@@ -136,48 +650,555 @@ impl InMemoryCache {
         Self {
             cache: Arc::new(RwLock::new(CacheData::default())),
             cache_dir,
+            load_retry: LoadRetry::default(),
+            stats: Arc::new(StatsCounters::default()),
+            layout: Arc::new(PerCrateLayout),
+            require_dir: false,
+            mmap_threshold: None,
+            load_concurrency: DEFAULT_LOAD_CONCURRENCY,
+            drop_empty_on_load: false,
+            ttl: None,
+            max_entries: None,
+            lru: Arc::new(std::sync::Mutex::new(LruOrder::default())),
+            ephemeral_after: Some(DEFAULT_EPHEMERAL_AFTER_FAILURES),
+            save_failures: Arc::new(AtomicU32::new(0)),
+            ephemeral: Arc::new(AtomicBool::new(false)),
+            dirty: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Creates a cache like [`Self::new`] that keeps at most `max_entries`
+    /// entries in memory, evicting the least recently used ones; see
+    /// [`Self::with_max_entries`].
+    #[allow(dead_code)]
+    pub fn with_capacity(cache_dir: PathBuf, max_entries: usize) -> Self {
+        Self::new(cache_dir).with_max_entries(Some(max_entries))
+    }
+
+    /// Sets the number of consecutive failed saves after which the cache
+    /// switches to ephemeral mode, [`DEFAULT_EPHEMERAL_AFTER_FAILURES`] by
+    /// default; `None` never switches.
+    pub fn with_ephemeral_after(mut self, failures: Option<u32>) -> Self {
+        self.ephemeral_after = failures;
+        self
+    }
+
+    /// Returns whether the cache is in ephemeral mode: saving has failed
+    /// repeatedly, so documentation is only kept in memory and lost on
+    /// shutdown. Saving is still attempted, and the first successful save
+    /// leaves ephemeral mode.
+    pub fn is_ephemeral(&self) -> bool {
+        self.ephemeral.load(Ordering::Relaxed)
+    }
+
+    /// Records the outcome of a save, entering or leaving ephemeral mode.
+    fn record_save(&self, result: &io::Result<()>) {
+        match result {
+            Ok(()) => {
+                self.save_failures.store(0, Ordering::Relaxed);
+                if self.ephemeral.swap(false, Ordering::Relaxed) {
+                    tracing::info!("Saved cache to {:?} again; leaving ephemeral mode.", self.cache_dir);
+                }
+            }
+            Err(e) => {
+                let failures = self.save_failures.fetch_add(1, Ordering::Relaxed) + 1;
+                if self.ephemeral_after.is_some_and(|limit| failures >= limit)
+                    && !self.ephemeral.swap(true, Ordering::Relaxed)
+                {
+                    tracing::error!(
+                        "!!! Saving the cache to {:?} failed {} times in a row (last error: {}). \
+                        Switching to EPHEMERAL MODE: documentation is served from memory only \
+                        and will be LOST on shutdown until the cache directory is writable again. !!!",
+                        self.cache_dir, failures, e
+                    );
+                }
+            }
+        }
+    }
+
+    /// Memory-maps cache files of at least `threshold` bytes on
+    /// [`Cache::load`] instead of reading them into memory. With a layout
+    /// keeping each crate in its own file, a mapped file is only decoded
+    /// when an entry of its crate is first needed, which lowers the memory
+    /// used by a large cache of which few crates are requested; other
+    /// files are decoded right away from the mapping. Files are read as
+    /// usual when `threshold` is `None`, the default.
+    ///
+    /// # Safety of mapped files
+    ///
+    /// The contents of a mapped file are read from disk on access. If the
+    /// file is truncated or rewritten while mapped, decoding it may read
+    /// changed data or crash the process with `SIGBUS`. The cache itself
+    /// decodes and unmaps all files before [`Cache::save`] writes them and
+    /// never reads a mapped file after [`Self::reload_file`] was told it
+    /// changed, but it cannot prevent other processes from writing to the
+    /// directory. Do not map files of a cache directory that is shared with
+    /// other instances or modified by other tools.
+    pub fn with_mmap_threshold(mut self, threshold: Option<u64>) -> Self {
+        self.mmap_threshold = threshold;
+        self
+    }
+
+    /// Makes [`Cache::load`] leave out entries whose content is empty or
+    /// only whitespace, so that junk cached by earlier versions is dropped
+    /// and fetched again. Entries of memory-mapped files are checked when
+    /// the files are decoded.
+    pub fn with_drop_empty_on_load(mut self, drop_empty_on_load: bool) -> Self {
+        self.drop_empty_on_load = drop_empty_on_load;
+        self
+    }
+
+    /// Sets the time entries are served after being fetched. Expired entries
+    /// are treated as missing, so that they are fetched again, but stay
+    /// readable through [`Cache::get_stale`] until they are replaced or the
+    /// cache is saved; they are dropped on [`Cache::load`] and left out on
+    /// [`Cache::save`]. `None` or a zero TTL, the default, keeps entries
+    /// forever.
+    pub fn with_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.ttl = ttl.filter(|ttl| !ttl.is_zero());
+        self
+    }
+
+    /// Limits the number of entries kept in memory to `max_entries`. When an
+    /// insert exceeds the limit, the least recently used entries, i.e.
+    /// those neither returned by [`Cache::get`] nor inserted for the longest
+    /// time, are evicted. Entries loaded from disk count as used before any
+    /// other; if more are loaded than the limit allows, arbitrary ones are
+    /// left out. `None` or 0, the default, keeps all entries.
+    pub fn with_max_entries(mut self, max_entries: Option<usize>) -> Self {
+        self.max_entries = max_entries.filter(|&max_entries| max_entries > 0);
+        self
+    }
+
+    fn lru(&self) -> std::sync::MutexGuard<'_, LruOrder> {
+        self.lru.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Evicts the least recently used entries of `data` beyond the entry
+    /// limit, returning their number. Entries not tracked yet, e.g. just
+    /// loaded, are tracked as the least recently used first.
+    fn enforce_max_entries(&self, data: &mut CacheData) -> usize {
+        let Some(max_entries) = self.max_entries else {
+            return 0;
+        };
+        let mut lru = self.lru();
+        let mut evicted = 0;
+        loop {
+            while data.data.len() > max_entries {
+                let Some(key) = lru.pop_oldest() else {
+                    break;
+                };
+                // Keys removed from the cache in the meantime are skipped
+                if data.data.remove(&key).is_some() {
+                    evicted += 1;
+                }
+            }
+            if data.data.len() <= max_entries {
+                return evicted;
+            }
+            for key in data.data.keys() {
+                lru.track_as_oldest(key);
+            }
+        }
+    }
+
+    /// Tracks entries added without being used as the least recently used
+    /// and enforces the entry limit.
+    fn track_added(&self, data: &mut CacheData) {
+        if self.max_entries.is_none() {
+            return;
+        }
+        {
+            let mut lru = self.lru();
+            for key in data.data.keys() {
+                lru.track_as_oldest(key);
+            }
+        }
+        let evicted = self.enforce_max_entries(data);
+        if evicted > 0 {
+            tracing::info!("Evicted {} cache entries beyond the limit of {:?}.", evicted, self.max_entries);
+        }
+    }
+
+    /// Removes all expired entries, returning their number.
+    async fn evict_all_expired(&self) -> usize {
+        if self.ttl.is_none() {
+            return 0;
+        }
+        let now = unix_now();
+        let mut cache = self.cache.write().await;
+        let before = cache.data.len();
+        let mut lru = self.lru();
+        cache.data.retain(|key, entry| {
+            let expired = entry.is_expired(self.ttl, now);
+            if expired {
+                lru.remove(key);
+            }
+            !expired
+        });
+        before - cache.data.len()
+    }
+
+    /// Decodes the mapped file of `crate_name` if it has not been yet.
+    async fn decode_mapped(&self, crate_name: &str) {
+        if self.cache.read().await.mapped.contains_key(crate_name) {
+            let mut cache = self.cache.write().await;
+            cache.decode_mapped(crate_name, self.layout.as_ref(), self.drop_empty_on_load);
+            self.track_added(&mut cache);
+        }
+    }
+
+    /// Decodes all mapped files not decoded yet.
+    async fn decode_all_mapped(&self) {
+        if !self.cache.read().await.mapped.is_empty() {
+            let mut cache = self.cache.write().await;
+            cache.decode_all_mapped(self.layout.as_ref(), self.drop_empty_on_load);
+            self.track_added(&mut cache);
         }
     }
+
+    /// Makes [`Cache::load`] fail when the cache directory does not exist,
+    /// e.g. because a volume expected to hold it is not mounted. By default
+    /// a missing directory is taken for a first run and the cache starts
+    /// empty.
+    pub fn with_require_dir(mut self, require_dir: bool) -> Self {
+        self.require_dir = require_dir;
+        self
+    }
+
+    /// Sets the on-disk layout of the cache directory, one file per crate by
+    /// default.
+    pub fn with_layout(mut self, layout: Arc<dyn CacheLayout>) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Returns the on-disk layout of the cache directory.
+    pub fn layout(&self) -> &Arc<dyn CacheLayout> {
+        &self.layout
+    }
+
+    /// Sets the retry policy for transient errors while reading cache files.
+    pub fn with_load_retry(mut self, load_retry: LoadRetry) -> Self {
+        self.load_retry = load_retry;
+        self
+    }
+
+    /// Sets the maximum number of cache files read and decoded at once while
+    /// loading, [`DEFAULT_LOAD_CONCURRENCY`] by default; 1 loads them one
+    /// after the other.
+    pub fn with_load_concurrency(mut self, load_concurrency: usize) -> Self {
+        self.load_concurrency = load_concurrency.max(1);
+        self
+    }
+
+    /// Returns the directory where cache files are stored.
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+
+    /// Returns the hit, miss and insert counters accumulated over all
+    /// server lifetimes, as restored by [`Cache::load`] and persisted by
+    /// [`Cache::save`].
+    pub fn stats(&self) -> CacheStats {
+        let restored = *self.stats.restored();
+        let since_start = self.stats_since_start();
+        CacheStats {
+            hits: restored.hits + since_start.hits,
+            misses: restored.misses + since_start.misses,
+            inserts: restored.inserts + since_start.inserts,
+        }
+    }
+
+    /// Returns the hit, miss and insert counters of the current server
+    /// lifetime.
+    #[allow(dead_code)]
+    pub fn stats_since_start(&self) -> CacheStats {
+        CacheStats {
+            hits: self.stats.hits.load(Ordering::Relaxed),
+            misses: self.stats.misses.load(Ordering::Relaxed),
+            inserts: self.stats.inserts.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Zeroes all counters, including the restored ones. The stats file is
+    /// overwritten on the next save.
+    pub fn reset_stats(&self) {
+        *self.stats.restored() = CacheStats::default();
+        self.stats.hits.store(0, Ordering::Relaxed);
+        self.stats.misses.store(0, Ordering::Relaxed);
+        self.stats.inserts.store(0, Ordering::Relaxed);
+        tracing::info!("Cache statistics reset.");
+    }
+
+    /// Restores the counters of earlier server lifetimes from the stats file.
+    /// A missing or unreadable file leaves them at zero.
+    async fn load_stats(&self) {
+        let path = self.cache_dir.join(STATS_FILE);
+        let restored = match fs::read_to_string(&path).await {
+            Ok(content) => match serde_json::from_str(&content) {
+                Ok(stats) => stats,
+                Err(e) => {
+                    tracing::warn!("Ignoring unreadable cache statistics {:?}: {}", path, e);
+                    CacheStats::default()
+                }
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => CacheStats::default(),
+            Err(e) => {
+                tracing::warn!("Failed to read cache statistics {:?}: {}", path, e);
+                CacheStats::default()
+            }
+        };
+        *self.stats.restored() = restored;
+    }
+
+    /// Re-reads a single cache file and merges its entries into memory.
+    ///
+    /// Entries from the file replace in-memory entries with the same key;
+    /// other in-memory entries are kept, so documents inserted since the file
+    /// was written are not lost. Files that are not part of the cache
+    /// layout are ignored. A mapping of the previous version of the file is
+    /// dropped without being read, as the file changed underneath it.
+    ///
+    /// # Returns
+    ///
+    /// The number of entries read from the file, `0` if it could not be read.
+    pub async fn reload_file(&self, file_name: &str) -> usize {
+        if !self.layout.is_cache_file(file_name) {
+            return 0;
+        }
+        let path = self.cache_dir.join(file_name);
+        let Some(items) = read_cache_file(&path, file_name, self.layout.clone(), self.load_retry).await else {
+            return 0;
+        };
+        let count = items.len();
+        let mut cache = self.cache.write().await;
+        if let Some(crate_name) = self.layout.crate_of(file_name) {
+            cache.mapped.remove(&crate_name);
+        }
+        cache.data.extend(items.into_iter().map(|(key, entry)| (key, Arc::new(entry))));
+        self.track_added(&mut cache);
+        drop(cache);
+        tracing::info!("Reloaded {} cache entries from {:?}.", count, path);
+        count
+    }
+
+    /// Reads the cache file `file_name` of `size` bytes for [`Cache::load`],
+    /// mapping it if it reaches the mmap threshold. Returns `None` if the
+    /// file is skipped.
+    async fn load_file(&self, path: &Path, file_name: &str, size: u64) -> Option<LoadedFile> {
+        if self.mmap_threshold.is_none_or(|threshold| size < threshold) {
+            let items = read_cache_file(path, file_name, self.layout.clone(), self.load_retry).await?;
+            return Some(LoadedFile::Entries(items, path.to_path_buf()));
+        }
+        let mapped = match read_with_retry(path, self.load_retry, || async { MappedFile::open(path, file_name) }).await {
+            Ok(mapped) => mapped,
+            Err(e) => {
+                tracing::error!("Failed to map cache file {:?}: {}. Skipping file.", path, e);
+                return None;
+            }
+        };
+        match self.layout.crate_of(file_name) {
+            Some(crate_name) => {
+                tracing::debug!("Mapped cache file {:?} of {} bytes for lazy decoding", file_name, size);
+                Some(LoadedFile::Mapped(crate_name, mapped))
+            }
+            None => Some(LoadedFile::Entries(mapped.decode(self.layout.as_ref()).unwrap_or_default(), path.to_path_buf())),
+        }
+    }
+
+    /// Writes the cache files and statistics, see [`Cache::save`].
+    async fn save_files(&self) -> Result<(), io::Error> {
+        let dir_path = &self.cache_dir;
+        // Mapped files are rewritten below, so they must be unmapped first
+        self.decode_all_mapped().await;
+        let expired = self.evict_all_expired().await;
+        if expired > 0 {
+            tracing::info!("Left {} expired entries out of the saved cache.", expired);
+        }
+        // Only snapshot the entries under the read lock, which merely counts
+        // references to their contents, so that inserts are not held up
+        // while the snapshot is encoded and written
+        let snapshot = self.cache.read().await.data.clone();
+        let files = self.layout.encode(&snapshot)?;
+    
+        // Ensure the main cache directory exists
+        fs::create_dir_all(dir_path).await?;
+    
+        let mut saved_files = std::collections::HashSet::new();
+        for (file_name, serialized) in &files {
+            write_atomically(&dir_path.join(file_name), serialized).await?;
+            saved_files.insert(file_name.as_str());
+            tracing::debug!("Saved cache file {:?}", file_name);
+        }
+    
+        // Clean up stale files only once every file has been replaced, so a
+        // failed save never leaves fewer entries on disk than the last one
+        let mut entries = fs::read_dir(dir_path).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if path.is_file() && self.layout.is_cache_file(file_name) && !saved_files.contains(file_name) {
+                match fs::remove_file(&path).await {
+                    Ok(_) => tracing::info!("Removed stale cache file: {:?}", path),
+                    Err(e) => tracing::warn!("Failed to remove stale cache file {:?}: {}", path, e),
+                }
+            } else if path.is_file() && is_leftover_temp_file(file_name, modified_age(&entry).await) {
+                match fs::remove_file(&path).await {
+                    Ok(_) => tracing::info!("Removed leftover temporary cache file: {:?}", path),
+                    Err(e) => tracing::warn!("Failed to remove temporary cache file {:?}: {}", path, e),
+                }
+            }
+        }
+    
+         if files.is_empty() {
+             tracing::info!("Cache is empty. Ensured cache directory {:?} is empty.", dir_path);
+         }
+
+        let stats = serde_json::to_string(&self.stats()).map_err(io::Error::other)?;
+        write_atomically(&dir_path.join(STATS_FILE), stats).await?;
+    
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl Cache for InMemoryCache {
-    /// Retrieves documentation from the cache if it exists.
+    /// Retrieves documentation from the cache if it exists and has not
+    /// expired. Expired entries are kept for [`Cache::get_stale`].
     ///
     /// This operation acquires a read lock on the cache.
-    async fn get(&self, key: &DocsRsParams) -> Option<DocContent> {
-        self.cache.read().await.data.get(key).cloned()
+    async fn get(&self, key: &CacheKey) -> Option<DocContent> {
+        self.decode_mapped(key.crate_name()).await;
+        let entry = self.cache.read().await.data.get(key).cloned();
+        let content = match entry {
+            Some(entry) if entry.is_expired(self.ttl, unix_now()) => None,
+            Some(entry) => {
+                if self.max_entries.is_some() {
+                    self.lru().touch(key);
+                }
+                Some(entry.value.clone())
+            }
+            None => None,
+        };
+        let counter = if content.is_some() { &self.stats.hits } else { &self.stats.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        content
+    }
+
+    /// Retrieves documentation from the cache whether or not it expired.
+    async fn get_stale(&self, key: &CacheKey) -> Option<DocContent> {
+        self.decode_mapped(key.crate_name()).await;
+        let entry = self.cache.read().await.data.get(key).cloned();
+        entry.map(|entry| entry.value.clone())
+    }
+
+    /// Collects the versions of the keys of `crate_name`. Only the mapped
+    /// file of the crate is decoded.
+    async fn cached_versions(&self, crate_name: &str) -> Vec<String> {
+        self.decode_mapped(crate_name).await;
+        let versions: std::collections::HashSet<String> = self.cache.read().await.data.keys()
+            .filter(|key| key.crate_name() == crate_name)
+            .map(|key| key.params.version.clone())
+            .collect();
+        versions.into_iter().collect()
     }
 
     /// Stores documentation in the cache.
     ///
-    /// This operation acquires a write lock on the cache.
-    async fn insert(&self, key: DocsRsParams, value: DocContent) {
-        self.cache.write().await.data.insert(key, value);
+    /// This operation acquires a write lock on the cache. The entry takes
+    /// precedence over one for the same key in a mapped file decoded later.
+    /// With an entry limit, the least recently used entries beyond it are
+    /// evicted.
+    async fn insert(&self, key: CacheKey, value: DocContent) {
+        let mut cache = self.cache.write().await;
+        if self.max_entries.is_some() {
+            self.lru().touch(&key);
+        }
+        cache.data.insert(key, Arc::new(CacheEntry::new(value)));
+        self.enforce_max_entries(&mut cache);
+        self.stats.inserts.fetch_add(1, Ordering::Relaxed);
+        self.dirty.store(true, Ordering::Relaxed);
     }
 
-    /// Checks if documentation exists in the cache.
+    /// Checks if documentation that has not expired exists in the cache.
     ///
     /// This operation acquires a read lock on the cache.
-    async fn contains_key(&self, key: &DocsRsParams) -> bool {
-        self.cache.read().await.data.contains_key(key)
+    async fn contains_key(&self, key: &CacheKey) -> bool {
+        self.decode_mapped(key.crate_name()).await;
+        let now = unix_now();
+        self.cache.read().await.data.get(key).is_some_and(|entry| !entry.is_expired(self.ttl, now))
     }
 
     /// Removes all entries from the cache.
     ///
-    /// This operation acquires a write lock on the cache.
-    async fn clear(&self) {
-        self.cache.write().await.data.clear();
+    /// Mapped files are decoded first to count their entries. This
+    /// operation acquires a write lock on the cache.
+    async fn clear(&self) -> usize {
+        self.decode_all_mapped().await;
+        let mut cache = self.cache.write().await;
+        let count = cache.data.len();
+        cache.data.clear();
+        cache.mapped.clear();
+        self.lru().clear();
+        self.dirty.store(true, Ordering::Relaxed);
+        count
+    }
+
+    /// Removes the entries of a crate, including those of its mapped file.
+    async fn clear_crate(&self, crate_name: &str) -> usize {
+        self.decode_mapped(crate_name).await;
+        let mut cache = self.cache.write().await;
+        let before = cache.data.len();
+        let mut lru = self.lru();
+        cache.data.retain(|key, _| {
+            let keep = key.crate_name() != crate_name;
+            if !keep {
+                lru.remove(key);
+            }
+            keep
+        });
+        let removed = before - cache.data.len();
+        if removed > 0 {
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+        removed
     }
 
-    /// Saves the cache content to disk, organizing files by crate.
+    /// Streams all entries of the cache.
+    ///
+    /// The keys are snapshotted under one read lock; each value is then
+    /// cloned under its own short-lived read lock when the consumer polls
+    /// for it. Entries removed after the snapshot and expired entries are
+    /// skipped.
+    fn entries(&self) -> BoxStream<'_, (CacheKey, DocContent)> {
+        stream::once(async move {
+            self.decode_all_mapped().await;
+            let keys: Vec<CacheKey> = self.cache.read().await.data.keys().cloned().collect();
+            stream::iter(keys)
+        })
+        .flatten()
+        .filter_map(move |key| async move {
+            let entry = self.cache.read().await.data.get(&key).cloned()?;
+            (!entry.is_expired(self.ttl, unix_now())).then(|| (key, entry.value.clone()))
+        })
+        .boxed()
+    }
+
+    /// Saves the cache content to disk, organizing files by the cache layout.
     ///
     /// This method:
-    /// 1. Groups cache entries by crate
-    /// 2. Creates a separate JSON file for each crate
-    /// 3. Removes any stale cache files
+    /// 1. Encodes the cache entries into files according to the layout
+    /// 2. Writes each file to the cache directory
+    /// 3. Removes any stale cache files of the layout
     /// 4. Handles concurrent access safely
     ///
+    /// Repeated failures switch the cache to ephemeral mode, see
+    /// [`InMemoryCache::is_ephemeral`]. Changes made while saving, or
+    /// before a failed save, are left marked as unsaved.
+    ///
     /// # Errors
     ///
     /// Returns an IO error if:
@@ -185,71 +1206,24 @@ impl Cache for InMemoryCache {
     /// - File operations fail
     /// - JSON serialization fails
     async fn save(&self) -> Result<(), io::Error> {
-        let dir_path = &self.cache_dir;
-        // 1. Prepare data outside the main async block to avoid holding lock across .await
-        let data_to_save: HashMap<String, CrateCacheData> = { // New scope for the lock guard
-            let cache_guard = self.cache.read().await;
-            let data_map = &cache_guard.data;
-    
-            data_map.iter()
-                .chunk_by(|(params, _)| &params.crate_name)
-                .into_iter()
-                .map(|(crate_name, group)| {
-                    let crate_cache_data: CrateCacheData = group
-                        .map(|(params, content)| (normalize_key(params), content.clone()))
-                        .collect();
-                    (crate_name.clone(), crate_cache_data)
-                })
-                .collect()
-            // cache_guard is dropped here
-        };
-    
-        // Ensure the main cache directory exists
-        fs::create_dir_all(dir_path).await?;
-    
-        let mut saved_crate_files = std::collections::HashSet::new();
-    
-        // 2. Iterate over the prepared data (which is Send)
-        for (crate_name, crate_cache_data) in &data_to_save {
-            if crate_cache_data.is_empty() {
-                continue;
-            }
-            let crate_file_name = format!("{}.json", crate_name);
-            let crate_file_path = dir_path.join(&crate_file_name);
-    
-            let serialized = serde_json::to_string_pretty(crate_cache_data)
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-    
-            // Perform async write
-            fs::write(&crate_file_path, serialized).await?;
-            saved_crate_files.insert(crate_file_path);
-            tracing::debug!("Saved cache for crate '{}' to {:?}", crate_name, crate_file_name);
-        }
-    
-        // Clean up stale files (this part is already async-safe)
-        let mut entries = fs::read_dir(dir_path).await?;
-        while let Some(entry) = entries.next_entry().await? {
-            let path = entry.path();
-            if path.is_file() && path.extension().map_or(false, |ext| ext == "json") {
-                 if !saved_crate_files.contains(&path) {
-                    match fs::remove_file(&path).await {
-                        Ok(_) => tracing::info!("Removed stale cache file: {:?}", path),
-                        Err(e) => tracing::warn!("Failed to remove stale cache file {:?}: {}", path, e),
-                    }
-                 }
-            }
+        let was_dirty = self.dirty.swap(false, Ordering::Relaxed);
+        let result = self.save_files().await;
+        if result.is_err() && was_dirty {
+            self.dirty.store(true, Ordering::Relaxed);
         }
-    
-         if data_to_save.is_empty() {
-             tracing::info!("Cache is empty. Ensured cache directory {:?} is empty.", dir_path);
-         }
-    
-        Ok(())
+        self.record_save(&result);
+        result
     }
 
-     /// Loads cache content from multiple JSON files within the configured directory.
+     /// Loads cache content from the files of the cache layout within the configured directory.
     async fn load(&self) -> Result<(), io::Error> {
         let dir_path = &self.cache_dir;
+        if !dir_path.exists() && self.require_dir {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("cache directory {:?} does not exist", dir_path),
+            ));
+        }
         if !dir_path.exists() {
             tracing::info!("Cache directory {:?} not found, starting with empty cache.", dir_path);
             // Ensure cache is empty
@@ -264,88 +1238,233 @@ impl Cache for InMemoryCache {
         }
 
 
-        let mut loaded_data = HashMap::new();
+        // Collect the files first, then read and decode them concurrently
+        let mut files = Vec::new();
         let mut entries = fs::read_dir(dir_path).await?;
-        let mut file_count = 0;
-        let mut item_count = 0;
-
         while let Some(entry) = entries.next_entry().await? {
             let path = entry.path();
-            if path.is_file() && path.extension().map_or(false, |ext| ext == "json") {
-                 if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
-                     let crate_name = stem;
-                     tracing::debug!("Attempting to load cache file for crate: {}", crate_name);
-                     match fs::read_to_string(&path).await {
-                         Ok(content) => {
-                             if content.trim().is_empty() {
-                                 tracing::warn!("Cache file {:?} is empty, skipping.", path);
-                                 continue;
-                             }
-                             match serde_json::from_str::<CrateCacheData>(&content) {
-                                 Ok(crate_cache_data) => {
-                                     file_count += 1;
-                                     for (norm_key, doc_content) in crate_cache_data {
-                                         match denormalize_key(crate_name, &norm_key) {
-                                             Ok(params) => {
-                                                 loaded_data.insert(params, doc_content);
-                                                 item_count += 1;
-                                             }
-                                             Err(e) => {
-                                                 tracing::error!(
-                                                    "Failed to denormalize key '{}' in file {:?}: {}. Skipping entry.",
-                                                    norm_key, path, e
-                                                 );
-                                             }
-                                         }
-                                     }
-                                 }
-                                 Err(e) => {
-                                     tracing::error!("Failed to deserialize cache file {:?}: {}. Skipping file.", path, e);
-                                 }
-                             }
-                         }
-                         Err(e) => {
-                            tracing::error!("Failed to read cache file {:?}: {}. Skipping file.", path, e);
-                         }
-                     }
-                 } else {
-                     tracing::warn!("Skipping cache file with invalid name: {:?}", path);
-                 }
+            let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+                tracing::warn!("Skipping cache file with invalid name: {:?}", path);
+                continue;
+            };
+            if !path.is_file() || !self.layout.is_cache_file(file_name) {
+                continue;
+            }
+            let size = entry.metadata().await.map(|metadata| metadata.len()).unwrap_or(0);
+            files.push((file_name.to_string(), path, size));
+        }
+
+        let mut loaded_files = stream::iter(files)
+            .map(|(file_name, path, size)| async move { self.load_file(&path, &file_name, size).await })
+            .buffer_unordered(self.load_concurrency);
+        let mut loaded_data = HashMap::new();
+        let mut loaded_mapped = HashMap::new();
+        let mut file_count = 0;
+        let mut item_count = 0;
+        let mut dropped_count = 0;
+        let mut expired_count = 0;
+        let now = unix_now();
+        while let Some(loaded) = loaded_files.next().await {
+            match loaded {
+                Some(LoadedFile::Entries(mut items, path)) => {
+                    if self.drop_empty_on_load {
+                        dropped_count += drop_empty_entries(&mut items, &path);
+                    }
+                    let before = items.len();
+                    items.retain(|(_, entry)| !entry.is_expired(self.ttl, now));
+                    expired_count += before - items.len();
+                    item_count += items.len();
+                    loaded_data.extend(items.into_iter().map(|(key, entry)| (key, Arc::new(entry))));
+                }
+                Some(LoadedFile::Mapped(crate_name, mapped)) => {
+                    loaded_mapped.insert(crate_name, Arc::new(mapped));
+                }
+                None => continue,
             }
+            file_count += 1;
         }
 
         // Replace the current cache data with the loaded data
-        let mut cache_guard = self.cache.write().await;
-        cache_guard.data = loaded_data;
+        let mapped_count = loaded_mapped.len();
+        {
+            let mut cache = self.cache.write().await;
+            *cache = CacheData { data: loaded_data, mapped: loaded_mapped };
+            self.lru().clear();
+            self.track_added(&mut cache);
+        }
+        self.load_stats().await;
 
         tracing::info!(
-            "Cache loaded from directory {:?} - {} files, {} items.",
-            dir_path, file_count, item_count
+            "Cache loaded from directory {:?} - {} files, {} items, {} files mapped for lazy decoding.",
+            dir_path, file_count, item_count, mapped_count
         );
+        if dropped_count > 0 {
+            tracing::warn!("Dropped {} empty cache entries while loading; they will be fetched again.", dropped_count);
+        }
+        if expired_count > 0 {
+            tracing::info!("Dropped {} expired cache entries while loading.", expired_count);
+        }
 
         Ok(())
     }
+
+    fn is_ephemeral(&self) -> bool {
+        InMemoryCache::is_ephemeral(self)
+    }
+
+    fn has_unsaved_changes(&self) -> bool {
+        self.dirty.load(Ordering::Relaxed)
+    }
+
+    fn stats(&self) -> CacheStats {
+        InMemoryCache::stats(self)
+    }
+
+    /// Counts the entries in memory and those of mapped files. Mapped files
+    /// are counted without decoding them, after releasing the lock.
+    async fn entry_count(&self) -> usize {
+        let (decoded, mapped) = {
+            let cache = self.cache.read().await;
+            (cache.data.len(), cache.mapped.values().cloned().collect::<Vec<_>>())
+        };
+        decoded + mapped.iter().map(|mapped| mapped.count(self.layout.as_ref())).sum::<usize>()
+    }
+
+    /// Sums the content lengths of the entries in memory and the sizes of
+    /// mapped files not decoded yet.
+    async fn size_bytes(&self) -> u64 {
+        let cache = self.cache.read().await;
+        let decoded: u64 = cache.data.values().map(|entry| entry.value.content.len() as u64).sum();
+        let mapped: u64 = cache.mapped.values().map(|mapped| mapped.map.len() as u64).sum();
+        decoded + mapped
+    }
+}
+
+/// Cache that stores nothing, so every lookup misses and every document is
+/// fetched again. Useful to measure or debug the server without caching.
+#[derive(Debug, Default)]
+pub struct NoopCache {
+    /// Lookups since the server started, all of them misses
+    misses: AtomicU64,
+}
+
+impl NoopCache {
+    /// Creates a cache that stores nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl Cache for NoopCache {
+    async fn get(&self, _key: &CacheKey) -> Option<DocContent> {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        None
+    }
+
+    async fn insert(&self, _key: CacheKey, _value: DocContent) {}
+
+    async fn contains_key(&self, _key: &CacheKey) -> bool {
+        false
+    }
+
+    async fn clear(&self) -> usize {
+        0
+    }
+
+    async fn clear_crate(&self, _crate_name: &str) -> usize {
+        0
+    }
+
+    fn entries(&self) -> BoxStream<'_, (CacheKey, DocContent)> {
+        stream::empty().boxed()
+    }
+
+    async fn save(&self) -> Result<(), io::Error> {
+        Ok(())
+    }
+
+    async fn load(&self) -> Result<(), io::Error> {
+        Ok(())
+    }
+
+    fn has_unsaved_changes(&self) -> bool {
+        false
+    }
+
+    fn stats(&self) -> CacheStats {
+        CacheStats { misses: self.misses.load(Ordering::Relaxed), ..CacheStats::default() }
+    }
+
+    async fn entry_count(&self) -> usize {
+        0
+    }
+
+    async fn size_bytes(&self) -> u64 {
+        0
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cache_key::normalize_key;
+    use crate::cache_layout::CrateCacheData;
+    use crate::docs_parser::DocsRsParams;
     use tempfile::tempdir;
 
-    fn create_params(name: &str) -> DocsRsParams {
-        DocsRsParams {
+    fn create_params(name: &str) -> CacheKey {
+        CacheKey::new(DocsRsParams {
             crate_name: name.to_string(),
             version: "1.0".to_string(),
             path: name.to_string(),
-        }
+        })
     }
 
     fn create_content(text: &str) -> DocContent {
         DocContent {
             content: text.to_string(),
+            source_url: None,
         }
     }
 
+    #[tokio::test]
+    async fn test_read_with_retry_recovers_from_transient_error() {
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+        let retry = LoadRetry { retries: 2, delay: Duration::from_millis(1) };
+        let read = || {
+            let attempt = attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            async move {
+                match attempt {
+                    0 => Err(io::Error::new(io::ErrorKind::TimedOut, "nfs server not responding")),
+                    _ => Ok("{}".to_string()),
+                }
+            }
+        };
+
+        let content = read_with_retry(Path::new("crate.json"), retry, read).await.unwrap();
+        assert_eq!(content, "{}");
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_read_with_retry_gives_up() {
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+        let retry = LoadRetry { retries: 2, delay: Duration::from_millis(1) };
+        let read = |kind: io::ErrorKind| {
+            let attempts = &attempts;
+            move || {
+                attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                async move { Err::<String, _>(io::Error::from(kind)) }
+            }
+        };
+
+        assert!(read_with_retry(Path::new("crate.json"), retry, read(io::ErrorKind::TimedOut)).await.is_err());
+        assert_eq!(attempts.swap(0, std::sync::atomic::Ordering::SeqCst), 3);
+        assert!(read_with_retry(Path::new("crate.json"), retry, read(io::ErrorKind::NotFound)).await.is_err());
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 1, "permanent errors are not retried");
+    }
+
     #[tokio::test]
     async fn test_insert_get_contains() {
         let dir = tempdir().unwrap();
@@ -364,6 +1483,338 @@ mod tests {
         assert!(!cache.contains_key(&params2).await);
     }
 
+    #[tokio::test]
+    async fn test_entries_stream() {
+        let dir = tempdir().unwrap();
+        let cache: Arc<dyn Cache> = Arc::new(InMemoryCache::new(dir.path().to_path_buf()));
+        for name in ["serde", "tokio", "rand"] {
+            cache.insert(create_params(name), create_content(&format!("{} content", name))).await;
+        }
+
+        let mut entries: Vec<(CacheKey, DocContent)> = cache.entries().collect().await;
+        entries.sort_by(|(a, _), (b, _)| a.crate_name().cmp(b.crate_name()));
+
+        assert_eq!(entries.len(), 3);
+        for (params, content) in entries {
+            assert_eq!(cache.get(&params).await, Some(content));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_entries_stream_does_not_block_writers() {
+        let dir = tempdir().unwrap();
+        let cache: Arc<dyn Cache> = Arc::new(InMemoryCache::new(dir.path().to_path_buf()));
+        cache.insert(create_params("serde"), create_content("serde content")).await;
+        cache.insert(create_params("tokio"), create_content("tokio content")).await;
+
+        let mut entries = cache.entries();
+        assert!(entries.next().await.is_some());
+        // Inserting while the stream is paused between items must not deadlock
+        tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            cache.insert(create_params("rand"), create_content("rand content")),
+        ).await.expect("insert blocked by an open entries stream");
+        assert!(entries.next().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_stats_persist_across_save_load() {
+        let dir = tempdir().unwrap();
+        let cache = InMemoryCache::new(dir.path().to_path_buf());
+        cache.insert(create_params("hit"), create_content("content")).await;
+        cache.get(&create_params("hit")).await;
+        cache.get(&create_params("hit")).await;
+        cache.get(&create_params("miss")).await;
+        assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 1, inserts: 1 });
+        cache.save().await.unwrap();
+
+        let restarted = InMemoryCache::new(dir.path().to_path_buf());
+        restarted.load().await.unwrap();
+        assert_eq!(restarted.stats(), CacheStats { hits: 2, misses: 1, inserts: 1 });
+        assert_eq!(restarted.stats_since_start(), CacheStats::default());
+        restarted.get(&create_params("hit")).await;
+        assert_eq!(restarted.stats(), CacheStats { hits: 3, misses: 1, inserts: 1 });
+        assert_eq!(restarted.stats_since_start(), CacheStats { hits: 1, misses: 0, inserts: 0 });
+
+        // The stats file is neither loaded as a crate nor removed as stale
+        restarted.save().await.unwrap();
+        restarted.reset_stats();
+        assert_eq!(restarted.stats(), CacheStats::default());
+        restarted.save().await.unwrap();
+
+        let reset = InMemoryCache::new(dir.path().to_path_buf());
+        reset.load().await.unwrap();
+        assert_eq!(reset.stats(), CacheStats::default());
+        assert_eq!(reset.get(&create_params("hit")).await, Some(create_content("content")));
+    }
+
+    #[tokio::test]
+    async fn test_usage_counts_gets_inserts_and_entries() {
+        let dir = tempdir().unwrap();
+        let cache = InMemoryCache::new(dir.path().to_path_buf());
+        cache.insert(create_params("serde"), create_content("serde docs")).await;
+        cache.insert(create_params("tokio"), create_content("tokio docs")).await;
+        // Replacing an entry counts as an insert, not as a new entry
+        cache.insert(create_params("serde"), create_content("new serde docs")).await;
+        cache.get(&create_params("serde")).await;
+        cache.get(&create_params("tokio")).await;
+        cache.get(&create_params("tokio")).await;
+        cache.get(&create_params("rand")).await;
+
+        let usage = CacheUsage::of(&cache).await;
+        assert_eq!(usage.stats, CacheStats { hits: 3, misses: 1, inserts: 3 });
+        assert_eq!((usage.gets, usage.entries), (4, 2));
+        assert_eq!(usage.size_bytes, ("new serde docs".len() + "tokio docs".len()) as u64);
+    }
+
+    #[tokio::test]
+    async fn test_noop_cache_always_misses() {
+        let cache = NoopCache::new();
+        cache.insert(create_params("serde"), create_content("docs")).await;
+        cache.save().await.unwrap();
+        cache.load().await.unwrap();
+
+        assert!(cache.get(&create_params("serde")).await.is_none());
+        assert!(!cache.contains_key(&create_params("serde")).await);
+        assert_eq!(cache.entries().count().await, 0);
+        assert_eq!(cache.stats(), CacheStats { hits: 0, misses: 1, inserts: 0 });
+        assert_eq!(cache.entry_count().await, 0);
+        assert_eq!(cache.size_bytes().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_size_bytes_sums_stored_content() {
+        let dir = tempdir().unwrap();
+        let cache = InMemoryCache::new(dir.path().to_path_buf());
+        assert_eq!(cache.size_bytes().await, 0);
+        cache.insert(create_params("serde"), create_content(&"s".repeat(3000))).await;
+        cache.insert(create_params("tokio"), create_content(&"t".repeat(5000))).await;
+        assert_eq!(cache.size_bytes().await, 8000);
+        cache.insert(create_params("serde"), create_content("short")).await;
+        assert_eq!(cache.size_bytes().await, 5005);
+        cache.save().await.unwrap();
+
+        // Files mapped but not decoded yet count with their size on disk
+        let mapped = InMemoryCache::new(dir.path().to_path_buf()).with_mmap_threshold(Some(0));
+        mapped.load().await.unwrap();
+        let size = mapped.size_bytes().await;
+        assert!((5005..5005 + 512).contains(&size), "size of mapped files: {}", size);
+        mapped.get(&create_params("tokio")).await;
+        let size = mapped.size_bytes().await;
+        assert!((5005..5005 + 512).contains(&size), "size after decoding: {}", size);
+    }
+
+    #[tokio::test]
+    async fn test_entry_count_leaves_mapped_files_undecoded() {
+        let dir = tempdir().unwrap();
+        let cache = InMemoryCache::new(dir.path().to_path_buf());
+        for path in ["a", "b", "c"] {
+            let key = CacheKey::new(DocsRsParams {
+                crate_name: "serde".to_string(),
+                version: "1.0".to_string(),
+                path: path.to_string(),
+            });
+            cache.insert(key, create_content(path)).await;
+        }
+        cache.insert(create_params("tokio"), create_content("tokio")).await;
+        cache.save().await.unwrap();
+
+        let mapped = InMemoryCache::new(dir.path().to_path_buf()).with_mmap_threshold(Some(0));
+        mapped.load().await.unwrap();
+        assert_eq!(mapped.entry_count().await, 4);
+        assert_eq!(mapped.cache.read().await.mapped.len(), 2);
+
+        // Decoded entries are counted once, from memory
+        mapped.get(&create_params("tokio")).await;
+        assert_eq!(mapped.cache.read().await.mapped.len(), 1);
+        assert_eq!(mapped.entry_count().await, 4);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_save_failures_switch_to_ephemeral_mode() {
+        let dir = tempdir().unwrap();
+        // A file where the cache directory should be makes every save fail
+        let cache_dir = dir.path().join("cache");
+        std::fs::write(&cache_dir, "").unwrap();
+        let cache = InMemoryCache::new(cache_dir.clone()).with_ephemeral_after(Some(2));
+        let params = create_params("test1");
+        cache.insert(params.clone(), create_content("content1")).await;
+
+        assert!(cache.save().await.is_err());
+        assert!(!cache.is_ephemeral());
+        assert!(cache.save().await.is_err());
+        assert!(cache.is_ephemeral());
+        // Still served from memory
+        assert_eq!(cache.get(&params).await, Some(create_content("content1")));
+
+        // Leaves ephemeral mode once the directory is writable again
+        std::fs::remove_file(&cache_dir).unwrap();
+        cache.save().await.unwrap();
+        assert!(!cache.is_ephemeral());
+        assert!(cache_dir.join("test1.json").exists());
+    }
+
+    #[tokio::test]
+    async fn test_unsaved_changes_are_tracked() {
+        let dir = tempdir().unwrap();
+        let cache_dir = dir.path().join("cache");
+        std::fs::write(&cache_dir, "").unwrap();
+        let cache = InMemoryCache::new(cache_dir.clone());
+        assert!(!cache.has_unsaved_changes());
+
+        cache.insert(create_params("test1"), create_content("content1")).await;
+        assert!(cache.has_unsaved_changes());
+        // A failed save leaves the changes unsaved
+        assert!(cache.save().await.is_err());
+        assert!(cache.has_unsaved_changes());
+
+        std::fs::remove_file(&cache_dir).unwrap();
+        cache.save().await.unwrap();
+        assert!(!cache.has_unsaved_changes());
+        cache.get(&create_params("test1")).await;
+        assert_eq!(cache.clear_crate("other").await, 0);
+        assert!(!cache.has_unsaved_changes());
+        cache.clear().await;
+        assert!(cache.has_unsaved_changes());
+    }
+
+    #[tokio::test]
+    async fn test_extraction_variants_are_distinct_entries() {
+        let dir = tempdir().unwrap();
+        let cache = InMemoryCache::new(dir.path().to_path_buf());
+        let page = create_params("serde");
+        let variants = [
+            page.clone(),
+            page.clone().with_format("markdown"),
+            page.clone().with_selector(".docblock"),
+            page.clone().with_parser_version(crate::cache_key::PARSER_VERSION + 1),
+        ];
+        for (i, key) in variants.iter().enumerate() {
+            cache.insert(key.clone(), create_content(&format!("variant {}", i))).await;
+        }
+
+        cache.save().await.unwrap();
+        let reloaded = InMemoryCache::new(dir.path().to_path_buf());
+        reloaded.load().await.unwrap();
+        assert_eq!(reloaded.entries().count().await, variants.len());
+        for (i, key) in variants.iter().enumerate() {
+            assert_eq!(reloaded.get(key).await, Some(create_content(&format!("variant {}", i))));
+        }
+        assert!(!reloaded.contains_key(&page.with_format("html")).await);
+    }
+
+    #[tokio::test]
+    async fn test_drop_empty_on_load() {
+        let dir = tempdir().unwrap();
+        let file = r#"{
+            "1.0::serde/index.html": {"content": "Serde docs"},
+            "1.0::serde/empty.html": {"content": ""},
+            "1.0::serde/blank.html": {"content": " \n\t "}
+        }"#;
+        std::fs::write(dir.path().join("serde.json"), file).unwrap();
+        let key = |path: &str| CacheKey::new(DocsRsParams {
+            crate_name: "serde".to_string(),
+            version: "1.0".to_string(),
+            path: path.to_string(),
+        });
+
+        let kept = InMemoryCache::new(dir.path().to_path_buf());
+        kept.load().await.unwrap();
+        assert_eq!(kept.entries().count().await, 3);
+
+        let healed = InMemoryCache::new(dir.path().to_path_buf()).with_drop_empty_on_load(true);
+        healed.load().await.unwrap();
+        assert_eq!(healed.entries().count().await, 1);
+        assert_eq!(healed.get(&key("serde/index.html")).await, Some(create_content("Serde docs")));
+        assert!(!healed.contains_key(&key("serde/empty.html")).await);
+        assert!(!healed.contains_key(&key("serde/blank.html")).await);
+
+        // Also when the file is memory-mapped and decoded lazily
+        let mapped = InMemoryCache::new(dir.path().to_path_buf())
+            .with_drop_empty_on_load(true)
+            .with_mmap_threshold(Some(0));
+        mapped.load().await.unwrap();
+        assert!(!mapped.contains_key(&key("serde/blank.html")).await);
+        assert!(mapped.contains_key(&key("serde/index.html")).await);
+    }
+
+    #[tokio::test]
+    async fn test_ttl_expires_entries() {
+        let dir = tempdir().unwrap();
+        let file = format!(r#"{{
+            "1.0::serde/fresh.html": {{"content": "Fresh", "fetched_at": {}}},
+            "1.0::serde/old.html": {{"content": "Old", "fetched_at": 1000}},
+            "1.0::serde/legacy.html": {{"content": "Legacy"}}
+        }}"#, unix_now());
+        std::fs::write(dir.path().join("serde.json"), file).unwrap();
+        let key = |path: &str| CacheKey::new(DocsRsParams {
+            crate_name: "serde".to_string(),
+            version: "1.0".to_string(),
+            path: path.to_string(),
+        });
+
+        let forever = InMemoryCache::new(dir.path().to_path_buf()).with_ttl(Some(Duration::ZERO));
+        forever.load().await.unwrap();
+        assert_eq!(forever.get(&key("serde/old.html")).await, Some(create_content("Old")));
+
+        // Expired entries are dropped on load; those without a fetch time
+        // count as fetched when loaded
+        let cache = InMemoryCache::new(dir.path().to_path_buf()).with_ttl(Some(Duration::from_secs(3600)));
+        cache.load().await.unwrap();
+        assert!(!cache.contains_key(&key("serde/old.html")).await);
+        assert_eq!(cache.get(&key("serde/fresh.html")).await, Some(create_content("Fresh")));
+        assert_eq!(cache.get(&key("serde/legacy.html")).await, Some(create_content("Legacy")));
+
+        // An entry expiring while cached is a miss, but still readable stale
+        // until the cache is saved
+        Arc::make_mut(cache.cache.write().await.data.get_mut(&key("serde/fresh.html")).unwrap()).fetched_at = 1000;
+        assert_eq!(cache.get(&key("serde/fresh.html")).await, None);
+        assert_eq!(cache.get_stale(&key("serde/fresh.html")).await, Some(create_content("Fresh")));
+        assert_eq!(cache.cached_versions("serde").await, ["1.0"]);
+
+        // Fetch times survive a restart
+        cache.save().await.unwrap();
+        assert_eq!(cache.get_stale(&key("serde/fresh.html")).await, None);
+        let saved: CrateCacheData = serde_json::from_str(&std::fs::read_to_string(dir.path().join("serde.json")).unwrap()).unwrap();
+        assert_eq!(saved.len(), 1);
+        let legacy_fetched_at = saved["1.0::serde/legacy.html"].fetched_at;
+        let reloaded = InMemoryCache::new(dir.path().to_path_buf()).with_ttl(Some(Duration::from_secs(3600)));
+        reloaded.load().await.unwrap();
+        assert_eq!(reloaded.cache.read().await.data[&key("serde/legacy.html")].fetched_at, legacy_fetched_at);
+    }
+
+    #[tokio::test]
+    async fn test_max_entries_evicts_least_recently_used() {
+        let dir = tempdir().unwrap();
+        let cache = InMemoryCache::with_capacity(dir.path().to_path_buf(), 3);
+        for name in ["a", "b", "c"] {
+            cache.insert(create_params(name), create_content(name)).await;
+        }
+        // Using "a" makes "b" the least recently used
+        assert!(cache.get(&create_params("a")).await.is_some());
+
+        cache.insert(create_params("d"), create_content("d")).await;
+        assert!(!cache.contains_key(&create_params("b")).await);
+        cache.insert(create_params("e"), create_content("e")).await;
+        assert!(!cache.contains_key(&create_params("c")).await);
+        for name in ["a", "d", "e"] {
+            assert!(cache.contains_key(&create_params(name)).await, "{} was evicted", name);
+        }
+        assert_eq!(cache.cache.read().await.data.len(), 3);
+
+        // Loading more entries than allowed keeps the limit, and loaded
+        // entries are evicted before ones inserted afterwards
+        cache.save().await.unwrap();
+        let reloaded = InMemoryCache::with_capacity(dir.path().to_path_buf(), 2);
+        reloaded.load().await.unwrap();
+        assert_eq!(reloaded.cache.read().await.data.len(), 2);
+        reloaded.insert(create_params("f"), create_content("f")).await;
+        reloaded.insert(create_params("g"), create_content("g")).await;
+        assert!(reloaded.contains_key(&create_params("f")).await);
+        assert!(reloaded.contains_key(&create_params("g")).await);
+        assert_eq!(reloaded.cache.read().await.data.len(), 2);
+    }
+
     #[tokio::test]
     async fn test_clear() {
         let dir = tempdir().unwrap();
@@ -379,6 +1830,133 @@ mod tests {
         assert!(cache.get(&params1).await.is_none());
     }
 
+    #[tokio::test]
+    async fn test_clear_crate_keeps_other_crates() {
+        let dir = tempdir().unwrap();
+        let writer = InMemoryCache::new(dir.path().to_path_buf());
+        writer.insert(create_params("serde"), create_content("serde docs")).await;
+        writer.save().await.unwrap();
+
+        // The entries of a crate still mapped are counted too
+        let cache = InMemoryCache::new(dir.path().to_path_buf()).with_mmap_threshold(Some(0));
+        cache.load().await.unwrap();
+        cache.insert(create_params("tokio"), create_content("tokio docs")).await;
+        let mut other_version = create_params("serde");
+        other_version.params.version = "2.0".to_string();
+        cache.insert(other_version.clone(), create_content("serde 2 docs")).await;
+
+        assert_eq!(cache.clear_crate("serde").await, 2);
+        assert!(!cache.contains_key(&create_params("serde")).await);
+        assert!(!cache.contains_key(&other_version).await);
+        assert!(cache.contains_key(&create_params("tokio")).await);
+        assert_eq!(cache.clear().await, 1);
+    }
+
+    /// Per-crate layout whose decoding takes a while, like that of large files.
+    #[derive(Debug)]
+    struct SlowDecodeLayout;
+
+    impl CacheLayout for SlowDecodeLayout {
+        fn is_cache_file(&self, file_name: &str) -> bool {
+            PerCrateLayout.is_cache_file(file_name)
+        }
+
+        fn decode(&self, file_name: &str, content: &str) -> Result<Vec<(CacheKey, CacheEntry)>, String> {
+            std::thread::sleep(Duration::from_millis(20));
+            PerCrateLayout.decode(file_name, content)
+        }
+
+        fn encode(&self, entries: &HashMap<CacheKey, Arc<CacheEntry>>) -> io::Result<Vec<(String, String)>> {
+            PerCrateLayout.encode(entries)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_reads_files_concurrently() {
+        let dir = tempdir().unwrap();
+        let writer = InMemoryCache::new(dir.path().to_path_buf());
+        for i in 0..40 {
+            writer.insert(create_params(&format!("crate{}", i)), create_content(&format!("content{}", i))).await;
+        }
+        writer.save().await.unwrap();
+
+        let cache_dir = dir.path().to_path_buf();
+        let load = |concurrency| {
+            let cache = InMemoryCache::new(cache_dir.clone())
+                .with_layout(Arc::new(SlowDecodeLayout))
+                .with_load_concurrency(concurrency);
+            async move {
+                let started = std::time::Instant::now();
+                cache.load().await.unwrap();
+                (started.elapsed(), cache)
+            }
+        };
+        let (sequential, sequential_cache) = load(1).await;
+        let (concurrent, concurrent_cache) = load(8).await;
+
+        for cache in [sequential_cache, concurrent_cache] {
+            assert_eq!(cache.entries().count().await, 40);
+            assert_eq!(cache.get(&create_params("crate7")).await, Some(create_content("content7")));
+        }
+        assert!(
+            concurrent * 2 < sequential,
+            "concurrent load took {:?}, sequential {:?}", concurrent, sequential
+        );
+    }
+
+    /// Per-crate layout whose encoding takes a while, flagging while it runs.
+    #[derive(Debug)]
+    struct SlowEncodeLayout {
+        encoding: Arc<AtomicBool>,
+    }
+
+    impl CacheLayout for SlowEncodeLayout {
+        fn is_cache_file(&self, file_name: &str) -> bool {
+            PerCrateLayout.is_cache_file(file_name)
+        }
+
+        fn decode(&self, file_name: &str, content: &str) -> Result<Vec<(CacheKey, CacheEntry)>, String> {
+            PerCrateLayout.decode(file_name, content)
+        }
+
+        fn encode(&self, entries: &HashMap<CacheKey, Arc<CacheEntry>>) -> io::Result<Vec<(String, String)>> {
+            self.encoding.store(true, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(500));
+            self.encoding.store(false, Ordering::SeqCst);
+            PerCrateLayout.encode(entries)
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_insert_not_blocked_while_save_encodes() {
+        let dir = tempdir().unwrap();
+        let encoding = Arc::new(AtomicBool::new(false));
+        let cache = Arc::new(
+            InMemoryCache::new(dir.path().to_path_buf())
+                .with_layout(Arc::new(SlowEncodeLayout { encoding: encoding.clone() })),
+        );
+        cache.insert(create_params("serde"), create_content("Serde docs")).await;
+
+        let saving = tokio::spawn({
+            let cache = cache.clone();
+            async move { cache.save().await }
+        });
+        while !encoding.load(Ordering::SeqCst) {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        let started = std::time::Instant::now();
+        cache.insert(create_params("tokio"), create_content("Tokio docs")).await;
+        assert!(encoding.load(Ordering::SeqCst), "insert waited {:?} for the save", started.elapsed());
+        saving.await.unwrap().unwrap();
+
+        // The save holds the entries of its snapshot only
+        let reloaded = InMemoryCache::new(dir.path().to_path_buf());
+        reloaded.load().await.unwrap();
+        assert!(reloaded.contains_key(&create_params("serde")).await);
+        assert!(!reloaded.contains_key(&create_params("tokio")).await);
+        assert!(cache.contains_key(&create_params("tokio")).await);
+    }
+
     #[tokio::test]
     async fn test_save_load_single_crate() {
         let dir = tempdir().unwrap();
@@ -387,11 +1965,11 @@ mod tests {
         let cache1 = Arc::new(InMemoryCache::new(cache_dir_path.clone()));
         let params1 = create_params("serde"); // crate_name: "serde"
         let content1 = create_content("serde content");
-        let params2 = DocsRsParams { // Same crate, different path/version
+        let params2 = CacheKey::new(DocsRsParams { // Same crate, different path/version
              crate_name: "serde".to_string(),
              version: "1.0.150".to_string(),
              path: "serde/derive".to_string(),
-         };
+         });
         let content2 = create_content("serde derive content");
 
         cache1.insert(params1.clone(), content1.clone()).await;
@@ -433,11 +2011,11 @@ mod tests {
         let content_serde = create_content("serde content");
         let params_tokio = create_params("tokio"); // crate_name: "tokio"
         let content_tokio = create_content("tokio content");
-         let params_rand = DocsRsParams {
+         let params_rand = CacheKey::new(DocsRsParams {
              crate_name: "rand".to_string(),
              version: "0.8".to_string(),
              path: "Rng".to_string(),
-         };
+         });
          let content_rand = create_content("rand content");
 
 
@@ -499,6 +2077,48 @@ mod tests {
         assert!(!cache_dir_path.join("tokio.json").exists(), "tokio.json should have been removed");
     }
 
+    #[tokio::test]
+    async fn test_save_replaces_partly_written_files() {
+        let dir = tempdir().unwrap();
+        let cache_dir_path = dir.path().to_path_buf();
+        // A save interrupted midway through writing a crate file, the
+        // temporary file of a save that never got renamed, and one of a
+        // save of another process that may still be running
+        fs::write(cache_dir_path.join("serde.json"), r#"{"1.0::serde": {"content": "trunc"#).await.unwrap();
+        let other_pid = std::process::id().wrapping_add(1);
+        let leftover = cache_dir_path.join(format!(".tokio.json.{}.0.tmp", other_pid));
+        fs::write(&leftover, "{").await.unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&leftover)
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() - LEFTOVER_TEMP_AGE * 2)
+            .unwrap();
+        let in_progress = cache_dir_path.join(format!(".rand.json.{}.0.tmp", other_pid));
+        fs::write(&in_progress, "{").await.unwrap();
+
+        let cache = Arc::new(InMemoryCache::new(cache_dir_path.clone()));
+        let params = create_params("serde");
+        let content = create_content("serde content");
+        cache.insert(params.clone(), content.clone()).await;
+        cache.save().await.expect("Failed to save cache");
+
+        let saved = fs::read_to_string(cache_dir_path.join("serde.json")).await.unwrap();
+        serde_json::from_str::<serde_json::Value>(&saved).expect("saved cache file should be valid JSON");
+        assert!(!leftover.exists(), "leftover temporary file should be removed");
+        assert!(in_progress.exists(), "recent temporary file of another process should be kept");
+        fs::remove_file(&in_progress).await.unwrap();
+        let mut entries = fs::read_dir(&cache_dir_path).await.unwrap();
+        while let Some(entry) = entries.next_entry().await.unwrap() {
+            let name = entry.file_name().into_string().unwrap();
+            assert!(!name.ends_with(".tmp"), "temporary file {:?} left behind", name);
+        }
+
+        let reloaded = Arc::new(InMemoryCache::new(cache_dir_path.clone()));
+        reloaded.load().await.expect("Failed to load cache");
+        assert_eq!(reloaded.get(&params).await, Some(content));
+    }
+
 
     #[tokio::test]
     async fn test_load_nonexistent_directory() {
@@ -512,6 +2132,53 @@ mod tests {
         assert!(cache.cache.read().await.data.is_empty(), "Cache should be empty");
     }
 
+    #[tokio::test]
+    async fn test_load_nonexistent_directory_when_required() {
+        let dir = tempdir().unwrap();
+        let cache = InMemoryCache::new(dir.path().join("missing")).with_require_dir(true);
+
+        let err = cache.load().await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(err.to_string().contains("missing"), "got {}", err);
+
+        let cache = InMemoryCache::new(dir.path().to_path_buf()).with_require_dir(true);
+        cache.load().await.expect("Loading an existing dir failed");
+    }
+
+    #[tokio::test]
+    async fn test_load_maps_large_files_lazily() {
+        let dir = tempdir().unwrap();
+        let large: HashMap<String, DocContent> = (0..2000)
+            .map(|i| (format!("1.0::page{}.html", i), create_content(&format!("{} {}", i, "x".repeat(1000)))))
+            .collect();
+        let small: HashMap<String, DocContent> = [(normalize_key(&create_params("small")), create_content("small content"))].into();
+        fs::write(dir.path().join("large.json"), serde_json::to_string(&large).unwrap()).await.unwrap();
+        fs::write(dir.path().join("small.json"), serde_json::to_string(&small).unwrap()).await.unwrap();
+
+        let cache = InMemoryCache::new(dir.path().to_path_buf()).with_mmap_threshold(Some(1 << 20));
+        cache.load().await.unwrap();
+        {
+            let data = cache.cache.read().await;
+            assert_eq!(data.data.len(), 1, "Only the small file should be decoded");
+            assert!(data.mapped.contains_key("large"));
+        }
+
+        let key = CacheKey::new(DocsRsParams { crate_name: "large".to_string(), version: "1.0".to_string(), path: "page1234.html".to_string() });
+        let content = cache.get(&key).await.expect("Mapped entry not found");
+        assert_eq!(content.content, format!("1234 {}", "x".repeat(1000)));
+        {
+            let data = cache.cache.read().await;
+            assert!(data.mapped.is_empty());
+            assert_eq!(data.data.len(), 2001);
+        }
+
+        // Saving rewrites the formerly mapped file with all its entries
+        cache.save().await.unwrap();
+        let reloaded = InMemoryCache::new(dir.path().to_path_buf());
+        reloaded.load().await.unwrap();
+        assert_eq!(reloaded.cache.read().await.data.len(), 2001);
+    }
+
     #[tokio::test]
     async fn test_load_invalid_file_in_directory() {
          let dir = tempdir().unwrap();
@@ -524,7 +2191,7 @@ mod tests {
          // Create a valid file
          let valid_params = create_params("valid");
          let valid_content = create_content("valid content");
-         let valid_data: CrateCacheData = [(normalize_key(&valid_params), valid_content.clone())].into();
+         let valid_data: HashMap<String, DocContent> = [(normalize_key(&valid_params), valid_content.clone())].into();
          fs::write(&valid_file_path, serde_json::to_string(&valid_data).unwrap()).await.unwrap();
 
 
@@ -0,0 +1,186 @@
+//! Keys of documentation cache entries.
+//!
+//! Besides the page it was fetched from, the content of an entry depends on
+//! how it was extracted: the output format, the CSS selector limiting the
+//! extraction and the version of the parser. A [`CacheKey`] holds all of
+//! them, so that content extracted differently is never served for a
+//! request it does not match.
+//!
+//! Layouts persist keys with [`normalize_key`] and read them back with
+//! [`denormalize_key`]. Keys with the default format, no selector and
+//! [`FIRST_PARSER_VERSION`] normalize to `{version}::{path}` as they did
+//! before keys had these parts, so existing cache files stay readable.
+//! Other keys add the parts as a query after the version, e.g.
+//! `1.0.0?format=markdown&parser=2::serde/index.html`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::docs_parser::DocsRsParams;
+
+/// Version of the content extraction. Bump it whenever a change of the
+/// parser changes the extracted content, so that entries extracted by an
+/// earlier version are no longer served.
+pub const PARSER_VERSION: u32 = 1;
+
+/// Parser version of entries persisted without one, i.e. written before
+/// keys carried the parser version.
+pub const FIRST_PARSER_VERSION: u32 = 1;
+
+/// Separator of the version and the path in normalized keys.
+const PATH_SEPARATOR: &str = "::";
+
+/// Key of a documentation cache entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CacheKey {
+    /// Page the content was fetched from
+    pub params: DocsRsParams,
+    /// Output format, `None` for the default plain text
+    pub format: Option<String>,
+    /// CSS selector the extraction was limited to, `None` for the whole page
+    pub selector: Option<String>,
+    /// Version of the parser that extracted the content
+    pub parser_version: u32,
+}
+
+impl CacheKey {
+    /// Creates the key of the plain text of the page `params`, extracted by
+    /// the current parser.
+    pub fn new(params: DocsRsParams) -> Self {
+        Self {
+            params,
+            format: None,
+            selector: None,
+            parser_version: PARSER_VERSION,
+        }
+    }
+
+    /// Sets the output format.
+    pub fn with_format(mut self, format: impl Into<String>) -> Self {
+        self.format = Some(format.into());
+        self
+    }
+
+    /// Sets the CSS selector the extraction was limited to.
+    #[allow(dead_code)]
+    pub fn with_selector(mut self, selector: impl Into<String>) -> Self {
+        self.selector = Some(selector.into());
+        self
+    }
+
+    /// Sets the version of the parser that extracted the content.
+    pub fn with_parser_version(mut self, parser_version: u32) -> Self {
+        self.parser_version = parser_version;
+        self
+    }
+
+    /// Name of the crate the entry belongs to.
+    pub fn crate_name(&self) -> &str {
+        &self.params.crate_name
+    }
+}
+
+impl From<DocsRsParams> for CacheKey {
+    fn from(params: DocsRsParams) -> Self {
+        Self::new(params)
+    }
+}
+
+impl From<&DocsRsParams> for CacheKey {
+    fn from(params: &DocsRsParams) -> Self {
+        Self::new(params.clone())
+    }
+}
+
+/// Normalizes a cache key into a string, leaving out the crate name, which
+/// layouts store separately.
+///
+/// # Returns
+///
+/// A string in the format "{version}::{path}", with the format, selector
+/// and parser version as a query after the version unless they are the
+/// defaults
+pub(crate) fn normalize_key(key: &CacheKey) -> String {
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    if let Some(format) = &key.format {
+        query.append_pair("format", format);
+    }
+    if let Some(selector) = &key.selector {
+        query.append_pair("selector", selector);
+    }
+    if key.parser_version != FIRST_PARSER_VERSION {
+        query.append_pair("parser", &key.parser_version.to_string());
+    }
+    let query = query.finish();
+    match query.is_empty() {
+        true => format!("{}{}{}", key.params.version, PATH_SEPARATOR, key.params.path),
+        false => format!("{}?{}{}{}", key.params.version, query, PATH_SEPARATOR, key.params.path),
+    }
+}
+
+/// Reconstructs a cache key from a normalized key and crate name.
+///
+/// # Arguments
+///
+/// * `crate_name` - Name of the crate
+/// * `normalized_key` - The normalized key string
+///
+/// # Returns
+///
+/// * `Ok(CacheKey)` - Successfully reconstructed key
+/// * `Err(String)` - Error message if the key format is invalid
+pub(crate) fn denormalize_key(crate_name: &str, normalized_key: &str) -> Result<CacheKey, String> {
+    let invalid = || format!("Invalid normalized key format: {}", normalized_key);
+    let (head, path) = normalized_key.split_once(PATH_SEPARATOR).ok_or_else(invalid)?;
+    let (version, query) = head.split_once('?').unwrap_or((head, ""));
+    let mut key = CacheKey::new(DocsRsParams {
+        crate_name: crate_name.to_string(),
+        version: version.to_string(),
+        path: path.to_string(),
+    })
+    .with_parser_version(FIRST_PARSER_VERSION);
+    for (name, value) in url::form_urlencoded::parse(query.as_bytes()) {
+        match name.as_ref() {
+            "format" => key.format = Some(value.into_owned()),
+            "selector" => key.selector = Some(value.into_owned()),
+            "parser" => key.parser_version = value.parse().map_err(|_| invalid())?,
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> DocsRsParams {
+        DocsRsParams {
+            crate_name: "serde".to_string(),
+            version: "1.0.0".to_string(),
+            path: "serde/trait.Serialize.html".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_default_key_normalizes_to_version_and_path() {
+        let key = CacheKey::new(params()).with_parser_version(FIRST_PARSER_VERSION);
+        assert_eq!(normalize_key(&key), "1.0.0::serde/trait.Serialize.html");
+        assert_eq!(denormalize_key("serde", "1.0.0::serde/trait.Serialize.html").unwrap(), key);
+    }
+
+    #[test]
+    fn test_keys_round_trip() {
+        let keys = [
+            CacheKey::new(params()).with_format("markdown"),
+            CacheKey::new(params()).with_selector("div.docblock > p::first-line, a[href=\"?x&y\"]"),
+            CacheKey::new(params()).with_format("html").with_selector("#main-content").with_parser_version(7),
+            CacheKey::new(DocsRsParams { path: "@submodules:a::b".to_string(), ..params() }).with_parser_version(2),
+        ];
+        for key in keys {
+            let normalized = normalize_key(&key);
+            assert_eq!(denormalize_key("serde", &normalized).unwrap(), key, "{}", normalized);
+        }
+        assert!(denormalize_key("serde", "1.0.0").is_err());
+        assert!(denormalize_key("serde", "1.0.0?colour=red::serde/index.html").is_err());
+    }
+}
@@ -0,0 +1,362 @@
+//! On-disk layouts of the documentation cache directory.
+//!
+//! A [`CacheLayout`] decides which files of the cache directory hold
+//! documentation entries and how entries are spread over and encoded in
+//! them. [`InMemoryCache`](crate::cache::InMemoryCache) reads and writes the
+//! files; the layout only translates between file contents and entries, so
+//! a cache written by another tool can be read by adding a layout.
+//!
+//! Two layouts are provided:
+//! - [`PerCrateLayout`] (default): one `{crate}.json` file per crate, mapping
+//!   `{version}::{path}` keys, see [`crate::cache_key`], to content and
+//!   fetch time
+//! - [`FlatLayout`]: a single `cache.json` file holding a list of entries
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::cache::{unix_now, CacheEntry};
+use crate::cache_key::{denormalize_key, normalize_key, CacheKey, FIRST_PARSER_VERSION};
+use crate::docs_parser::{is_safe_path_segment, DocContent, DocsRsParams};
+
+/// Maps cache entries to files of the cache directory and back.
+pub trait CacheLayout: Send + Sync + fmt::Debug {
+    /// Returns whether the file `file_name` of the cache directory holds
+    /// entries of this layout. Other files are neither read nor removed.
+    fn is_cache_file(&self, file_name: &str) -> bool;
+
+    /// Decodes the entries of the cache file `file_name`.
+    ///
+    /// Entries that cannot be decoded are logged and left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message if the file as a whole cannot be decoded.
+    fn decode(&self, file_name: &str, content: &str) -> Result<Vec<(CacheKey, CacheEntry)>, String>;
+
+    /// Counts the entries of the cache file `file_name`. Layouts can do so
+    /// without decoding the entries, by default they are decoded.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message if the file as a whole cannot be decoded.
+    fn count(&self, file_name: &str, content: &str) -> Result<usize, String> {
+        self.decode(file_name, content).map(|items| items.len())
+    }
+
+    /// Returns the crate all entries of the cache file `file_name` belong
+    /// to, if the layout keeps crates in files of their own. Such files can
+    /// be decoded lazily, once an entry of their crate is first needed.
+    fn crate_of(&self, _file_name: &str) -> Option<String> {
+        None
+    }
+
+    /// Encodes `entries` into cache files, as pairs of file name and content.
+    /// No file is returned for an empty cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error if serialization fails.
+    fn encode(&self, entries: &HashMap<CacheKey, Arc<CacheEntry>>) -> io::Result<Vec<(String, String)>>;
+}
+
+/// Layouts selectable on the command line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum CacheLayoutKind {
+    /// One `{crate}.json` file per crate
+    #[default]
+    PerCrate,
+    /// A single `cache.json` file holding all entries
+    Flat,
+}
+
+impl CacheLayoutKind {
+    /// Returns the layout of this kind.
+    pub fn layout(self) -> Arc<dyn CacheLayout> {
+        match self {
+            Self::PerCrate => Arc::new(PerCrateLayout),
+            Self::Flat => Arc::new(FlatLayout),
+        }
+    }
+}
+
+/// Cache data for a single crate, mapping version+path to content and
+/// fetch time.
+///
+/// The key is a normalized string in the format "{version}::{path}", see
+/// [`normalize_key`].
+pub(crate) type CrateCacheData = HashMap<String, CacheEntry>;
+
+/// One `{crate}.json` file per crate, the crate name being the file stem.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PerCrateLayout;
+
+impl CacheLayout for PerCrateLayout {
+    fn is_cache_file(&self, file_name: &str) -> bool {
+        file_name.strip_suffix(".json").is_some_and(|stem| !stem.is_empty())
+    }
+
+    fn decode(&self, file_name: &str, content: &str) -> Result<Vec<(CacheKey, CacheEntry)>, String> {
+        let crate_name = file_name.strip_suffix(".json").unwrap_or(file_name);
+        let crate_cache_data: CrateCacheData = serde_json::from_str(content).map_err(|e| e.to_string())?;
+        let items = crate_cache_data
+            .into_iter()
+            .filter_map(|(norm_key, entry)| match denormalize_key(crate_name, &norm_key) {
+                Ok(key) => Some((key, entry)),
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to denormalize key '{}' in file {:?}: {}. Skipping entry.",
+                        norm_key, file_name, e
+                    );
+                    None
+                }
+            })
+            .collect();
+        Ok(items)
+    }
+
+    /// Counts the keys of the file, skipping over the entries.
+    fn count(&self, _file_name: &str, content: &str) -> Result<usize, String> {
+        serde_json::from_str::<HashMap<String, serde::de::IgnoredAny>>(content)
+            .map(|entries| entries.len())
+            .map_err(|e| e.to_string())
+    }
+
+    fn crate_of(&self, file_name: &str) -> Option<String> {
+        file_name.strip_suffix(".json").map(str::to_string)
+    }
+
+    fn encode(&self, entries: &HashMap<CacheKey, Arc<CacheEntry>>) -> io::Result<Vec<(String, String)>> {
+        let mut crates: BTreeMap<&str, HashMap<String, &CacheEntry>> = BTreeMap::new();
+        for (key, entry) in entries {
+            if !is_safe_path_segment(key.crate_name()) {
+                tracing::error!("Not saving cache entry {:?}: crate name cannot be a file name.", key);
+                continue;
+            }
+            crates
+                .entry(key.crate_name())
+                .or_default()
+                .insert(normalize_key(key), entry.as_ref());
+        }
+        crates
+            .into_iter()
+            .map(|(crate_name, crate_cache_data)| {
+                let serialized = serde_json::to_string_pretty(&crate_cache_data).map_err(io::Error::other)?;
+                Ok((format!("{}.json", crate_name), serialized))
+            })
+            .collect()
+    }
+}
+
+/// Name of the single file of the [`FlatLayout`].
+pub const FLAT_CACHE_FILE: &str = "cache.json";
+
+/// Entry of the [`FlatLayout`] file.
+#[derive(Serialize, Deserialize)]
+struct FlatEntry {
+    crate_name: String,
+    version: String,
+    path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    format: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    selector: Option<String>,
+    /// Left out for [`FIRST_PARSER_VERSION`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parser_version: Option<u32>,
+    content: String,
+    /// URL the content was fetched from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source_url: Option<String>,
+    /// Seconds since the Unix epoch at which the content was fetched,
+    /// taken as the load time if missing
+    #[serde(default = "unix_now")]
+    fetched_at: u64,
+}
+
+/// A single [`FLAT_CACHE_FILE`] holding a JSON list of entries, each with
+/// `crate_name`, `version`, `path`, `content` and `fetched_at` fields, the
+/// optional `format`, `selector` and `parser_version` fields of the
+/// [`CacheKey`], and the optional `source_url` of the content.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FlatLayout;
+
+impl CacheLayout for FlatLayout {
+    fn is_cache_file(&self, file_name: &str) -> bool {
+        file_name == FLAT_CACHE_FILE
+    }
+
+    fn decode(&self, _file_name: &str, content: &str) -> Result<Vec<(CacheKey, CacheEntry)>, String> {
+        let entries: Vec<FlatEntry> = serde_json::from_str(content).map_err(|e| e.to_string())?;
+        let items = entries
+            .into_iter()
+            .map(|entry| {
+                let params = DocsRsParams {
+                    crate_name: entry.crate_name,
+                    version: entry.version,
+                    path: entry.path,
+                };
+                let key = CacheKey {
+                    params,
+                    format: entry.format,
+                    selector: entry.selector,
+                    parser_version: entry.parser_version.unwrap_or(FIRST_PARSER_VERSION),
+                };
+                let value = DocContent { content: entry.content, source_url: entry.source_url };
+                (key, CacheEntry { value, fetched_at: entry.fetched_at })
+            })
+            .collect();
+        Ok(items)
+    }
+
+    fn encode(&self, entries: &HashMap<CacheKey, Arc<CacheEntry>>) -> io::Result<Vec<(String, String)>> {
+        if entries.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut flat: Vec<FlatEntry> = entries
+            .iter()
+            .map(|(key, entry)| FlatEntry {
+                crate_name: key.params.crate_name.clone(),
+                version: key.params.version.clone(),
+                path: key.params.path.clone(),
+                format: key.format.clone(),
+                selector: key.selector.clone(),
+                parser_version: (key.parser_version != FIRST_PARSER_VERSION).then_some(key.parser_version),
+                content: entry.value.content.clone(),
+                source_url: entry.value.source_url.clone(),
+                fetched_at: entry.fetched_at,
+            })
+            .collect();
+        flat.sort_by(|a, b| {
+            (&a.crate_name, &a.version, &a.path, &a.format, &a.selector, a.parser_version)
+                .cmp(&(&b.crate_name, &b.version, &b.path, &b.format, &b.selector, b.parser_version))
+        });
+        let serialized = serde_json::to_string_pretty(&flat).map_err(io::Error::other)?;
+        Ok(vec![(FLAT_CACHE_FILE.to_string(), serialized)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::{Cache, InMemoryCache};
+    use crate::cache_key::PARSER_VERSION;
+    use futures::StreamExt;
+    use tempfile::tempdir;
+
+    fn entries() -> Vec<(CacheKey, DocContent)> {
+        // Several crates with several entries each, so grouping is exercised
+        let mut entries: Vec<_> = ["serde", "tokio", "rand", "anyhow"]
+            .iter()
+            .flat_map(|crate_name| {
+                ["1.0.0", "2.0.0"].map(|version| {
+                    let params = DocsRsParams {
+                        crate_name: crate_name.to_string(),
+                        version: version.to_string(),
+                        path: format!("{}/index.html", crate_name),
+                    };
+                    let content = DocContent {
+                        content: format!("{} {} docs", crate_name, version),
+                        source_url: Some(format!("https://docs.rs/{}/{}/{}/index.html", crate_name, version, crate_name)),
+                    };
+                    (CacheKey::new(params), content)
+                })
+            })
+            .collect();
+        // Entries of the same page extracted differently
+        let page = entries[0].0.clone();
+        entries.push((page.clone().with_format("markdown"), DocContent { content: "# serde".to_string(), source_url: None }));
+        entries.push((page.clone().with_selector("#main-content > .docblock"), DocContent { content: "serde".to_string(), source_url: None }));
+        entries.push((page.with_parser_version(PARSER_VERSION + 1), DocContent { content: "serde, reparsed".to_string(), source_url: None }));
+        entries
+    }
+
+    /// Sorts entries by their normalized keys, to compare them.
+    fn sorted(mut entries: Vec<(CacheKey, DocContent)>) -> Vec<(CacheKey, DocContent)> {
+        entries.sort_by_key(|(key, _)| (key.crate_name().to_string(), normalize_key(key)));
+        entries
+    }
+
+    #[tokio::test]
+    async fn test_layouts_round_trip_same_entries() {
+        for kind in CacheLayoutKind::value_variants() {
+            let dir = tempdir().unwrap();
+            let cache = InMemoryCache::new(dir.path().to_path_buf()).with_layout(kind.layout());
+            for (key, content) in entries() {
+                cache.insert(key, content).await;
+            }
+            cache.save().await.unwrap();
+
+            let reloaded = InMemoryCache::new(dir.path().to_path_buf()).with_layout(kind.layout());
+            reloaded.load().await.unwrap();
+            let loaded = sorted(reloaded.entries().collect().await);
+            assert_eq!(loaded, sorted(entries()), "{:?} layout", kind);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_flat_layout_reads_file_written_by_other_tool() {
+        let dir = tempdir().unwrap();
+        let file = r#"[
+            {"crate_name": "serde", "version": "1.0.0", "path": "serde/index.html", "content": "Serde docs"},
+            {"crate_name": "tokio", "version": "1.0.0", "path": "tokio/index.html", "content": "Tokio docs"}
+        ]"#;
+        std::fs::write(dir.path().join(FLAT_CACHE_FILE), file).unwrap();
+        std::fs::write(dir.path().join("serde.json"), "{}").unwrap();
+
+        let cache = InMemoryCache::new(dir.path().to_path_buf()).with_layout(CacheLayoutKind::Flat.layout());
+        cache.load().await.unwrap();
+        let params = DocsRsParams {
+            crate_name: "tokio".to_string(),
+            version: "1.0.0".to_string(),
+            path: "tokio/index.html".to_string(),
+        };
+        assert_eq!(cache.get(&params.into()).await, Some(DocContent { content: "Tokio docs".to_string(), source_url: None }));
+        assert_eq!(cache.entries().count().await, 2);
+
+        // Files of other layouts are left alone on save
+        cache.save().await.unwrap();
+        assert!(dir.path().join("serde.json").exists());
+    }
+
+    #[tokio::test]
+    async fn test_per_crate_layout_persists_edge_case_names() {
+        let dir = tempdir().unwrap();
+        let cache_dir = dir.path().join("cache");
+        let cache = InMemoryCache::new(cache_dir.clone());
+        let key = |crate_name: &str| CacheKey::new(DocsRsParams {
+            crate_name: crate_name.to_string(),
+            version: "1.0.0".to_string(),
+            path: "index.html".to_string(),
+        });
+        for crate_name in ["a", "async", "foo.bar", "../evil"] {
+            cache.insert(key(crate_name), DocContent { content: format!("{} docs", crate_name), source_url: None }).await;
+        }
+        cache.save().await.unwrap();
+
+        assert!(cache_dir.join("a.json").exists());
+        assert!(cache_dir.join("foo.bar.json").exists());
+        assert!(!dir.path().join("evil.json").exists());
+
+        let reloaded = InMemoryCache::new(cache_dir);
+        reloaded.load().await.unwrap();
+        for crate_name in ["a", "async", "foo.bar"] {
+            let content = reloaded.get(&key(crate_name)).await.unwrap();
+            assert_eq!(content.content, format!("{} docs", crate_name));
+        }
+        assert_eq!(reloaded.entries().count().await, 3);
+    }
+
+    #[test]
+    fn test_per_crate_layout_cache_files() {
+        assert!(PerCrateLayout.is_cache_file("serde.json"));
+        assert!(!PerCrateLayout.is_cache_file("serde.json.tmp"));
+        assert!(!PerCrateLayout.is_cache_file("notes.txt"));
+        assert!(!PerCrateLayout.is_cache_file(".stats"));
+    }
+}
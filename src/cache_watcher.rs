@@ -0,0 +1,131 @@
+//! Watching of the cache directory for changes made by other processes.
+//!
+//! When several server instances share one cache directory, crate files
+//! written by one instance are normally only seen by the others after a
+//! restart. [`CacheWatcher`] observes the directory and reloads the entries
+//! of a cache file into the in-memory cache whenever the file changes.
+//!
+//! Writers usually produce several events per file (create, write, close),
+//! so events are debounced: a file is reloaded once no further change to
+//! any cache file has been seen for the debounce interval.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::cache::InMemoryCache;
+
+/// Default quiet period after the last change before files are reloaded.
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Keeps the cache directory watched for as long as it is alive.
+pub struct CacheWatcher {
+    /// Underlying file system watcher; dropping it stops event delivery
+    _watcher: RecommendedWatcher,
+    /// Task applying debounced reloads
+    task: JoinHandle<()>,
+}
+
+impl Drop for CacheWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Starts watching the cache directory of `cache`.
+///
+/// The directory is created if it does not exist yet, since it can only be
+/// watched once present.
+///
+/// # Arguments
+///
+/// * `cache` - Cache whose directory is watched and which receives reloads
+/// * `debounce` - Quiet period after the last change before reloading
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be created or watched.
+pub fn watch_cache_dir(cache: Arc<InMemoryCache>, debounce: Duration) -> notify::Result<CacheWatcher> {
+    let dir = cache.cache_dir().to_path_buf();
+    std::fs::create_dir_all(&dir)?;
+    let layout = cache.layout().clone();
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        match event {
+            Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                let file_names = event.paths.iter().filter_map(|p| p.file_name()?.to_str());
+                for file_name in file_names.filter(|name| layout.is_cache_file(name)) {
+                    // The receiver only goes away when the watcher is dropped
+                    let _ = tx.send(file_name.to_string());
+                }
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Cache directory watch error: {}", e),
+        }
+    })?;
+    watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+    tracing::info!("Watching cache directory {:?} for external updates.", dir);
+
+    let task = tokio::spawn(reload_debounced(cache, rx, debounce));
+    Ok(CacheWatcher { _watcher: watcher, task })
+}
+
+/// Collects changed cache files until the debounce interval passes without
+/// new events, then reloads each of them once.
+async fn reload_debounced(cache: Arc<InMemoryCache>, mut rx: mpsc::UnboundedReceiver<String>, debounce: Duration) {
+    while let Some(first) = rx.recv().await {
+        let mut pending = HashSet::from([first]);
+        loop {
+            match tokio::time::timeout(debounce, rx.recv()).await {
+                Ok(Some(file_name)) => {
+                    pending.insert(file_name);
+                }
+                Ok(None) => break,
+                Err(_elapsed) => break,
+            }
+        }
+        for file_name in pending {
+            cache.reload_file(&file_name).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::Cache;
+    use crate::cache_key::CacheKey;
+    use crate::docs_parser::{DocContent, DocsRsParams};
+    use tempfile::tempdir;
+    use tokio::time::Instant;
+
+    #[tokio::test]
+    async fn test_external_crate_file_is_picked_up() {
+        let dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(dir.path().to_path_buf()));
+        let _watcher = watch_cache_dir(cache.clone(), Duration::from_millis(50)).unwrap();
+
+        // Another instance saves a new crate file into the shared directory
+        let params = DocsRsParams {
+            crate_name: "shared".to_string(),
+            version: "1.0.0".to_string(),
+            path: "shared/index.html".to_string(),
+        };
+        let content = DocContent { content: "shared docs".to_string(), source_url: None };
+        let other = InMemoryCache::new(dir.path().to_path_buf());
+        other.insert(CacheKey::from(&params), content.clone()).await;
+        other.save().await.unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !cache.contains_key(&CacheKey::from(&params)).await {
+            assert!(Instant::now() < deadline, "watcher did not pick up the new crate file");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(cache.get(&CacheKey::from(&params)).await, Some(content));
+    }
+}
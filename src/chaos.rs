@@ -0,0 +1,93 @@
+//! Failure injection for chaos testing.
+//!
+//! With the `chaos` cargo feature, `--chaos-rate` makes a share of
+//! documentation fetches fail on purpose, so that clients and the
+//! retry and circuit breaker logic can be exercised without an actual
+//! docs.rs outage. The feature is meant for test builds only and cannot be
+//! combined with release builds.
+
+#[cfg(not(debug_assertions))]
+compile_error!("the `chaos` feature injects failures and must not be enabled in release builds");
+
+use std::time::Duration;
+
+use clap::ValueEnum;
+
+use crate::circuit_breaker::random_unit;
+
+/// Time an injected timeout takes to fail, imitating a stalled request.
+pub const INJECTED_TIMEOUT_DELAY: Duration = Duration::from_secs(1);
+
+/// Kind of failure injected into a fetch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Fault {
+    /// The request times out
+    Timeout,
+    /// The server answers with a 503
+    ServerError,
+    /// The response body is cut off halfway
+    TruncatedBody,
+}
+
+/// Decides which fetches fail and how.
+#[derive(Debug, Clone)]
+pub struct Chaos {
+    /// Share of fetches that fail, between 0 and 1
+    rate: f64,
+    /// Faults to choose from
+    faults: Vec<Fault>,
+}
+
+impl Chaos {
+    /// Creates a failure injector.
+    ///
+    /// # Arguments
+    /// * `rate` - Share of fetches that fail, clamped to `0.0..=1.0`
+    /// * `faults` - Faults to choose from uniformly; all kinds if empty
+    pub fn new(rate: f64, faults: Vec<Fault>) -> Self {
+        let faults = if faults.is_empty() { Fault::value_variants().to_vec() } else { faults };
+        Self { rate: rate.clamp(0.0, 1.0), faults }
+    }
+
+    /// Returns the fault to inject into the next fetch, if any.
+    pub fn roll(&self) -> Option<Fault> {
+        if random_unit() >= self.rate {
+            return None;
+        }
+        let index = (random_unit() * self.faults.len() as f64) as usize;
+        let fault = self.faults[index.min(self.faults.len() - 1)];
+        tracing::warn!("Chaos: injecting {:?}", fault);
+        Some(fault)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docs_parser::{DocsFetchError, DocsRsClient, DocsRsParams};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_full_rate_always_injects_fault() {
+        let mut server = mockito::Server::new_async().await;
+        let upstream = server.mock("GET", mockito::Matcher::Any).expect(0).create();
+
+        let chaos = Arc::new(Chaos::new(1.0, vec![Fault::ServerError]));
+        let client = DocsRsClient::new_with_base_url(&server.url()).with_chaos(Some(chaos));
+        for _ in 0..20 {
+            let result = client.fetch_docs(DocsRsParams {
+                crate_name: "demo".to_string(),
+                version: "1.0.0".to_string(),
+                path: "demo/index.html".to_string(),
+            }).await;
+            assert!(matches!(result, Err(DocsFetchError::ServiceUnavailable)));
+        }
+        upstream.assert();
+    }
+
+    #[test]
+    fn test_zero_rate_never_injects() {
+        let chaos = Chaos::new(0.0, Vec::new());
+        assert!((0..1000).all(|_| chaos.roll().is_none()));
+    }
+}
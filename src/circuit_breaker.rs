@@ -0,0 +1,253 @@
+//! Circuit breaker guarding requests to the documentation server.
+//!
+//! After a number of consecutive transient failures (see
+//! [`DocsFetchError::is_retryable`]) the breaker opens and requests are
+//! rejected without contacting the server until a cooldown has passed. The
+//! breaker is then half-open: the first request is let through as a trial
+//! while the others are still rejected, and success closes the breaker,
+//! another failure opens it again. A trial that reports neither within
+//! another cooldown is given up, letting the next request try.
+//!
+//! The open period can be randomized with [`RetryJitter`], so that many
+//! servers that saw the same outage do not all retry at the same moment once
+//! the documentation server recovers.
+//!
+//! [`DocsFetchError::is_retryable`]: crate::docs_parser::DocsFetchError::is_retryable
+
+use std::hash::{BuildHasher, RandomState};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+
+/// Consecutive failures after which the breaker opens by default.
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+
+/// Time the breaker stays open by default before allowing a trial request.
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Upper bound of decorrelated jitter open periods, as a multiple of the
+/// cooldown.
+const DECORRELATED_CAP_FACTOR: u32 = 8;
+
+/// Randomization of the time the breaker stays open.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum RetryJitter {
+    /// Always stay open for exactly the cooldown
+    #[default]
+    None,
+    /// Stay open for a random time between zero and the cooldown
+    Full,
+    /// Stay open for a random time between the cooldown and three times the
+    /// previous open period, capped at eight times the cooldown
+    Decorrelated,
+}
+
+/// Mutable state of a [`CircuitBreaker`].
+#[derive(Debug, Default)]
+struct BreakerState {
+    /// Number of transient failures since the last success
+    consecutive_failures: u32,
+    /// End of the current open period, `None` while closed
+    open_until: Option<Instant>,
+    /// Length of the current or last open period since the last success
+    open_period: Option<Duration>,
+    /// Start of the trial request of the half-open breaker
+    trial_started: Option<Instant>,
+}
+
+/// Tracks upstream health and decides whether requests may be sent.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    /// Consecutive failures after which the breaker opens
+    failure_threshold: u32,
+    /// Time the breaker stays open
+    cooldown: Duration,
+    /// Randomization of the open period
+    jitter: RetryJitter,
+    state: Mutex<BreakerState>,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(DEFAULT_FAILURE_THRESHOLD, DEFAULT_COOLDOWN)
+    }
+}
+
+impl CircuitBreaker {
+    /// Creates a closed breaker.
+    ///
+    /// # Arguments
+    /// * `failure_threshold` - Consecutive failures after which the breaker opens, at least 1
+    /// * `cooldown` - Time the breaker stays open before allowing a trial request
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            jitter: RetryJitter::None,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    /// Sets how the time the breaker stays open is randomized.
+    pub fn with_jitter(mut self, jitter: RetryJitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Returns the length of the next open period, given the previous one
+    /// since the last success.
+    fn next_open_period(&self, previous: Option<Duration>) -> Duration {
+        match self.jitter {
+            RetryJitter::None => self.cooldown,
+            RetryJitter::Full => self.cooldown.mul_f64(random_unit()),
+            RetryJitter::Decorrelated => {
+                let upper = previous.unwrap_or(self.cooldown).saturating_mul(3);
+                let period = self.cooldown + upper.saturating_sub(self.cooldown).mul_f64(random_unit());
+                period.min(self.cooldown.saturating_mul(DECORRELATED_CAP_FACTOR))
+            }
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, BreakerState> {
+        match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Returns whether the breaker is open, i.e. requests are being rejected.
+    pub fn is_open(&self) -> bool {
+        self.state().open_until.is_some_and(|until| Instant::now() < until)
+    }
+
+    /// Returns whether a request may be sent to the server.
+    ///
+    /// Once the open period has passed, only one caller at a time is allowed
+    /// the trial request; the caller must report its outcome with
+    /// [`Self::record_success`] or [`Self::record_failure`].
+    pub fn allow_request(&self) -> bool {
+        let mut state = self.state();
+        let now = Instant::now();
+        match state.open_until {
+            None => true,
+            Some(until) if now < until => false,
+            Some(_) if state.trial_started.is_some_and(|started| now.duration_since(started) < self.cooldown) => false,
+            Some(_) => {
+                state.trial_started = Some(now);
+                true
+            }
+        }
+    }
+
+    /// Records a request the server answered, closing the breaker.
+    pub fn record_success(&self) {
+        let mut state = self.state();
+        if state.open_until.is_some() {
+            tracing::info!("Documentation server recovered, closing circuit breaker.");
+        }
+        *state = BreakerState::default();
+    }
+
+    /// Records a transient failure, opening the breaker once the threshold
+    /// of consecutive failures is reached.
+    ///
+    /// Without jitter every failure restarts the open period. With jitter a
+    /// new random period is only drawn when the breaker opens or a trial
+    /// request fails, so late failures of requests sent before it opened
+    /// do not redraw it.
+    pub fn record_failure(&self) {
+        let mut state = self.state();
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+        if state.consecutive_failures < self.failure_threshold {
+            return;
+        }
+        let now = Instant::now();
+        let open = state.open_until.is_some_and(|until| now < until);
+        if open && self.jitter != RetryJitter::None {
+            return;
+        }
+        let period = self.next_open_period(state.open_period);
+        if state.open_until.is_none() {
+            tracing::warn!(
+                "Documentation server failed {} times in a row, opening circuit breaker for {:?}.",
+                state.consecutive_failures,
+                period
+            );
+        }
+        state.open_period = Some(period);
+        state.open_until = Some(now + period);
+        state.trial_started = None;
+    }
+}
+
+/// Returns a pseudo-random number in `0.0..1.0`.
+pub(crate) fn random_unit() -> f64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let bits = RandomState::new().hash_one(COUNTER.fetch_add(1, Ordering::Relaxed));
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opens_after_threshold_and_recovers() {
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(50));
+        breaker.record_failure();
+        assert!(breaker.allow_request());
+        breaker.record_failure();
+        assert!(breaker.is_open());
+        assert!(!breaker.allow_request());
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(breaker.allow_request(), "trial request allowed after cooldown");
+        assert!(!breaker.allow_request(), "only one trial request at a time");
+        breaker.record_failure();
+        assert!(breaker.is_open(), "failed trial reopens the breaker");
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(breaker.allow_request());
+        breaker.record_success();
+        assert!(breaker.allow_request() && breaker.allow_request(), "successful trial closes the breaker");
+        breaker.record_failure();
+        assert!(breaker.allow_request(), "success resets the failure count");
+    }
+
+    #[test]
+    fn test_jitter_varies_open_periods() {
+        let cooldown = Duration::from_secs(30);
+        let plain = CircuitBreaker::new(1, cooldown);
+        assert!((0..20).all(|_| plain.next_open_period(Some(cooldown)) == cooldown));
+
+        let full = CircuitBreaker::new(1, cooldown).with_jitter(RetryJitter::Full);
+        let periods: Vec<Duration> = (0..20).map(|_| full.next_open_period(None)).collect();
+        assert!(periods.iter().all(|period| *period <= cooldown));
+        assert!(periods.windows(2).any(|pair| pair[0] != pair[1]), "got {:?}", periods);
+
+        let decorrelated = CircuitBreaker::new(1, cooldown).with_jitter(RetryJitter::Decorrelated);
+        let mut previous = None;
+        let mut periods = Vec::new();
+        for _ in 0..20 {
+            let period = decorrelated.next_open_period(previous);
+            assert!(period >= cooldown && period <= cooldown * DECORRELATED_CAP_FACTOR, "got {:?}", period);
+            periods.push(period);
+            previous = Some(period);
+        }
+        assert!(periods.windows(2).any(|pair| pair[0] != pair[1]), "got {:?}", periods);
+    }
+
+    #[test]
+    fn test_unreported_trial_is_given_up_after_cooldown() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(50));
+        breaker.record_failure();
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(breaker.allow_request());
+        assert!(!breaker.allow_request());
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(breaker.allow_request(), "a new trial replaces one that never reported");
+    }
+}
@@ -0,0 +1,115 @@
+//! Optional configuration file for curated deployments.
+//!
+//! Settings that are too detailed for command line flags are read from a
+//! JSON file passed with `--config`. Every field is optional:
+//!
+//! ```json
+//! {
+//!     "default_paths": {
+//!         "tokio": "tokio/prelude/index.html"
+//!     },
+//!     "boilerplate": {
+//!         "selectors": ["script", "style", "#help", "#settings"],
+//!         "trailing_text": ["Generated by our docs mirror"]
+//!     },
+//!     "denied_crates": ["internal-secrets"]
+//! }
+//! ```
+//!
+//! The file can be read again while the server runs with the
+//! `reload_config` tool.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::docs_parser::Boilerplate;
+
+/// Contents of the configuration file.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileConfig {
+    /// Page fetched when a request for a crate does not specify a path,
+    /// keyed by crate name
+    pub default_paths: HashMap<String, String>,
+    /// Page parts left out of extracted documentation text
+    pub boilerplate: Boilerplate,
+    /// Crates whose documentation is never fetched or served
+    pub denied_crates: Vec<String>,
+}
+
+impl FileConfig {
+    /// Reads and parses the configuration file at `path`.
+    ///
+    /// Crate names used as keys or in the deny list are lowercased, matching
+    /// how requested crate names are normalized.
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error if the file cannot be read or is not valid,
+    /// including boilerplate selectors that are not valid CSS.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let mut config: Self = serde_json::from_str(&content)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let invalid = config.boilerplate.invalid_selectors();
+        if !invalid.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid boilerplate selectors: {}", invalid.join(", ")),
+            ));
+        }
+        config.default_paths = config
+            .default_paths
+            .into_iter()
+            .map(|(crate_name, path)| (crate_name.trim().to_lowercase(), path))
+            .collect();
+        for crate_name in &mut config.denied_crates {
+            *crate_name = crate_name.trim().to_lowercase();
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_load_normalizes_crate_names() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        std::fs::write(&path, r#"{"default_paths": {"Tokio": "tokio/prelude/index.html"}, "denied_crates": [" Secret-Crate "]}"#).unwrap();
+
+        let config = FileConfig::load(&path).unwrap();
+        assert_eq!(config.default_paths.get("tokio").map(String::as_str), Some("tokio/prelude/index.html"));
+        assert_eq!(config.denied_crates, vec!["secret-crate"]);
+    }
+
+    #[test]
+    fn test_load_boilerplate_defaults_and_validation() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        std::fs::write(&path, r#"{"boilerplate": {"trailing_text": ["Mirror footer"]}}"#).unwrap();
+
+        let config = FileConfig::load(&path).unwrap();
+        assert_eq!(config.boilerplate.selectors, Boilerplate::default().selectors);
+        assert_eq!(config.boilerplate.trailing_text, vec!["Mirror footer"]);
+
+        std::fs::write(&path, r#"{"boilerplate": {"selectors": ["div[["]}}"#).unwrap();
+        assert_eq!(FileConfig::load(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_load_rejects_unknown_fields() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        std::fs::write(&path, r#"{"default_path": {}}"#).unwrap();
+
+        let err = FileConfig::load(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
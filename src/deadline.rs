@@ -0,0 +1,95 @@
+//! Overall time budgets of operations made of several fetches.
+//!
+//! A [`Deadline`] is computed once when a batch or bundle starts and passed
+//! to each of its fetches, which then only wait for the time that remains of
+//! the whole operation instead of getting a full timeout each. Fetches
+//! started once the budget is nearly used up fail right away with
+//! [`DocsFetchError::Timeout`].
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use crate::docs_parser::DocsFetchError;
+
+/// Remaining time below which a fetch is not started at all, as it could not
+/// complete anyway.
+pub const MIN_FETCH_BUDGET: Duration = Duration::from_millis(20);
+
+/// Point in time by which an operation must have completed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Deadline {
+    /// End of the budget, `None` for an unlimited one
+    at: Option<Instant>,
+}
+
+impl Deadline {
+    /// Returns a deadline `budget` from now, or one that never expires if
+    /// `budget` is `None`.
+    pub fn after(budget: Option<Duration>) -> Self {
+        Self { at: budget.map(|budget| Instant::now() + budget) }
+    }
+
+    /// Time left until the deadline, `None` if it never expires.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.at.map(|at| at.saturating_duration_since(Instant::now()))
+    }
+
+    /// Whether too little time is left to start another fetch.
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining().is_some_and(|remaining| remaining < MIN_FETCH_BUDGET)
+    }
+
+    /// Runs `fetch` within the remaining time, failing with
+    /// [`DocsFetchError::Timeout`] if the deadline passes first or has
+    /// nearly passed already.
+    pub async fn run<T, F>(&self, fetch: F) -> Result<T, DocsFetchError>
+    where
+        F: Future<Output = Result<T, DocsFetchError>>,
+    {
+        if self.is_exhausted() {
+            return Err(DocsFetchError::Timeout);
+        }
+        match self.remaining() {
+            Some(remaining) => tokio::time::timeout(remaining, fetch)
+                .await
+                .unwrap_or(Err(DocsFetchError::Timeout)),
+            None => fetch.await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_respects_remaining_time() {
+        let deadline = Deadline::after(Some(Duration::from_millis(100)));
+        let fast = deadline.run(async { Ok::<_, DocsFetchError>(1) }).await;
+        assert_eq!(fast.unwrap(), 1);
+
+        let slow = deadline.run(async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok::<_, DocsFetchError>(2)
+        }).await;
+        assert!(matches!(slow, Err(DocsFetchError::Timeout)));
+
+        // The budget is used up, so nothing else is started
+        assert!(deadline.is_exhausted());
+        let started = std::sync::atomic::AtomicBool::new(false);
+        let skipped = deadline.run(async {
+            started.store(true, std::sync::atomic::Ordering::SeqCst);
+            Ok::<_, DocsFetchError>(3)
+        }).await;
+        assert!(matches!(skipped, Err(DocsFetchError::Timeout)));
+        assert!(!started.load(std::sync::atomic::Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_unlimited_deadline() {
+        let deadline = Deadline::after(None);
+        assert_eq!(deadline.remaining(), None);
+        assert!(!deadline.is_exhausted());
+        assert_eq!(deadline.run(async { Ok::<_, DocsFetchError>(1) }).await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,229 @@
+//! Sources documentation pages are fetched from.
+//!
+//! Pages missing from the cache are fetched from docs.rs by default. In
+//! environments without network access, the output of `cargo doc` can be
+//! served instead by a [`LocalDocSource`] reading the pages from its
+//! `target/doc` directory. Both extract the documentation the same way.
+//! Tools relying on other parts of docs.rs, such as build logs, the source
+//! browser or search indexes, fail with
+//! [`DocsFetchError::UnsupportedLocally`] with a local source.
+
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use clap::ValueEnum;
+
+use crate::docs_parser::{DocContent, DocsFetchError, DocsRsClient, DocsRsParams, OutputFormat};
+
+/// Documentation sources selectable on the command line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum DocSourceKind {
+    /// Pages fetched from the documentation server
+    #[default]
+    DocsRs,
+    /// Pages read from a local `cargo doc` output directory
+    Local,
+}
+
+/// Source of the documentation pages missing from the cache.
+#[async_trait]
+pub trait DocSource: Send + Sync {
+    /// Fetches the page at `params` and extracts its documentation in
+    /// `format`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::DocsNotFound`] if the source has no such
+    /// page, or the error that prevented reading it.
+    async fn fetch(&self, params: DocsRsParams, format: OutputFormat) -> Result<DocContent, DocsFetchError>;
+
+    /// Fetches a page like [`Self::fetch`], also returning the path of the
+    /// page the documentation was extracted from if the source led from
+    /// `params` to another page. Sources without redirects return `None`.
+    async fn fetch_following_redirects(
+        &self,
+        params: DocsRsParams,
+        format: OutputFormat,
+    ) -> Result<(DocContent, Option<String>), DocsFetchError> {
+        Ok((self.fetch(params, format).await?, None))
+    }
+
+    /// Checks whether the page at `params` exists, without extracting it.
+    async fn page_exists(&self, params: &DocsRsParams) -> Result<bool, DocsFetchError>;
+
+    /// Returns the raw HTML of the page at `params`, for tools parsing
+    /// parts of the page other than its documentation.
+    async fn fetch_html(&self, params: &DocsRsParams) -> Result<String, DocsFetchError>;
+}
+
+#[async_trait]
+impl DocSource for DocsRsClient {
+    async fn fetch(&self, params: DocsRsParams, format: OutputFormat) -> Result<DocContent, DocsFetchError> {
+        self.fetch_docs_as(params, format).await
+    }
+
+    async fn fetch_following_redirects(
+        &self,
+        params: DocsRsParams,
+        format: OutputFormat,
+    ) -> Result<(DocContent, Option<String>), DocsFetchError> {
+        self.fetch_docs_following_redirects(params, format).await
+    }
+
+    async fn page_exists(&self, params: &DocsRsParams) -> Result<bool, DocsFetchError> {
+        DocsRsClient::page_exists(self, params).await
+    }
+
+    async fn fetch_html(&self, params: &DocsRsParams) -> Result<String, DocsFetchError> {
+        DocsRsClient::fetch_html(self, params).await
+    }
+}
+
+/// Documentation read from the output directory of `cargo doc`.
+///
+/// `cargo doc` writes the pages of each crate below a directory named after
+/// the crate, as docs.rs serves them below the crate's version, so paths
+/// are resolved against the root directory as is. The output holds a
+/// single version of each crate; the requested version is ignored.
+pub struct LocalDocSource {
+    /// The `target/doc` directory
+    root: PathBuf,
+    /// Client whose extraction settings are applied to the pages read
+    extractor: Arc<DocsRsClient>,
+}
+
+impl LocalDocSource {
+    /// Creates a source reading the pages below `root`, extracted with the
+    /// default settings.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            extractor: Arc::new(DocsRsClient::new()),
+        }
+    }
+
+    /// Sets the client whose boilerplate and whitespace settings are used to
+    /// extract the documentation of pages.
+    pub fn with_extractor(mut self, extractor: Arc<DocsRsClient>) -> Self {
+        self.extractor = extractor;
+        self
+    }
+
+    /// Returns the file of the page at `params`, or `None` if its path
+    /// leaves the root directory.
+    ///
+    /// As on docs.rs, an empty path is the crate's root page, and a path
+    /// naming a directory its index page.
+    fn page_file(&self, params: &DocsRsParams) -> Option<PathBuf> {
+        let path = params.path.trim().trim_start_matches('/');
+        let path = match path {
+            "" => format!("{}/index.html", params.crate_name.replace('-', "_")),
+            path if path.ends_with('/') => format!("{}index.html", path),
+            path => path.to_string(),
+        };
+        let path = Path::new(&path);
+        if !path.components().all(|component| matches!(component, Component::Normal(_))) {
+            return None;
+        }
+        Some(self.root.join(path))
+    }
+}
+
+/// Converts the error of reading a page file.
+fn read_error(e: io::Error) -> DocsFetchError {
+    match e.kind() {
+        io::ErrorKind::NotFound => DocsFetchError::DocsNotFound,
+        _ => DocsFetchError::Internal(format!("Failed to read documentation page: {}", e)),
+    }
+}
+
+#[async_trait]
+impl DocSource for LocalDocSource {
+    async fn fetch(&self, params: DocsRsParams, format: OutputFormat) -> Result<DocContent, DocsFetchError> {
+        let file = self.page_file(&params).ok_or(DocsFetchError::DocsNotFound)?;
+        let html = tokio::fs::read_to_string(&file).await.map_err(read_error)?;
+        let url = reqwest::Url::from_file_path(&file)
+            .map(String::from)
+            .unwrap_or_else(|()| file.display().to_string());
+        let content = self.extractor.extract_page(&html, &url, &params, format)?;
+        Ok(DocContent { content, source_url: Some(url) })
+    }
+
+    async fn page_exists(&self, params: &DocsRsParams) -> Result<bool, DocsFetchError> {
+        match self.page_file(params) {
+            Some(file) => Ok(tokio::fs::try_exists(&file).await.map_err(read_error)?),
+            None => Ok(false),
+        }
+    }
+
+    async fn fetch_html(&self, params: &DocsRsParams) -> Result<String, DocsFetchError> {
+        let file = self.page_file(params).ok_or(DocsFetchError::DocsNotFound)?;
+        tokio::fs::read_to_string(&file).await.map_err(read_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn params(path: &str) -> DocsRsParams {
+        DocsRsParams {
+            crate_name: "demo-lib".to_string(),
+            version: "0.1.0".to_string(),
+            path: path.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_local_source_reads_cargo_doc_output() {
+        let dir = tempdir().unwrap();
+        let sync = dir.path().join("demo_lib/sync");
+        std::fs::create_dir_all(&sync).unwrap();
+        // cargo doc output has no docs.rs wrapper around the main content
+        std::fs::write(
+            sync.join("struct.Barrier.html"),
+            r##"<!DOCTYPE html><html><head><title>Barrier in demo_lib::sync - Rust</title></head>
+            <body class="rustdoc struct"><nav class="sidebar"><a href="#method.wait">sidebar_wait_link</a></nav>
+            <main><section id="main-content" class="content"><h1>Struct <span class="struct">Barrier</span></h1>
+            <div class="docblock"><p>A barrier enables multiple threads to synchronize the beginning of some computation.</p></div>
+            </section></main></body></html>"##,
+        ).unwrap();
+        std::fs::write(
+            dir.path().join("demo_lib/index.html"),
+            r#"<html><body><section id="main-content"><h1>Crate demo_lib</h1><p>Demo library.</p></section></body></html>"#,
+        ).unwrap();
+        let source = LocalDocSource::new(dir.path());
+
+        let doc = source.fetch(params("demo_lib/sync/struct.Barrier.html"), OutputFormat::PlainText).await.unwrap();
+        assert!(doc.content.contains("A barrier enables multiple threads"), "{}", doc.content);
+        assert!(!doc.content.contains("sidebar_wait_link"), "{}", doc.content);
+        let source_url = doc.source_url.unwrap();
+        assert!(source_url.starts_with("file://"), "{}", source_url);
+        assert!(source_url.ends_with("demo_lib/sync/struct.Barrier.html"), "{}", source_url);
+
+        let markdown = source.fetch(params("demo_lib/sync/struct.Barrier.html"), OutputFormat::Markdown).await.unwrap();
+        assert!(markdown.content.starts_with("# Struct Barrier"), "{}", markdown.content);
+        for root in ["", "demo_lib/"] {
+            let index = source.fetch(params(root), OutputFormat::PlainText).await.unwrap();
+            assert!(index.content.contains("Demo library."), "{:?}: {}", root, index.content);
+        }
+
+        let missing = source.fetch(params("demo_lib/struct.Missing.html"), OutputFormat::PlainText).await;
+        assert!(matches!(missing, Err(DocsFetchError::DocsNotFound)), "got {:?}", missing);
+        assert!(!source.page_exists(&params("demo_lib/struct.Missing.html")).await.unwrap());
+        assert!(source.page_exists(&params("demo_lib/sync/struct.Barrier.html")).await.unwrap());
+        let html = source.fetch_html(&params("demo_lib/index.html")).await.unwrap();
+        assert!(html.contains("<h1>Crate demo_lib</h1>"), "{}", html);
+
+        // Paths cannot leave the output directory
+        std::fs::write(dir.path().join("secret.html"), "<main>secret</main>").unwrap();
+        for path in ["demo_lib/../secret.html", "../secret.html"] {
+            let escaped = source.fetch(params(path), OutputFormat::PlainText).await;
+            assert!(matches!(escaped, Err(DocsFetchError::DocsNotFound)), "{}: got {:?}", path, escaped);
+            assert!(matches!(source.fetch_html(&params(path)).await, Err(DocsFetchError::DocsNotFound)), "{}", path);
+        }
+    }
+}
@@ -7,32 +7,99 @@
 //! - Content parsing and extraction utilities
 //! - Error handling specific to documentation fetching
 
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::ValueEnum;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use rmcp::schemars;
 
+use crate::search_index::{self, SearchIndex};
+
 /// Errors that can occur when fetching and parsing documentation.
-#[derive(Debug, Error)]
+///
+/// Errors are cheap to clone so that one failed request can be reported to
+/// every caller waiting on it.
+#[derive(Debug, Clone, Error)]
 pub enum DocsFetchError {
     /// Error occurred during HTTP request
     #[error("Request error: {0}")]
-    RequestError(#[from] reqwest::Error),
+    RequestError(Arc<reqwest::Error>),
     
     /// Error parsing or constructing URLs
     #[error("Invalid URL: {0}")]
     UrlError(#[from] url::ParseError),
     
+    /// The request to the documentation server timed out
+    #[error("Request timed out")]
+    Timeout,
+
     /// Documentation was not found at the specified location
     #[error("Failed to find documentation")]
     DocsNotFound,
+
+    /// The documentation server is temporarily unavailable, e.g. during
+    /// docs.rs maintenance
+    #[error("Documentation server is temporarily unavailable, try again later")]
+    ServiceUnavailable,
+
+    /// The documentation server failed to answer with a server error status
+    /// other than 503
+    #[error("Documentation server failed with status {0}, try again later")]
+    UpstreamError(u16),
     
     /// Error occurred while parsing documentation content
-    #[allow(dead_code)]
     #[error("Failed to parse documentation: {0}")]
     ParseError(String),
+
+    /// The fetch failed unexpectedly inside the server, e.g. by panicking
+    #[error("Internal error: {0}")]
+    Internal(String),
+
+    /// The crate is on the server's deny list
+    #[error("Documentation of crate '{0}' is not served here")]
+    Denied(String),
+
+    /// The crate name is not accepted by the configured [`CrateNamePolicy`]
+    #[error("'{0}' is not a valid crate name")]
+    InvalidCrateName(String),
+
+    /// The request needs docs.rs, but pages are read from a local `cargo
+    /// doc` output
+    #[error("{0} is not supported with a local documentation source")]
+    UnsupportedLocally(String),
+}
+
+impl From<reqwest::Error> for DocsFetchError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            return Self::Timeout;
+        }
+        Self::RequestError(Arc::new(e))
+    }
+}
+
+impl DocsFetchError {
+    /// Returns whether the failed operation may succeed when retried later.
+    ///
+    /// Missing documentation and unparseable content are permanent, while
+    /// server unavailability and transport failures are transient.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::ServiceUnavailable | Self::UpstreamError(_) | Self::Timeout => true,
+            Self::RequestError(e) => e.is_connect(),
+            Self::UrlError(_) | Self::DocsNotFound | Self::ParseError(_) | Self::Internal(_) | Self::Denied(_) | Self::InvalidCrateName(_) | Self::UnsupportedLocally(_) => false,
+        }
+    }
 }
 
+/// Phrases identifying the docs.rs maintenance page, which may be served
+/// with a status other than 503.
+const MAINTENANCE_SIGNATURES: [&str; 2] = ["down for maintenance", "scheduled maintenance"];
+
 /// Parameters for specifying which documentation to fetch from docs.rs.
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema, Eq, PartialEq, Hash)]
 pub struct DocsRsParams {
@@ -51,28 +118,829 @@ pub struct DocsRsParams {
     pub path: String,
 }
 
+/// Format of the documentation content extracted from a page.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, schemars::JsonSchema, Eq, PartialEq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    /// The page's text, with its markup dropped
+    #[default]
+    PlainText,
+    /// The page converted to Markdown, keeping headings, code blocks, lists
+    /// and links
+    Markdown,
+    /// The page's signature, description, methods and examples as the JSON
+    /// object of a [`ParsedDoc`]
+    Json,
+}
+
+impl OutputFormat {
+    /// Name of the format in cache keys, `None` for plain text, whose keys
+    /// predate formats.
+    pub fn key_name(self) -> Option<&'static str> {
+        match self {
+            Self::PlainText => None,
+            Self::Markdown => Some("markdown"),
+            Self::Json => Some("json"),
+        }
+    }
+}
+
 /// Documentation content fetched from docs.rs.
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 pub struct DocContent {
-    /// The extracted documentation content as plain text
+    /// The extracted documentation content, as plain text unless another
+    /// [`OutputFormat`] was requested
+    pub content: String,
+    /// URL the content was fetched from, after following redirects; absent
+    /// in entries cached before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+}
+
+/// Registry information about a crate, as reported by crates.io.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct CrateMetadata {
+    /// Canonical name of the crate
+    pub name: String,
+    /// Highest non-prerelease version, absent if only prereleases exist
+    #[serde(default)]
+    pub max_stable_version: Option<String>,
+    /// Most recently published version
+    #[serde(default)]
+    pub newest_version: Option<String>,
+}
+
+impl CrateMetadata {
+    /// Returns the version docs.rs serves as `latest`: the highest stable
+    /// version, or the newest version if there is no stable release.
+    pub fn latest_version(&self) -> Option<&str> {
+        self.max_stable_version.as_deref().or(self.newest_version.as_deref())
+    }
+}
+
+/// A published version of a crate, as listed by crates.io.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct PublishedVersion {
+    /// Version number, e.g. `1.2.3`
+    pub num: String,
+    /// Whether the version was yanked
+    #[serde(default)]
+    pub yanked: bool,
+}
+
+/// A crates.io user who published versions of a crate.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Publisher {
+    /// crates.io login, e.g. `dtolnay`
+    pub login: String,
+    /// Display name, if the user set one
+    pub name: Option<String>,
+    /// Number of versions the user published
+    pub versions: usize,
+}
+
+/// Everything crates.io reports about a crate in one response of its crate
+/// endpoint, see [`DocsRsClient::fetch_full_metadata`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct CrateFullMetadata {
+    /// Canonical name of the crate
+    pub crate_name: String,
+    /// Description from the crate's manifest
+    pub description: Option<String>,
+    /// License expression of the latest version, e.g. `MIT OR Apache-2.0`
+    pub license: Option<String>,
+    /// Homepage URL
+    pub homepage: Option<String>,
+    /// Repository URL
+    pub repository: Option<String>,
+    /// Documentation URL, if the crate names one besides docs.rs
+    pub documentation: Option<String>,
+    /// Downloads of all versions
+    pub downloads: u64,
+    /// Downloads of the last 90 days
+    pub recent_downloads: Option<u64>,
+    /// Highest non-prerelease version, absent if only prereleases exist
+    pub max_stable_version: Option<String>,
+    /// Most recently published version
+    pub newest_version: Option<String>,
+    /// Category slugs, e.g. `network-programming`
+    pub categories: Vec<String>,
+    /// Free-form keywords, e.g. `async`
+    pub keywords: Vec<String>,
+    /// Published versions, newest first
+    pub versions: Vec<PublishedVersion>,
+    /// Users who published the versions, those with the most versions
+    /// first; a summary of the crate's owners
+    pub publishers: Vec<Publisher>,
+}
+
+impl CrateFullMetadata {
+    /// Returns the categories and keywords of the crate.
+    pub fn tags(&self) -> CrateTags {
+        CrateTags {
+            crate_name: self.crate_name.clone(),
+            categories: self.categories.clone(),
+            keywords: self.keywords.clone(),
+        }
+    }
+}
+
+/// What crates.io reports about one version of a crate, see
+/// [`DocsRsClient::fetch_crate_metadata`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct CrateVersionMetadata {
+    /// Canonical name of the crate
+    pub crate_name: String,
+    /// The version described
+    pub version: String,
+    /// Description from the crate's manifest
+    pub description: Option<String>,
+    /// License expression of the version, e.g. `MIT OR Apache-2.0`
+    pub license: Option<String>,
+    /// Homepage URL
+    pub homepage: Option<String>,
+    /// Repository URL
+    pub repository: Option<String>,
+    /// Downloads of all versions
+    pub downloads: u64,
+    /// The `[features]` table of the version, mapping each feature to the
+    /// entries it enables
+    pub features: BTreeMap<String, Vec<String>>,
+}
+
+/// Maximum number of characters of a failed build's errors returned as
+/// excerpt; the end of the errors is kept, as it usually names the cause.
+const BUILD_LOG_EXCERPT_CHARS: usize = 2000;
+
+/// State of a documentation build on docs.rs.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BuildState {
+    /// The documentation was built
+    Success,
+    /// The build failed, so there is no documentation
+    Failed,
+    /// The build is queued or running
+    InProgress,
+}
+
+/// Outcome of the latest docs.rs build of a crate version.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct BuildStatus {
+    /// Name of the crate
+    pub crate_name: String,
+    /// Version of the crate
+    pub version: String,
+    /// State of the latest build
+    pub status: BuildState,
+    /// Identifier of the build on docs.rs, `None` while it is queued
+    pub build_id: Option<u64>,
+    /// Compiler the documentation was built with
+    pub rustc_version: Option<String>,
+    /// When the build ran
+    pub build_time: Option<String>,
+    /// docs.rs page of the build, linking to its full logs
+    pub log_url: Option<String>,
+    /// End of the errors reported by a failed build
+    pub log_excerpt: Option<String>,
+}
+
+/// The categories and keywords a crate declares on crates.io.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct CrateTags {
+    /// Canonical name of the crate
+    pub crate_name: String,
+    /// Category slugs, e.g. `network-programming`
+    pub categories: Vec<String>,
+    /// Free-form keywords, e.g. `async`
+    pub keywords: Vec<String>,
+}
+
+/// A type implementing a trait, as listed on the trait's documentation page.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Implementor {
+    /// The full impl header, e.g. `impl<T: Clone> Clone for Vec<T>`
+    pub declaration: String,
+    /// The implementing type as written in the header, e.g. `Vec<T>`
+    pub type_name: String,
+    /// Fully qualified path of the implementing type, if it is a documented item
+    pub type_path: Option<String>,
+}
+
+/// The implementors listed on a trait's documentation page.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Implementors {
+    /// Implementors rendered into the page itself
+    pub implementors: Vec<Implementor>,
+    /// Script from which rustdoc loads implementors defined in other crates
+    /// at view time. These are not part of the page HTML and therefore not
+    /// included in `implementors`.
+    pub external_implementors_script: Option<String>,
+}
+
+/// An auto trait implementation listed on a type's page.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct AutoTraitImpl {
+    /// Name of the auto trait, e.g. `Send`
+    pub trait_name: String,
+    /// Whether this is a negative impl (`impl !Send for ...`)
+    pub negative: bool,
+    /// Whether the impl only applies under a `where` clause
+    pub conditional: bool,
+    /// The full impl header, e.g. `impl<T> Send for Arc<T> where T: Send + Sync`
+    pub declaration: String,
+}
+
+/// The "Auto Trait Implementations" section of a type's page.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct AutoTraits {
+    /// Auto trait impls in the order rustdoc lists them
+    pub impls: Vec<AutoTraitImpl>,
+}
+
+/// A blanket implementation listed on a type's page.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct BlanketImpl {
+    /// Implemented trait with its generic arguments, e.g. `Borrow<T>`
+    pub trait_name: String,
+    /// The full impl header, e.g. `impl<T> Borrow<T> for T where T: ?Sized`
+    pub declaration: String,
+}
+
+/// The "Blanket Implementations" section of a type's page.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct BlanketImpls {
+    /// Blanket impls in the order rustdoc lists them
+    pub impls: Vec<BlanketImpl>,
+}
+
+/// A method of an inherent impl block.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct InherentMethod {
+    /// Method name, e.g. `push`
+    pub name: String,
+    /// The method signature, e.g. `pub fn push(&mut self, value: T)`
+    pub signature: String,
+}
+
+/// An inherent `impl` block of a type and the methods it provides.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct InherentImpl {
+    /// The full impl header, e.g. `impl<T: Clone> Wrapper<T> where T: Send,`
+    pub declaration: String,
+    /// Generic parameters of the impl with their inline bounds, e.g. `T: Clone`
+    pub generics: Option<String>,
+    /// Predicates of the where clause, e.g. `T: Send`
+    pub where_clause: Option<String>,
+    /// Methods of the block in the order rustdoc lists them
+    pub methods: Vec<InherentMethod>,
+}
+
+/// The "Implementations" section of a type's page, one entry per `impl`
+/// block, so that methods only available under some bounds are told apart.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct InherentImpls {
+    /// Impl blocks in the order rustdoc lists them
+    pub impls: Vec<InherentImpl>,
+}
+
+/// A function that looks like a way to construct a type, see
+/// [`DocsRsClient::parse_construction_hints`].
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct ConstructionHint {
+    /// Function name, e.g. `new` or `from`
+    pub name: String,
+    /// The function signature, e.g. `pub fn new(capacity: usize) -> Self`
+    pub signature: String,
+    /// The implemented trait if the function belongs to a trait impl, e.g.
+    /// `From<&str>`, `None` for inherent functions
+    pub trait_name: Option<String>,
+}
+
+/// The candidate construction entry points of a type.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct ConstructionHints {
+    /// Documentation path of the type page
+    pub path: String,
+    /// Inherent functions in page order, then those of trait impls
+    pub hints: Vec<ConstructionHint>,
+}
+
+/// A type alias and the type it expands to.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct TypeAlias {
+    /// The alias declaration, e.g. `pub type Result<T> = Result<T, Error>;`
+    pub declaration: String,
+    /// The aliased type as written on the right-hand side, e.g. `Result<T, Error>`
+    pub aliased_type: String,
+    /// Fully qualified path of the aliased type, if it is a documented item
+    pub aliased_type_path: Option<String>,
+    /// Definition of the aliased type with the alias' generics substituted,
+    /// as shown in the "Aliased Type" section of recent rustdoc versions
+    pub aliased_definition: Option<String>,
+}
+
+/// One step of an item's navigation trail.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct BreadcrumbSegment {
+    /// Name of the crate, module or item
+    pub name: String,
+    /// Documentation path of its page, e.g. `tokio/sync/index.html`
+    pub path: String,
+}
+
+/// The navigation trail of an item page, from the crate root to the item.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Breadcrumb {
+    /// Documentation path of the page the trail was read from
+    pub path: String,
+    /// Segments in order, the crate first and the item itself last
+    pub segments: Vec<BreadcrumbSegment>,
+}
+
+/// The deprecation notice of an item page.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Deprecation {
+    /// Version the item was deprecated in, if stated
+    pub since: Option<String>,
+    /// Explanation given with the notice, e.g. "use `bar` instead"
+    pub note: Option<String>,
+    /// Documentation path of the item the notice links to as its
+    /// replacement, e.g. `demo/fn.bar.html`
+    pub replacement: Option<String>,
+}
+
+/// A link from the documentation of a page to another item of the same
+/// crate.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct DocLink {
+    /// Text of the link, e.g. `MutexGuard`
+    pub name: String,
+    /// Documentation path of the linked page, e.g. `tokio/sync/struct.MutexGuard.html`
+    pub path: String,
+}
+
+/// A named part of a documentation page, e.g. "Fields" or "Examples".
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct PageSection {
+    /// Heading of the section
+    pub name: String,
+    /// Text of the section; code blocks keep their line structure
     pub content: String,
 }
 
+/// A documentation page split into its sections.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct PageSections {
+    /// Documentation path of the page
+    pub path: String,
+    /// Sections in page order
+    pub sections: Vec<PageSection>,
+}
+
+/// The main parts of an item page, kept apart instead of flattened into
+/// one text, see [`DocsRsClient::parse_html_content`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(default)]
+pub struct ParsedDoc {
+    /// Declaration of the item, e.g. a function signature
+    pub signature: Option<String>,
+    /// The item's own documentation as markdown
+    pub description: Option<String>,
+    /// Headers of the item's inherent methods and associated items
+    pub methods: Vec<String>,
+    /// Code of the examples in the item's documentation
+    pub examples: Vec<String>,
+}
+
+impl ParsedDoc {
+    /// Returns the first paragraph of the description, the one-line summary
+    /// rustdoc also shows in item listings.
+    pub fn summary(&self) -> Option<&str> {
+        let description = self.description.as_deref()?;
+        description.split("\n\n").map(str::trim).find(|block| !block.is_empty())
+    }
+}
+
+/// A constant or static item listed on a documentation page.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct ConstantItem {
+    /// Name of the item
+    pub name: String,
+    /// `const` or `static`
+    pub kind: String,
+    /// Whether this is an associated constant of a type or trait rather
+    /// than a module-level item
+    pub associated: bool,
+    /// Documentation path of the item, with an anchor for associated constants
+    pub path: String,
+    /// Declared type, if known
+    #[serde(rename = "type")]
+    pub ty: Option<String>,
+    /// Rendered value, if rustdoc shows one
+    pub value: Option<String>,
+    /// The item's declaration, if known
+    pub declaration: Option<String>,
+}
+
+impl ConstantItem {
+    /// Sets the declaration and the type and value parsed from it.
+    pub fn set_declaration(&mut self, declaration: String) {
+        let (ty, value) = split_const_declaration(&declaration);
+        self.ty = ty;
+        self.value = value;
+        self.declaration = Some(declaration);
+    }
+}
+
+/// Constants and statics of a module, type or trait page.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Constants {
+    /// Documentation path of the page
+    pub path: String,
+    /// Module-level items first, then associated constants, in page order
+    pub constants: Vec<ConstantItem>,
+}
+
+/// Associated types declared by a trait, as shown on its page.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct AssociatedTypes {
+    /// Documentation path of the trait page
+    pub path: String,
+    /// Declaration of each associated type, including its bounds and where
+    /// clause, e.g. `type Item: Clone + Debug`, keyed by name
+    pub types: BTreeMap<String, String>,
+}
+
+/// A method declared by a trait, as shown on the trait's page.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct TraitMethod {
+    /// Documentation path of the trait page
+    pub path: String,
+    /// Name of the method
+    pub name: String,
+    /// The method signature, e.g. `fn read_to_end(&mut self, buf: &mut Vec<u8>) -> ReadToEnd<'_, Self>`
+    pub signature: String,
+    /// Whether implementors must define the method; provided methods have
+    /// a default implementation
+    pub required: bool,
+    /// The method's documentation as markdown, if it has any
+    pub docs: Option<String>,
+    /// Link to the default implementation of a provided method
+    pub default_source: Option<SourceLink>,
+}
+
+/// A module listed on a module page.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Submodule {
+    /// Name of the module
+    pub name: String,
+    /// Documentation path of the module's page
+    pub path: String,
+    /// First sentence of the module's documentation, if any
+    pub summary: Option<String>,
+}
+
+/// The immediate sub-modules of a module page.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Submodules {
+    /// Documentation path of the page
+    pub path: String,
+    /// Sub-modules in page order
+    pub modules: Vec<Submodule>,
+}
+
+/// Crate-level documentation, i.e. the crate's `//!` docs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct CrateDocs {
+    /// Name of the crate
+    pub crate_name: String,
+    /// Version of the crate
+    pub version: String,
+    /// Documentation as markdown, empty if the crate has none
+    pub markdown: String,
+}
+
+/// Link from an item page to the item's definition in the source view.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct SourceLink {
+    /// Documentation path of the source page, e.g. `src/tokio/sync/mutex.rs.html`
+    pub path: String,
+    /// First line of the item, if the link points to a line range
+    pub start_line: Option<usize>,
+    /// Last line of the item, if the link points to a line range
+    pub end_line: Option<usize>,
+}
+
+/// Source code of a single item, as shown in the source view.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct ItemSource {
+    /// Documentation path of the item page
+    pub item_path: String,
+    /// Documentation path of the source page
+    pub source_path: String,
+    /// First line of `code` in the source file
+    pub start_line: usize,
+    /// Last line of `code` in the source file
+    pub end_line: usize,
+    /// The item's source lines
+    pub code: String,
+}
+
+/// A whole file of rustdoc's source view.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct RenderedSource {
+    /// Documentation path of the source page, e.g. `src/tokio/sync/mutex.rs.html`
+    pub source_path: String,
+    /// Number of lines in the file
+    pub lines: usize,
+    /// Contents of the file, without line numbers
+    pub code: String,
+}
+
+/// An entry of a directory in docs.rs's source browser.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct SourceEntry {
+    /// File or directory name
+    pub name: String,
+    /// Path relative to the crate root, e.g. `examples/chat.rs`
+    pub path: String,
+    /// Whether the entry is a directory
+    pub is_dir: bool,
+}
+
+/// Contents of a crate's `examples/` directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct ExampleFiles {
+    /// Canonical name of the crate
+    pub crate_name: String,
+    /// Version the files were listed for
+    pub version: String,
+    /// Files and directories in the directory, empty if the crate publishes
+    /// no examples
+    pub entries: Vec<SourceEntry>,
+}
+
+/// A file of a crate's published sources.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct SourceFile {
+    /// Path relative to the crate root
+    pub path: String,
+    /// Contents of the file
+    pub code: String,
+}
+
+/// Page parts left out of the text extracted by [`DocsRsClient::fetch_docs`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Boilerplate {
+    /// CSS selectors of elements removed before the text is extracted, such
+    /// as rustdoc's navigation, search and settings panels
+    pub selectors: Vec<String>,
+    /// Phrases removed from the end of the extracted text, as often as they
+    /// occur there
+    pub trailing_text: Vec<String>,
+}
+
+impl Default for Boilerplate {
+    fn default() -> Self {
+        Self {
+            selectors: [
+                "script", "style", "noscript", "footer", "nav", ".sidebar", ".rustdoc-breadcrumbs",
+                "#search", "rustdoc-search", "#help", "#settings", "#copy-path",
+            ]
+                .map(String::from)
+                .to_vec(),
+            trailing_text: Vec::new(),
+        }
+    }
+}
+
+impl Boilerplate {
+    /// Returns the selectors that fail to parse.
+    pub fn invalid_selectors(&self) -> Vec<&str> {
+        self.selectors
+            .iter()
+            .filter(|selector| scraper::Selector::parse(selector).is_err())
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Removes the configured trailing phrases, and the whitespace around
+    /// them, from the end of `text`.
+    fn trim_trailing<'a>(&self, mut text: &'a str) -> &'a str {
+        loop {
+            text = text.trim_end();
+            match self.trailing_text.iter().find_map(|phrase| text.strip_suffix(phrase.as_str())) {
+                Some(rest) if !self.trailing_text.iter().all(String::is_empty) => text = rest,
+                _ => return text,
+            }
+        }
+    }
+}
+
+/// Returns whether `html` is a docs.rs error page for a missing crate,
+/// version or file, see [`NOT_FOUND_MARKERS`].
+fn is_not_found_page(html: &str) -> bool {
+    let document = scraper::Html::parse_document(html);
+    let text = document.root_element().text().collect::<String>().to_lowercase();
+    NOT_FOUND_MARKERS.iter().any(|marker| text.contains(marker))
+}
+
+/// Selectors of the element wrapping a page's documentation, tried in
+/// order: the docs.rs page wrapper, then the main content section of
+/// current rustdoc versions and that of rustdoc versions before 1.54.
+const CONTENT_SELECTORS: &[&str] = &["#rustdoc_body_wrapper", "#main-content", "#main"];
+
+/// Messages of the error pages docs.rs serves, sometimes with status 200,
+/// for crates, versions and files it does not have, matched ignoring case.
+const NOT_FOUND_MARKERS: &[&str] = &[
+    "the requested crate does not exist",
+    "the requested version does not exist",
+    "the requested resource does not exist",
+    "no such resource",
+];
+
+/// Length in bytes from which text extracted with one of
+/// [`CONTENT_SELECTORS`] is taken without trying the next ones.
+const MIN_SUBSTANTIAL_CONTENT: usize = 64;
+
+/// Default base URL of the crates.io registry API.
+pub const CRATES_IO_URL: &str = "https://crates.io";
+
+/// Base URL of docs.rs, which canonical links of mirrored pages still
+/// point to.
+const DOCS_RS_URL: &str = "https://docs.rs";
+
+/// User agent sent with every request; crates.io rejects requests without one.
+const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Version under which docs.rs serves the newest release of a crate.
+pub const LATEST_VERSION: &str = "latest";
+
+/// Returns whether `version` asks for the newest release of a crate rather
+/// than a concrete version: `latest` or an empty version.
+pub fn is_latest(version: &str) -> bool {
+    matches!(version.trim(), "" | LATEST_VERSION)
+}
+
+/// Maximum number of redirect stubs followed for one page, so that stubs
+/// redirecting to each other cannot loop.
+const MAX_REDIRECT_STUBS: usize = 3;
+
+/// Maximum length of a crate name on crates.io.
+const MAX_CRATE_NAME_LEN: usize = 64;
+
+/// Which crate names requests may name.
+///
+/// Names are embedded in URLs and, by the per-crate cache layout, in file
+/// names, so every policy rejects names that would change their meaning
+/// there, such as `..` or names containing `/`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum CrateNamePolicy {
+    /// Only names crates.io accepts: up to 64 ASCII letters, digits, `-`
+    /// and `_`, starting with a letter
+    #[default]
+    CratesIo,
+    /// Any name that is safe as a URL path segment and a file name, e.g.
+    /// dotted names of a private registry's documentation server
+    Permissive,
+}
+
+impl CrateNamePolicy {
+    /// Checks that `crate_name`, as normalized for requests, is accepted.
+    ///
+    /// Rust keywords such as `async` and single-character names are valid
+    /// crate names and accepted by both policies.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::InvalidCrateName`] if the name is rejected.
+    pub fn check(self, crate_name: &str) -> Result<(), DocsFetchError> {
+        let valid = match self {
+            Self::CratesIo => {
+                crate_name.len() <= MAX_CRATE_NAME_LEN
+                    && crate_name.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && crate_name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            }
+            Self::Permissive => is_safe_path_segment(crate_name),
+        };
+        match valid {
+            true => Ok(()),
+            false => Err(DocsFetchError::InvalidCrateName(crate_name.to_string())),
+        }
+    }
+}
+
+/// Returns whether `segment` can be used as is as one segment of a URL path
+/// and as a file name, without escaping and without referring to another
+/// directory.
+pub fn is_safe_path_segment(segment: &str) -> bool {
+    !matches!(segment, "" | "." | "..")
+        && !segment
+            .chars()
+            .any(|c| matches!(c, '/' | '\\' | '?' | '#' | '%' | ':') || c.is_whitespace() || c.is_control())
+}
+
+/// Minimum TLS version of connections to documentation servers and
+/// registries.
+///
+/// Which versions can be enforced, and which ciphers are offered, depends
+/// on the TLS backend; the platform's native backend cannot require TLS 1.3,
+/// see [`check_min_tls_version`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum TlsVersion {
+    #[value(name = "1.0")]
+    Tls1_0,
+    #[value(name = "1.1")]
+    Tls1_1,
+    #[value(name = "1.2")]
+    Tls1_2,
+    #[value(name = "1.3")]
+    Tls1_3,
+}
+
+impl TlsVersion {
+    fn to_reqwest(self) -> reqwest::tls::Version {
+        match self {
+            Self::Tls1_0 => reqwest::tls::Version::TLS_1_0,
+            Self::Tls1_1 => reqwest::tls::Version::TLS_1_1,
+            Self::Tls1_2 => reqwest::tls::Version::TLS_1_2,
+            Self::Tls1_3 => reqwest::tls::Version::TLS_1_3,
+        }
+    }
+}
+
+/// Time a request to the documentation server or registry may take, from
+/// connecting until its body is read, before failing with
+/// [`DocsFetchError::Timeout`].
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Returns the builder of the HTTP client of a [`DocsRsClient`], refusing
+/// connections that negotiate a TLS version below `min_tls_version` and
+/// failing requests that take longer than `request_timeout`. Without a
+/// minimum, the TLS backend's defaults apply.
+fn http_client_builder(
+    min_tls_version: Option<TlsVersion>,
+    request_timeout: Option<Duration>,
+) -> reqwest::ClientBuilder {
+    let mut builder = Client::builder().user_agent(USER_AGENT);
+    if let Some(version) = min_tls_version {
+        builder = builder.min_tls_version(version.to_reqwest());
+    }
+    if let Some(timeout) = request_timeout {
+        builder = builder.timeout(timeout);
+    }
+    builder
+}
+
+/// Checks that the TLS backend can enforce `min_tls_version`, so that an
+/// unsupported minimum is reported on start instead of being ignored.
+///
+/// # Errors
+///
+/// Returns the error of building an HTTP client with the minimum.
+pub fn check_min_tls_version(min_tls_version: Option<TlsVersion>) -> Result<(), reqwest::Error> {
+    http_client_builder(min_tls_version, None).build().map(drop)
+}
+
 /// Client for fetching documentation from docs.rs.
+#[derive(Clone)]
 pub struct DocsRsClient {
     /// HTTP client for making requests
     client: Client,
+    /// HTTP client returning redirects instead of following them, used to
+    /// read where docs.rs redirects `latest` to; built on first use
+    no_redirect_client: std::sync::OnceLock<Client>,
     /// Base URL for the docs.rs service
     base_url: String,
+    /// Base URL for the crates.io registry API
+    crates_io_url: String,
+    /// Page parts left out of extracted text
+    boilerplate: Arc<Boilerplate>,
+    /// Collapse blank lines and strip trailing spaces in extracted text
+    normalize_whitespace: bool,
+    /// Report the canonical page of pages with content of their own
+    merge_canonical: bool,
+    /// Minimum TLS version of connections, `None` for the backend default
+    min_tls_version: Option<TlsVersion>,
+    /// Time a request may take, `None` to wait indefinitely
+    request_timeout: Option<Duration>,
+    /// Failure injection for chaos testing
+    #[cfg(feature = "chaos")]
+    chaos: Option<Arc<crate::chaos::Chaos>>,
+}
+
+impl Default for DocsRsClient {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DocsRsClient {
     /// Creates a new client instance with the default docs.rs base URL.
     pub fn new() -> Self {
-        Self {
-            client: Client::new(),
-            base_url: "https://docs.rs".to_string(),
-        }
+        Self::new_with_base_url(DOCS_RS_URL)
     }
 
     /// Creates a new client instance with a custom base URL.
@@ -83,12 +951,102 @@ impl DocsRsClient {
     /// # Arguments
     ///
     /// * `base_url` - The base URL of the documentation server
-    #[allow(dead_code)]
     pub fn new_with_base_url(base_url: &str) -> Self {
+        let client = http_client_builder(None, Some(DEFAULT_REQUEST_TIMEOUT))
+            .build()
+            .unwrap_or_default();
         Self {
-            client: Client::new(),
+            client,
+            no_redirect_client: std::sync::OnceLock::new(),
             base_url: base_url.to_string(),
+            crates_io_url: CRATES_IO_URL.to_string(),
+            boilerplate: Arc::new(Boilerplate::default()),
+            normalize_whitespace: true,
+            merge_canonical: false,
+            min_tls_version: None,
+            request_timeout: Some(DEFAULT_REQUEST_TIMEOUT),
+            #[cfg(feature = "chaos")]
+            chaos: None,
+        }
+    }
+
+    /// Sets whether the text extracted by [`Self::fetch_docs`] is
+    /// normalized: trailing spaces are stripped from every line and runs of
+    /// blank lines collapsed into one, except inside code blocks. Enabled by
+    /// default.
+    pub fn with_normalize_whitespace(mut self, normalize_whitespace: bool) -> Self {
+        self.normalize_whitespace = normalize_whitespace;
+        self
+    }
+
+    /// Sets whether [`Self::fetch_docs_following_redirects`] also reports
+    /// the canonical path of a page with content of its own whose canonical
+    /// link names another page of the crate, as re-exports do. The page is
+    /// not fetched again, since both show the same item. Disabled by default.
+    pub fn with_merge_canonical(mut self, merge_canonical: bool) -> Self {
+        self.merge_canonical = merge_canonical;
+        self
+    }
+
+    /// Sets the page parts left out of the text extracted by
+    /// [`Self::fetch_docs`].
+    pub fn with_boilerplate(mut self, boilerplate: Arc<Boilerplate>) -> Self {
+        self.boilerplate = boilerplate;
+        self
+    }
+
+    /// Sets the minimum TLS version of connections, rebuilding the HTTP
+    /// client. The minimum must have passed [`check_min_tls_version`]; if
+    /// the client cannot be built, the error is logged and the previous
+    /// client is kept.
+    pub fn with_min_tls_version(mut self, min_tls_version: Option<TlsVersion>) -> Self {
+        if min_tls_version == self.min_tls_version {
+            return self;
+        }
+        match http_client_builder(min_tls_version, self.request_timeout).build() {
+            Ok(client) => {
+                self.client = client;
+                self.no_redirect_client = std::sync::OnceLock::new();
+                self.min_tls_version = min_tls_version;
+            }
+            Err(e) => tracing::error!("Failed to require TLS {:?}: {}", min_tls_version, e),
+        }
+        self
+    }
+
+    /// Sets the time a request may take before failing with
+    /// [`DocsFetchError::Timeout`], rebuilding the HTTP client; `None`
+    /// waits indefinitely. Defaults to [`DEFAULT_REQUEST_TIMEOUT`].
+    pub fn with_request_timeout(mut self, request_timeout: Option<Duration>) -> Self {
+        if request_timeout == self.request_timeout {
+            return self;
         }
+        match http_client_builder(self.min_tls_version, request_timeout).build() {
+            Ok(client) => {
+                self.client = client;
+                self.no_redirect_client = std::sync::OnceLock::new();
+                self.request_timeout = request_timeout;
+            }
+            Err(e) => tracing::error!("Failed to set request timeout {:?}: {}", request_timeout, e),
+        }
+        self
+    }
+
+    /// Sets the failure injector used by [`Self::fetch_docs`].
+    #[cfg(feature = "chaos")]
+    pub fn with_chaos(mut self, chaos: Option<Arc<crate::chaos::Chaos>>) -> Self {
+        self.chaos = chaos;
+        self
+    }
+
+    /// Sets the base URL of the crates.io registry API.
+    ///
+    /// # Arguments
+    ///
+    /// * `crates_io_url` - The base URL of a crates.io-compatible registry
+    pub fn with_crates_io_url(mut self, crates_io_url: &str) -> Self {
+        self.crates_io_url = crates_io_url.to_string();
+        self
     }
     
     /// Fetches documentation for the specified crate, version, and path.
@@ -96,8 +1054,9 @@ impl DocsRsClient {
     /// This method:
     /// 1. Constructs the appropriate URL for the documentation
     /// 2. Makes an HTTP request to fetch the HTML content
-    /// 3. Extracts the relevant documentation from the HTML
-    /// 4. Returns the parsed content or an error
+    /// 3. Follows redirect stubs, see [`Self::fetch_docs_following_redirects`]
+    /// 4. Extracts the relevant documentation from the HTML
+    /// 5. Returns the parsed content or an error
     ///
     /// # Arguments
     ///
@@ -109,32 +1068,781 @@ impl DocsRsClient {
     /// - The HTTP request fails
     /// - The documentation is not found
     /// - The content cannot be parsed
+    #[allow(dead_code)]
     pub async fn fetch_docs(&self, params: DocsRsParams) -> Result<DocContent, DocsFetchError> {
-        // Construct URL for the API documentation
-        let url = format!(
-            "{}/{}/{}/{}",
-            self.base_url,
+        self.fetch_docs_as(params, OutputFormat::PlainText).await
+    }
+
+    /// Fetches documentation like [`Self::fetch_docs`], extracting it in
+    /// `format` instead of plain text.
+    pub async fn fetch_docs_as(&self, params: DocsRsParams, format: OutputFormat) -> Result<DocContent, DocsFetchError> {
+        self.fetch_docs_following_redirects(params, format).await.map(|(doc, _)| doc)
+    }
+
+    /// Fetches documentation like [`Self::fetch_docs_as`], following
+    /// redirect stubs to the page they point to.
+    ///
+    /// The path is normalized with [`normalize_doc_path`], and an empty
+    /// version fetches the `latest` page. `latest` is not resolved here;
+    /// callers wanting the concrete version resolve it beforehand, see
+    /// [`Self::resolve_latest_version`].
+    ///
+    /// rustdoc leaves stubs at old paths of renamed or re-exported items
+    /// that only redirect elsewhere, by a meta refresh or, without content
+    /// of their own, a canonical link. Stubs pointing to another page of the
+    /// same crate are followed, up to [`MAX_REDIRECT_STUBS`] in a row.
+    ///
+    /// # Returns
+    ///
+    /// The documentation and, if stubs were followed, the path of the page
+    /// it was extracted from
+    pub async fn fetch_docs_following_redirects(
+        &self,
+        mut params: DocsRsParams,
+        format: OutputFormat,
+    ) -> Result<(DocContent, Option<String>), DocsFetchError> {
+        params.path = normalize_doc_path(&params.crate_name, &params.path);
+        if is_latest(&params.version) {
+            params.version = LATEST_VERSION.to_string();
+        }
+        let mut canonical_path = None;
+        let mut hops = 0;
+        loop {
+            let url = self.docs_url(&params);
+            #[cfg(feature = "chaos")]
+            let (html_content, source_url) = self.fetch_page_with_chaos(&params).await?;
+            #[cfg(not(feature = "chaos"))]
+            let (html_content, source_url) = self.fetch_page(&params).await?;
+
+            // Parse the main content from the rustdoc_body_wrapper div
+            let parsed_content = self.extract_rustdoc_content(&html_content, format);
+            if hops < MAX_REDIRECT_STUBS
+                && let Some(target) = self.redirect_stub_target(&html_content, &params, parsed_content.is_some())
+            {
+                tracing::debug!("Following redirect stub {:?} to '{}'", params, target);
+                params.path = target.clone();
+                canonical_path = Some(target);
+                hops += 1;
+                continue;
+            }
+            if self.merge_canonical
+                && parsed_content.is_some()
+                && let Some(target) = self.canonical_link_target(&html_content, &params)
+            {
+                tracing::debug!("{:?} is the same page as '{}'", params, target);
+                canonical_path = Some(target);
+            }
+            let content = Self::page_content(parsed_content, &html_content, &url, &params)?;
+            return Ok((DocContent { content, source_url: Some(source_url) }, canonical_path));
+        }
+    }
+
+    /// Extracts the documentation of the page at `params`, served from
+    /// `url`, like [`Self::fetch_docs_as`] does for pages of the
+    /// documentation server.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::DocsNotFound`] if the page is an error page.
+    pub fn extract_page(&self, html: &str, url: &str, params: &DocsRsParams, format: OutputFormat) -> Result<String, DocsFetchError> {
+        Self::page_content(self.extract_rustdoc_content(html, format), html, url, params)
+    }
+
+    /// Returns the extracted content of a page, or a pointer to `url` if
+    /// nothing was extracted from a page that is not an error page.
+    fn page_content(parsed_content: Option<String>, html: &str, url: &str, params: &DocsRsParams) -> Result<String, DocsFetchError> {
+        match parsed_content {
+            Some(content) => Ok(content),
+            None if is_not_found_page(html) => {
+                tracing::debug!("{:?} is an error page of the documentation server", params);
+                Err(DocsFetchError::DocsNotFound)
+            }
+            None => Ok(format!("Documentation available at {}", url)),
+        }
+    }
+
+    /// Returns the path of the page a redirect stub at `params` points to,
+    /// or `None` if the page is not a stub or points outside the crate.
+    ///
+    /// A meta refresh always makes a page a stub; a canonical link only if
+    /// the page has no documentation content of its own, since regular
+    /// pages link to their `latest` version as canonical.
+    fn redirect_stub_target(&self, html: &str, params: &DocsRsParams, has_content: bool) -> Option<String> {
+        use scraper::{Html, Selector};
+
+        let document = Html::parse_document(html);
+        let refresh = Selector::parse("meta[http-equiv]").ok()?;
+        let refresh_target = document
+            .select(&refresh)
+            .filter(|meta| meta.value().attr("http-equiv").is_some_and(|v| v.eq_ignore_ascii_case("refresh")))
+            .find_map(|meta| {
+                let content = meta.value().attr("content")?;
+                let (_, target) = content.split_once(';')?;
+                let target = target.trim();
+                let (key, url) = target.split_once('=')?;
+                key.trim().eq_ignore_ascii_case("url").then(|| url.trim().trim_matches(['"', '\'']).to_string())
+            });
+        match refresh_target {
+            Some(target) => self.crate_page_path(&target, params),
+            None if !has_content => self.canonical_link_target(html, params),
+            None => None,
+        }
+    }
+
+    /// Returns the path of the page the canonical link of the page at
+    /// `params` names, or `None` if it has none or names the page itself or
+    /// a page outside the crate. The version of the link is ignored.
+    fn canonical_link_target(&self, html: &str, params: &DocsRsParams) -> Option<String> {
+        use scraper::{Html, Selector};
+
+        let document = Html::parse_document(html);
+        let canonical = Selector::parse("link[rel=canonical][href]").ok()?;
+        let target = document.select(&canonical).next()?.value().attr("href")?;
+        self.crate_page_path(target, params)
+    }
+
+    /// Resolves a link on the page at `params` and returns the path of the
+    /// page of the same crate it points to, on the documentation server or
+    /// on docs.rs, or `None` if it points elsewhere or to the page itself.
+    fn crate_page_path(&self, target: &str, params: &DocsRsParams) -> Option<String> {
+        let mut resolved = url::Url::parse(&self.docs_url(params)).ok()?.join(target).ok()?;
+        resolved.set_query(None);
+        resolved.set_fragment(None);
+        let (_version, path) = [self.base_url.as_str(), DOCS_RS_URL]
+            .iter()
+            .find_map(|base| {
+                let crate_root = format!("{}/{}/", base.trim_end_matches('/'), params.crate_name);
+                resolved.as_str().strip_prefix(&crate_root)
+            })?
+            .split_once('/')?;
+        (!path.is_empty() && path != params.path.trim_start_matches('/')).then(|| path.to_string())
+    }
+
+    /// Fetches a page like [`Self::fetch_page`], unless the failure injector
+    /// decides the fetch fails.
+    #[cfg(feature = "chaos")]
+    async fn fetch_page_with_chaos(&self, params: &DocsRsParams) -> Result<(String, String), DocsFetchError> {
+        use crate::chaos::{Fault, INJECTED_TIMEOUT_DELAY};
+
+        match self.chaos.as_ref().and_then(|chaos| chaos.roll()) {
+            None => self.fetch_page(params).await,
+            Some(Fault::Timeout) => {
+                tokio::time::sleep(INJECTED_TIMEOUT_DELAY).await;
+                Err(DocsFetchError::Timeout)
+            }
+            Some(Fault::ServerError) => Err(DocsFetchError::ServiceUnavailable),
+            Some(Fault::TruncatedBody) => {
+                let (html, url) = self.fetch_page(params).await?;
+                let mut cut = html.len() / 2;
+                while !html.is_char_boundary(cut) {
+                    cut -= 1;
+                }
+                Ok((html[..cut].to_string(), url))
+            }
+        }
+    }
+
+    /// Builds the URL of the documentation page described by `params`.
+    fn docs_url(&self, params: &DocsRsParams) -> String {
+        let version = match is_latest(&params.version) {
+            true => LATEST_VERSION,
+            false => params.version.trim(),
+        };
+        format!(
+            "{}/{}/{}/{}",
+            self.base_url,
             params.crate_name,
-            params.version,
+            version,
             params.path.trim_start_matches('/')
-        );
-        
+        )
+    }
+
+    /// Resolves the concrete version docs.rs serves as `latest` for a crate.
+    ///
+    /// The version is read from where docs.rs redirects the crate's `latest`
+    /// documentation to. If it does not redirect to a concrete version of
+    /// the crate, the version is looked up on crates.io instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::DocsNotFound`] if crates.io knows no such
+    /// crate or no version of it, or the error of the crates.io request.
+    pub async fn resolve_latest_version(&self, crate_name: &str) -> Result<String, DocsFetchError> {
+        match self.latest_version_from_redirect(crate_name).await {
+            Ok(Some(version)) => return Ok(version),
+            Ok(None) => {}
+            Err(e) => tracing::debug!("Failed to read latest version of '{}' from docs.rs: {}", crate_name, e),
+        }
+        let metadata = self.crate_metadata(crate_name).await?;
+        metadata.latest_version().map(str::to_string).ok_or(DocsFetchError::DocsNotFound)
+    }
+
+    /// Reads the version docs.rs redirects the `latest` documentation of a
+    /// crate to, or `None` if it does not redirect to a concrete version.
+    async fn latest_version_from_redirect(&self, crate_name: &str) -> Result<Option<String>, DocsFetchError> {
+        let url = format!("{}/{}/{}/", self.base_url, crate_name, LATEST_VERSION);
+        let client = self.no_redirect_client.get_or_init(|| {
+            http_client_builder(self.min_tls_version, self.request_timeout)
+                .redirect(reqwest::redirect::Policy::none())
+                .build()
+                .unwrap_or_default()
+        });
+        let response = client.get(&url).send().await?;
+        if response.status() == reqwest::StatusCode::SERVICE_UNAVAILABLE {
+            return Err(DocsFetchError::ServiceUnavailable);
+        }
+        if response.status().is_server_error() {
+            return Err(DocsFetchError::UpstreamError(response.status().as_u16()));
+        }
+        let Some(location) = response
+            .status()
+            .is_redirection()
+            .then(|| response.headers().get(reqwest::header::LOCATION))
+            .flatten()
+            .and_then(|location| location.to_str().ok())
+        else {
+            return Ok(None);
+        };
+        let resolved = url::Url::parse(&url)?.join(location)?;
+        let crate_root = format!("{}/{}/", self.base_url.trim_end_matches('/'), crate_name);
+        Ok(resolved
+            .as_str()
+            .strip_prefix(&crate_root)
+            .and_then(|rest| rest.split('/').next())
+            .filter(|version| !is_latest(version))
+            .map(str::to_string))
+    }
+
+    /// Builds the URL of a file or directory in docs.rs's source browser,
+    /// which shows all published files of a crate, not only those rustdoc
+    /// renders.
+    fn source_browser_url(&self, crate_name: &str, version: &str, path: &str) -> String {
+        format!("{}/crate/{}/{}/source/{}", self.base_url, crate_name, version, path.trim_start_matches('/'))
+    }
+
+    /// Lists a directory of a crate's published sources.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - Directory relative to the crate root, e.g. `examples`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::DocsNotFound`] if the directory does not
+    /// exist, or the errors of [`Self::fetch_html`].
+    pub async fn fetch_source_listing(&self, crate_name: &str, version: &str, dir: &str) -> Result<Vec<SourceEntry>, DocsFetchError> {
+        let dir = dir.trim_matches('/');
+        let html = self.fetch_text(&self.source_browser_url(crate_name, version, &format!("{}/", dir))).await?;
+        Ok(self.parse_source_listing(&html, dir))
+    }
+
+    /// Fetches a file of a crate's published sources.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - File path relative to the crate root, e.g. `examples/chat.rs`
+    pub async fn fetch_source_file(&self, crate_name: &str, version: &str, path: &str) -> Result<SourceFile, DocsFetchError> {
+        let path = path.trim_start_matches('/');
+        let html = self.fetch_text(&self.source_browser_url(crate_name, version, path)).await?;
+        let code = self.parse_source_file(&html)?;
+        Ok(SourceFile { path: path.to_string(), code })
+    }
+
+    /// Extracts a whole file from a page of rustdoc's source view, which
+    /// only holds the files rustdoc compiled, unlike docs.rs's source
+    /// browser.
+    ///
+    /// # Arguments
+    ///
+    /// * `html` - The source page
+    /// * `source_path` - Path of the page, see [`source_page_path`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::DocsNotFound`] if the page is no source page.
+    pub fn parse_rendered_source(&self, html: &str, source_path: String) -> Result<RenderedSource, DocsFetchError> {
+        // docs.rs answers some unknown paths with another page instead of a 404
+        let (_, lines, code) = self.parse_source_lines(html, None).map_err(|_| DocsFetchError::DocsNotFound)?;
+        Ok(RenderedSource { source_path, lines, code })
+    }
+
+    /// Fetches the raw HTML of the documentation page described by `params`.
+    ///
+    /// This is the building block for tools that parse a specific part of a
+    /// page instead of its whole text content.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::ServiceUnavailable`] if docs.rs is down or in
+    /// maintenance, [`DocsFetchError::DocsNotFound`] for any other unsuccessful
+    /// status, or a request error if the request fails.
+    pub async fn fetch_html(&self, params: &DocsRsParams) -> Result<String, DocsFetchError> {
+        self.fetch_page(params).await.map(|(html, _)| html)
+    }
+
+    /// Fetches a page like [`Self::fetch_html`], also returning the URL it
+    /// was served from after following redirects.
+    async fn fetch_page(&self, params: &DocsRsParams) -> Result<(String, String), DocsFetchError> {
+        let url = self.docs_url(params);
         let response = self.client.get(&url)
             .header("Accept", "text/html")
             .send()
             .await?;
-        
-        if !response.status().is_success() {
-            return Err(DocsFetchError::DocsNotFound);
+        let source_url = response.url().to_string();
+        Ok((Self::response_text(response).await?, source_url))
+    }
+
+    /// Checks with a `HEAD` request whether the documentation page described
+    /// by `params` exists, without downloading it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::ServiceUnavailable`] if docs.rs is down,
+    /// [`DocsFetchError::UpstreamError`] if it fails with another server
+    /// error, or a request error if the request fails.
+    pub async fn page_exists(&self, params: &DocsRsParams) -> Result<bool, DocsFetchError> {
+        let response = self.client.head(self.docs_url(params)).send().await?;
+        let status = response.status();
+        if status == reqwest::StatusCode::SERVICE_UNAVAILABLE {
+            return Err(DocsFetchError::ServiceUnavailable);
         }
-        
-        let html_content = response.text().await?;
-        
-        // Parse the main content from the rustdoc_body_wrapper div
-        let parsed_content = self.extract_rustdoc_content(&html_content)
-            .unwrap_or_else(|| format!("Documentation available at {}", url));
-        
-        Ok(DocContent { content: parsed_content })
+        if status.is_server_error() {
+            return Err(DocsFetchError::UpstreamError(status.as_u16()));
+        }
+        Ok(status.is_success())
+    }
+
+    /// Returns the URL of a crates.io API endpoint below `/api/v1/crates`,
+    /// with each of `segments`, such as a client-supplied crate name,
+    /// percent-encoded as one path segment.
+    fn crates_io_api_url(&self, segments: &[&str]) -> String {
+        let base = format!("{}/api/v1/crates", self.crates_io_url.trim_end_matches('/'));
+        let Ok(mut url) = reqwest::Url::parse(&base) else {
+            return format!("{}/{}", base, segments.join("/"));
+        };
+        if let Ok(mut path) = url.path_segments_mut() {
+            path.extend(segments);
+        }
+        url.into()
+    }
+
+    /// Resolves the canonical name of a crate as published on crates.io.
+    ///
+    /// crates.io looks crate names up case-insensitively and treats `-` and
+    /// `_` as equivalent, so e.g. `Tokio_Util` resolves to `tokio-util`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::DocsNotFound`] if no such crate exists.
+    pub async fn resolve_crate_name(&self, crate_name: &str) -> Result<String, DocsFetchError> {
+        Ok(self.crate_metadata(crate_name).await?.name)
+    }
+
+    /// Fetches the canonical name and latest versions of a crate from
+    /// crates.io. Names are resolved as in [`Self::resolve_crate_name`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::DocsNotFound`] if no such crate exists.
+    pub async fn crate_metadata(&self, crate_name: &str) -> Result<CrateMetadata, DocsFetchError> {
+        #[derive(Deserialize)]
+        struct CrateResponse {
+            #[serde(rename = "crate")]
+            krate: CrateMetadata,
+        }
+
+        let url = self.crates_io_api_url(&[crate_name]);
+        let body = self.fetch_text(&url).await?;
+        let response: CrateResponse = serde_json::from_str(&body)
+            .map_err(|e| DocsFetchError::ParseError(format!("invalid crates.io response: {}", e)))?;
+        Ok(response.krate)
+    }
+
+    /// Fetches all published versions of a crate from crates.io, in the
+    /// order crates.io lists them.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::DocsNotFound`] if no such crate exists.
+    pub async fn fetch_versions(&self, crate_name: &str) -> Result<Vec<PublishedVersion>, DocsFetchError> {
+        #[derive(Deserialize)]
+        struct VersionsResponse {
+            versions: Vec<PublishedVersion>,
+        }
+
+        let url = self.crates_io_api_url(&[crate_name, "versions"]);
+        let body = self.fetch_text(&url).await?;
+        let response: VersionsResponse = serde_json::from_str(&body)
+            .map_err(|e| DocsFetchError::ParseError(format!("invalid crates.io response: {}", e)))?;
+        Ok(response.versions)
+    }
+
+    /// Fetches the status of the latest docs.rs build of a crate version.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::DocsNotFound`] if docs.rs knows no such
+    /// crate version, or [`DocsFetchError::ParseError`] for an unknown
+    /// build status.
+    pub async fn fetch_build_status(&self, crate_name: &str, version: &str) -> Result<BuildStatus, DocsFetchError> {
+        #[derive(Deserialize)]
+        struct Build {
+            #[serde(default)]
+            id: Option<u64>,
+            #[serde(default)]
+            rustc_version: Option<String>,
+            #[serde(default)]
+            build_time: Option<String>,
+            /// A string in current docs.rs versions, a boolean in older ones
+            build_status: serde_json::Value,
+            #[serde(default)]
+            errors: Option<String>,
+        }
+
+        let url = format!("{}/crate/{}/{}/builds.json", self.base_url, crate_name, version);
+        let body = self.fetch_text(&url).await?;
+        let builds: Vec<Build> = serde_json::from_str(&body)
+            .map_err(|e| DocsFetchError::ParseError(format!("invalid docs.rs builds response: {}", e)))?;
+
+        // Builds are listed newest first; none are listed while the first is queued
+        let Some(build) = builds.into_iter().next() else {
+            return Ok(BuildStatus {
+                crate_name: crate_name.to_string(),
+                version: version.to_string(),
+                status: BuildState::InProgress,
+                build_id: None,
+                rustc_version: None,
+                build_time: None,
+                log_url: None,
+                log_excerpt: None,
+            });
+        };
+        let status = match &build.build_status {
+            serde_json::Value::Bool(true) => BuildState::Success,
+            serde_json::Value::Bool(false) => BuildState::Failed,
+            serde_json::Value::String(status) => match status.as_str() {
+                "success" => BuildState::Success,
+                "failure" | "failed" => BuildState::Failed,
+                "in_progress" | "queued" => BuildState::InProgress,
+                other => return Err(DocsFetchError::ParseError(format!("unknown build status '{}'", other))),
+            },
+            other => return Err(DocsFetchError::ParseError(format!("unknown build status {}", other))),
+        };
+        let log_excerpt = build
+            .errors
+            .filter(|errors| status == BuildState::Failed && !errors.trim().is_empty())
+            .map(|errors| {
+                let errors = errors.trim_end();
+                let skip = errors.chars().count().saturating_sub(BUILD_LOG_EXCERPT_CHARS);
+                errors.chars().skip(skip).collect()
+            });
+        Ok(BuildStatus {
+            crate_name: crate_name.to_string(),
+            version: version.to_string(),
+            status,
+            log_url: build.id.map(|id| format!("{}/crate/{}/{}/builds/{}", self.base_url, crate_name, version, id)),
+            build_id: build.id,
+            rustc_version: build.rustc_version,
+            build_time: build.build_time,
+            log_excerpt,
+        })
+    }
+
+    /// Fetches the categories and keywords of a crate from crates.io.
+    ///
+    /// Crates that declare none yield empty lists.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::DocsNotFound`] if no such crate exists.
+    pub async fn fetch_tags(&self, crate_name: &str) -> Result<CrateTags, DocsFetchError> {
+        #[derive(Deserialize)]
+        struct CrateResponse {
+            #[serde(rename = "crate")]
+            krate: CrateInfo,
+        }
+        #[derive(Deserialize)]
+        struct CrateInfo {
+            name: String,
+            #[serde(default)]
+            categories: Option<Vec<String>>,
+            #[serde(default)]
+            keywords: Option<Vec<String>>,
+        }
+
+        let url = self.crates_io_api_url(&[crate_name]);
+        let body = self.fetch_text(&url).await?;
+        let response: CrateResponse = serde_json::from_str(&body)
+            .map_err(|e| DocsFetchError::ParseError(format!("invalid crates.io response: {}", e)))?;
+        Ok(CrateTags {
+            crate_name: response.krate.name,
+            categories: response.krate.categories.unwrap_or_default(),
+            keywords: response.krate.keywords.unwrap_or_default(),
+        })
+    }
+
+    /// Fetches the registry information of a crate from a single response
+    /// of the crates.io crate endpoint: description, license, links,
+    /// downloads, tags, versions and who published them.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::DocsNotFound`] if no such crate exists.
+    pub async fn fetch_full_metadata(&self, crate_name: &str) -> Result<CrateFullMetadata, DocsFetchError> {
+        #[derive(Deserialize)]
+        struct CrateResponse {
+            #[serde(rename = "crate")]
+            krate: CrateInfo,
+            #[serde(default)]
+            versions: Option<Vec<VersionInfo>>,
+        }
+        #[derive(Deserialize)]
+        struct CrateInfo {
+            name: String,
+            #[serde(default)]
+            description: Option<String>,
+            #[serde(default)]
+            homepage: Option<String>,
+            #[serde(default)]
+            repository: Option<String>,
+            #[serde(default)]
+            documentation: Option<String>,
+            #[serde(default)]
+            downloads: u64,
+            #[serde(default)]
+            recent_downloads: Option<u64>,
+            #[serde(default)]
+            max_stable_version: Option<String>,
+            #[serde(default)]
+            newest_version: Option<String>,
+            #[serde(default)]
+            categories: Option<Vec<String>>,
+            #[serde(default)]
+            keywords: Option<Vec<String>>,
+        }
+        #[derive(Deserialize)]
+        struct VersionInfo {
+            #[serde(flatten)]
+            version: PublishedVersion,
+            #[serde(default)]
+            license: Option<String>,
+            #[serde(default)]
+            published_by: Option<User>,
+        }
+        #[derive(Deserialize)]
+        struct User {
+            login: String,
+            #[serde(default)]
+            name: Option<String>,
+        }
+
+        let url = self.crates_io_api_url(&[crate_name]);
+        let body = self.fetch_text(&url).await?;
+        let response: CrateResponse = serde_json::from_str(&body)
+            .map_err(|e| DocsFetchError::ParseError(format!("invalid crates.io response: {}", e)))?;
+        let krate = response.krate;
+        let versions = response.versions.unwrap_or_default();
+
+        let latest = krate.max_stable_version.as_deref().or(krate.newest_version.as_deref());
+        let license = versions
+            .iter()
+            .find(|info| Some(info.version.num.as_str()) == latest)
+            .or(versions.first())
+            .and_then(|info| info.license.clone());
+        let mut publishers: Vec<Publisher> = Vec::new();
+        for user in versions.iter().filter_map(|info| info.published_by.as_ref()) {
+            match publishers.iter_mut().find(|publisher| publisher.login == user.login) {
+                Some(publisher) => publisher.versions += 1,
+                None => publishers.push(Publisher { login: user.login.clone(), name: user.name.clone(), versions: 1 }),
+            }
+        }
+        publishers.sort_by_key(|publisher| std::cmp::Reverse(publisher.versions));
+
+        Ok(CrateFullMetadata {
+            crate_name: krate.name,
+            description: krate.description.map(|description| description.trim().to_string()),
+            license,
+            homepage: krate.homepage,
+            repository: krate.repository,
+            documentation: krate.documentation,
+            downloads: krate.downloads,
+            recent_downloads: krate.recent_downloads,
+            max_stable_version: krate.max_stable_version,
+            newest_version: krate.newest_version,
+            categories: krate.categories.unwrap_or_default(),
+            keywords: krate.keywords.unwrap_or_default(),
+            versions: versions.into_iter().map(|info| info.version).collect(),
+            publishers,
+        })
+    }
+
+    /// Fetches the description, license, links, downloads and features of a
+    /// crate version from a single response of the crates.io crate
+    /// endpoint. Without a concrete `version`, the version docs.rs serves as
+    /// `latest` is described.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::DocsNotFound`] if no such crate or version
+    /// exists.
+    pub async fn fetch_crate_metadata(&self, crate_name: &str, version: Option<&str>) -> Result<CrateVersionMetadata, DocsFetchError> {
+        #[derive(Deserialize)]
+        struct CrateResponse {
+            #[serde(rename = "crate")]
+            krate: CrateInfo,
+            #[serde(default)]
+            versions: Option<Vec<VersionInfo>>,
+        }
+        #[derive(Deserialize)]
+        struct CrateInfo {
+            name: String,
+            #[serde(default)]
+            description: Option<String>,
+            #[serde(default)]
+            homepage: Option<String>,
+            #[serde(default)]
+            repository: Option<String>,
+            #[serde(default)]
+            downloads: u64,
+            #[serde(default)]
+            max_stable_version: Option<String>,
+            #[serde(default)]
+            newest_version: Option<String>,
+        }
+        #[derive(Deserialize)]
+        struct VersionInfo {
+            num: String,
+            #[serde(default)]
+            license: Option<String>,
+            #[serde(default)]
+            features: BTreeMap<String, Vec<String>>,
+        }
+
+        let url = self.crates_io_api_url(&[crate_name]);
+        let body = self.fetch_text(&url).await?;
+        let response: CrateResponse = serde_json::from_str(&body)
+            .map_err(|e| DocsFetchError::ParseError(format!("invalid crates.io response: {}", e)))?;
+        let krate = response.krate;
+        let wanted = match version.map(str::trim) {
+            Some(version) if !is_latest(version) => Some(version),
+            _ => krate.max_stable_version.as_deref().or(krate.newest_version.as_deref()),
+        };
+        let info = response
+            .versions
+            .unwrap_or_default()
+            .into_iter()
+            .find(|info| Some(info.num.as_str()) == wanted)
+            .ok_or(DocsFetchError::DocsNotFound)?;
+
+        Ok(CrateVersionMetadata {
+            crate_name: krate.name,
+            version: info.num,
+            description: krate.description.map(|description| description.trim().to_string()),
+            license: info.license,
+            homepage: krate.homepage,
+            repository: krate.repository,
+            downloads: krate.downloads,
+            features: info.features,
+        })
+    }
+
+    /// Fetches the `[features]` table of a published crate version from
+    /// crates.io, mapping each feature to the entries it enables.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::DocsNotFound`] if no such crate version exists.
+    pub async fn fetch_features(&self, crate_name: &str, version: &str) -> Result<BTreeMap<String, Vec<String>>, DocsFetchError> {
+        #[derive(Deserialize)]
+        struct VersionResponse {
+            version: VersionInfo,
+        }
+        #[derive(Deserialize)]
+        struct VersionInfo {
+            #[serde(default)]
+            features: BTreeMap<String, Vec<String>>,
+        }
+
+        let url = self.crates_io_api_url(&[crate_name, version]);
+        let body = self.fetch_text(&url).await?;
+        let response: VersionResponse = serde_json::from_str(&body)
+            .map_err(|e| DocsFetchError::ParseError(format!("invalid crates.io response: {}", e)))?;
+        Ok(response.version.features)
+    }
+
+    /// Fetches and parses the rustdoc search index of a crate.
+    ///
+    /// The location of the index file is read from the `data-search-index-js`
+    /// attribute of the crate's index page, since recent rustdoc versions add a
+    /// version suffix to the file name. If the attribute is missing, the
+    /// historical `search-index.js` location is used.
+    ///
+    /// # Arguments
+    ///
+    /// * `crate_name` - Name of the crate
+    /// * `version` - Version of the crate, or `latest`
+    pub async fn fetch_search_index(&self, crate_name: &str, version: &str) -> Result<SearchIndex, DocsFetchError> {
+        let crate_root = format!("{}/{}/{}/", self.base_url, crate_name, version);
+        let index_page_url = url::Url::parse(&crate_root)?
+            .join(&format!("{}/index.html", crate_name.replace('-', "_")))?;
+
+        let index_html = self.fetch_text(index_page_url.as_str()).await?;
+        let search_index_url = match Self::search_index_location(&index_html) {
+            Some(location) => index_page_url.join(&location)?,
+            None => url::Url::parse(&crate_root)?.join("search-index.js")?,
+        };
+
+        let js = self.fetch_text(search_index_url.as_str()).await?;
+        search_index::parse_search_index(&js, crate_name)
+    }
+
+    /// Reads the search index location advertised in a rustdoc page.
+    fn search_index_location(html: &str) -> Option<String> {
+        use scraper::{Html, Selector};
+
+        let document = Html::parse_document(html);
+        let selector = Selector::parse("#rustdoc-vars[data-search-index-js]").ok()?;
+        document
+            .select(&selector)
+            .next()?
+            .value()
+            .attr("data-search-index-js")
+            .map(str::to_string)
+    }
+
+    /// Performs a GET request and returns the body, mapping unsuccessful
+    /// statuses as described in [`Self::response_text`].
+    async fn fetch_text(&self, url: &str) -> Result<String, DocsFetchError> {
+        let response = self.client.get(url).send().await?;
+        Self::response_text(response).await
+    }
+
+    /// Returns the body of a successful response.
+    ///
+    /// A 503 status or a maintenance page body is reported as
+    /// [`DocsFetchError::ServiceUnavailable`], any other server error status
+    /// as the transient [`DocsFetchError::UpstreamError`], and the remaining
+    /// unsuccessful statuses as [`DocsFetchError::DocsNotFound`].
+    async fn response_text(response: reqwest::Response) -> Result<String, DocsFetchError> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response.text().await?);
+        }
+        if status == reqwest::StatusCode::SERVICE_UNAVAILABLE {
+            tracing::warn!("Documentation server unavailable ({})", status);
+            return Err(DocsFetchError::ServiceUnavailable);
+        }
+
+        let body = response.text().await.unwrap_or_default().to_lowercase();
+        if MAINTENANCE_SIGNATURES.iter().any(|signature| body.contains(signature)) {
+            tracing::warn!("Documentation server in maintenance ({})", status);
+            return Err(DocsFetchError::ServiceUnavailable);
+        }
+        if status.is_server_error() {
+            tracing::warn!("Documentation server failed ({})", status);
+            return Err(DocsFetchError::UpstreamError(status.as_u16()));
+        }
+        Err(DocsFetchError::DocsNotFound)
     }
     
     /// Extracts the main documentation content from a rustdoc HTML page.
@@ -145,95 +1853,1571 @@ impl DocsRsClient {
     /// # Arguments
     ///
     /// * `html` - The raw HTML content from docs.rs
+    /// * `format` - Whether to extract the text or convert the content to
+    ///   Markdown; the boilerplate is removed from the page first either way.
+    ///   JSON is parsed by [`Self::parse_html_content`] instead.
     ///
     /// # Returns
     ///
-    /// Returns the extracted text content if found, or None if the content
-    /// cannot be located or parsed.
-    fn extract_rustdoc_content(&self, html: &str) -> Option<String> {
+    /// Returns the extracted content if found, or None if the content
+    /// cannot be located or parsed.
+    fn extract_rustdoc_content(&self, html: &str, format: OutputFormat) -> Option<String> {
+        use scraper::{Html, Selector};
+
+        if format == OutputFormat::Json {
+            let parsed = self.parse_html_content(html);
+            if parsed == ParsedDoc::default() {
+                return None;
+            }
+            return serde_json::to_string(&parsed).ok();
+        }
+        
+        // Parse the HTML document
+        let mut document = Html::parse_document(html);
+        let skip: Vec<Selector> = self.boilerplate
+            .selectors
+            .iter()
+            .filter_map(|selector| Selector::parse(selector).ok())
+            .collect();
+        remove_matching(&mut document, &skip);
+
+        // Different rustdoc versions wrap the documentation differently; take
+        // the first wrapper with substantial text, else the first one found
+        let mut fallback = None;
+        for wrapper_selector in CONTENT_SELECTORS {
+            let selector = Selector::parse(wrapper_selector).unwrap();
+            let Some(wrapper) = document.select(&selector).next() else {
+                continue;
+            };
+
+            // JSON was parsed from the page above
+            let content = match format {
+                OutputFormat::PlainText | OutputFormat::Json => {
+                    let mut texts = Vec::new();
+                    collect_text(wrapper, &[], false, &mut texts);
+                    join_text(&texts, self.normalize_whitespace)
+                }
+                OutputFormat::Markdown => markdown_blocks(wrapper).join("\n\n"),
+            };
+            let content = self.boilerplate.trim_trailing(&content).to_string();
+            if content.trim().len() >= MIN_SUBSTANTIAL_CONTENT {
+                tracing::debug!("Extracted documentation from {}", wrapper_selector);
+                return Some(content);
+            }
+            fallback.get_or_insert((*wrapper_selector, content));
+        }
+        let (wrapper_selector, content) = fallback?;
+        tracing::debug!("Extracted documentation from {}, which has little text", wrapper_selector);
+        Some(content)
+    }
+    
+    /// Parses the "Implementors" section of a trait page.
+    ///
+    /// Both the current markup (`section.impl` with a `.code-header`) and the
+    /// older one (`div.impl` with a `code` element) are supported. rustdoc
+    /// loads implementors from downstream crates with a script at view time;
+    /// when such a script is referenced its location is reported instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `html` - The raw HTML content of a trait page
+    pub fn parse_implementors(&self, html: &str) -> Implementors {
+        use scraper::{Html, Selector};
+
+        let document = Html::parse_document(html);
+        let impl_selector = Selector::parse("#implementors-list > .impl").unwrap();
+        let header_selector = Selector::parse(".code-header, code").unwrap();
+        let link_selector = Selector::parse("a[title]").unwrap();
+        let script_selector = Selector::parse("script[src]").unwrap();
+
+        let implementors = document
+            .select(&impl_selector)
+            .filter_map(|section| {
+                let header = section.select(&header_selector).next()?;
+                let declaration = block_aware_text(header);
+                let type_name = impl_target(&declaration)?.to_string();
+                let type_head = type_name.split('<').next().unwrap_or(&type_name).trim();
+                let type_path = header
+                    .select(&link_selector)
+                    .filter(|a| a.text().collect::<String>().trim() == type_head)
+                    .filter_map(|a| a.value().attr("title"))
+                    .filter_map(|title| title.split_once(' ').map(|(_, path)| path.to_string()))
+                    .last();
+                Some(Implementor { declaration, type_name, type_path })
+            })
+            .collect();
+
+        let external_implementors_script = document
+            .select(&script_selector)
+            .filter_map(|script| script.value().attr("src"))
+            .find(|src| src.contains("trait.impl/") || src.contains("implementors/"))
+            .map(str::to_string);
+
+        Implementors { implementors, external_implementors_script }
+    }
+
+    /// Parses the "Auto Trait Implementations" section of a type page.
+    ///
+    /// As with [`Self::parse_implementors`], both the `section.impl` and the
+    /// older `div.impl` markup are supported. A page without the section
+    /// yields no impls.
+    ///
+    /// # Arguments
+    ///
+    /// * `html` - The raw HTML content of a struct, enum or union page
+    pub fn parse_auto_traits(&self, html: &str) -> AutoTraits {
+        use scraper::{Html, Selector};
+
+        let document = Html::parse_document(html);
+        let impl_selector = Selector::parse("#synthetic-implementations-list > .impl").unwrap();
+        let header_selector = Selector::parse(".code-header, code").unwrap();
+
+        let impls = document
+            .select(&impl_selector)
+            .filter_map(|section| {
+                let header = section.select(&header_selector).next()?;
+                let declaration = block_aware_text(header);
+                let implemented = impl_trait(&declaration)?;
+                let (trait_name, negative) = match implemented.strip_prefix('!') {
+                    Some(trait_name) => (trait_name.trim().to_string(), true),
+                    None => (implemented.to_string(), false),
+                };
+                let conditional = declaration.contains(" where ");
+                Some(AutoTraitImpl { trait_name, negative, conditional, declaration })
+            })
+            .collect();
+
+        AutoTraits { impls }
+    }
+
+    /// Splits a documentation page into sections following its headings.
+    ///
+    /// The item declaration becomes "Declaration" and the text before the
+    /// first heading of the item's documentation "Summary". After that, a
+    /// section starts at every heading of the item's own documentation (e.g.
+    /// "Examples", "Panics") and at every page-level section header (e.g.
+    /// "Fields", "Implementations", "Trait Implementations"). Headings inside
+    /// the documentation of methods or trait impls do not start sections.
+    /// Sections with the same heading are merged and empty ones dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::ParseError`] if the page has no rustdoc
+    /// content.
+    pub fn parse_sections(&self, html: &str, page_path: &str) -> Result<PageSections, DocsFetchError> {
+        use scraper::{Html, Selector};
+
+        let document = Html::parse_document(html);
+        let root = ["#main-content", "section#main", "#rustdoc_body_wrapper"]
+            .iter()
+            .filter_map(|selector| document.select(&Selector::parse(selector).unwrap()).next())
+            .next()
+            .ok_or_else(|| DocsFetchError::ParseError("no documentation content found".to_string()))?;
+
+        let mut writer = SectionWriter::default();
+        writer.start("Summary");
+        writer.walk_children(root, root, DocContext::Page, false);
+
+        Ok(PageSections {
+            path: page_path.trim_start_matches('/').to_string(),
+            sections: writer.finish(),
+        })
+    }
+
+    /// Parses the declaration of a type alias page (`type.*.html`).
+    ///
+    /// The declaration is read from the first `pre.item-decl` block (or the
+    /// older `pre.typedef`), and the aliased type's path from the first item
+    /// link after the `=`. The "Aliased Type" section is included when present.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::ParseError`] if the page has no type alias
+    /// declaration.
+    pub fn parse_type_alias(&self, html: &str) -> Result<TypeAlias, DocsFetchError> {
+        use scraper::{Html, Node, Selector};
+
+        let document = Html::parse_document(html);
+        let decl_selector = Selector::parse("pre.item-decl, pre.typedef").unwrap();
+        let aliased_heading = Selector::parse("#aliased-type").unwrap();
+
+        let decl = document
+            .select(&decl_selector)
+            .next()
+            .ok_or_else(|| DocsFetchError::ParseError("no type alias declaration found".to_string()))?;
+        let declaration = collapse_whitespace(&decl.text().collect::<String>());
+        let aliased_type = declaration
+            .split_once(" = ")
+            .map(|(_, rhs)| rhs.trim_end_matches(';').trim().to_string())
+            .ok_or_else(|| DocsFetchError::ParseError(format!("not a type alias declaration: {}", declaration)))?;
+
+        // The first linked item after the `=` is the aliased type
+        let mut after_eq = false;
+        let mut aliased_type_path = None;
+        for node in decl.descendants() {
+            match node.value() {
+                Node::Text(t) if t.contains('=') => after_eq = true,
+                Node::Element(e) if after_eq && e.name() == "a" => {
+                    if let Some((_, path)) = e.attr("title").and_then(|t| t.split_once(' ')) {
+                        aliased_type_path = Some(path.to_string());
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // In current rustdoc the section heading is followed by the definition
+        let aliased_definition = document
+            .select(&aliased_heading)
+            .next()
+            .and_then(|heading| heading.next_siblings().filter_map(scraper::ElementRef::wrap).next())
+            .filter(|sibling| sibling.value().name() == "pre")
+            .map(|pre| pre.text().collect::<String>().trim().to_string());
+
+        Ok(TypeAlias { declaration, aliased_type, aliased_type_path, aliased_definition })
+    }
+
+    /// Parses the constants and statics listed on a page.
+    ///
+    /// Module pages list module-level items in their "Constants" and
+    /// "Statics" tables, without declarations; see
+    /// [`Self::parse_item_declaration`] for those. Type and trait pages
+    /// show associated constants with their full declaration. Links are
+    /// resolved relative to `page_path`.
+    pub fn parse_constants(&self, html: &str, page_path: &str) -> Result<Constants, DocsFetchError> {
+        use scraper::{Html, Selector};
+
+        let page_path = page_path.trim_start_matches('/');
+        let page_url = url::Url::parse("https://docs.rs/")?.join(page_path)?;
+        let document = Html::parse_document(html);
+        let listed_selector = Selector::parse(".item-table a.constant, .item-table a.static").unwrap();
+        let associated_selector = Selector::parse("section.associatedconstant[id], div.associatedconstant[id], h4.associatedconstant[id]").unwrap();
+        let header_selector = Selector::parse(".code-header, code").unwrap();
+        let name_selector = Selector::parse("a.constant").unwrap();
+
+        let listed = document.select(&listed_selector).filter_map(|link| {
+            let href = link.value().attr("href")?;
+            Some(ConstantItem {
+                name: collapse_whitespace(&link.text().collect::<String>()),
+                kind: if link.value().classes().any(|class| class == "static") { "static" } else { "const" }.to_string(),
+                associated: false,
+                path: page_url.join(href).ok()?.path().trim_start_matches('/').to_string(),
+                ty: None,
+                value: None,
+                declaration: None,
+            })
+        });
+
+        let associated = document.select(&associated_selector).filter_map(|section| {
+            let id = section.value().attr("id")?;
+            let header = section.select(&header_selector).next().unwrap_or(section);
+            let name = header
+                .select(&name_selector)
+                .next()
+                .map(|a| collapse_whitespace(&a.text().collect::<String>()))
+                .or_else(|| id.strip_prefix("associatedconstant.").map(|name| name.split('-').next().unwrap_or(name).to_string()))?;
+            let mut item = ConstantItem {
+                name,
+                kind: "const".to_string(),
+                associated: true,
+                path: format!("{}#{}", page_path, id),
+                ty: None,
+                value: None,
+                declaration: None,
+            };
+            item.set_declaration(block_aware_text(header));
+            Some(item)
+        });
+
+        Ok(Constants {
+            path: page_path.to_string(),
+            constants: listed.chain(associated).collect(),
+        })
+    }
+
+    /// Parses the associated types declared on a trait page.
+    ///
+    /// Only the trait's own declarations are read, not the associated types
+    /// set by the implementations listed below them. Both the current
+    /// `section` markup and the older `h3` one are supported.
+    pub fn parse_associated_types(&self, html: &str, page_path: &str) -> AssociatedTypes {
+        use scraper::{Html, Selector};
+
+        let document = Html::parse_document(html);
+        let selector = Selector::parse(r#".methods [id^="associatedtype."]"#).unwrap();
+        let header_selector = Selector::parse(".code-header, code").unwrap();
+        let name_selector = Selector::parse("a.associatedtype, a.type").unwrap();
+
+        let types = document
+            .select(&selector)
+            .filter_map(|item| {
+                let header = item.select(&header_selector).next().unwrap_or(item);
+                let name = header
+                    .select(&name_selector)
+                    .next()
+                    .map(|a| collapse_whitespace(&a.text().collect::<String>()))
+                    .or_else(|| item.value().attr("id")?.strip_prefix("associatedtype.").map(str::to_string))?;
+                Some((name, block_aware_text(header)))
+            })
+            .collect();
+
+        AssociatedTypes { path: page_path.trim_start_matches('/').to_string(), types }
+    }
+
+    /// Parses the declaration of the method `method` on a trait page.
+    ///
+    /// Required methods are told apart from provided ones by their
+    /// `tymethod.` anchors. Only the trait's own declarations are read, not
+    /// the methods of the implementations listed below them. The source link
+    /// of a provided method is resolved relative to `page_path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::ParseError`] if the trait declares no such
+    /// method.
+    pub fn parse_trait_method(&self, html: &str, page_path: &str, method: &str) -> Result<TraitMethod, DocsFetchError> {
+        use scraper::{ElementRef, Html, Selector};
+
+        let page_path = page_path.trim_start_matches('/');
+        let document = Html::parse_document(html);
+        let selector = Selector::parse(".methods [id]").unwrap();
+        let header_selector = Selector::parse(".code-header, code").unwrap();
+        let source_selector = Selector::parse("a.src, a.srclink").unwrap();
+
+        let (item, required) = document
+            .select(&selector)
+            .find_map(|item| match item.value().attr("id")?.split_once('.')? {
+                ("tymethod", name) if name == method => Some((item, true)),
+                ("method", name) if name == method => Some((item, false)),
+                _ => None,
+            })
+            .ok_or_else(|| DocsFetchError::ParseError(format!("no method '{}' found on the trait page", method)))?;
+
+        let header = item.select(&header_selector).next().unwrap_or(item);
+        // Current rustdoc puts the docblock next to the method's summary,
+        // older versions next to the method's own element
+        let anchor = item
+            .parent()
+            .and_then(ElementRef::wrap)
+            .filter(|parent| parent.value().name() == "summary")
+            .unwrap_or(item);
+        let docs = anchor
+            .next_siblings()
+            .filter_map(ElementRef::wrap)
+            .next()
+            .filter(|sibling| sibling.value().classes().any(|class| class == "docblock"))
+            .map(|docblock| markdown_blocks(docblock).join("\n\n"))
+            .filter(|docs| !docs.is_empty());
+        let default_source = match required {
+            true => None,
+            false => item
+                .select(&source_selector)
+                .find_map(|link| link.value().attr("href"))
+                .map(|href| resolve_source_link(page_path, href))
+                .transpose()?,
+        };
+
+        Ok(TraitMethod {
+            path: page_path.to_string(),
+            name: method.to_string(),
+            signature: block_aware_text(header),
+            required,
+            docs,
+            default_source,
+        })
+    }
+
+    /// Parses the "Modules" table of a module page.
+    ///
+    /// Both the current `dl.item-table` markup and the older list and
+    /// `item-row` ones are supported. Links are resolved relative to
+    /// `page_path`.
+    pub fn parse_submodules(&self, html: &str, page_path: &str) -> Result<Submodules, DocsFetchError> {
+        use scraper::{ElementRef, Html, Selector};
+
+        let page_path = page_path.trim_start_matches('/');
+        let page_url = url::Url::parse("https://docs.rs/")?.join(page_path)?;
+        let document = Html::parse_document(html);
+        let link_selector = Selector::parse(".item-table a.mod").unwrap();
+        let summary_selector = Selector::parse(".desc, .item-right").unwrap();
+
+        let modules = document.select(&link_selector).filter_map(|link| {
+            let href = link.value().attr("href")?;
+            let row = link.ancestors().filter_map(ElementRef::wrap).find(|row| {
+                matches!(row.value().name(), "dt" | "li") || row.value().classes().any(|class| class == "item-row")
+            });
+            let summary = row.and_then(|row| match row.value().name() {
+                "dt" => row.next_siblings().filter_map(ElementRef::wrap).next().filter(|dd| dd.value().name() == "dd"),
+                _ => row.select(&summary_selector).next(),
+            });
+            Some(Submodule {
+                name: collapse_whitespace(&link.text().collect::<String>()),
+                path: page_url.join(href).ok()?.path().trim_start_matches('/').to_string(),
+                summary: summary
+                    .map(|summary| collapse_whitespace(&summary.text().collect::<String>()))
+                    .filter(|summary| !summary.is_empty()),
+            })
+        });
+
+        Ok(Submodules { path: page_path.to_string(), modules: modules.collect() })
+    }
+
+    /// Parses the declaration shown at the top of an item page, e.g.
+    /// `pub const MAX: u32 = 4_294_967_295u32;`.
+    pub fn parse_item_declaration(&self, html: &str) -> Option<String> {
+        use scraper::{Html, Selector};
+
+        let document = Html::parse_document(html);
+        let selector = Selector::parse("pre.item-decl, .item-decl pre, pre.rust.const, pre.rust.static").unwrap();
+        let declaration = block_aware_text(document.select(&selector).next()?);
+        (!declaration.is_empty()).then_some(declaration)
+    }
+
+    /// Parses the link to an item's source from its page.
+    ///
+    /// Recent rustdoc versions render it as `a.src` in the heading, older
+    /// versions as `a.srclink` in the `.out-of-band` block; the first link
+    /// on the page belongs to the item itself, later ones to its members.
+    /// The link is resolved relative to `page_path`, and a `#start-end` or
+    /// `#line` fragment gives the item's lines.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::ParseError`] if the page has no source link.
+    pub fn parse_source_link(&self, html: &str, page_path: &str) -> Result<SourceLink, DocsFetchError> {
+        use scraper::{Html, Selector};
+
+        let document = Html::parse_document(html);
+        let selector = Selector::parse("a.src, a.srclink").unwrap();
+        let href = document
+            .select(&selector)
+            .find_map(|link| link.value().attr("href"))
+            .ok_or_else(|| DocsFetchError::ParseError("no source link found".to_string()))?;
+        resolve_source_link(page_path, href)
+    }
+
+    /// Extracts lines `start..=end` (1-based, clamped to the file) of the
+    /// code on a rustdoc source page, or the whole file without a range.
+    ///
+    /// Line numbers are left out, whether rustdoc renders them in a column
+    /// of their own or as anchors at the start of each line.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::ParseError`] if the page has no source code
+    /// or the range lies outside of it.
+    pub fn parse_source_lines(&self, html: &str, range: Option<(usize, usize)>) -> Result<(usize, usize, String), DocsFetchError> {
+        use scraper::{Html, Selector};
+
+        let document = Html::parse_document(html);
+        let code_selector = Selector::parse("pre.rust").unwrap();
+        let line_numbers = [Selector::parse("[data-nosnippet], .src-line-numbers, .line-numbers").unwrap()];
+        let code = document
+            .select(&code_selector)
+            .find(|pre| !pre.value().classes().any(|class| class == "src-line-numbers" || class == "line-numbers"))
+            .ok_or_else(|| DocsFetchError::ParseError("no source code found".to_string()))?;
+
+        let mut texts = Vec::new();
+        collect_text(code, &line_numbers, true, &mut texts);
+        let text: String = texts.into_iter().map(|(text, _)| text).collect();
+        let lines: Vec<&str> = text.lines().collect();
+
+        let (start, end) = range.unwrap_or((1, lines.len()));
+        let end = end.min(lines.len());
+        if start == 0 || start > end {
+            return Err(DocsFetchError::ParseError(format!(
+                "lines {}-{} are outside of the {} line source file",
+                start, end, lines.len()
+            )));
+        }
+        Ok((start, end, lines[start - 1..end].join("\n")))
+    }
+
+    /// Parses the entries of directory `dir` from a page of docs.rs's source
+    /// browser. Links to the parent and to other directories are skipped.
+    pub fn parse_source_listing(&self, html: &str, dir: &str) -> Vec<SourceEntry> {
+        use scraper::{Html, Selector};
+
+        let document = Html::parse_document(html);
+        let selector = Selector::parse("a[href]").unwrap();
+        let Ok(base) = url::Url::parse("https://docs.rs/crate/_/_/source/").and_then(|base| base.join(&format!("{}/", dir))) else {
+            return Vec::new();
+        };
+        let prefix = format!("{}/", dir);
+
+        let mut entries: Vec<SourceEntry> = Vec::new();
+        for link in document.select(&selector) {
+            let Some(url) = link.value().attr("href").and_then(|href| base.join(href).ok()) else {
+                continue;
+            };
+            let Some((_, path)) = url.path().split_once("/source/") else {
+                continue;
+            };
+            let Some(name) = path.strip_prefix(&prefix) else {
+                continue;
+            };
+            let is_dir = name.ends_with('/');
+            let name = name.trim_end_matches('/');
+            if name.is_empty() || name.contains('/') || entries.iter().any(|entry| entry.name == name) {
+                continue;
+            }
+            entries.push(SourceEntry { name: name.to_string(), path: format!("{}{}", prefix, name), is_dir });
+        }
+        entries
+    }
+
+    /// Parses the contents of a file from a page of docs.rs's source
+    /// browser.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::ParseError`] if the page shows no file, e.g.
+    /// because the path is a directory.
+    pub fn parse_source_file(&self, html: &str) -> Result<String, DocsFetchError> {
+        use scraper::{Html, Selector};
+
+        let document = Html::parse_document(html);
+        let selector = Selector::parse("#source-code pre").unwrap();
+        let code = document
+            .select(&selector)
+            .next()
+            .ok_or_else(|| DocsFetchError::ParseError("no source file found".to_string()))?;
+        Ok(code.text().collect::<String>().trim_end().to_string())
+    }
+
+    /// Parses the navigation trail (`crate::module::Item`) of an item page.
+    ///
+    /// Recent rustdoc versions render the parent modules in a
+    /// `.rustdoc-breadcrumbs` block above the heading and the item name in
+    /// the heading itself; older versions link every segment inside the
+    /// heading, next to the `.out-of-band` version and source links. Links are
+    /// resolved relative to `page_path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::ParseError`] if the page has no item heading.
+    pub fn parse_breadcrumb(&self, html: &str, page_path: &str) -> Result<Breadcrumb, DocsFetchError> {
+        use scraper::{ElementRef, Html, Selector};
+
+        let page_path = page_path.trim_start_matches('/');
+        let page_url = url::Url::parse("https://docs.rs/")?.join(page_path)?;
+        let resolve = |href: &str| -> String {
+            match page_url.join(href) {
+                Ok(url) if !href.is_empty() && !href.starts_with('#') => url.path().trim_start_matches('/').to_string(),
+                _ => page_path.to_string(),
+            }
+        };
+
+        let document = Html::parse_document(html);
+        let heading_selector = Selector::parse(".main-heading h1, h1.fqn, h1").unwrap();
+        let crumbs_selector = Selector::parse(".rustdoc-breadcrumbs a").unwrap();
+        let legacy_selector = Selector::parse(".in-band > a").unwrap();
+        let link_selector = Selector::parse(":scope > a").unwrap();
+
+        let heading = document
+            .select(&heading_selector)
+            .next()
+            .ok_or_else(|| DocsFetchError::ParseError("no item heading found".to_string()))?;
+
+        let links: Vec<ElementRef> = {
+            let crumbs: Vec<_> = document.select(&crumbs_selector).collect();
+            if !crumbs.is_empty() {
+                crumbs
+            } else {
+                let legacy: Vec<_> = heading.select(&legacy_selector).collect();
+                if legacy.is_empty() { heading.select(&link_selector).collect() } else { legacy }
+            }
+        };
+        let mut segments: Vec<BreadcrumbSegment> = links
+            .into_iter()
+            .map(|link| BreadcrumbSegment {
+                name: collapse_whitespace(&link.text().collect::<String>()),
+                path: resolve(link.value().attr("href").unwrap_or_default()),
+            })
+            .filter(|segment| !segment.name.is_empty())
+            .collect();
+
+        // Recent rustdoc shows the item name as a plain span in the heading
+        if segments.last().is_none_or(|segment| segment.path != page_path) {
+            let item = heading
+                .children()
+                .filter_map(ElementRef::wrap)
+                .find(|child| {
+                    child.value().name() == "span"
+                        && !child.value().classes().any(|class| class == "in-band" || class == "out-of-band")
+                })
+                .map(|span| collapse_whitespace(&span.text().collect::<String>()));
+            if let Some(name) = item.filter(|name| !name.is_empty()) {
+                segments.push(BreadcrumbSegment { name, path: page_path.to_string() });
+            }
+        }
+
+        if segments.is_empty() {
+            return Err(DocsFetchError::ParseError("no breadcrumb found".to_string()));
+        }
+        Ok(Breadcrumb { path: page_path.to_string(), segments })
+    }
+
+    /// Parses the code of the Rust examples in the documentation of a page,
+    /// in page order.
+    ///
+    /// Lines hidden with `#` are not part of rendered pages, so only the
+    /// visible code is returned. Code blocks marked as another language are
+    /// skipped.
+    pub fn parse_examples(&self, html: &str) -> Vec<String> {
+        use scraper::{Html, Selector};
+
+        let document = Html::parse_document(html);
+        let selector = Selector::parse(".docblock pre.rust").unwrap();
+        document
+            .select(&selector)
+            .map(|pre| pre.text().collect::<String>().trim_end().to_string())
+            .filter(|code| !code.trim().is_empty())
+            .collect()
+    }
+
+    /// Parses the crate-level documentation of a crate's index page as
+    /// markdown.
+    ///
+    /// Only the docblock at the top of the page is converted, not the item
+    /// listing that follows it. Returns an empty string for crates without
+    /// crate-level docs.
+    pub fn parse_crate_docs(&self, html: &str) -> String {
+        use scraper::{Html, Selector};
+
+        let document = Html::parse_document(html);
+        // Older rustdoc versions put the docblock directly into the main section
+        let selector = Selector::parse("details.top-doc > .docblock, #main-content > .docblock").unwrap();
+        document
+            .select(&selector)
+            .next()
+            .map(|docblock| markdown_blocks(docblock).join("\n\n"))
+            .unwrap_or_default()
+    }
+
+    /// Parses the deprecation notice of the item documented on a page, if it
+    /// has one.
+    ///
+    /// Only a notice above the page's first section heading counts, so
+    /// deprecated fields, variants or methods of an item that is not
+    /// deprecated itself are ignored. The first link inside the notice is
+    /// taken as the replacement, resolved relative to `page_path`; links to
+    /// other sites are ignored.
+    pub fn parse_deprecation(&self, html: &str, page_path: &str) -> Option<Deprecation> {
+        use scraper::{Html, Selector};
+
+        let page_path = page_path.trim_start_matches('/');
+        let document = Html::parse_document(html);
+        let selector = Selector::parse("h2, .stab.deprecated").unwrap();
+        let link_selector = Selector::parse("a[href]").unwrap();
+
+        let notice = document
+            .select(&selector)
+            .take_while(|element| element.value().name() != "h2")
+            .next()?;
+
+        let text = collapse_whitespace(&notice.text().collect::<String>());
+        let text = text.trim_start_matches(|c: char| !c.is_alphanumeric());
+        let rest = text.strip_prefix("Deprecated").unwrap_or(text).trim_start();
+        let (since, note) = match rest.strip_prefix("since ") {
+            Some(rest) => match rest.split_once(':') {
+                Some((since, note)) => (Some(since.trim().to_string()), note.trim()),
+                None => (Some(rest.trim().to_string()), ""),
+            },
+            None => (None, rest.trim_start_matches(':').trim()),
+        };
+
+        let base = url::Url::parse("https://docs.rs/_/_/").ok()?.join(page_path).ok()?;
+        let replacement = notice
+            .select(&link_selector)
+            .filter_map(|link| base.join(link.value().attr("href")?).ok())
+            .filter(|url| url.host_str() == Some("docs.rs"))
+            .find_map(|url| url.path().strip_prefix("/_/_/").map(str::to_string))
+            .filter(|path| !path.is_empty() && path != page_path);
+
+        Some(Deprecation {
+            since,
+            note: (!note.is_empty()).then(|| note.to_string()),
+            replacement,
+        })
+    }
+
+    /// Parses the links of a page's documentation text to other pages of
+    /// the same crate, in page order and without duplicates.
+    ///
+    /// Links are resolved relative to `page_path`; links to other crates or
+    /// sites, and to sections of the page itself, are skipped.
+    pub fn parse_doc_links(&self, html: &str, page_path: &str) -> Vec<DocLink> {
         use scraper::{Html, Selector};
-        
-        // Parse the HTML document
+
+        let page_path = page_path.trim_start_matches('/');
+        let Some(crate_dir) = page_path.split('/').next().filter(|dir| !dir.is_empty()) else {
+            return Vec::new();
+        };
+        let Some(base) = url::Url::parse("https://docs.rs/_/_/").ok().and_then(|base| base.join(page_path).ok()) else {
+            return Vec::new();
+        };
         let document = Html::parse_document(html);
-        
-        // Create a selector for the rustdoc body wrapper
-        let selector = Selector::parse("#rustdoc_body_wrapper").ok()?;
-        
-        // Find the wrapper element
-        let wrapper = document.select(&selector).next()?;
-        
-        // Get the text content
-        let content = wrapper.text().collect::<Vec<_>>().join(" ");
-        
-        // Clean up the content
-        Some(content)
+        let selector = Selector::parse(".docblock a[href]").unwrap();
+
+        let mut seen = HashSet::new();
+        document
+            .select(&selector)
+            .filter_map(|link| {
+                let url = base.join(link.value().attr("href")?).ok()?;
+                let path = url.path().strip_prefix("/_/_/")?;
+                let in_crate = path.split('/').next() == Some(crate_dir) && path.ends_with(".html");
+                (url.host_str() == Some("docs.rs") && in_crate && path != page_path).then(|| DocLink {
+                    name: collapse_whitespace(&link.text().collect::<String>()),
+                    path: path.to_string(),
+                })
+            })
+            .filter(|link| seen.insert(link.path.clone()))
+            .collect()
     }
-    
-    /// Parses HTML content to extract function signatures, descriptions, and examples.
-    ///
-    /// This is a more detailed parser that attempts to extract structured information
-    /// from the documentation HTML.
-    ///
-    /// # Arguments
-    ///
-    /// * `html` - The raw HTML content to parse
+
+    /// Parses the "Blanket Implementations" section of a type page, i.e.
+    /// the impls of generic traits for any type satisfying their bounds.
+    pub fn parse_blanket_impls(&self, html: &str) -> BlanketImpls {
+        use scraper::{Html, Selector};
+
+        let document = Html::parse_document(html);
+        // Current rustdoc nests each impl in a toggle together with its items
+        let impl_selector = Selector::parse("#blanket-implementations-list .impl").unwrap();
+        let header_selector = Selector::parse(".code-header, code").unwrap();
+
+        let impls = document
+            .select(&impl_selector)
+            .filter_map(|section| {
+                let header = section.select(&header_selector).next()?;
+                let declaration = block_aware_text(header);
+                let trait_name = impl_trait(&declaration)?.to_string();
+                Some(BlanketImpl { trait_name, declaration })
+            })
+            .collect();
+
+        BlanketImpls { impls }
+    }
+
+    /// Parses the "Implementations" section of a type page into its inherent
+    /// impl blocks, each with its generic bounds and methods.
+    pub fn parse_inherent_impls(&self, html: &str) -> InherentImpls {
+        let document = scraper::Html::parse_document(html);
+        InherentImpls { impls: impl_blocks(&document, "#implementations-list .impl") }
+    }
+
+    /// Parses the functions of a type page that look like ways to construct
+    /// the type: associated functions without a `self` receiver named `new`,
+    /// `default`, `builder` or `from`, or starting with `new_`, `from_` or
+    /// `with_`. Both inherent impls and trait impls such as `Default` and
+    /// `From<T>` are searched.
     ///
-    /// # Returns
+    /// This is a heuristic on names only; functions returning something
+    /// other than the type, such as a `Result` or a builder, are included.
+    pub fn parse_construction_hints(&self, html: &str, page_path: &str) -> ConstructionHints {
+        let document = scraper::Html::parse_document(html);
+        let inherent = impl_blocks(&document, "#implementations-list .impl")
+            .into_iter()
+            .map(|block| (None, block));
+        let trait_impls = impl_blocks(&document, "#trait-implementations-list .impl")
+            .into_iter()
+            .map(|block| (impl_trait(&block.declaration).map(str::to_string), block));
+        let hints = inherent
+            .chain(trait_impls)
+            .flat_map(|(trait_name, block)| {
+                block
+                    .methods
+                    .into_iter()
+                    .filter(|method| is_construction_name(&method.name) && !takes_self(&method.signature))
+                    .map(move |method| ConstructionHint {
+                        name: method.name,
+                        signature: method.signature,
+                        trait_name: trait_name.clone(),
+                    })
+            })
+            .collect();
+
+        ConstructionHints { path: page_path.trim_start_matches('/').to_string(), hints }
+    }
+
+    /// Parses the main parts of an item page into a [`ParsedDoc`].
     ///
-    /// Returns a tuple of:
-    /// - Optional function signature
-    /// - Optional description
-    /// - Optional vector of examples
-    #[allow(dead_code)]
-    fn parse_html_content(&self, html: &str) -> Result<(Option<String>, Option<String>, Option<Vec<String>>), DocsFetchError> {
-        // In a real implementation, we would use a proper HTML parser like scraper or html5ever
-        // For simplicity, we'll use basic string matching
-        
-        // Try to extract function signature (in a real impl, would use proper selectors)
-        let function_signature = if let Some(start_idx) = html.find("<pre class=\"rust fn\">") {
-            if let Some(end_idx) = html[start_idx..].find("</pre>") {
-                let signature = &html[start_idx + 21..start_idx + end_idx];
-                Some(signature.to_string())
-            } else {
-                None
+    /// The signature is read from `.item-decl` (or the `pre.rust.fn` of
+    /// older rustdoc versions), the description from the docblock at the top
+    /// of the page, the method headers from `#implementations-list` and the
+    /// examples from the `.example-wrap` blocks of the top docblock, without
+    /// their hidden lines. Parts missing from the page are left empty.
+    pub fn parse_html_content(&self, html: &str) -> ParsedDoc {
+        use scraper::{Html, Selector};
+
+        let document = Html::parse_document(html);
+        let signature_selector = Selector::parse("pre.item-decl, .item-decl pre, pre.rust.fn").unwrap();
+        // Older rustdoc versions put the docblock directly into the main section
+        let docblock_selector = Selector::parse("details.top-doc > .docblock, #main-content > .docblock, #main > .docblock").unwrap();
+        let method_selector = Selector::parse("#implementations-list .method .code-header, #implementations-list .method > code").unwrap();
+        let example_selector = Selector::parse(".example-wrap pre.rust").unwrap();
+
+        let signature = document
+            .select(&signature_selector)
+            .next()
+            .map(block_aware_text)
+            .filter(|signature| !signature.is_empty());
+        let docblock = document.select(&docblock_selector).next();
+        let description = docblock
+            .map(|docblock| markdown_blocks(docblock).join("\n\n"))
+            .filter(|description| !description.is_empty());
+        let methods = document
+            .select(&method_selector)
+            .map(block_aware_text)
+            .filter(|header| !header.is_empty())
+            .collect();
+        let examples = docblock
+            .into_iter()
+            .flat_map(|docblock| docblock.select(&example_selector))
+            .map(|pre| strip_hidden_lines(&pre.text().collect::<String>()).trim_end().to_string())
+            .filter(|code| !code.trim().is_empty())
+            .collect();
+
+        ParsedDoc { signature, description, methods, examples }
+    }
+}
+
+/// Returns the documentation path of the source view page of a file.
+///
+/// `path` is either such a path already, e.g. `src/tokio/sync/mutex.rs.html`,
+/// or the path of the file relative to the crate root, e.g.
+/// `src/sync/mutex.rs`, which rustdoc renders below `src/{library name}/`.
+pub fn source_page_path(crate_name: &str, path: &str) -> String {
+    let path = path.trim_start_matches('/');
+    if path.ends_with(".rs.html") {
+        return path.to_string();
+    }
+    let file = path.strip_prefix("src/").unwrap_or(path);
+    format!("src/{}/{}.html", crate_name.replace('-', "_"), file)
+}
+
+/// File name prefixes of the pages rustdoc writes for items, e.g. `struct`
+/// in `struct.Vec.html`.
+const ITEM_PAGE_PREFIXES: &[&str] = &[
+    "struct", "enum", "trait", "fn", "macro", "type", "constant", "static", "union",
+    "primitive", "keyword", "attr", "derive", "traitalias", "foreigntype",
+];
+
+/// Normalizes the documentation path of a request to a page of rustdoc's
+/// layout, leaving paths that already name a page unchanged.
+///
+/// Deterministic, as rustdoc's layout implies them:
+///
+/// - An empty path becomes the crate's root page, `{library}/index.html`.
+/// - A path ending in `/` gets `index.html` appended.
+/// - A page name with an item kind prefix but no extension, e.g.
+///   `std/vec/struct.Vec`, gets `.html` appended.
+/// - A lowercase last segment without extension, e.g. `std/vec`, is a
+///   module and becomes its `index.html`.
+///
+/// Best effort, as the kind of an item cannot be told from its name:
+///
+/// - Rust paths, e.g. `vec::Vec` or `std::vec::Vec`, and single names are
+///   mapped below the crate's library directory.
+/// - A last segment ending in `!` is taken for a macro, and a capitalized
+///   one for a struct, so enums, traits and type aliases still miss.
+///
+/// Paths with a file extension, query or fragment, such as `all.html` or
+/// `src/tokio/lib.rs.html`, are only stripped of a leading `/`.
+pub fn normalize_doc_path(crate_name: &str, path: &str) -> String {
+    let path = path.trim().trim_start_matches('/');
+    let library = crate_name.trim().to_ascii_lowercase().replace('-', "_");
+    if path.is_empty() {
+        return format!("{}/index.html", library);
+    }
+    if path.ends_with('/') {
+        return format!("{}index.html", path);
+    }
+    if path.contains(['#', '?']) {
+        return path.to_string();
+    }
+
+    let (parent, name) = if path.contains("::") || !path.contains(['/', '.']) {
+        let mut segments: Vec<&str> = path.split("::").filter(|segment| !segment.is_empty()).collect();
+        if segments.first() != Some(&library.as_str()) {
+            segments.insert(0, &library);
+        }
+        let name = segments.pop().unwrap_or_default();
+        (segments.join("/"), name)
+    } else {
+        match path.rsplit_once('/') {
+            Some((parent, name)) => (parent.to_string(), name),
+            None => (String::new(), path),
+        }
+    };
+    let page = match name.split_once('.') {
+        Some((prefix, item)) if ITEM_PAGE_PREFIXES.contains(&prefix) && !item.contains('.') => format!("{}.html", name),
+        Some(_) => name.to_string(),
+        None => match name.strip_suffix('!') {
+            Some(mac) => format!("macro.{}.html", mac),
+            None if name.starts_with(|c: char| c.is_uppercase()) => format!("struct.{}.html", name),
+            None => format!("{}/index.html", name),
+        },
+    };
+    match parent.is_empty() {
+        true => page,
+        false => format!("{}/{}", parent, page),
+    }
+}
+
+/// Resolves the source link `href` of the page at `page_path`, reading the
+/// item's lines from a `#start-end` or `#line` fragment.
+fn resolve_source_link(page_path: &str, href: &str) -> Result<SourceLink, DocsFetchError> {
+    let page_url = url::Url::parse("https://docs.rs/")?.join(page_path.trim_start_matches('/'))?;
+    let source_url = page_url.join(href)?;
+    let lines: Vec<Option<usize>> = source_url
+        .fragment()
+        .unwrap_or_default()
+        .splitn(2, '-')
+        .map(|line| line.parse().ok())
+        .collect();
+    let start_line = lines.first().copied().flatten();
+    let end_line = lines.get(1).copied().flatten().or(start_line);
+
+    Ok(SourceLink {
+        path: source_url.path().trim_start_matches('/').to_string(),
+        start_line,
+        end_line,
+    })
+}
+
+/// Collects the text nodes below `element` in document order, along with
+/// whether they are inside a `<pre>` block, skipping elements matching any
+/// of `skip` with everything they contain.
+fn collect_text<'a>(element: scraper::ElementRef<'a>, skip: &[scraper::Selector], in_pre: bool, texts: &mut Vec<(&'a str, bool)>) {
+    for child in element.children() {
+        match child.value() {
+            scraper::Node::Text(text) => texts.push((text, in_pre)),
+            scraper::Node::Element(value) => {
+                let child = scraper::ElementRef::wrap(child).unwrap();
+                if !skip.iter().any(|selector| selector.matches(&child)) {
+                    collect_text(child, skip, in_pre || value.name() == "pre", texts);
+                }
             }
-        } else {
-            None
+            _ => {}
+        }
+    }
+}
+
+/// Joins text collected by [`collect_text`] with spaces. With `normalize`,
+/// text outside `<pre>` blocks is passed through [`normalize_lines`].
+fn join_text(texts: &[(&str, bool)], normalize: bool) -> String {
+    let join = |texts: &[(&str, bool)]| texts.iter().map(|(text, _)| *text).collect::<Vec<_>>().join(" ");
+    if !normalize {
+        return join(texts);
+    }
+    texts
+        .chunk_by(|a, b| a.1 == b.1)
+        .map(|group| if group[0].1 { join(group) } else { normalize_lines(&join(group)) })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Strips trailing whitespace from every line and collapses runs of three
+/// or more newlines into two.
+fn normalize_lines(text: &str) -> String {
+    let mut lines = text.split('\n').map(str::trim_end);
+    let mut normalized = lines.next().unwrap_or_default().to_string();
+    let mut newlines = 0;
+    for line in lines {
+        newlines += 1;
+        if !line.is_empty() {
+            normalized.push_str(&"\n".repeat(newlines.min(2)));
+            normalized.push_str(line);
+            newlines = 0;
+        }
+    }
+    normalized.push_str(&"\n".repeat(newlines.min(2)));
+    normalized
+}
+
+/// Whether an element of a docblock is decoration added by rustdoc.
+fn is_doc_decoration(element: scraper::ElementRef<'_>) -> bool {
+    let value = element.value();
+    matches!(value.name(), "script" | "style" | "button")
+        || value.classes().any(|class| matches!(class, "doc-anchor" | "anchor" | "tooltip" | "hideme"))
+}
+
+/// Detaches every element matching one of `selectors`, with everything it
+/// contains, from `document`.
+fn remove_matching(document: &mut scraper::Html, selectors: &[scraper::Selector]) {
+    let ids: Vec<_> = selectors
+        .iter()
+        .flat_map(|selector| document.select(selector).map(|element| element.id()).collect::<Vec<_>>())
+        .collect();
+    for id in ids {
+        if let Some(mut node) = document.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+}
+
+/// Converts the block content of a docblock element to markdown blocks.
+///
+/// Inline content between blocks becomes a paragraph of its own.
+fn markdown_blocks(element: scraper::ElementRef<'_>) -> Vec<String> {
+    use scraper::{ElementRef, Node};
+
+    let mut blocks = Vec::new();
+    let mut inline = String::new();
+    let flush = |inline: &mut String, blocks: &mut Vec<String>| {
+        let paragraph = inline.trim();
+        if !paragraph.is_empty() {
+            blocks.push(paragraph.to_string());
+        }
+        inline.clear();
+    };
+    for child in element.children() {
+        let child_element = match child.value() {
+            Node::Text(text) => {
+                push_markdown_text(&mut inline, text);
+                continue;
+            }
+            Node::Element(_) => ElementRef::wrap(child).unwrap(),
+            _ => continue,
         };
-        
-        // Try to extract description
-        let description = if let Some(start_idx) = html.find("<div class=\"docblock\">") {
-            if let Some(end_idx) = html[start_idx..].find("</div>") {
-                let desc = &html[start_idx + 22..start_idx + end_idx];
-                Some(desc.to_string())
-            } else {
-                None
+        if is_doc_decoration(child_element) {
+            continue;
+        }
+        let block = match child_element.value().name() {
+            "p" => Some(inline_markdown(child_element).trim().to_string()),
+            name @ ("h1" | "h2" | "h3" | "h4" | "h5" | "h6") => {
+                let level = name[1..].parse().unwrap_or(1);
+                // Headings of items and impls are declarations
+                let heading = match child_element.value().classes().any(|class| class == "code-header") {
+                    true => format!("`{}`", block_aware_text(child_element)),
+                    false => inline_markdown(child_element).trim().to_string(),
+                };
+                Some(format!("{} {}", "#".repeat(level), heading))
             }
-        } else {
-            None
+            "pre" => {
+                let classes: Vec<_> = child_element.value().classes().collect();
+                let code = child_element.text().collect::<String>();
+                let (language, code) = match classes.iter().find_map(|class| class.strip_prefix("language-")) {
+                    _ if classes.contains(&"rust") => ("rust", strip_hidden_lines(&code)),
+                    Some(language) => (language, code),
+                    None => ("", code),
+                };
+                Some(format!("```{}\n{}\n```", language, code.trim_end()))
+            }
+            list @ ("ul" | "ol") => Some(markdown_list(child_element, list == "ol")),
+            "blockquote" => {
+                let quoted = markdown_blocks(child_element).join("\n\n");
+                Some(quoted.lines().map(|line| format!("> {}", line).trim_end().to_string()).collect::<Vec<_>>().join("\n"))
+            }
+            "table" => Some(markdown_table(child_element)),
+            "hr" => Some("---".to_string()),
+            "div" | "section" | "details" | "summary" | "main" | "dl" | "dd" | "dt" => {
+                flush(&mut inline, &mut blocks);
+                blocks.extend(markdown_blocks(child_element));
+                continue;
+            }
+            _ => None,
         };
-        
-        // Try to extract examples
-        let mut examples = Vec::new();
-        
-        if let Some(start_idx) = html.find("<h3>Examples</h3>") {
-            if let Some(example_block_start) = html[start_idx..].find("<pre class=\"rust\">") {
-                let abs_start = start_idx + example_block_start;
-                if let Some(example_block_end) = html[abs_start..].find("</pre>") {
-                    let example = &html[abs_start + 18..abs_start + example_block_end];
-                    examples.push(example.to_string());
+        match block {
+            Some(block) => {
+                flush(&mut inline, &mut blocks);
+                if !block.trim().is_empty() {
+                    blocks.push(block);
                 }
             }
+            None => inline.push_str(&inline_markdown_element(child_element)),
         }
-        
-        let examples_option = if examples.is_empty() {
-            None
+    }
+    flush(&mut inline, &mut blocks);
+    blocks
+}
+
+/// Appends text to inline markdown, collapsing whitespace.
+fn push_markdown_text(inline: &mut String, text: &str) {
+    let collapsed = collapse_whitespace(text);
+    if text.starts_with(char::is_whitespace) && !inline.is_empty() && !inline.ends_with([' ', '\n']) {
+        inline.push(' ');
+    }
+    inline.push_str(&collapsed);
+    if text.ends_with(char::is_whitespace) && !collapsed.is_empty() {
+        inline.push(' ');
+    }
+}
+
+/// Converts the content of an element to inline markdown.
+fn inline_markdown(element: scraper::ElementRef<'_>) -> String {
+    use scraper::{ElementRef, Node};
+
+    let mut inline = String::new();
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => push_markdown_text(&mut inline, text),
+            Node::Element(_) => {
+                let child = ElementRef::wrap(child).unwrap();
+                if !is_doc_decoration(child) {
+                    inline.push_str(&inline_markdown_element(child));
+                }
+            }
+            _ => {}
+        }
+    }
+    inline
+}
+
+/// Converts an inline element, such as a link or code span, to markdown.
+fn inline_markdown_element(element: scraper::ElementRef<'_>) -> String {
+    let value = element.value();
+    match value.name() {
+        "code" => format!("`{}`", element.text().collect::<String>()),
+        "strong" | "b" => format!("**{}**", inline_markdown(element).trim()),
+        "em" | "i" => format!("*{}*", inline_markdown(element).trim()),
+        "br" => "\n".to_string(),
+        "a" => match value.attr("href") {
+            Some(href) if href != "#" => format!("[{}]({})", inline_markdown(element).trim(), href),
+            _ => inline_markdown(element),
+        },
+        _ => inline_markdown(element),
+    }
+}
+
+/// Converts a list to markdown, indenting the continuation lines of items.
+fn markdown_list(list: scraper::ElementRef<'_>, ordered: bool) -> String {
+    use scraper::ElementRef;
+
+    list.children()
+        .filter_map(ElementRef::wrap)
+        .filter(|item| item.value().name() == "li")
+        .enumerate()
+        .map(|(index, item)| {
+            let marker = match ordered {
+                true => format!("{}. ", index + 1),
+                false => "- ".to_string(),
+            };
+            let indent = " ".repeat(marker.len());
+            let content = markdown_blocks(item).join("\n");
+            content
+                .lines()
+                .enumerate()
+                .map(|(line_index, line)| match line_index {
+                    0 => format!("{}{}", marker, line),
+                    _ if line.is_empty() => String::new(),
+                    _ => format!("{}{}", indent, line),
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Converts a table to a markdown table, taking its first row as header.
+fn markdown_table(table: scraper::ElementRef<'_>) -> String {
+    use scraper::Selector;
+
+    let row_selector = Selector::parse("tr").unwrap();
+    let cell_selector = Selector::parse("th, td").unwrap();
+    let mut lines = Vec::new();
+    for (index, row) in table.select(&row_selector).enumerate() {
+        let cells: Vec<_> = row
+            .select(&cell_selector)
+            .map(|cell| inline_markdown(cell).trim().replace('|', "\\|"))
+            .collect();
+        lines.push(format!("| {} |", cells.join(" | ")));
+        if index == 0 {
+            lines.push(format!("|{}", " --- |".repeat(cells.len())));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Collapses runs of whitespace into single spaces and trims the ends.
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Replaces `latest` with `version` in docs.rs links to the documentation or
+/// crate page of `crate_name` in `content`, so that they keep pointing at the
+/// version the content was read from. Links to other crates are left alone,
+/// as their concrete version is not known.
+pub fn pin_latest_links(content: &str, crate_name: &str, version: &str) -> String {
+    let mut names = vec![crate_name.to_string(), crate_name.replace('-', "_"), crate_name.replace('_', "-")];
+    names.dedup();
+
+    let mut pinned = content.to_string();
+    for name in &names {
+        for prefix in [format!("docs.rs/{}/", name), format!("docs.rs/crate/{}/", name)] {
+            let latest = format!("{}latest", prefix);
+            let mut rewritten = String::with_capacity(pinned.len());
+            let mut rest = pinned.as_str();
+            while let Some(start) = rest.find(&latest) {
+                let end = start + latest.len();
+                // Only a whole path segment, not e.g. `latest-changes`
+                let whole = rest[end..]
+                    .chars()
+                    .next()
+                    .is_none_or(|c| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.')));
+                rewritten.push_str(&rest[..start]);
+                match whole {
+                    true => {
+                        rewritten.push_str(&prefix);
+                        rewritten.push_str(version);
+                    }
+                    false => rewritten.push_str(&latest),
+                }
+                rest = &rest[end..];
+            }
+            rewritten.push_str(rest);
+            pinned = rewritten;
+        }
+    }
+    pinned
+}
+
+/// Removes the lines of a Rust doc example that rustdoc hides: those
+/// starting with `# ` or consisting of a lone `#`. Lines starting with the
+/// escape `##` are kept with one `#` removed, as rustdoc renders them.
+fn strip_hidden_lines(code: &str) -> String {
+    code.lines()
+        .filter_map(|line| {
+            let trimmed = line.trim_start();
+            let indent = &line[..line.len() - trimmed.len()];
+            if trimmed == "#" || trimmed.starts_with("# ") {
+                None
+            } else if let Some(rest) = trimmed.strip_prefix("##") {
+                Some(format!("{}#{}", indent, rest))
+            } else {
+                Some(line.to_string())
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Splits a constant or static declaration such as `pub const MAX: u32 = 5;`
+/// into its type and value, either of which may be missing.
+fn split_const_declaration(declaration: &str) -> (Option<String>, Option<String>) {
+    let declaration = declaration.trim().trim_end_matches(';');
+    let Some((_, rest)) = declaration.split_once(':') else {
+        return (None, None);
+    };
+    let non_empty = |text: &str| Some(text.trim().to_string()).filter(|text| !text.is_empty());
+    match rest.split_once(" = ") {
+        Some((ty, value)) => (non_empty(ty), non_empty(value)),
+        None => (non_empty(rest), None),
+    }
+}
+
+/// Collects the text of an element, separating block-level children such as
+/// rustdoc's `<div class="where">` clauses with a space, and collapses
+/// whitespace.
+fn block_aware_text(element: scraper::ElementRef<'_>) -> String {
+    let mut text = String::new();
+    for node in element.descendants() {
+        match node.value() {
+            scraper::Node::Text(t) => text.push_str(t),
+            scraper::Node::Element(e) if matches!(e.name(), "div" | "br" | "p") => text.push(' '),
+            _ => {}
+        }
+    }
+    collapse_whitespace(&text)
+}
+
+/// Which documentation block a node of a page belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DocContext {
+    /// Outside any documentation block
+    Page,
+    /// The documentation of the page's item
+    Item,
+    /// The documentation of a member, e.g. a method or trait impl
+    Member,
+}
+
+/// Collects the text of a page into sections, see
+/// [`DocsRsClient::parse_sections`].
+#[derive(Debug, Default)]
+struct SectionWriter {
+    sections: Vec<PageSection>,
+    current: usize,
+}
+
+impl SectionWriter {
+    /// Makes `name` the section text is appended to, reopening an earlier
+    /// section of the same name.
+    fn start(&mut self, name: &str) {
+        self.current = match self.sections.iter().position(|section| section.name == name) {
+            Some(index) => index,
+            None => {
+                self.sections.push(PageSection { name: name.to_string(), content: String::new() });
+                self.sections.len() - 1
+            }
+        };
+        self.break_line();
+    }
+
+    fn buffer(&mut self) -> &mut String {
+        &mut self.sections[self.current].content
+    }
+
+    /// Appends inline text, collapsing whitespace.
+    fn push_inline(&mut self, text: &str) {
+        let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let buffer = self.buffer();
+        let at_line_start = buffer.is_empty() || buffer.ends_with('\n');
+        if text.starts_with(char::is_whitespace) && !at_line_start && !buffer.ends_with(' ') {
+            buffer.push(' ');
+        }
+        buffer.push_str(&collapsed);
+        if text.ends_with(char::is_whitespace) && !collapsed.is_empty() {
+            buffer.push(' ');
+        }
+    }
+
+    /// Ends the current line, if it has any text.
+    fn break_line(&mut self) {
+        let buffer = self.buffer();
+        let trimmed = buffer.trim_end_matches(' ').len();
+        buffer.truncate(trimmed);
+        if !buffer.is_empty() && !buffer.ends_with('\n') {
+            buffer.push('\n');
+        }
+    }
+
+    fn walk_children(&mut self, element: scraper::ElementRef<'_>, root: scraper::ElementRef<'_>, context: DocContext, in_pre: bool) {
+        use scraper::{ElementRef, Node};
+
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) if in_pre => self.buffer().push_str(text),
+                Node::Text(text) => self.push_inline(text),
+                Node::Element(_) => self.walk(ElementRef::wrap(child).unwrap(), root, context, in_pre),
+                _ => {}
+            }
+        }
+    }
+
+    fn walk(&mut self, element: scraper::ElementRef<'_>, root: scraper::ElementRef<'_>, context: DocContext, in_pre: bool) {
+        use scraper::ElementRef;
+
+        let value = element.value();
+        let name = value.name();
+        let has_class = |class: &str| value.classes().any(|c| c == class);
+
+        // Page chrome and controls carry no documentation
+        if matches!(name, "script" | "style" | "button" | "nav" | "noscript" | "rustdoc-toolbar")
+            || ["main-heading", "sub-heading", "out-of-band", "hideme", "anchor", "doc-anchor", "rightside", "src"]
+                .iter()
+                .any(|class| has_class(class))
+        {
+            return;
+        }
+
+        let heading = matches!(name, "h1" | "h2" | "h3" | "h4" | "h5" | "h6");
+        let section_heading = match context {
+            DocContext::Page => heading && (has_class("section-header") || has_class("small-section-header")),
+            DocContext::Item => heading,
+            DocContext::Member => false,
+        };
+        if section_heading {
+            let title = collapse_whitespace(&element.text().collect::<String>().replace('§', ""));
+            if !title.is_empty() {
+                self.start(&title);
+            }
+            return;
+        }
+
+        if name == "pre" && (has_class("item-decl") || context == DocContext::Page && self.sections.len() == 1) {
+            let summary = self.current;
+            self.start("Declaration");
+            self.buffer().push_str(element.text().collect::<String>().trim());
+            self.current = summary;
+            return;
+        }
+
+        // Doc examples may contain the hidden setup lines rustdoc leaves out
+        if name == "pre" && has_class("rust") && context != DocContext::Page {
+            self.break_line();
+            self.buffer().push_str(&strip_hidden_lines(&element.text().collect::<String>()));
+            self.break_line();
+            return;
+        }
+
+        let context = if context == DocContext::Page && has_class("docblock") {
+            let parent = element.parent().and_then(ElementRef::wrap);
+            let top_level = parent.is_some_and(|p| p == root || p.value().classes().any(|c| c == "top-doc"));
+            if top_level { DocContext::Item } else { DocContext::Member }
         } else {
-            Some(examples)
+            context
         };
-        
-        Ok((function_signature, description, examples_option))
+        let block = matches!(
+            name,
+            "p" | "div" | "pre" | "li" | "ul" | "ol" | "section" | "details" | "summary" | "table" | "tr"
+                | "br" | "dt" | "dd" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "blockquote"
+        );
+        if block {
+            self.break_line();
+        }
+        self.walk_children(element, root, context, in_pre || name == "pre");
+        if block {
+            self.break_line();
+        }
+    }
+
+    /// Returns the non-empty sections with trimmed content.
+    fn finish(self) -> Vec<PageSection> {
+        self.sections
+            .into_iter()
+            .map(|section| PageSection { content: section.content.trim().to_string(), ..section })
+            .filter(|section| !section.content.is_empty())
+            .collect()
+    }
+}
+
+/// Returns the implementing type of an impl header, i.e. the part after the
+/// last top-level ` for ` and before any `where` clause.
+fn impl_target(declaration: &str) -> Option<&str> {
+    let target = &declaration[last_top_level_for(declaration)? + 5..];
+    Some(target.split(" where ").next().unwrap_or(target).trim())
+}
+
+/// Parses the impl blocks matched by `selector`, each with its generic
+/// bounds and methods.
+fn impl_blocks(document: &scraper::Html, selector: &str) -> Vec<InherentImpl> {
+    use scraper::{ElementRef, Selector};
+
+    let impl_selector = Selector::parse(selector).unwrap();
+    let header_selector = Selector::parse(".code-header, code").unwrap();
+    let items_selector = Selector::parse(".impl-items").unwrap();
+    let method_selector = Selector::parse(".method").unwrap();
+    let name_selector = Selector::parse(".fn").unwrap();
+
+    document
+        .select(&impl_selector)
+        .filter_map(|section| {
+            let header = section.select(&header_selector).next()?;
+            let declaration = block_aware_text(header);
+            let (generics, where_clause) = impl_constraints(&declaration);
+
+            // Current rustdoc puts the items next to the impl's summary
+            // in a toggle, older versions right after the impl header
+            let toggle = section
+                .parent()
+                .and_then(ElementRef::wrap)
+                .filter(|parent| parent.value().name() == "summary")
+                .and_then(|summary| summary.parent())
+                .and_then(ElementRef::wrap);
+            let items = match toggle {
+                Some(toggle) => toggle
+                    .children()
+                    .filter_map(ElementRef::wrap)
+                    .find(|child| items_selector.matches(child)),
+                None => section
+                    .next_siblings()
+                    .filter_map(ElementRef::wrap)
+                    .next()
+                    .filter(|sibling| items_selector.matches(sibling)),
+            };
+
+            let methods = items
+                .into_iter()
+                .flat_map(|items| items.select(&method_selector))
+                .filter_map(|method| {
+                    let header = method.select(&header_selector).next()?;
+                    let name = match header.select(&name_selector).next() {
+                        Some(name) => name.text().collect::<String>(),
+                        None => method.value().id()?.strip_prefix("method.")?.to_string(),
+                    };
+                    Some(InherentMethod { name, signature: block_aware_text(header) })
+                })
+                .collect();
+
+            Some(InherentImpl { declaration, generics, where_clause, methods })
+        })
+        .collect()
+}
+
+/// Whether a function name suggests that it constructs its type, see
+/// [`DocsRsClient::parse_construction_hints`].
+fn is_construction_name(name: &str) -> bool {
+    matches!(name, "new" | "default" | "builder" | "from")
+        || ["new_", "from_", "with_"].iter().any(|prefix| name.starts_with(prefix))
+}
+
+/// Whether a function signature such as `pub fn get(&'a self) -> T` has a
+/// `self` receiver.
+fn takes_self(signature: &str) -> bool {
+    let Some((_, params)) = signature.split_once('(') else {
+        return false;
+    };
+    let first = params.split([',', ')']).next().unwrap_or_default().trim();
+    let first = first.trim_start_matches('&').trim_start();
+    let first = match first.strip_prefix('\'') {
+        Some(lifetime) => lifetime.split_once(' ').map_or("", |(_, rest)| rest).trim_start(),
+        None => first,
+    };
+    let first = first.strip_prefix("mut ").unwrap_or(first).trim_start();
+    first == "self" || first.starts_with("self:") || first.starts_with("self :")
+}
+
+/// Returns the implemented trait of an impl header, i.e. the part between
+/// the impl generics and the last top-level ` for `, with a leading `!` for
+/// negative impls.
+fn impl_trait(declaration: &str) -> Option<&str> {
+    let rest = declaration.trim_start().strip_prefix("impl")?;
+    let generics_end = generics_len(rest);
+    let for_at = last_top_level_for(rest)?;
+    (generics_end <= for_at).then(|| rest[generics_end..for_at].trim())
+}
+
+/// Returns the length in bytes of the generic parameter list `<...>` at the
+/// start of `rest`, or 0 if it does not start with one.
+fn generics_len(rest: &str) -> usize {
+    if !rest.starts_with('<') {
+        return 0;
+    }
+    let mut depth = 0i32;
+    for (i, c) in rest.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return i + 1;
+        }
+    }
+    0
+}
+
+/// Splits an inherent impl header such as `impl<T: Clone> Wrapper<T> where
+/// T: Send,` into its generic parameters and its where clause, both without
+/// their delimiters.
+fn impl_constraints(declaration: &str) -> (Option<String>, Option<String>) {
+    let rest = declaration.trim_start().strip_prefix("impl").unwrap_or_default();
+    let generics_end = generics_len(rest);
+    let generics = (generics_end > 2).then(|| rest[1..generics_end - 1].trim().to_string());
+    let where_clause = rest[generics_end..]
+        .split_once(" where ")
+        .map(|(_, clause)| clause.trim().trim_end_matches(',').trim_end().to_string())
+        .filter(|clause| !clause.is_empty());
+    (generics, where_clause)
+}
+
+/// Returns the byte offset of the last ` for ` outside any brackets.
+fn last_top_level_for(declaration: &str) -> Option<usize> {
+    let mut depth = 0i32;
+    let mut found = None;
+    for (i, c) in declaration.char_indices() {
+        match c {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth -= 1,
+            _ if depth == 0 && declaration[i..].starts_with(" for ") => found = Some(i),
+            _ => {}
+        }
     }
+    found
 }
 
 #[cfg(test)]
@@ -276,6 +3460,118 @@ mod tests {
         assert!(doc_content.content.contains("sleep") || doc_content.content.contains("test description"));
     }
 
+    #[tokio::test]
+    async fn test_fetch_docs_reports_error_pages_as_not_found() {
+        let mut server = Server::new_async().await;
+        server.mock("GET", "/demo/9.9.9/demo/index.html")
+            .with_status(200)
+            .with_body(r#"<!DOCTYPE html><html><head><title>Version not found -- Docs.rs</title></head>
+                <body><div class="container"><div class="notification is-danger">
+                <div class="title">Version not found</div>The requested version does not exist</div></div></body></html>"#)
+            .create();
+        server.mock("GET", "/demo/1.0.0/demo/landing.html")
+            .with_status(200)
+            .with_body("<html><head><title>Welcome</title></head><body><h1>Welcome</h1><p>Pick a crate.</p></body></html>")
+            .create();
+
+        let client = DocsRsClient::new_with_base_url(&server.url());
+        let params = |version: &str, path: &str| DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: version.to_string(),
+            path: path.to_string(),
+        };
+
+        let result = client.fetch_docs(params("9.9.9", "demo/index.html")).await;
+        assert!(matches!(result, Err(DocsFetchError::DocsNotFound)), "got {:?}", result);
+
+        // Other pages without documentation are not mistaken for errors
+        let doc = client.fetch_docs(params("1.0.0", "demo/landing.html")).await.unwrap();
+        assert!(doc.content.starts_with("Documentation available at "), "got {:?}", doc.content);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_docs_follows_redirect_stubs() {
+        let mut server = Server::new_async().await;
+        server.mock("GET", "/demo/1.0.0/demo/struct.Old.html")
+            .with_status(200)
+            .with_body(r#"<!DOCTYPE html><html><head>
+                <meta http-equiv="refresh" content="0;URL=../demo/inner/struct.New.html">
+                <title>Redirection</title></head>
+                <body><p>Redirecting to <a href="../demo/inner/struct.New.html">../demo/inner/struct.New.html</a>...</p></body></html>"#)
+            .create();
+        server.mock("GET", "/demo/1.0.0/demo/inner/struct.New.html")
+            .with_status(200)
+            .with_body(r#"<html><head><link rel="canonical" href="https://docs.rs/demo/latest/demo/inner/struct.New.html"></head>
+                <body><div id="rustdoc_body_wrapper">New docs</div></body></html>"#)
+            .create();
+        server.mock("GET", "/demo/1.0.0/demo/struct.Outside.html")
+            .with_status(200)
+            .with_body(r#"<meta http-equiv="refresh" content="0;URL=https://example.com/elsewhere.html">"#)
+            .create();
+
+        let client = DocsRsClient::new_with_base_url(&server.url());
+        let params = |path: &str| DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            path: path.to_string(),
+        };
+
+        let (doc, canonical_path) = client.fetch_docs_following_redirects(params("demo/struct.Old.html"), OutputFormat::PlainText).await.unwrap();
+        assert_eq!(doc.content, "New docs");
+        assert_eq!(canonical_path.as_deref(), Some("demo/inner/struct.New.html"));
+
+        // Pages with content ignore their canonical link, stubs leaving the crate are not followed
+        let (_, canonical_path) = client.fetch_docs_following_redirects(params("demo/inner/struct.New.html"), OutputFormat::PlainText).await.unwrap();
+        assert_eq!(canonical_path, None);
+        let (_, canonical_path) = client.fetch_docs_following_redirects(params("demo/struct.Outside.html"), OutputFormat::PlainText).await.unwrap();
+        assert_eq!(canonical_path, None);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_latest_version() {
+        let mut server = Server::new_async().await;
+        server.mock("GET", "/demo/latest/")
+            .with_status(302)
+            .with_header("location", "/demo/1.2.3/demo/")
+            .create();
+        // Without a redirect to a concrete version, crates.io is asked
+        server.mock("GET", "/other/latest/")
+            .with_status(200)
+            .with_body("Other")
+            .create();
+        server.mock("GET", "/api/v1/crates/other")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"other","max_stable_version":"0.4.0","newest_version":"0.5.0-rc.1"}}"#)
+            .create();
+
+        let client = DocsRsClient::new_with_base_url(&server.url()).with_crates_io_url(&server.url());
+        assert_eq!(client.resolve_latest_version("demo").await.unwrap(), "1.2.3");
+        assert_eq!(client.resolve_latest_version("other").await.unwrap(), "0.4.0");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_docs_leaves_latest_unresolved() {
+        let mut server = Server::new_async().await;
+        let redirect = server.mock("GET", "/demo/latest/").expect(0).create();
+        let page = server.mock("GET", "/demo/latest/demo/index.html")
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper">Demo latest</div>"#)
+            .expect(2)
+            .create();
+
+        let client = DocsRsClient::new_with_base_url(&server.url()).with_crates_io_url(&server.url());
+        for version in ["latest", ""] {
+            let doc = client.fetch_docs(DocsRsParams {
+                crate_name: "demo".to_string(),
+                version: version.to_string(),
+                path: "demo/index.html".to_string(),
+            }).await.unwrap();
+            assert_eq!(doc.content, "Demo latest");
+        }
+        redirect.assert();
+        page.assert();
+    }
+
     #[tokio::test]
     async fn test_fetch_docs_real_server() {
         let client = DocsRsClient::new();
@@ -340,6 +3636,1011 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_resolve_crate_name() {
+        let mut server = Server::new_async().await;
+        let m = server.mock("GET", "/api/v1/crates/tokio_util")
+            .match_header("user-agent", mockito::Matcher::Regex("^rdoc-mcp/".to_string()))
+            .with_status(200)
+            .with_body(r#"{"crate":{"id":"tokio-util","name":"tokio-util"},"versions":[]}"#)
+            .create();
+        let missing = server.mock("GET", "/api/v1/crates/nope")
+            .with_status(404)
+            .create();
+
+        let client = DocsRsClient::new().with_crates_io_url(&server.url());
+        assert_eq!(client.resolve_crate_name("tokio_util").await.unwrap(), "tokio-util");
+        assert!(matches!(client.resolve_crate_name("nope").await, Err(DocsFetchError::DocsNotFound)));
+        m.assert();
+        missing.assert();
+    }
+
+    #[tokio::test]
+    async fn test_crates_io_requests_encode_crate_names() {
+        let mut server = Server::new_async().await;
+        let encoded = server.mock("GET", "/api/v1/crates/a%3Fb%23c%2Fd")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"a?b#c/d"}}"#)
+            .create();
+
+        let client = DocsRsClient::new().with_crates_io_url(&server.url());
+        assert_eq!(client.resolve_crate_name("a?b#c/d").await.unwrap(), "a?b#c/d");
+        encoded.assert();
+    }
+
+    #[tokio::test]
+    async fn test_fetch_docs_service_unavailable() {
+        let mut server = Server::new_async().await;
+        server.mock("GET", "/down/1.0.0/down/index.html")
+            .with_status(503)
+            .with_body("<html><body><h1>Service Unavailable</h1></body></html>")
+            .create();
+        server.mock("GET", "/maintenance/1.0.0/maintenance/index.html")
+            .with_status(502)
+            .with_body("<html><body><h1>docs.rs is down for maintenance</h1></body></html>")
+            .create();
+        server.mock("GET", "/missing/1.0.0/missing/index.html")
+            .with_status(404)
+            .create();
+        server.mock("GET", "/broken/1.0.0/broken/index.html")
+            .with_status(500)
+            .with_body("<html><body><h1>Internal Server Error</h1></body></html>")
+            .create();
+        server.mock("GET", "/gateway/1.0.0/gateway/index.html")
+            .with_status(502)
+            .create();
+
+        let client = DocsRsClient::new_with_base_url(&server.url());
+        let fetch = |crate_name: &str| {
+            client.fetch_docs(DocsRsParams {
+                crate_name: crate_name.to_string(),
+                version: "1.0.0".to_string(),
+                path: format!("{}/index.html", crate_name),
+            })
+        };
+
+        let err = fetch("down").await.unwrap_err();
+        assert!(matches!(err, DocsFetchError::ServiceUnavailable));
+        assert!(err.is_retryable());
+
+        let err = fetch("maintenance").await.unwrap_err();
+        assert!(matches!(err, DocsFetchError::ServiceUnavailable));
+
+        let err = fetch("missing").await.unwrap_err();
+        assert!(matches!(err, DocsFetchError::DocsNotFound));
+        assert!(!err.is_retryable());
+
+        // Other server errors are transient, not a missing crate
+        let err = fetch("broken").await.unwrap_err();
+        assert!(matches!(err, DocsFetchError::UpstreamError(500)), "got {:?}", err);
+        assert!(err.is_retryable());
+        let err = fetch("gateway").await.unwrap_err();
+        assert!(matches!(err, DocsFetchError::UpstreamError(502)), "got {:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_tags_from_crates_io() {
+        let mut server = Server::new_async().await;
+        server.mock("GET", "/api/v1/crates/tokio")
+            .with_status(200)
+            .with_body(r#"{
+                "categories": [{"id": "asynchronous", "category": "Asynchronous", "slug": "asynchronous"}],
+                "crate": {
+                    "id": "tokio",
+                    "name": "tokio",
+                    "categories": ["asynchronous", "network-programming"],
+                    "keywords": ["io", "async", "non-blocking", "futures"]
+                },
+                "keywords": [{"id": "io", "keyword": "io"}]
+            }"#)
+            .create();
+        server.mock("GET", "/api/v1/crates/bare")
+            .with_status(200)
+            .with_body(r#"{"crate": {"id": "bare", "name": "bare", "categories": null}}"#)
+            .create();
+
+        let client = DocsRsClient::new().with_crates_io_url(&server.url());
+        let tags = client.fetch_tags("tokio").await.unwrap();
+        assert_eq!(tags.categories, vec!["asynchronous", "network-programming"]);
+        assert_eq!(tags.keywords, vec!["io", "async", "non-blocking", "futures"]);
+
+        let bare = client.fetch_tags("bare").await.unwrap();
+        assert_eq!(bare, CrateTags { crate_name: "bare".to_string(), ..CrateTags::default() });
+    }
+
+    #[tokio::test]
+    async fn test_fetch_features_from_crates_io() {
+        let mut server = Server::new_async().await;
+        server.mock("GET", "/api/v1/crates/demo/1.0.0")
+            .with_status(200)
+            .with_body(r#"{"version":{"num":"1.0.0","features":{"default":["std"],"std":["dep:libc"]}}}"#)
+            .create();
+
+        let client = DocsRsClient::new().with_crates_io_url(&server.url());
+        let features = client.fetch_features("demo", "1.0.0").await.unwrap();
+        assert_eq!(features["default"], vec!["std"]);
+        assert_eq!(features["std"], vec!["dep:libc"]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_search_index_follows_rustdoc_vars() {
+        let mut server = Server::new_async().await;
+        let page = server.mock("GET", "/demo/1.0.0/demo/index.html")
+            .with_status(200)
+            .with_body(r#"<html><body><div id="rustdoc-vars" data-root-path="../" data-search-index-js="../search-index-1.80.0.js"></div></body></html>"#)
+            .create();
+        let index = server.mock("GET", "/demo/1.0.0/search-index-1.80.0.js")
+            .with_status(200)
+            .with_body(r#"var searchIndex = new Map(JSON.parse('[["demo",{"t":"DF","n":["Widget","build"],"q":[[0,"demo"]]}]]'));"#)
+            .create();
+
+        let client = DocsRsClient::new_with_base_url(&server.url());
+        let result = client.fetch_search_index("demo", "1.0.0").await.unwrap();
+        page.assert();
+        index.assert();
+
+        let paths: Vec<&str> = result.items.iter().map(|i| i.doc_path.as_str()).collect();
+        assert_eq!(paths, ["demo/struct.Widget.html", "demo/fn.build.html"]);
+    }
+
+    const TRAIT_PAGE: &str = r##"<!DOCTYPE html><html><body>
+        <div id="rustdoc_body_wrapper"><main>
+        <h1>Trait <a class="trait" href="#">Shape</a></h1>
+        <h2 id="implementors" class="section-header">Implementors<a href="#implementors" class="anchor">§</a></h2>
+        <div id="implementors-list">
+            <section id="impl-Shape-for-Circle" class="impl"><a href="#impl-Shape-for-Circle" class="anchor">§</a>
+                <h3 class="code-header">impl <a class="trait" href="trait.Shape.html" title="trait geo::Shape">Shape</a> for <a class="struct" href="struct.Circle.html" title="struct geo::Circle">Circle</a></h3>
+            </section>
+            <section id="impl-Shape-for-Polygon%3CT%3E" class="impl"><a href="#impl-Shape-for-Polygon%3CT%3E" class="anchor">§</a>
+                <h3 class="code-header">impl&lt;T: <a class="trait" href="https://doc.rust-lang.org/core/clone/trait.Clone.html" title="trait core::clone::Clone">Clone</a>&gt; <a class="trait" href="trait.Shape.html" title="trait geo::Shape">Shape</a> for <a class="struct" href="poly/struct.Polygon.html" title="struct geo::poly::Polygon">Polygon</a>&lt;T&gt;<div class="where">where T: Default</div></h3>
+            </section>
+            <section id="impl-Shape-for-Box%3CS%3E" class="impl">
+                <h3 class="code-header">impl&lt;S: <a class="trait" href="trait.Shape.html" title="trait geo::Shape">Shape</a>&gt; <a class="trait" href="trait.Shape.html" title="trait geo::Shape">Shape</a> for <a class="struct" href="https://doc.rust-lang.org/alloc/boxed/struct.Box.html" title="struct alloc::boxed::Box">Box</a>&lt;S&gt;</h3>
+            </section>
+        </div>
+        </main></div>
+        <script src="../trait.impl/geo/trait.Shape.js" async></script>
+        </body></html>"##;
+
+    #[test]
+    fn test_parse_implementors() {
+        let client = DocsRsClient::new();
+        let parsed = client.parse_implementors(TRAIT_PAGE);
+
+        let types: Vec<(&str, Option<&str>)> = parsed.implementors.iter()
+            .map(|i| (i.type_name.as_str(), i.type_path.as_deref()))
+            .collect();
+        assert_eq!(types, [
+            ("Circle", Some("geo::Circle")),
+            ("Polygon<T>", Some("geo::poly::Polygon")),
+            ("Box<S>", Some("alloc::boxed::Box")),
+        ]);
+        assert_eq!(parsed.implementors[1].declaration, "impl<T: Clone> Shape for Polygon<T> where T: Default");
+        assert_eq!(parsed.external_implementors_script.as_deref(), Some("../trait.impl/geo/trait.Shape.js"));
+    }
+
+    #[test]
+    fn test_parse_implementors_only_loaded_asynchronously() {
+        let html = r#"<html><body>
+            <h2 id="implementors" class="section-header">Implementors</h2>
+            <div id="implementors-list"></div>
+            <script src="../implementors/ext/trait.Plugin.js" async></script>
+            </body></html>"#;
+
+        let parsed = DocsRsClient::new().parse_implementors(html);
+        assert!(parsed.implementors.is_empty());
+        assert_eq!(parsed.external_implementors_script.as_deref(), Some("../implementors/ext/trait.Plugin.js"));
+    }
+
+    #[test]
+    fn test_parse_type_alias() {
+        let html = r##"<html><body><div id="rustdoc_body_wrapper"><main>
+            <h1>Type Alias <a href="../index.html">std</a>::<a href="index.html">io</a>::<a class="type" href="#">Result</a></h1>
+            <pre class="rust item-decl"><code>pub type Result&lt;T&gt; = <a class="enum" href="../result/enum.Result.html" title="enum core::result::Result">Result</a>&lt;T, <a class="struct" href="struct.Error.html" title="struct std::io::Error">Error</a>&gt;;</code></pre>
+            <details class="toggle top-doc"><div class="docblock"><p>A specialized Result type for I/O operations.</p></div></details>
+            <h2 id="aliased-type" class="section-header">Aliased Type<a href="#aliased-type" class="anchor">§</a></h2>
+            <pre class="rust item-decl"><code>pub enum Result&lt;T&gt; {
+    Ok(T),
+    Err(<a class="struct" href="struct.Error.html" title="struct std::io::Error">Error</a>),
+}</code></pre>
+            </main></div></body></html>"##;
+
+        let alias = DocsRsClient::new().parse_type_alias(html).unwrap();
+        assert_eq!(alias.declaration, "pub type Result<T> = Result<T, Error>;");
+        assert_eq!(alias.aliased_type, "Result<T, Error>");
+        assert_eq!(alias.aliased_type_path.as_deref(), Some("core::result::Result"));
+        assert_eq!(alias.aliased_definition.as_deref(), Some("pub enum Result<T> {\n    Ok(T),\n    Err(Error),\n}"));
+    }
+
+    #[test]
+    fn test_parse_type_alias_legacy_markup() {
+        let html = r#"<html><body>
+            <pre class="rust typedef"><code>type Callback = <a class="struct" href="../boxed/struct.Box.html" title="struct alloc::boxed::Box">Box</a>&lt;dyn Fn()&gt;;</code></pre>
+            </body></html>"#;
+
+        let alias = DocsRsClient::new().parse_type_alias(html).unwrap();
+        assert_eq!(alias.aliased_type, "Box<dyn Fn()>");
+        assert_eq!(alias.aliased_type_path.as_deref(), Some("alloc::boxed::Box"));
+        assert_eq!(alias.aliased_definition, None);
+        assert!(DocsRsClient::new().parse_type_alias("<html></html>").is_err());
+    }
+
+    #[test]
+    fn test_parse_breadcrumb_nested_item() {
+        let html = r##"<html><body><nav class="sidebar"><h2><a href="../../../tokio/index.html">tokio</a></h2></nav>
+            <main><section id="main-content" class="content"><div class="main-heading">
+            <div class="rustdoc-breadcrumbs"><a href="../../../index.html">tokio</a>::<wbr><a href="../../index.html">sync</a>::<wbr><a href="../index.html">mpsc</a>::<wbr><a href="index.html">error</a></div>
+            <h1>Enum <span class="enum">TrySendError</span><button id="copy-path" title="Copy item path to clipboard">Copy item path</button></h1>
+            <rustdoc-toolbar></rustdoc-toolbar><span class="sub-heading"><a class="src" href="../../../../src/tokio/sync/mpsc/error.rs.html#59-66">Source</a></span>
+            </div></section></main></body></html>"##;
+
+        let breadcrumb = DocsRsClient::new()
+            .parse_breadcrumb(html, "tokio/sync/mpsc/error/enum.TrySendError.html")
+            .unwrap();
+        let segments: Vec<_> = breadcrumb.segments.iter().map(|s| (s.name.as_str(), s.path.as_str())).collect();
+        assert_eq!(segments, vec![
+            ("tokio", "tokio/index.html"),
+            ("sync", "tokio/sync/index.html"),
+            ("mpsc", "tokio/sync/mpsc/index.html"),
+            ("error", "tokio/sync/mpsc/error/index.html"),
+            ("TrySendError", "tokio/sync/mpsc/error/enum.TrySendError.html"),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_associated_types() {
+        let html = r##"<html><body><section id="main-content">
+            <pre class="rust item-decl"><code>pub trait Source {
+    type Item: Clone + Debug;
+    type Iter&lt;'a&gt;: Iterator&lt;Item = &amp;'a Self::Item&gt; where Self: 'a;
+}</code></pre>
+            <h2 id="required-associated-types" class="section-header">Required Associated Types</h2>
+            <div class="methods"><details class="toggle" open><summary><section id="associatedtype.Item" class="method"><a class="src rightside" href="../src/demo/lib.rs.html#4">Source</a>
+            <h4 class="code-header">type <a href="#associatedtype.Item" class="associatedtype">Item</a>: <a class="trait" href="https://doc.rust-lang.org/nightly/core/clone/trait.Clone.html">Clone</a> + <a class="trait" href="https://doc.rust-lang.org/nightly/core/fmt/trait.Debug.html">Debug</a></h4></section></summary><div class="docblock"><p>Items produced.</p></div></details>
+            <section id="associatedtype.Iter" class="method"><h4 class="code-header">type <a href="#associatedtype.Iter" class="associatedtype">Iter</a>&lt;'a&gt;: <a class="trait" href="#">Iterator</a>&lt;Item = &amp;'a Self::<a class="associatedtype" href="#associatedtype.Item">Item</a>&gt;
+<div class="where">where Self: 'a</div></h4></section></div>
+            <h2 id="implementors" class="section-header">Implementors</h2>
+            <div id="implementors-list"><details class="toggle implementors-toggle"><summary><section id="impl-Source-for-Bytes" class="impl"><h3 class="code-header">impl Source for Bytes</h3></section></summary>
+            <div class="impl-items"><section id="associatedtype.Item-1" class="associatedtype trait-impl"><h4 class="code-header">type <a href="#associatedtype.Item" class="associatedtype">Item</a> = u8</h4></section></div></details></div>
+            </section></body></html>"##;
+
+        let types = DocsRsClient::new().parse_associated_types(html, "/demo/trait.Source.html");
+        assert_eq!(types.path, "demo/trait.Source.html");
+        assert_eq!(types.types, BTreeMap::from([
+            ("Item".to_string(), "type Item: Clone + Debug".to_string()),
+            ("Iter".to_string(), "type Iter<'a>: Iterator<Item = &'a Self::Item> where Self: 'a".to_string()),
+        ]));
+    }
+
+    #[test]
+    fn test_parse_associated_types_legacy_markup() {
+        let html = r##"<div class="methods"><h3 id="associatedtype.Output" class="method"><code>type <a href="#associatedtype.Output" class="type">Output</a>: <a class="trait" href="#">Send</a> + 'static</code></h3></div>"##;
+
+        let types = DocsRsClient::new().parse_associated_types(html, "demo/trait.Task.html");
+        assert_eq!(types.types.get("Output").map(String::as_str), Some("type Output: Send + 'static"));
+    }
+
+    #[test]
+    fn test_parse_trait_method() {
+        let html = include_str!("../tests/fixtures/trait.AsyncRead.html");
+        let client = DocsRsClient::new();
+
+        let provided = client.parse_trait_method(html, "/demo_io/trait.AsyncRead.html", "read_to_end").unwrap();
+        assert_eq!(provided, TraitMethod {
+            path: "demo_io/trait.AsyncRead.html".to_string(),
+            name: "read_to_end".to_string(),
+            signature: "fn read_to_end<'a>(&'a mut self, buf: &'a mut Vec<u8>) -> ReadToEnd<'a, Self> where Self: Unpin,".to_string(),
+            required: false,
+            docs: Some("Reads all bytes until EOF, appending them to `buf`.".to_string()),
+            default_source: Some(SourceLink {
+                path: "src/demo_io/read.rs.html".to_string(),
+                start_line: Some(50),
+                end_line: Some(57),
+            }),
+        });
+
+        // The trait's declaration is found, not that of an implementation
+        let required = client.parse_trait_method(html, "demo_io/trait.AsyncRead.html", "poll_read").unwrap();
+        assert!(required.required);
+        assert!(required.signature.starts_with("fn poll_read( self: Pin<&mut Self>,"), "{}", required.signature);
+        assert!(required.docs.unwrap().starts_with("Attempt to read from the source into `buf`."));
+        assert_eq!(required.default_source, None);
+
+        assert!(matches!(
+            client.parse_trait_method(html, "demo_io/trait.AsyncRead.html", "read_exact"),
+            Err(DocsFetchError::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_trait_method_legacy_markup() {
+        let html = r##"<div class="methods"><h3 id="method.flush" class="method"><code>fn <a href="#method.flush" class="fnname">flush</a>(&amp;mut self)</code><a class="srclink" href="../src/demo/io.rs.html#8-10">[src]</a></h3><div class="docblock"><p>Flushes the buffer.</p></div></div>"##;
+
+        let method = DocsRsClient::new().parse_trait_method(html, "demo/trait.Write.html", "flush").unwrap();
+        assert_eq!(method.signature, "fn flush(&mut self)");
+        assert_eq!(method.docs.as_deref(), Some("Flushes the buffer."));
+        assert_eq!(method.default_source.unwrap().path, "src/demo/io.rs.html");
+    }
+
+    #[test]
+    fn test_parse_html_content() {
+        let html = include_str!("../tests/fixtures/struct.Mutex.html");
+
+        let parsed = DocsRsClient::new().parse_html_content(html);
+        assert_eq!(parsed.signature.as_deref(), Some("pub struct Mutex<T: ?Sized> { /* private fields */ }"));
+        assert_eq!(parsed.summary(), Some("An asynchronous `Mutex`-like type."));
+        let description = parsed.description.unwrap();
+        assert!(description.starts_with("An asynchronous `Mutex`-like type."));
+        assert!(description.contains("## Which kind of mutex should you use?"));
+        assert_eq!(parsed.methods, [
+            "pub fn new(t: T) -> Self where T: Sized,",
+            "pub const fn const_new(t: T) -> Self where T: Sized,",
+            "pub async fn lock(&self) -> MutexGuard<'_, T>",
+            "pub fn try_lock(&self) -> Result<MutexGuard<'_, T>, TryLockError>",
+        ]);
+        // Only the item's own Rust examples, not those of its methods
+        assert_eq!(parsed.examples.len(), 1);
+        assert!(parsed.examples[0].starts_with("use tokio::sync::Mutex;\nuse std::sync::Arc;"));
+        assert!(parsed.examples[0].ends_with("*lock += 1;\n}"));
+
+        let empty = DocsRsClient::new().parse_html_content("<html><body><p>Not rustdoc</p></body></html>");
+        assert_eq!(empty, ParsedDoc::default());
+        assert_eq!(empty.summary(), None);
+    }
+
+    #[test]
+    fn test_parse_doc_links() {
+        let html = include_str!("../tests/fixtures/struct.Mutex.html");
+
+        // Links to std, to the page itself and within declarations are skipped
+        let links = DocsRsClient::new().parse_doc_links(html, "tokio/sync/struct.Mutex.html");
+        assert_eq!(links, [
+            DocLink { name: "MutexGuard".to_string(), path: "tokio/sync/struct.MutexGuard.html".to_string() },
+            DocLink { name: "TryLockError".to_string(), path: "tokio/sync/struct.TryLockError.html".to_string() },
+        ]);
+
+        let html = r#"<div class="docblock"><p>See <a href="../index.html">the crate</a>,
+            <a href="../../../bytes/1.0.0/bytes/struct.Bytes.html">Bytes</a> and
+            <a href="../index.html#modules">its modules</a>.</p></div>"#;
+        let links = DocsRsClient::new().parse_doc_links(html, "/tokio/sync/struct.Mutex.html");
+        assert_eq!(links, [DocLink { name: "the crate".to_string(), path: "tokio/index.html".to_string() }]);
+    }
+
+    #[test]
+    fn test_parse_crate_docs() {
+        let html = r##"<html><body><section id="main-content" class="content">
+            <div class="main-heading"><h1>Crate <span>demo</span></h1><rustdoc-toolbar></rustdoc-toolbar></div>
+            <details class="toggle top-doc" open><summary class="hideme"><span>Expand description</span></summary>
+            <div class="docblock"><p>A <strong>demo</strong> crate for <a href="https://example.com/">parsing</a> with <a href="struct.Parser.html" title="struct demo::Parser"><code>Parser</code></a>.</p>
+            <h2 id="usage"><a class="doc-anchor" href="#usage">§</a>Usage</h2>
+            <ul><li>Fast</li>
+            <li>Safe, with
+            <ul><li>no <code>unsafe</code></li></ul></li></ul>
+            <div class="example-wrap"><pre class="rust rust-example-rendered"><code><span class="kw">use </span>demo::Parser;
+<span class="kw">let </span>p = Parser::new();</code></pre></div>
+            <div class="example-wrap"><pre class="language-toml"><code>[dependencies]
+demo = "1"</code></pre></div>
+            <blockquote><p>Note: <em>experimental</em>.</p></blockquote></div></details>
+            <h2 id="structs" class="section-header">Structs<a href="#structs" class="anchor">§</a></h2>
+            <dl class="item-table"><dt><a class="struct" href="struct.Parser.html">Parser</a></dt><dd><p>A parser</p></dd></dl>
+            </section></body></html>"##;
+
+        let markdown = DocsRsClient::new().parse_crate_docs(html);
+        assert_eq!(markdown, [
+            "A **demo** crate for [parsing](https://example.com/) with [`Parser`](struct.Parser.html).",
+            "## Usage",
+            "- Fast\n- Safe, with\n  - no `unsafe`",
+            "```rust\nuse demo::Parser;\nlet p = Parser::new();\n```",
+            "```toml\n[dependencies]\ndemo = \"1\"\n```",
+            "> Note: *experimental*.",
+        ].join("\n\n"));
+
+        // Crates without crate-level docs
+        let undocumented = r##"<section id="main-content"><h2 id="structs" class="section-header">Structs</h2>
+            <dl class="item-table"><dt><a class="struct" href="struct.Parser.html">Parser</a></dt><dd><p>A parser</p></dd></dl></section>"##;
+        assert_eq!(DocsRsClient::new().parse_crate_docs(undocumented), "");
+    }
+
+    #[test]
+    fn test_parse_submodules() {
+        let html = r##"<html><body><section id="main-content">
+            <h2 id="modules" class="section-header">Modules<a href="#modules" class="anchor">§</a></h2>
+            <dl class="item-table"><dt><a class="mod" href="io/index.html" title="mod demo::net::io">io</a></dt><dd>Asynchronous <code>I/O</code> primitives</dd>
+            <dt><a class="mod" href="tcp/index.html" title="mod demo::net::tcp">tcp</a></dt>
+            <dt><a class="mod" href="unix/datagram/index.html" title="mod demo::net::unix::datagram">unix::<wbr>datagram</a></dt><dd>Unix datagram sockets</dd></dl>
+            <h2 id="structs" class="section-header">Structs</h2>
+            <dl class="item-table"><dt><a class="struct" href="struct.Addr.html">Addr</a></dt><dd>An address</dd></dl>
+            </section></body></html>"##;
+
+        let submodules = DocsRsClient::new().parse_submodules(html, "demo/net/index.html").unwrap();
+        let modules: Vec<_> = submodules.modules
+            .iter()
+            .map(|m| (m.name.as_str(), m.path.as_str(), m.summary.as_deref()))
+            .collect();
+        assert_eq!(modules, vec![
+            ("io", "demo/net/io/index.html", Some("Asynchronous I/O primitives")),
+            ("tcp", "demo/net/tcp/index.html", None),
+            ("unix::datagram", "demo/net/unix/datagram/index.html", Some("Unix datagram sockets")),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_submodules_legacy_markup() {
+        let html = r##"<div class="item-table"><div class="item-row"><div class="item-left module-item"><a class="mod" href="sync/index.html">sync</a></div>
+            <div class="item-right docblock-short">Synchronization primitives</div></div></div>"##;
+
+        let submodules = DocsRsClient::new().parse_submodules(html, "demo/index.html").unwrap();
+        assert_eq!(submodules.modules, vec![Submodule {
+            name: "sync".to_string(),
+            path: "demo/sync/index.html".to_string(),
+            summary: Some("Synchronization primitives".to_string()),
+        }]);
+    }
+
+    #[test]
+    fn test_parse_constants_module_page() {
+        let html = r##"<html><body><section id="main-content">
+            <h2 id="constants" class="section-header">Constants<a href="#constants" class="anchor">§</a></h2>
+            <dl class="item-table"><dt><a class="constant" href="constant.MAX_RETRIES.html" title="constant demo::net::MAX_RETRIES">MAX_<wbr>RETRIES</a></dt><dd>Retries before giving up</dd>
+            <dt><a class="constant" href="constant.NAME.html" title="constant demo::net::NAME">NAME</a></dt></dl>
+            <h2 id="statics" class="section-header">Statics</h2>
+            <ul class="item-table"><li><div class="item-name"><a class="static" href="static.COUNTER.html" title="static demo::net::COUNTER">COUNTER</a></div></li></ul>
+            <h2 id="functions" class="section-header">Functions</h2>
+            <dl class="item-table"><dt><a class="fn" href="fn.connect.html">connect</a></dt></dl>
+            </section></body></html>"##;
+
+        let client = DocsRsClient::new();
+        let constants = client.parse_constants(html, "demo/net/index.html").unwrap();
+        let listed: Vec<_> = constants.constants.iter().map(|c| (c.name.as_str(), c.kind.as_str(), c.path.as_str())).collect();
+        assert_eq!(listed, vec![
+            ("MAX_RETRIES", "const", "demo/net/constant.MAX_RETRIES.html"),
+            ("NAME", "const", "demo/net/constant.NAME.html"),
+            ("COUNTER", "static", "demo/net/static.COUNTER.html"),
+        ]);
+
+        let item = r##"<pre class="rust item-decl"><code>pub const MAX_RETRIES: <a class="primitive" href="https://doc.rust-lang.org/nightly/std/primitive.u32.html">u32</a> = 3;</code></pre>"##;
+        let mut max_retries = constants.constants[0].clone();
+        max_retries.set_declaration(client.parse_item_declaration(item).unwrap());
+        assert_eq!(max_retries.ty.as_deref(), Some("u32"));
+        assert_eq!(max_retries.value.as_deref(), Some("3"));
+        assert_eq!(max_retries.declaration.as_deref(), Some("pub const MAX_RETRIES: u32 = 3;"));
+    }
+
+    #[test]
+    fn test_parse_constants_associated() {
+        let html = r##"<div class="impl-items">
+            <details class="toggle" open><summary><section id="associatedconstant.MAX" class="associatedconstant"><a class="src rightside" href="../src/core/num/mod.rs.html#1">Source</a>
+            <h4 class="code-header">pub const <a href="#associatedconstant.MAX" class="constant">MAX</a>: <a class="primitive" href="primitive.u32.html">u32</a> = 4_294_967_295u32</h4></section></summary></details>
+            <section id="associatedconstant.ID" class="associatedconstant"><h4 class="code-header">const <a href="#associatedconstant.ID" class="constant">ID</a>: TypeId</h4></section>
+            </div>"##;
+
+        let constants = DocsRsClient::new().parse_constants(html, "std/primitive.u32.html").unwrap();
+        assert_eq!(constants.constants.len(), 2);
+        let max = &constants.constants[0];
+        assert_eq!(max.name, "MAX");
+        assert!(max.associated);
+        assert_eq!(max.path, "std/primitive.u32.html#associatedconstant.MAX");
+        assert_eq!(max.ty.as_deref(), Some("u32"));
+        assert_eq!(max.value.as_deref(), Some("4_294_967_295u32"));
+
+        let id = &constants.constants[1];
+        assert_eq!(id.ty.as_deref(), Some("TypeId"));
+        assert_eq!(id.value, None);
+    }
+
+    #[test]
+    fn test_parse_item_source() {
+        let item = r##"<html><body><section id="main-content"><div class="main-heading">
+            <h1>Struct <span class="struct">Point</span></h1>
+            <span class="sub-heading"><a class="src" href="../src/geo/point.rs.html#3-6">Source</a></span></div>
+            <details class="toggle method-toggle"><summary><section id="method.x" class="method">
+            <a class="src rightside" href="../src/geo/point.rs.html#10-12">Source</a></section></summary></details>
+            </section></body></html>"##;
+        let source = r##"<html><body><section id="main-content"><div class="example-wrap digits-2"><pre class="rust"><code><a href=#1 id=1 data-nosnippet>1</a><span class="comment">//! Points</span>
+<a href=#2 id=2 data-nosnippet>2</a>
+<a href=#3 id=3 data-nosnippet>3</a><span class="doccomment">/// A point</span>
+<a href=#4 id=4 data-nosnippet>4</a><span class="kw">pub struct </span>Point {
+<a href=#5 id=5 data-nosnippet>5</a>    <span class="kw">pub </span>x: f64,
+<a href=#6 id=6 data-nosnippet>6</a>}
+<a href=#7 id=7 data-nosnippet>7</a>
+<a href=#8 id=8 data-nosnippet>8</a><span class="kw">impl </span>Point {}</code></pre></div></section></body></html>"##;
+
+        let client = DocsRsClient::new();
+        let link = client.parse_source_link(item, "geo/struct.Point.html").unwrap();
+        assert_eq!(link, SourceLink {
+            path: "src/geo/point.rs.html".to_string(),
+            start_line: Some(3),
+            end_line: Some(6),
+        });
+
+        let (start, end, code) = client.parse_source_lines(source, Some((3, 6))).unwrap();
+        assert_eq!((start, end), (3, 6));
+        assert_eq!(code, "/// A point\npub struct Point {\n    pub x: f64,\n}");
+        assert!(client.parse_source_lines(source, Some((9, 12))).is_err());
+    }
+
+    #[test]
+    fn test_parse_item_source_legacy_markup() {
+        let item = r##"<h1 class="fqn"><span class="in-band">Struct <a class="struct" href="">Mutex</a></span>
+            <span class="out-of-band"><a class="srclink" href="../../src/tokio/sync/mutex.rs.html#116">[src]</a></span></h1>"##;
+        let source = r##"<div class="example-wrap"><pre class="src-line-numbers"><a href="#1">1</a>
+<a href="#2">2</a></pre><pre class="rust"><code>// line 1
+<span class="kw">pub struct</span> Mutex;</code></pre></div>"##;
+
+        let client = DocsRsClient::new();
+        let link = client.parse_source_link(item, "tokio/sync/struct.Mutex.html").unwrap();
+        assert_eq!(link.path, "src/tokio/sync/mutex.rs.html");
+        assert_eq!((link.start_line, link.end_line), (Some(116), Some(116)));
+
+        // Without line numbers in the code, line 2 of the block is line 2 of the file
+        let (_, _, code) = client.parse_source_lines(source, Some((2, 2))).unwrap();
+        assert_eq!(code, "pub struct Mutex;");
+    }
+
+    #[test]
+    fn test_source_page_path() {
+        assert_eq!(source_page_path("tokio-util", "src/codec/mod.rs"), "src/tokio_util/codec/mod.rs.html");
+        assert_eq!(source_page_path("demo", "lib.rs"), "src/demo/lib.rs.html");
+        assert_eq!(source_page_path("tokio", "/src/tokio/sync/mutex.rs.html"), "src/tokio/sync/mutex.rs.html");
+    }
+
+    #[test]
+    fn test_normalize_doc_path() {
+        // Empty paths and trailing slashes
+        assert_eq!(normalize_doc_path("tokio-util", ""), "tokio_util/index.html");
+        assert_eq!(normalize_doc_path("std", " / "), "std/index.html");
+        assert_eq!(normalize_doc_path("tokio", "tokio/sync/"), "tokio/sync/index.html");
+        // Missing .html
+        assert_eq!(normalize_doc_path("std", "std/vec/struct.Vec"), "std/vec/struct.Vec.html");
+        assert_eq!(normalize_doc_path("tokio", "tokio/macro.select"), "tokio/macro.select.html");
+        assert_eq!(normalize_doc_path("std", "std/vec"), "std/vec/index.html");
+        assert_eq!(normalize_doc_path("std", "std"), "std/index.html");
+        // Best-effort mapping of Rust paths
+        assert_eq!(normalize_doc_path("std", "vec::Vec"), "std/vec/struct.Vec.html");
+        assert_eq!(normalize_doc_path("std", "std::vec::Vec"), "std/vec/struct.Vec.html");
+        assert_eq!(normalize_doc_path("tokio", "tokio::select!"), "tokio/macro.select.html");
+        assert_eq!(normalize_doc_path("tokio", "sync"), "tokio/sync/index.html");
+        assert_eq!(normalize_doc_path("std", "std/vec/Vec"), "std/vec/struct.Vec.html");
+        // Paths naming a page are kept
+        for path in [
+            "std/vec/struct.Vec.html",
+            "tokio/sync/index.html",
+            "tokio/all.html",
+            "src/tokio/lib.rs.html",
+            "search-index-1.80.0.js",
+            "tokio/sync/struct.Mutex.html#method.lock",
+        ] {
+            assert_eq!(normalize_doc_path("tokio", path), path);
+            assert_eq!(normalize_doc_path("tokio", &format!("/{}", path)), path);
+        }
+    }
+
+    #[test]
+    fn test_parse_deprecation() {
+        let html = r##"<section id="main-content" class="content">
+            <div class="main-heading"><h1>Function <span class="fn">old_api</span></h1></div>
+            <span class="item-info"><div class="stab deprecated"><span class="emoji">👎</span><span>Deprecated since 1.2.0: use <a href="fn.new_api.html"><code>new_api</code></a> instead</span></div></span>
+            <pre class="rust item-decl"><code>pub fn old_api()</code></pre></section>"##;
+        let deprecation = DocsRsClient::new().parse_deprecation(html, "demo/fn.old_api.html").unwrap();
+        assert_eq!(deprecation, Deprecation {
+            since: Some("1.2.0".to_string()),
+            note: Some("use new_api instead".to_string()),
+            replacement: Some("demo/fn.new_api.html".to_string()),
+        });
+
+        // A deprecated field does not make the struct deprecated
+        let html = r##"<section id="main-content" class="content">
+            <div class="main-heading"><h1>Struct <span class="struct">Config</span></h1></div>
+            <h2 id="fields" class="fields section-header">Fields</h2>
+            <span id="structfield.old" class="structfield section-header"><code>old: u32</code></span>
+            <span class="item-info"><div class="stab deprecated"><span class="emoji">👎</span><span>Deprecated</span></div></span></section>"##;
+        assert_eq!(DocsRsClient::new().parse_deprecation(html, "demo/struct.Config.html"), None);
+    }
+
+    #[test]
+    fn test_parse_source_listing() {
+        let html = r##"<html><body><div class="package-menu"><ul class="pure-menu-list">
+            <li class="pure-menu-item"><a href="/crate/demo/1.0.0/source/" class="pure-menu-link"><span class="fa fa-folder-open"></span> ..</a></li>
+            <li class="pure-menu-item"><a href="/crate/demo/1.0.0/source/examples/web/" class="pure-menu-link"><span class="fa fa-folder"></span> web</a></li>
+            <li class="pure-menu-item"><a href="/crate/demo/1.0.0/source/examples/chat.rs" class="pure-menu-link"><span class="fa fa-file-code"></span> chat.rs</a></li>
+            <li class="pure-menu-item"><a href="echo.rs" class="pure-menu-link"><span class="fa fa-file-code"></span> echo.rs</a></li>
+            </ul></div>
+            <div id="source-code-container"><a href="/crate/demo/1.0.0/source/Cargo.toml">Cargo.toml</a></div></body></html>"##;
+
+        let entries = DocsRsClient::new().parse_source_listing(html, "examples");
+        let entries: Vec<_> = entries.iter().map(|e| (e.name.as_str(), e.path.as_str(), e.is_dir)).collect();
+        assert_eq!(entries, vec![
+            ("web", "examples/web", true),
+            ("chat.rs", "examples/chat.rs", false),
+            ("echo.rs", "examples/echo.rs", false),
+        ]);
+
+        let file = r##"<div id="source-code-container"><pre id="line-numbers"><span>1</span><span>2</span></pre>
+            <div id="source-code" class="rust"><pre><code><span class="kw">fn</span> main() {
+    demo::chat(&amp;[]);
+}
+</code></pre></div></div>"##;
+        assert_eq!(DocsRsClient::new().parse_source_file(file).unwrap(), "fn main() {\n    demo::chat(&[]);\n}");
+    }
+
+    #[test]
+    fn test_parse_breadcrumb_legacy_markup() {
+        let html = r##"<html><body><section id="main" class="content">
+            <h1 class="fqn"><span class="in-band">Struct <a href="../index.html">tokio</a>::<wbr><a href="index.html">sync</a>::<wbr><a class="struct" href="">Mutex</a></span><span class="out-of-band"><span class="since">1.0.0</span><a class="srclink" href="../../src/tokio/sync/mutex.rs.html#116-120">[src]</a></span></h1>
+            </section></body></html>"##;
+
+        let breadcrumb = DocsRsClient::new().parse_breadcrumb(html, "tokio/sync/struct.Mutex.html").unwrap();
+        let names: Vec<_> = breadcrumb.segments.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["tokio", "sync", "Mutex"]);
+        assert_eq!(breadcrumb.segments[2].path, "tokio/sync/struct.Mutex.html");
+        assert!(DocsRsClient::new().parse_breadcrumb("<html></html>", "x.html").is_err());
+    }
+
+    #[test]
+    fn test_parse_auto_traits() {
+        let html = r##"<html><body><section id="main-content" class="content">
+            <h2 id="synthetic-implementations" class="section-header">Auto Trait Implementations<a href="#synthetic-implementations" class="anchor">§</a></h2>
+            <div id="synthetic-implementations-list">
+            <section id="impl-Freeze-for-Rc%3CT,+A%3E" class="impl"><a href="#impl-Freeze-for-Rc%3CT,+A%3E" class="anchor">§</a><h3 class="code-header">impl&lt;T, A&gt; <a class="trait" href="../../core/marker/trait.Freeze.html" title="trait core::marker::Freeze">Freeze</a> for <a class="struct" href="struct.Rc.html" title="struct alloc::rc::Rc">Rc</a>&lt;T, A&gt;<div class="where">where
+    A: <a class="trait" href="../../core/marker/trait.Freeze.html" title="trait core::marker::Freeze">Freeze</a>,
+    T: ?<a class="trait" href="../../core/marker/trait.Sized.html" title="trait core::marker::Sized">Sized</a>,</div></h3></section>
+            <section id="impl-Send-for-Rc%3CT,+A%3E" class="impl"><a href="#impl-Send-for-Rc%3CT,+A%3E" class="anchor">§</a><h3 class="code-header">impl&lt;T, A = <a class="struct" href="../alloc/struct.Global.html" title="struct alloc::alloc::Global">Global</a>&gt; !<a class="trait" href="../../core/marker/trait.Send.html" title="trait core::marker::Send">Send</a> for <a class="struct" href="struct.Rc.html" title="struct alloc::rc::Rc">Rc</a>&lt;T, A&gt;</h3></section>
+            <section id="impl-Sync-for-Rc%3CT,+A%3E" class="impl"><h3 class="code-header">impl&lt;T, A&gt; !<a class="trait" href="../../core/marker/trait.Sync.html" title="trait core::marker::Sync">Sync</a> for <a class="struct" href="struct.Rc.html" title="struct alloc::rc::Rc">Rc</a>&lt;T, A&gt;</h3></section>
+            <section id="impl-Unpin-for-Rc%3CT,+A%3E" class="impl"><h3 class="code-header">impl&lt;T, A&gt; <a class="trait" href="../../core/marker/trait.Unpin.html" title="trait core::marker::Unpin">Unpin</a> for <a class="struct" href="struct.Rc.html" title="struct alloc::rc::Rc">Rc</a>&lt;T, A&gt;<div class="where">where
+    A: <a class="trait" href="../../core/marker/trait.Unpin.html" title="trait core::marker::Unpin">Unpin</a>,</div></h3></section>
+            </div>
+            <h2 id="blanket-implementations" class="section-header">Blanket Implementations</h2>
+            <div id="blanket-implementations-list"><section id="impl-Any-for-T" class="impl"><h3 class="code-header">impl&lt;T&gt; Any for T</h3></section></div>
+            </section></body></html>"##;
+
+        let auto_traits = DocsRsClient::new().parse_auto_traits(html);
+        let summary: Vec<_> = auto_traits.impls
+            .iter()
+            .map(|i| (i.trait_name.as_str(), i.negative, i.conditional))
+            .collect();
+        assert_eq!(summary, vec![
+            ("Freeze", false, true),
+            ("Send", true, false),
+            ("Sync", true, false),
+            ("Unpin", false, true),
+        ]);
+        assert_eq!(auto_traits.impls[1].declaration, "impl<T, A = Global> !Send for Rc<T, A>");
+        assert!(DocsRsClient::new().parse_auto_traits("<html></html>").impls.is_empty());
+    }
+
+    #[test]
+    fn test_parse_blanket_impls() {
+        let html = r##"<html><body><section id="main-content" class="content">
+            <h2 id="synthetic-implementations" class="section-header">Auto Trait Implementations</h2>
+            <div id="synthetic-implementations-list"><section id="impl-Send-for-Point" class="impl"><h3 class="code-header">impl Send for Point</h3></section></div>
+            <h2 id="blanket-implementations" class="section-header">Blanket Implementations<a href="#blanket-implementations" class="anchor">§</a></h2>
+            <div id="blanket-implementations-list">
+            <details class="toggle implementors-toggle"><summary><section id="impl-Any-for-T" class="impl"><a class="src rightside" href="https://doc.rust-lang.org/nightly/src/core/any.rs.html#138">Source</a><a href="#impl-Any-for-T" class="anchor">§</a><h3 class="code-header">impl&lt;T&gt; <a class="trait" href="https://doc.rust-lang.org/nightly/core/any/trait.Any.html" title="trait core::any::Any">Any</a> for T<div class="where">where
+    T: 'static + ?<a class="trait" href="https://doc.rust-lang.org/nightly/core/marker/trait.Sized.html" title="trait core::marker::Sized">Sized</a>,</div></h3></section></summary>
+            <div class="impl-items"><details class="toggle method-toggle" open><summary><section id="method.type_id" class="method trait-impl"><h4 class="code-header">fn <a class="fn">type_id</a>(&amp;self) -&gt; <a class="struct" title="struct core::any::TypeId">TypeId</a></h4></section></summary><div class="docblock"><p>Gets the <code>TypeId</code> of <code>self</code>.</p></div></details></div></details>
+            <details class="toggle implementors-toggle"><summary><section id="impl-Borrow%3CT%3E-for-T" class="impl"><h3 class="code-header">impl&lt;T&gt; <a class="trait" title="trait core::borrow::Borrow">Borrow</a>&lt;T&gt; for T<div class="where">where
+    T: ?<a class="trait" title="trait core::marker::Sized">Sized</a>,</div></h3></section></summary></details>
+            <details class="toggle implementors-toggle"><summary><section id="impl-Into%3CU%3E-for-T" class="impl"><h3 class="code-header">impl&lt;T, U&gt; <a class="trait" title="trait core::convert::Into">Into</a>&lt;U&gt; for T<div class="where">where
+    U: <a class="trait" title="trait core::convert::From">From</a>&lt;T&gt;,</div></h3></section></summary></details>
+            </div></section></body></html>"##;
+
+        let blanket = DocsRsClient::new().parse_blanket_impls(html);
+        let traits: Vec<_> = blanket.impls.iter().map(|i| i.trait_name.as_str()).collect();
+        assert_eq!(traits, vec!["Any", "Borrow<T>", "Into<U>"]);
+        assert!(blanket.impls[0].declaration.starts_with("impl<T> Any for T"));
+        assert!(blanket.impls[0].declaration.contains("T: 'static + ?Sized"));
+        assert!(blanket.impls.iter().all(|i| !i.declaration.contains("type_id")));
+        assert!(DocsRsClient::new().parse_blanket_impls("<html></html>").impls.is_empty());
+    }
+
+    #[test]
+    fn test_parse_inherent_impls_groups_conditional_blocks() {
+        let html = r##"<html><body><section id="main-content" class="content">
+            <h2 id="implementations" class="section-header">Implementations<a href="#implementations" class="anchor">§</a></h2>
+            <div id="implementations-list">
+            <details class="toggle implementors-toggle" open><summary><section id="impl-Wrapper%3CT%3E" class="impl"><a class="src rightside" href="../src/wrap/lib.rs.html#10">Source</a><h3 class="code-header">impl&lt;T&gt; <a class="struct" href="struct.Wrapper.html" title="struct wrap::Wrapper">Wrapper</a>&lt;T&gt;</h3></section></summary>
+            <div class="impl-items">
+            <details class="toggle method-toggle" open><summary><section id="method.new" class="method"><h4 class="code-header">pub fn <a href="#method.new" class="fn">new</a>(value: T) -&gt; Self</h4></section></summary><div class="docblock"><p>Wraps a value.</p></div></details>
+            <section id="method.into_inner" class="method"><h4 class="code-header">pub fn <a href="#method.into_inner" class="fn">into_inner</a>(self) -&gt; T</h4></section>
+            </div></details>
+            <details class="toggle implementors-toggle" open><summary><section id="impl-Wrapper%3CT%3E-1" class="impl"><h3 class="code-header">impl&lt;T: <a class="trait" title="trait core::clone::Clone">Clone</a>&gt; <a class="struct" title="struct wrap::Wrapper">Wrapper</a>&lt;T&gt;<div class="where">where
+    T: <a class="trait" title="trait core::marker::Send">Send</a>,</div></h3></section></summary>
+            <div class="impl-items">
+            <details class="toggle method-toggle" open><summary><section id="method.duplicate" class="method"><h4 class="code-header">pub fn <a href="#method.duplicate" class="fn">duplicate</a>(&amp;self) -&gt; (T, T)</h4></section></summary><div class="docblock"><p>Clones the value twice.</p></div></details>
+            </div></details>
+            </div>
+            <h2 id="trait-implementations" class="section-header">Trait Implementations</h2>
+            <div id="trait-implementations-list"><details class="toggle implementors-toggle"><summary><section id="impl-Debug-for-Wrapper%3CT%3E" class="impl"><h3 class="code-header">impl&lt;T&gt; Debug for Wrapper&lt;T&gt;</h3></section></summary>
+            <div class="impl-items"><section id="method.fmt" class="method trait-impl"><h4 class="code-header">fn <a class="fn">fmt</a>(&amp;self, f: &amp;mut Formatter)</h4></section></div></details></div>
+            </section></body></html>"##;
+
+        let inherent = DocsRsClient::new().parse_inherent_impls(html);
+        assert_eq!(inherent.impls.len(), 2);
+
+        let plain = &inherent.impls[0];
+        assert_eq!(plain.declaration, "impl<T> Wrapper<T>");
+        assert_eq!(plain.generics.as_deref(), Some("T"));
+        assert_eq!(plain.where_clause, None);
+        let names: Vec<_> = plain.methods.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["new", "into_inner"]);
+        assert_eq!(plain.methods[0].signature, "pub fn new(value: T) -> Self");
+
+        let conditional = &inherent.impls[1];
+        assert_eq!(conditional.generics.as_deref(), Some("T: Clone"));
+        assert_eq!(conditional.where_clause.as_deref(), Some("T: Send"));
+        assert_eq!(conditional.methods.len(), 1);
+        assert_eq!(conditional.methods[0].name, "duplicate");
+        assert!(DocsRsClient::new().parse_inherent_impls("<html></html>").impls.is_empty());
+    }
+
+    #[test]
+    fn test_parse_construction_hints() {
+        let html = include_str!("../tests/fixtures/struct.Client.html");
+
+        let hints = DocsRsClient::new().parse_construction_hints(html, "/demo_api/struct.Client.html");
+        assert_eq!(hints.path, "demo_api/struct.Client.html");
+        let found: Vec<_> = hints
+            .hints
+            .iter()
+            .map(|hint| (hint.name.as_str(), hint.trait_name.as_deref()))
+            .collect();
+        // Methods taking self, other trait impls and blanket impls are left out
+        assert_eq!(found, [
+            ("new", None),
+            ("builder", None),
+            ("from_env", None),
+            ("with_base_url", None),
+            ("default", Some("Default")),
+            ("from", Some("From<Config>")),
+        ]);
+        assert_eq!(hints.hints[1].signature, "pub fn builder() -> ClientBuilder");
+        assert_eq!(hints.hints[2].signature, "pub fn from_env() -> Result<Client>");
+        assert_eq!(hints.hints[5].signature, "fn from(config: Config) -> Self");
+    }
+
+    #[test]
+    fn test_takes_self() {
+        assert!(takes_self("pub fn len(&self) -> usize"));
+        assert!(takes_self("pub fn push(&mut self, value: T)"));
+        assert!(takes_self("pub fn get<'a>(&'a self) -> &'a T"));
+        assert!(takes_self("pub fn set(&'a mut self, value: T)"));
+        assert!(takes_self("pub fn into_inner(self) -> T"));
+        assert!(takes_self("pub fn map(mut self, f: F) -> Self"));
+        assert!(takes_self("pub fn poll(self: Pin<&mut Self>) -> Poll<T>"));
+        assert!(!takes_self("pub fn new() -> Self"));
+        assert!(!takes_self("pub fn with_capacity(capacity: usize) -> Self"));
+        assert!(!takes_self("pub fn from_self_describing(selfie: Bytes) -> Self"));
+    }
+
+    #[test]
+    fn test_crate_name_policies() {
+        for name in ["serde", "async", "a", "tokio-util", "proc_macro"] {
+            assert!(CrateNamePolicy::CratesIo.check(name).is_ok(), "{}", name);
+            assert!(CrateNamePolicy::Permissive.check(name).is_ok(), "{}", name);
+        }
+        for name in ["foo.bar", "1up", "_private"] {
+            assert!(matches!(CrateNamePolicy::CratesIo.check(name), Err(DocsFetchError::InvalidCrateName(_))), "{}", name);
+            assert!(CrateNamePolicy::Permissive.check(name).is_ok(), "{}", name);
+        }
+        assert!(CrateNamePolicy::CratesIo.check(&"a".repeat(65)).is_err());
+        for name in ["", ".", "..", "../evil", "a/b", "a?b", "a b", "c:"] {
+            assert!(CrateNamePolicy::CratesIo.check(name).is_err(), "{}", name);
+            assert!(CrateNamePolicy::Permissive.check(name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_min_tls_version_configures_client_builder() {
+        let builder = format!("{:?}", http_client_builder(Some(TlsVersion::Tls1_2), None));
+        assert!(builder.contains("min_tls_version"), "{}", builder);
+        assert!(builder.contains("Tls1_2"), "{}", builder);
+        assert!(!format!("{:?}", http_client_builder(None, None)).contains("min_tls_version"));
+
+        assert!(check_min_tls_version(Some(TlsVersion::Tls1_2)).is_ok());
+        let client = DocsRsClient::new().with_min_tls_version(Some(TlsVersion::Tls1_2));
+        assert_eq!(client.min_tls_version, Some(TlsVersion::Tls1_2));
+        assert_eq!(DocsRsClient::new().min_tls_version, None);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_docs_times_out_slow_responses() {
+        let mut server = Server::new_async().await;
+        server.mock("GET", "/demo/1.0.0/demo/struct.Slow.html")
+            .with_status(200)
+            .with_chunked_body(|w| {
+                std::thread::sleep(Duration::from_millis(1000));
+                w.write_all(br#"<div id="rustdoc_body_wrapper">Slow docs</div>"#)
+            })
+            .create();
+
+        assert_eq!(DocsRsClient::new().request_timeout, Some(DEFAULT_REQUEST_TIMEOUT));
+        let client = DocsRsClient::new_with_base_url(&server.url())
+            .with_request_timeout(Some(Duration::from_millis(200)));
+        let started = std::time::Instant::now();
+        let result = client.fetch_docs(DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            path: "demo/struct.Slow.html".to_string(),
+        }).await;
+        assert!(matches!(result, Err(DocsFetchError::Timeout)), "got {:?}", result);
+        assert!(started.elapsed() < Duration::from_millis(900), "took {:?}", started.elapsed());
+    }
+
+    #[test]
+    fn test_parse_auto_traits_legacy_markup() {
+        let html = r##"<html><body>
+            <div id="synthetic-implementations-list" class="item-list">
+            <div id="impl-Send" class="impl"><code class="in-band">impl Send for Handle</code><a href="#impl-Send" class="anchor"></a></div>
+            <div id="impl-Sync" class="impl"><code class="in-band">impl !Sync for Handle</code><a href="#impl-Sync" class="anchor"></a></div>
+            </div></body></html>"##;
+
+        let auto_traits = DocsRsClient::new().parse_auto_traits(html);
+        assert_eq!(auto_traits.impls.len(), 2);
+        assert_eq!(auto_traits.impls[0].trait_name, "Send");
+        assert!(!auto_traits.impls[0].negative);
+        assert_eq!(auto_traits.impls[1].trait_name, "Sync");
+        assert!(auto_traits.impls[1].negative);
+    }
+
+    #[test]
+    fn test_parse_sections_struct_page() {
+        let html = r##"<html><body><div id="rustdoc_body_wrapper"><nav class="sidebar"><h2>Sidebar</h2></nav>
+            <main><section id="main-content" class="content">
+            <div class="main-heading"><h1>Struct <span class="struct">Point</span><button id="copy-path">Copy item path</button></h1>
+            <span class="sub-heading"><a class="src" href="../src/geo/lib.rs.html#3-6">Source</a></span></div>
+            <pre class="rust item-decl"><code>pub struct Point {
+    pub x: <a class="primitive" href="https://doc.rust-lang.org/nightly/std/primitive.f64.html">f64</a>,
+    pub y: f64,
+}</code></pre>
+            <details class="toggle top-doc" open><summary class="hideme"><span>Expand description</span></summary>
+            <div class="docblock"><p>A point in the <em>plane</em>.</p>
+            <h2 id="examples"><a class="doc-anchor" href="#examples">§</a>Examples</h2>
+            <div class="example-wrap"><pre class="rust rust-example-rendered"><code>let p = Point { x: 1.0, y: 2.0 };
+assert_eq!(p.x, 1.0);</code></pre></div></div></details>
+            <h2 id="fields" class="fields section-header">Fields<a href="#fields" class="anchor">§</a></h2>
+            <span id="structfield.x" class="structfield section-header"><a href="#structfield.x" class="anchor field">§</a><code>x: f64</code></span><div class="docblock"><p>Horizontal coordinate.</p></div>
+            <h2 id="implementations" class="section-header">Implementations<a href="#implementations" class="anchor">§</a></h2>
+            <div id="implementations-list"><details class="toggle implementors-toggle" open><summary><section id="impl-Point" class="impl"><h3 class="code-header">impl Point</h3></section></summary>
+            <div class="impl-items"><details class="toggle method-toggle" open><summary><section id="method.norm" class="method"><h4 class="code-header">pub fn norm(&amp;self) -&gt; f64</h4></section></summary>
+            <div class="docblock"><p>Distance from the origin.</p><h5 id="panics"><a class="doc-anchor" href="#panics">§</a>Panics</h5><p>Never.</p></div></details></div></details></div>
+            <h2 id="trait-implementations" class="section-header">Trait Implementations<a href="#trait-implementations" class="anchor">§</a></h2>
+            <div id="trait-implementations-list"><section id="impl-Clone-for-Point" class="impl"><h3 class="code-header">impl Clone for Point</h3></section></div>
+            </section></main></div></body></html>"##;
+
+        let page = DocsRsClient::new().parse_sections(html, "geo/struct.Point.html").unwrap();
+        let names: Vec<_> = page.sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["Summary", "Declaration", "Examples", "Fields", "Implementations", "Trait Implementations"]);
+
+        let section = |name: &str| &page.sections.iter().find(|s| s.name == name).unwrap().content;
+        assert_eq!(section("Summary"), "A point in the plane.");
+        assert_eq!(section("Declaration"), "pub struct Point {\n    pub x: f64,\n    pub y: f64,\n}");
+        assert_eq!(section("Examples"), "let p = Point { x: 1.0, y: 2.0 };\nassert_eq!(p.x, 1.0);");
+        assert_eq!(section("Fields"), "x: f64\nHorizontal coordinate.");
+        assert!(section("Implementations").contains("pub fn norm(&self) -> f64\nDistance from the origin.\nPanics\nNever."));
+        assert_eq!(section("Trait Implementations"), "impl Clone for Point");
+        assert!(DocsRsClient::new().parse_sections("<html></html>", "x.html").is_err());
+    }
+
+    #[test]
+    fn test_parse_sections_strips_hidden_example_lines() {
+        let html = r##"<html><body><section id="main-content">
+            <details class="toggle top-doc" open><div class="docblock"><p>Parses input.</p>
+            <h2 id="examples">Examples</h2>
+            <div class="example-wrap"><pre class="rust rust-example-rendered"><code># use demo::parse;
+# fn main() -> Result&lt;(), demo::Error&gt; {
+let tokens = parse("a # b")?;
+##[derive(Debug)]
+struct Wrapper;
+    # let _ = Wrapper;
+#
+# Ok(())
+# }</code></pre></div>
+            <div class="example-wrap"><pre class="language-sh"><code># install
+cargo add demo</code></pre></div></div></details>
+            </section></body></html>"##;
+
+        let page = DocsRsClient::new().parse_sections(html, "demo/fn.parse.html").unwrap();
+        let examples = &page.sections.iter().find(|s| s.name == "Examples").unwrap().content;
+        assert_eq!(examples, "let tokens = parse(\"a # b\")?;\n#[derive(Debug)]\nstruct Wrapper;\n# install\ncargo add demo");
+    }
+
+    #[test]
+    fn test_extract_rustdoc_content_strips_trailing_boilerplate() {
+        let html = r##"<html><body><div id="rustdoc_body_wrapper">
+            <section id="main-content"><h2 id="trait-implementations">Trait Implementations</h2>
+            <section class="impl"><h3 class="code-header">impl Clone for Point</h3></section></section>
+            <div id="help" class="hidden"><h2>Keyboard Shortcuts</h2><dl><dt>?</dt><dd>Show this help dialog</dd></dl></div>
+            <script>window.searchIndex = {};</script>
+            <p class="generated">Auto-generated by rustdoc</p>
+            </div></body></html>"##;
+
+        let content = DocsRsClient::new().extract_rustdoc_content(html, OutputFormat::PlainText).unwrap();
+        assert!(!content.contains("Keyboard Shortcuts"));
+        assert!(!content.contains("searchIndex"));
+        assert!(content.contains("Auto-generated by rustdoc"));
+
+        let boilerplate = Boilerplate {
+            trailing_text: vec!["Auto-generated by rustdoc".to_string()],
+            ..Boilerplate::default()
+        };
+        let client = DocsRsClient::new().with_boilerplate(Arc::new(boilerplate));
+        let content = client.extract_rustdoc_content(html, OutputFormat::PlainText).unwrap();
+        assert!(content.ends_with("impl Clone for Point"), "got {:?}", content);
+        assert!(content.contains("Trait Implementations"));
+    }
+
+    #[test]
+    fn test_extract_rustdoc_content_normalizes_whitespace() {
+        let html = "<html><body><div id=\"rustdoc_body_wrapper\"><p>First   \n\n\n\n\nSecond \t</p>\
+            <pre class=\"rust\"><code>fn main() {   \n\n\n\n    let x = 1;  \n}</code></pre>\
+            <p>Third\n \n \n \nFourth</p></div></body></html>";
+
+        let content = DocsRsClient::new().extract_rustdoc_content(html, OutputFormat::PlainText).unwrap();
+        assert!(content.contains("First\n\nSecond"), "got {:?}", content);
+        assert!(content.contains("Third\n\nFourth"), "got {:?}", content);
+        assert!(content.contains("fn main() {   \n\n\n\n    let x = 1;  \n}"), "got {:?}", content);
+        assert!(!content.contains("Second \t"));
+
+        let raw = DocsRsClient::new()
+            .with_normalize_whitespace(false)
+            .extract_rustdoc_content(html, OutputFormat::PlainText)
+            .unwrap();
+        assert!(raw.contains("First   \n\n\n\n\nSecond"), "got {:?}", raw);
+    }
+
+    #[test]
+    fn test_extract_rustdoc_content_across_rustdoc_versions() {
+        // rustdoc 1.50, self-hosted without the docs.rs wrapper
+        let legacy = r##"<html><body><nav class="sidebar"><p class="location">Struct Widget</p></nav>
+            <section id="main" class="content"><h1 class="fqn"><span class="in-band">Struct <a class="struct" href="">Widget</a></span></h1>
+            <pre class="rust struct">pub struct Widget { /* fields omitted */ }</pre>
+            <div class="docblock"><p>A widget rendered by an old rustdoc version.</p></div></section>
+            <section id="search" class="content hidden"></section></body></html>"##;
+        // rustdoc 1.80 on docs.rs, with an empty docs.rs wrapper left by a
+        // page template change
+        let current = r##"<html><body><div id="rustdoc_body_wrapper"></div>
+            <main><div class="width-limiter"><section id="main-content" class="content">
+            <div class="main-heading"><h1>Struct <span class="struct">Widget</span></h1></div>
+            <pre class="rust item-decl"><code>pub struct Widget { /* private fields */ }</code></pre>
+            <details class="toggle top-doc" open><div class="docblock"><p>A widget rendered by a current rustdoc version.</p></div></details>
+            </section></div></main></body></html>"##;
+
+        let client = DocsRsClient::new();
+        let legacy = client.extract_rustdoc_content(legacy, OutputFormat::PlainText).unwrap();
+        assert!(legacy.contains("A widget rendered by an old rustdoc version."), "got {:?}", legacy);
+        assert!(!legacy.contains("Struct Widget\n"), "sidebar left out: {:?}", legacy);
+        let current = client.extract_rustdoc_content(current, OutputFormat::PlainText).unwrap();
+        assert!(current.contains("pub struct Widget { /* private fields */ }"), "got {:?}", current);
+        assert!(current.contains("A widget rendered by a current rustdoc version."), "got {:?}", current);
+
+        assert_eq!(client.extract_rustdoc_content("<html><body><p>Not rustdoc</p></body></html>", OutputFormat::PlainText), None);
+    }
+
+    #[test]
+    fn test_extract_rustdoc_content_as_markdown() {
+        let html = include_str!("../tests/fixtures/struct.Mutex.html");
+        let expected = include_str!("../tests/fixtures/struct.Mutex.md");
+
+        let markdown = DocsRsClient::new().extract_rustdoc_content(html, OutputFormat::Markdown).unwrap();
+        assert_eq!(markdown, expected.trim_end());
+        assert!(!markdown.contains("Copy item path"), "boilerplate left out");
+    }
+
+    #[test]
+    fn test_extract_rustdoc_content_strips_page_chrome() {
+        let html = include_str!("../tests/fixtures/struct.Barrier.html");
+        let client = DocsRsClient::new();
+
+        for format in [OutputFormat::PlainText, OutputFormat::Markdown] {
+            let content = client.extract_rustdoc_content(html, format).unwrap();
+            assert!(content.contains("A barrier enables multiple tasks"), "{:?}: {}", format, content);
+            assert!(content.contains("Does not resolve until all tasks have rendezvoused here."), "{:?}: {}", format, content);
+            for chrome in ["Sidebar Methods", "sidebar_wait_link", "In demo::sync", "Type ‘S’", "Settings", "demo::sync", "Copy item path", "Docs.rs footer"] {
+                assert!(!content.contains(chrome), "{:?} kept {:?}: {}", format, chrome, content);
+            }
+        }
+    }
+
     #[test]
     fn test_extract_rustdoc_content() {
         let html = r#"<!DOCTYPE html><html><body>
@@ -352,7 +4653,7 @@ mod tests {
             </body></html>"#;
 
         let client = DocsRsClient::new();
-        let content = client.extract_rustdoc_content(html);
+        let content = client.extract_rustdoc_content(html, OutputFormat::PlainText);
         
         assert!(content.is_some());
         let parsed = content.unwrap();
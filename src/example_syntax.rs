@@ -0,0 +1,64 @@
+//! Syntax checking of documentation examples.
+//!
+//! Examples are parsed with `syn` the way rustdoc compiles doctests: code
+//! without a `fn main` is wrapped in one first, so that examples made of
+//! plain statements are accepted. Nothing is compiled or executed, so type
+//! and borrow errors go unnoticed.
+
+use serde::{Deserialize, Serialize};
+
+/// Where and why an example failed to parse.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct SyntaxError {
+    /// Description of the error
+    pub message: String,
+    /// Line of the error in the example, starting at 1
+    pub line: usize,
+    /// Column of the error in the line, starting at 1
+    pub column: usize,
+}
+
+/// Checks that `code` is syntactically valid Rust.
+///
+/// # Errors
+///
+/// Returns the first [`SyntaxError`] found by the parser.
+pub fn check(code: &str) -> Result<(), SyntaxError> {
+    let wrapped = !code.contains("fn main");
+    let source = match wrapped {
+        true => format!("fn main() {{\n{}\n}}", code),
+        false => code.to_string(),
+    };
+    syn::parse_file(&source).map(drop).map_err(|e| {
+        let start = e.span().start();
+        // Errors in the wrapper, e.g. an unclosed delimiter reported at the
+        // closing brace, are attributed to the nearest line of the example
+        let line = match wrapped {
+            true => start.line.saturating_sub(1),
+            false => start.line,
+        };
+        SyntaxError {
+            message: e.to_string(),
+            line: line.clamp(1, code.lines().count().max(1)),
+            column: start.column + 1,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_accepts_statements_and_items() {
+        assert_eq!(check("let v = vec![1, 2, 3];\nassert_eq!(v.len(), 3);"), Ok(()));
+        assert_eq!(check("use std::fmt;\n\nfn main() {\n    println!(\"{}\", 1);\n}"), Ok(()));
+    }
+
+    #[test]
+    fn test_check_reports_error_location() {
+        let error = check("let v = vec![1, 2, 3];\nlet n = v.len(;\n").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert!(!error.message.is_empty());
+    }
+}
@@ -0,0 +1,196 @@
+//! Parsing of a crate's `[features]` table into a feature dependency graph.
+//!
+//! crates.io reports the features of a published version as a map from
+//! feature name to the entries it enables, exactly as written in the
+//! manifest. Entries take one of these forms:
+//! - `feature`: another feature of the crate, or, for manifests without
+//!   `dep:` syntax, an optional dependency through its implicit feature
+//! - `dep:name`: the optional dependency `name`, without enabling a feature
+//! - `name/feature`: the dependency `name` (enabling it if optional) and its
+//!   feature `feature`
+//! - `name?/feature`: the feature `feature` of `name`, only if `name` is
+//!   enabled by something else
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+/// A feature of a dependency enabled by a feature of the crate.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct DependencyFeature {
+    /// Name of the dependency
+    pub dependency: String,
+    /// Feature enabled on the dependency
+    pub feature: String,
+    /// Whether the feature only applies if the dependency is enabled
+    /// elsewhere (`name?/feature`), rather than enabling it
+    pub weak: bool,
+}
+
+/// What a single feature enables.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureNode {
+    /// Features of the crate enabled directly
+    pub enables: Vec<String>,
+    /// Features of the crate enabled directly or through other features
+    pub transitive: Vec<String>,
+    /// Optional dependencies enabled directly or through other features
+    pub dependencies: Vec<String>,
+    /// Dependency features enabled directly or through other features
+    pub dependency_features: Vec<DependencyFeature>,
+}
+
+/// Feature dependency graph of a crate version.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureGraph {
+    /// Name of the crate
+    pub crate_name: String,
+    /// Version the features were read from
+    pub version: String,
+    /// Every declared feature and what it enables
+    pub features: BTreeMap<String, FeatureNode>,
+}
+
+/// Entry of a feature's enable list, classified.
+enum Enable<'a> {
+    Feature(&'a str),
+    Dependency(&'a str),
+    DependencyFeature(DependencyFeature),
+}
+
+impl<'a> Enable<'a> {
+    /// Classifies an entry; `declared` are the names of the crate's features.
+    fn parse(entry: &'a str, declared: &BTreeMap<String, Vec<String>>) -> Self {
+        if let Some(dependency) = entry.strip_prefix("dep:") {
+            return Self::Dependency(dependency);
+        }
+        if let Some((dependency, feature)) = entry.split_once('/') {
+            let (dependency, weak) = match dependency.strip_suffix('?') {
+                Some(dependency) => (dependency, true),
+                None => (dependency, false),
+            };
+            return Self::DependencyFeature(DependencyFeature {
+                dependency: dependency.to_string(),
+                feature: feature.to_string(),
+                weak,
+            });
+        }
+        if declared.contains_key(entry) {
+            Self::Feature(entry)
+        } else {
+            // Implicit feature of an optional dependency
+            Self::Dependency(entry)
+        }
+    }
+}
+
+impl FeatureGraph {
+    /// Builds the graph of a crate version's `[features]` table.
+    ///
+    /// Cycles, which cargo rejects but a hand-written table may contain, are
+    /// tolerated: every feature is visited once per traversal.
+    pub fn from_features(crate_name: &str, version: &str, declared: &BTreeMap<String, Vec<String>>) -> Self {
+        let features = declared
+            .iter()
+            .map(|(name, entries)| (name.clone(), Self::node(name, entries, declared)))
+            .collect();
+        Self {
+            crate_name: crate_name.to_string(),
+            version: version.to_string(),
+            features,
+        }
+    }
+
+    fn node(name: &str, entries: &[String], declared: &BTreeMap<String, Vec<String>>) -> FeatureNode {
+        let mut enables = BTreeSet::new();
+        let mut transitive = BTreeSet::new();
+        let mut dependencies = BTreeSet::new();
+        let mut dependency_features = BTreeSet::new();
+
+        let mut visited = BTreeSet::from([name]);
+        let mut pending: Vec<(&str, bool)> = entries.iter().map(|entry| (entry.as_str(), true)).collect();
+        while let Some((entry, direct)) = pending.pop() {
+            match Enable::parse(entry, declared) {
+                Enable::Feature(feature) => {
+                    if direct {
+                        enables.insert(feature.to_string());
+                    }
+                    if feature != name {
+                        transitive.insert(feature.to_string());
+                    }
+                    if visited.insert(feature) {
+                        pending.extend(declared[feature].iter().map(|entry| (entry.as_str(), false)));
+                    }
+                }
+                Enable::Dependency(dependency) => {
+                    dependencies.insert(dependency.to_string());
+                }
+                Enable::DependencyFeature(dependency_feature) => {
+                    if !dependency_feature.weak {
+                        dependencies.insert(dependency_feature.dependency.clone());
+                    }
+                    dependency_features.insert(dependency_feature);
+                }
+            }
+        }
+
+        FeatureNode {
+            enables: enables.into_iter().collect(),
+            transitive: transitive.into_iter().collect(),
+            dependencies: dependencies.into_iter().collect(),
+            dependency_features: dependency_features.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(entries: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        entries
+            .iter()
+            .map(|(name, enables)| (name.to_string(), enables.iter().map(|e| e.to_string()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_feature_graph_resolves_transitive_enables() {
+        let declared = table(&[
+            ("default", &["std"]),
+            ("std", &["alloc", "serde?/std"]),
+            ("alloc", &["dep:hashbrown"]),
+            ("serde", &["dep:serde", "indexmap/serde"]),
+            ("full", &["default", "serde", "rayon"]),
+        ]);
+
+        let graph = FeatureGraph::from_features("demo", "1.0.0", &declared);
+
+        let default = &graph.features["default"];
+        assert_eq!(default.enables, vec!["std"]);
+        assert_eq!(default.transitive, vec!["alloc", "std"]);
+        assert_eq!(default.dependencies, vec!["hashbrown"]);
+        assert_eq!(default.dependency_features, vec![DependencyFeature {
+            dependency: "serde".to_string(),
+            feature: "std".to_string(),
+            weak: true,
+        }]);
+
+        let full = &graph.features["full"];
+        assert_eq!(full.enables, vec!["default", "serde"]);
+        assert_eq!(full.transitive, vec!["alloc", "default", "serde", "std"]);
+        // `rayon` is not a declared feature, so it names an optional dependency
+        assert_eq!(full.dependencies, vec!["hashbrown", "indexmap", "rayon", "serde"]);
+        assert_eq!(full.dependency_features.len(), 2);
+
+        assert_eq!(graph.features["alloc"].transitive, Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_feature_graph_tolerates_cycles() {
+        let declared = table(&[("a", &["b"]), ("b", &["a"])]);
+        let graph = FeatureGraph::from_features("demo", "1.0.0", &declared);
+        assert_eq!(graph.features["a"].transitive, vec!["b"]);
+        assert_eq!(graph.features["b"].transitive, vec!["a"]);
+    }
+}
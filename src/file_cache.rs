@@ -0,0 +1,497 @@
+//! Documentation cache kept on disk, one file per entry.
+//!
+//! [`InMemoryCache`](crate::cache::InMemoryCache) holds every entry in
+//! memory and only uses the disk to persist them. [`FileCache`] instead reads
+//! and writes the file of an entry when it is requested or stored, so its
+//! memory use does not grow with the number of cached crates.
+//!
+//! Entries live in `{shard}/{hash}.json` below the cache directory, where
+//! `hash` is the hex SHA-256 of the crate name and normalized key, see
+//! [`crate::cache_key`], and `shard` its first two characters. Each file
+//! holds the key, content and fetch time of its entry. The shard
+//! directories are never taken for files of a [`CacheLayout`], so both
+//! caches can share a directory.
+//!
+//! [`CacheLayout`]: crate::cache_layout::CacheLayout
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use clap::ValueEnum;
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs;
+
+use crate::cache::{unix_now, write_atomically, Cache, CacheEntry, CacheStats};
+use crate::cache_key::{normalize_key, CacheKey};
+use crate::docs_parser::DocContent;
+
+/// Cache implementations selectable on the command line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum CacheBackend {
+    /// All entries in memory, persisted on save
+    #[default]
+    Memory,
+    /// Entries read from and written to disk one file at a time
+    File,
+    /// Nothing cached, every document fetched again
+    None,
+}
+
+/// Length of the hash prefix naming the shard directory of an entry.
+const SHARD_LEN: usize = 2;
+
+/// Contents of an entry file.
+#[derive(Debug, Serialize, Deserialize)]
+struct FileEntry {
+    /// Key of the entry, checked on reads against hash collisions
+    key: CacheKey,
+    #[serde(flatten)]
+    entry: CacheEntry,
+}
+
+/// Cache storing each entry in a file of its own, see the
+/// [module documentation](self).
+#[derive(Debug)]
+pub struct FileCache {
+    /// Directory below which the entry files are stored
+    cache_dir: PathBuf,
+    /// Fail loading when the cache directory does not exist
+    require_dir: bool,
+    /// Time entries are served after being fetched, `None` to keep them
+    /// forever
+    ttl: Option<Duration>,
+    /// Lookups answered since the cache was created
+    hits: AtomicU64,
+    /// Lookups of missing entries since the cache was created
+    misses: AtomicU64,
+    /// Entries written since the cache was created
+    inserts: AtomicU64,
+}
+
+impl FileCache {
+    /// Creates a cache storing its entries below `cache_dir`.
+    pub fn new(cache_dir: PathBuf) -> Self {
+        Self {
+            cache_dir,
+            require_dir: false,
+            ttl: None,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            inserts: AtomicU64::new(0),
+        }
+    }
+
+    /// Makes [`Cache::load`] fail when the cache directory does not exist,
+    /// instead of creating it.
+    pub fn with_require_dir(mut self, require_dir: bool) -> Self {
+        self.require_dir = require_dir;
+        self
+    }
+
+    /// Sets the time entries are served after being fetched; expired
+    /// entries count as missing, but stay readable through
+    /// [`Cache::get_stale`] until written again. `None` or a zero TTL, the
+    /// default, keeps entries forever.
+    pub fn with_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.ttl = ttl.filter(|ttl| !ttl.is_zero());
+        self
+    }
+
+    /// Returns the location of the file of `key`.
+    fn entry_path(&self, key: &CacheKey) -> PathBuf {
+        let mut hasher = Sha256::new();
+        hasher.update(key.crate_name().as_bytes());
+        hasher.update([0]);
+        hasher.update(normalize_key(key).as_bytes());
+        let hash: String = hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect();
+        self.cache_dir.join(&hash[..SHARD_LEN]).join(format!("{}.json", hash))
+    }
+
+    /// Reads the entry file at `path`, or `None` if it is missing or
+    /// unreadable.
+    async fn read_entry(path: &Path) -> Option<FileEntry> {
+        let contents = match fs::read(path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                tracing::warn!("Failed to read cache entry {:?}: {}", path, e);
+                return None;
+            }
+        };
+        match serde_json::from_slice(&contents) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                tracing::warn!("Skipping unreadable cache entry {:?}: {}", path, e);
+                None
+            }
+        }
+    }
+
+    /// Returns the entry of `key`, whether or not it expired.
+    async fn read_any(&self, key: &CacheKey) -> Option<CacheEntry> {
+        let path = self.entry_path(key);
+        Self::read_entry(&path).await.filter(|file_entry| file_entry.key == *key).map(|file_entry| file_entry.entry)
+    }
+
+    /// Returns the unexpired entry of `key`. Expired entry files are kept
+    /// for [`Cache::get_stale`] until the entry is fetched and written again.
+    async fn read_valid(&self, key: &CacheKey) -> Option<CacheEntry> {
+        self.read_any(key).await.filter(|entry| !entry.is_expired(self.ttl, unix_now()))
+    }
+
+    /// Writes the entry file of `key` with [`write_atomically`], so that
+    /// readers never see a partly written entry.
+    async fn write_entry(&self, key: CacheKey, value: DocContent) -> io::Result<()> {
+        let path = self.entry_path(&key);
+        if let Some(shard) = path.parent() {
+            fs::create_dir_all(shard).await?;
+        }
+        let contents = serde_json::to_vec(&FileEntry { key, entry: CacheEntry::new(value) })?;
+        write_atomically(&path, contents).await
+    }
+
+    /// Returns the shard directories below the cache directory.
+    async fn shard_dirs(&self) -> io::Result<Vec<PathBuf>> {
+        let mut dirs = Vec::new();
+        let mut entries = match fs::read_dir(&self.cache_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(dirs),
+            Err(e) => return Err(e),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let is_shard = entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.len() == SHARD_LEN && name.bytes().all(|byte| byte.is_ascii_hexdigit()));
+            if is_shard && entry.file_type().await?.is_dir() {
+                dirs.push(entry.path());
+            }
+        }
+        Ok(dirs)
+    }
+
+    /// Returns the entry files of the shard directory `dir`.
+    async fn entry_files(dir: PathBuf) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        let mut entries = match fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                tracing::warn!("Failed to list cache entries in {:?}: {}", dir, e);
+                return files;
+            }
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            if path.extension().is_some_and(|extension| extension == "json") {
+                files.push(path);
+            }
+        }
+        files
+    }
+}
+
+#[async_trait]
+impl Cache for FileCache {
+    /// Reads the entry of `key` from its file.
+    async fn get(&self, key: &CacheKey) -> Option<DocContent> {
+        let content = self.read_valid(key).await.map(|entry| entry.value);
+        let counter = if content.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        content
+    }
+
+    /// Reads the entry of `key` from its file whether or not it expired.
+    async fn get_stale(&self, key: &CacheKey) -> Option<DocContent> {
+        self.read_any(key).await.map(|entry| entry.value)
+    }
+
+    /// Writes the entry to its file. Failures are logged, leaving the entry
+    /// uncached.
+    async fn insert(&self, key: CacheKey, value: DocContent) {
+        let path = self.entry_path(&key);
+        match self.write_entry(key, value).await {
+            Ok(()) => {
+                self.inserts.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(e) => tracing::error!("Failed to write cache entry {:?}: {}", path, e),
+        }
+    }
+
+    /// Checks whether the file of `key` exists. With a time to live the
+    /// file is read to check that the entry has not expired.
+    async fn contains_key(&self, key: &CacheKey) -> bool {
+        match self.ttl {
+            Some(_) => self.read_valid(key).await.is_some(),
+            None => fs::try_exists(self.entry_path(key)).await.unwrap_or(false),
+        }
+    }
+
+    /// Removes all entry files, leaving other files of the cache directory
+    /// alone.
+    async fn clear(&self) -> usize {
+        let dirs = match self.shard_dirs().await {
+            Ok(dirs) => dirs,
+            Err(e) => {
+                tracing::error!("Failed to list cache directory {:?}: {}", self.cache_dir, e);
+                return 0;
+            }
+        };
+        let mut count = 0;
+        for dir in dirs {
+            let files = Self::entry_files(dir.clone()).await.len();
+            match fs::remove_dir_all(&dir).await {
+                Ok(()) => count += files,
+                Err(e) => tracing::error!("Failed to remove cache entries in {:?}: {}", dir, e),
+            }
+        }
+        count
+    }
+
+    /// Removes the entry files of a crate. Entries are spread over the
+    /// shards by hash, so every entry file is read to find them.
+    async fn clear_crate(&self, crate_name: &str) -> usize {
+        let dirs = match self.shard_dirs().await {
+            Ok(dirs) => dirs,
+            Err(e) => {
+                tracing::error!("Failed to list cache directory {:?}: {}", self.cache_dir, e);
+                return 0;
+            }
+        };
+        let mut count = 0;
+        for dir in dirs {
+            for path in Self::entry_files(dir).await {
+                let Some(file_entry) = Self::read_entry(&path).await else {
+                    continue;
+                };
+                if file_entry.key.crate_name() != crate_name {
+                    continue;
+                }
+                match fs::remove_file(&path).await {
+                    Ok(()) => count += 1,
+                    Err(e) => tracing::error!("Failed to remove cache entry {:?}: {}", path, e),
+                }
+            }
+        }
+        count
+    }
+
+    /// Streams all entries, reading one file at a time. Expired and
+    /// unreadable entries are skipped.
+    fn entries(&self) -> BoxStream<'_, (CacheKey, DocContent)> {
+        stream::once(async move {
+            let dirs = self.shard_dirs().await.unwrap_or_else(|e| {
+                tracing::error!("Failed to list cache directory {:?}: {}", self.cache_dir, e);
+                Vec::new()
+            });
+            stream::iter(dirs).then(Self::entry_files).flat_map(stream::iter)
+        })
+        .flatten()
+        .filter_map(move |path| async move {
+            let file_entry = Self::read_entry(&path).await?;
+            (!file_entry.entry.is_expired(self.ttl, unix_now())).then_some((file_entry.key, file_entry.entry.value))
+        })
+        .boxed()
+    }
+
+    /// Does nothing, as every entry is written when it is inserted.
+    async fn save(&self) -> Result<(), io::Error> {
+        Ok(())
+    }
+
+    /// Returns `false`, as every entry is written when it is inserted.
+    fn has_unsaved_changes(&self) -> bool {
+        false
+    }
+
+    /// Creates the cache directory if needed; entries are only read when
+    /// requested.
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error if the directory is required but missing, or
+    /// cannot be created.
+    async fn load(&self) -> Result<(), io::Error> {
+        if self.require_dir && !fs::try_exists(&self.cache_dir).await? {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("cache directory {:?} does not exist", self.cache_dir),
+            ));
+        }
+        fs::create_dir_all(&self.cache_dir).await
+    }
+
+    fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            inserts: self.inserts.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Counts the entry files.
+    async fn entry_count(&self) -> usize {
+        let dirs = self.shard_dirs().await.unwrap_or_else(|e| {
+            tracing::warn!("Failed to list cache directory {:?}: {}", self.cache_dir, e);
+            Vec::new()
+        });
+        let mut count = 0;
+        for dir in dirs {
+            count += Self::entry_files(dir).await.len();
+        }
+        count
+    }
+
+    /// Sums the sizes of the entry files, which include their keys and
+    /// fetch times besides the content.
+    async fn size_bytes(&self) -> u64 {
+        let dirs = self.shard_dirs().await.unwrap_or_else(|e| {
+            tracing::warn!("Failed to list cache directory {:?}: {}", self.cache_dir, e);
+            Vec::new()
+        });
+        let mut size = 0;
+        for dir in dirs {
+            for path in Self::entry_files(dir).await {
+                size += fs::metadata(&path).await.map_or(0, |metadata| metadata.len());
+            }
+        }
+        size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::InMemoryCache;
+    use crate::docs_parser::DocsRsParams;
+    use tempfile::tempdir;
+
+    fn key(crate_name: &str, path: &str) -> CacheKey {
+        CacheKey::new(DocsRsParams {
+            crate_name: crate_name.to_string(),
+            version: "1.0.0".to_string(),
+            path: path.to_string(),
+        })
+    }
+
+    fn content(text: &str) -> DocContent {
+        DocContent { content: text.to_string(), source_url: None }
+    }
+
+    #[tokio::test]
+    async fn test_entries_are_read_from_disk() {
+        let dir = tempdir().unwrap();
+        let cache = FileCache::new(dir.path().to_path_buf());
+        cache.load().await.unwrap();
+        let serde = key("serde", "serde/index.html");
+        let markdown = serde.clone().with_format("markdown");
+        cache.insert(serde.clone(), content("serde docs")).await;
+        cache.insert(markdown.clone(), content("# serde")).await;
+
+        // A second instance sees the entries without loading them
+        let reopened = FileCache::new(dir.path().to_path_buf());
+        assert!(reopened.contains_key(&serde).await);
+        assert_eq!(reopened.get(&serde).await, Some(content("serde docs")));
+        assert_eq!(reopened.get(&markdown).await, Some(content("# serde")));
+        assert_eq!(reopened.get(&key("tokio", "tokio/index.html")).await, None);
+        assert_eq!(reopened.stats(), CacheStats { hits: 2, misses: 1, inserts: 0 });
+        assert_eq!(reopened.entry_count().await, 2);
+        assert_eq!(cache.stats().inserts, 2);
+
+        let mut entries: Vec<_> = reopened.entries().collect().await;
+        entries.sort_by(|a, b| a.1.content.cmp(&b.1.content));
+        assert_eq!(entries, [(markdown, content("# serde")), (serde.clone(), content("serde docs"))]);
+
+        reopened.insert(serde.clone(), content("new serde docs")).await;
+        assert_eq!(cache.get(&serde).await, Some(content("new serde docs")));
+    }
+
+    #[tokio::test]
+    async fn test_clear_keeps_files_of_other_caches() {
+        let dir = tempdir().unwrap();
+        let memory = InMemoryCache::new(dir.path().to_path_buf());
+        memory.insert(key("serde", "serde/index.html"), content("serde docs")).await;
+        memory.save().await.unwrap();
+        let cache = FileCache::new(dir.path().to_path_buf());
+        cache.insert(key("tokio", "tokio/index.html"), content("tokio docs")).await;
+
+        cache.clear().await;
+        assert!(!cache.contains_key(&key("tokio", "tokio/index.html")).await);
+        assert_eq!(cache.entries().count().await, 0);
+        let reloaded = InMemoryCache::new(dir.path().to_path_buf());
+        reloaded.load().await.unwrap();
+        assert_eq!(reloaded.get(&key("serde", "serde/index.html")).await, Some(content("serde docs")));
+    }
+
+    #[tokio::test]
+    async fn test_clear_crate_removes_only_its_entries() {
+        let dir = tempdir().unwrap();
+        let cache = FileCache::new(dir.path().to_path_buf());
+        cache.insert(key("serde", "serde/index.html"), content("serde docs")).await;
+        cache.insert(key("serde", "serde/trait.Serialize.html"), content("Serialize docs")).await;
+        cache.insert(key("tokio", "tokio/index.html"), content("tokio docs")).await;
+
+        assert_eq!(cache.clear_crate("serde").await, 2);
+        assert!(!cache.contains_key(&key("serde", "serde/index.html")).await);
+        assert_eq!(cache.get(&key("tokio", "tokio/index.html")).await, Some(content("tokio docs")));
+        assert_eq!(cache.clear().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_size_bytes_sums_entry_files() {
+        let dir = tempdir().unwrap();
+        let cache = FileCache::new(dir.path().to_path_buf());
+        assert_eq!(cache.size_bytes().await, 0);
+        cache.insert(key("serde", "serde/index.html"), content(&"s".repeat(3000))).await;
+        cache.insert(key("tokio", "tokio/index.html"), content(&"t".repeat(5000))).await;
+
+        // Each file adds its key and fetch time to the content
+        let size = cache.size_bytes().await;
+        assert!((8000..8000 + 2 * 256).contains(&size), "size of entry files: {}", size);
+        cache.clear().await;
+        assert_eq!(cache.size_bytes().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_expired_entries_are_only_read_stale() {
+        let dir = tempdir().unwrap();
+        let cache = FileCache::new(dir.path().to_path_buf()).with_ttl(Some(Duration::from_secs(60)));
+        let stale = key("serde", "serde/index.html");
+        let entry = FileEntry {
+            key: stale.clone(),
+            entry: CacheEntry { value: content("old"), fetched_at: unix_now() - 120 },
+        };
+        let path = cache.entry_path(&stale);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, serde_json::to_vec(&entry).unwrap()).unwrap();
+
+        assert!(!cache.contains_key(&stale).await);
+        assert_eq!(cache.get(&stale).await, None);
+        assert_eq!(cache.get_stale(&stale).await, Some(content("old")));
+        assert!(path.exists());
+
+        let required = FileCache::new(dir.path().join("missing")).with_require_dir(true);
+        assert_eq!(required.load().await.unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_zero_ttl_keeps_entries() {
+        let dir = tempdir().unwrap();
+        let cache = FileCache::new(dir.path().to_path_buf()).with_ttl(Some(Duration::ZERO));
+        let old = key("serde", "serde/index.html");
+        let entry = FileEntry {
+            key: old.clone(),
+            entry: CacheEntry { value: content("old"), fetched_at: unix_now() - 365 * 24 * 60 * 60 },
+        };
+        let path = cache.entry_path(&old);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, serde_json::to_vec(&entry).unwrap()).unwrap();
+
+        assert!(cache.contains_key(&old).await);
+        assert_eq!(cache.get(&old).await, Some(content("old")));
+    }
+}
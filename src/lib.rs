@@ -13,10 +13,45 @@
 //!
 //! # Modules
 //!
+//! - [`access_log`]: Access log used to warm the cache on start
 //! - [`cache`]: Caching implementation for documentation
+//! - [`cache_key`]: Keys of documentation cache entries
+//! - [`cache_layout`]: On-disk layouts of the cache directory
+//! - [`cache_watcher`]: Reloading of cache files changed by other instances
+//! - `chaos`: Failure injection for chaos testing (`chaos` feature only)
+//! - [`circuit_breaker`]: Upstream health tracking
+//! - [`config`]: Optional configuration file
+//! - [`deadline`]: Overall time budgets of batch operations
+//! - [`doc_source`]: Sources documentation pages are fetched from
 //! - [`docs_parser`]: Interface with docs.rs and documentation parsing
+//! - [`example_syntax`]: Syntax checking of documentation examples
+//! - [`features`]: Feature dependency graphs of crates
+//! - [`file_cache`]: Disk-backed cache reading entries on demand
 //! - [`mcp`]: MCP server implementation and protocol handling
+//! - [`request_log`]: Replayable log of `fetch_document` requests
+//! - [`search_index`]: Parsing of rustdoc search indexes
+//! - [`sse_front`]: Health endpoint in front of the SSE transport
+//! - [`status`]: Health and metrics endpoints of the SSE server
+//! - [`webhook`]: Notification of downstream systems about fetched documentation
 
+pub mod access_log;
 pub mod cache;
+pub mod cache_key;
+pub mod cache_layout;
+pub mod cache_watcher;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+pub mod circuit_breaker;
+pub mod config;
+pub mod deadline;
+pub mod doc_source;
 pub mod docs_parser;
-pub mod mcp;
\ No newline at end of file
+pub mod example_syntax;
+pub mod features;
+pub mod file_cache;
+pub mod mcp;
+pub mod request_log;
+pub mod search_index;
+pub mod sse_front;
+pub mod status;
+pub mod webhook;
\ No newline at end of file
@@ -1,14 +1,45 @@
+mod access_log;
 mod docs_parser;
+mod example_syntax;
+mod features;
+mod file_cache;
 mod server;
 mod cache;
+mod cache_key;
+mod cache_layout;
+mod cache_watcher;
+#[cfg(feature = "chaos")]
+mod chaos;
+mod circuit_breaker;
+mod config;
+mod deadline;
+mod doc_source;
 mod mcp;
+mod request_log;
+mod search_index;
+mod sse_front;
+mod status;
+mod webhook;
 
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum};
 use anyhow::Result;
+use std::path::PathBuf;
+
+use crate::cache_layout::CacheLayoutKind;
+use crate::file_cache::CacheBackend;
+use crate::circuit_breaker::RetryJitter;
+use crate::config::FileConfig;
+use crate::doc_source::DocSourceKind;
+use crate::docs_parser::{CrateNamePolicy, TlsVersion};
+use crate::server::ServerOptions;
+use crate::status::CacheControlPolicy;
 
 #[derive(Parser, Debug)]
 #[command(version, about = "Rust API Documentation MCP Server")]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Type of server to run
     #[arg(short, long, value_enum, default_value_t = ServerType::Sse)]
     server_type: ServerType,
@@ -16,6 +47,268 @@ struct Cli {
     /// Address for the SSE server
     #[arg(short, long, default_value = "127.0.0.1:8080")]
     address: String,
+
+    /// Serve the /health and /metrics endpoints of the SSE server on this
+    /// address. Disabled if not set
+    #[arg(long, value_name = "ADDRESS")]
+    status_address: Option<String>,
+
+    /// Cache-Control header of /health responses, letting proxies answer
+    /// repeated health checks
+    #[arg(long, value_name = "VALUE", default_value = status::DEFAULT_HEALTH_CACHE_CONTROL)]
+    health_cache_control: String,
+
+    /// Cache-Control header of /metrics responses
+    #[arg(long, value_name = "VALUE", default_value = status::DEFAULT_METRICS_CACHE_CONTROL)]
+    metrics_cache_control: String,
+
+    /// Record requested documents to this access log and, on start, preload
+    /// the most frequently requested ones from it
+    #[arg(long, value_name = "FILE")]
+    preload_from_log: Option<PathBuf>,
+
+    /// Maximum number of documents preloaded from the access log
+    #[arg(long, default_value_t = 50)]
+    preload_limit: usize,
+
+    /// Append the parameters and arrival time of every fetch_document call
+    /// to this file as JSON lines, for replaying with replay-log
+    #[arg(long, value_name = "FILE")]
+    request_log: Option<PathBuf>,
+
+    /// Watch the cache directory and reload cache files written by other
+    /// instances sharing it
+    #[arg(long)]
+    watch_cache: bool,
+
+    /// Default maximum size in bytes of fetched documents returned to
+    /// clients; longer content is truncated. Unlimited if not set
+    #[arg(long, value_name = "BYTES")]
+    max_content_bytes: Option<usize>,
+
+    /// While docs.rs is failing, answer requests missing from the cache with a
+    /// cached copy of the same page for another version of the crate
+    #[arg(long)]
+    serve_stale_on_outage: bool,
+
+    /// Minimum length in bytes of extracted documentation for a fetch to be
+    /// considered successful; shorter content is rejected and not cached
+    #[arg(long, value_name = "BYTES", default_value_t = 0)]
+    min_content_len: usize,
+
+    /// Seconds a page that was not found is answered as missing without
+    /// asking docs.rs again; afterwards a HEAD request checks whether it
+    /// appeared. 0 disables remembering missing pages
+    #[arg(long, value_name = "SECONDS", default_value_t = 300)]
+    not_found_ttl: u64,
+
+    /// Randomization of the time docs.rs is left alone after repeated
+    /// failures, so that many servers do not retry in lockstep once it
+    /// recovers
+    #[arg(long, value_enum, default_value_t = RetryJitter::Full)]
+    retry_jitter: RetryJitter,
+
+    /// Number of times reading a cache file is retried after a transient IO
+    /// error, e.g. on a networked filesystem
+    #[arg(long, value_name = "N", default_value_t = 2)]
+    cache_load_retries: u32,
+
+    /// Maximum number of cache files read and decoded in parallel on
+    /// start; 1 loads them one after the other
+    #[arg(long, value_name = "N", default_value_t = cache::DEFAULT_LOAD_CONCURRENCY)]
+    cache_load_concurrency: usize,
+
+    /// Collapse runs of blank lines and strip trailing spaces in extracted
+    /// documentation, leaving code blocks untouched
+    #[arg(long, value_name = "BOOL", default_value_t = true, action = clap::ArgAction::Set)]
+    normalize_whitespace: bool,
+
+    /// Rewrite docs.rs links to the latest documentation of a fetched crate
+    /// to the concrete version fetched, so cached copies keep pointing at
+    /// the version they show
+    #[arg(long, value_name = "BOOL", default_value_t = true, action = clap::ArgAction::Set)]
+    pin_latest_links: bool,
+
+    /// Keep crate metadata, such as crates.io tags and features, in a
+    /// separate cache persisted in this directory
+    #[arg(long, value_name = "DIR")]
+    metadata_cache_dir: Option<PathBuf>,
+
+    /// Seconds cached crate metadata is served before being refetched
+    #[arg(long, value_name = "SECONDS", default_value_t = 86400)]
+    metadata_ttl: u64,
+
+    /// Seconds the cached list of published versions of a crate is served
+    /// before being refetched, and `latest` keeps resolving to the same
+    /// version, so that new releases show up
+    #[arg(long, value_name = "SECONDS", default_value_t = 900)]
+    versions_ttl: u64,
+
+    /// Overall seconds a batch of documents, such as fetch_crate_paths or
+    /// fetch_crate_bundle, may take; pages still missing when it runs out
+    /// are reported as timed out. Unlimited if not set
+    #[arg(long, value_name = "SECONDS")]
+    batch_timeout: Option<u64>,
+
+    /// Maximum number of pages fetched from docs.rs at once, across all
+    /// clients and tools. Unlimited if not set
+    #[arg(long, value_name = "N")]
+    max_concurrent_fetches: Option<usize>,
+
+    /// Maximum number of pages fetch_crate_bundle fetches at once, within
+    /// --max-concurrent-fetches
+    #[arg(long, value_name = "N", default_value_t = mcp::DEFAULT_BUNDLE_CONCURRENCY)]
+    bundle_concurrency: usize,
+
+    /// Let concurrent requests for the same page share one fetch from the
+    /// documentation server
+    #[arg(long, value_name = "BOOL", default_value_t = true, action = clap::ArgAction::Set)]
+    coalesce_fetches: bool,
+
+    /// When a fetched item is deprecated and its notice links to a
+    /// replacement, append the replacement's documentation
+    #[arg(long)]
+    follow_deprecation: bool,
+
+    /// Store pages whose canonical link names another page of the same
+    /// crate, such as re-exports, once under the canonical page, shared by
+    /// every path leading to it
+    #[arg(long)]
+    merge_canonical_pages: bool,
+
+    /// POST the crate, version, path and content hash of every document
+    /// fetched from docs.rs on a cache miss to this URL, in the background
+    #[arg(long, value_name = "URL")]
+    fetch_webhook_url: Option<String>,
+
+    /// Refuse connections to docs.rs and crates.io that negotiate a lower
+    /// TLS version. The TLS backend's default if not set
+    #[arg(long, value_enum, value_name = "VERSION")]
+    min_tls_version: Option<TlsVersion>,
+
+    /// Seconds a request to docs.rs or crates.io may take, including
+    /// reading the page, before it fails as timed out. 0 waits indefinitely
+    #[arg(long, value_name = "SECONDS", default_value_t = 30)]
+    request_timeout: u64,
+
+    /// Where documentation missing from the cache is fetched from: docs.rs,
+    /// or the local output of `cargo doc` in --doc-root, for environments
+    /// without network access
+    #[arg(long, value_enum, value_name = "SOURCE", default_value_t = DocSourceKind::DocsRs)]
+    doc_source: DocSourceKind,
+
+    /// Output directory of `cargo doc`, usually `target/doc`, read by the
+    /// local documentation source
+    #[arg(long, value_name = "PATH", required_if_eq("doc_source", "local"))]
+    doc_root: Option<PathBuf>,
+
+    /// Crate names accepted in requests; permissive also accepts names
+    /// crates.io does not, such as dotted names of a private registry
+    #[arg(long, value_enum, value_name = "POLICY", default_value_t = CrateNamePolicy::CratesIo)]
+    crate_names: CrateNamePolicy,
+
+    /// On-disk layout of the cache directory
+    #[arg(long, value_enum, default_value_t = CacheLayoutKind::PerCrate)]
+    cache_layout: CacheLayoutKind,
+
+    /// Where cached documentation lives: memory keeps every entry in RAM
+    /// and persists it on save; file reads and writes one file per entry
+    /// on demand, bounding memory use for large caches; none caches
+    /// nothing and fetches every document again
+    #[arg(long, value_enum, value_name = "BACKEND", default_value_t = CacheBackend::Memory)]
+    cache_backend: CacheBackend,
+
+    /// Directory the documentation cache is persisted in. `.cache` in the
+    /// working directory if not set
+    #[arg(long, value_name = "PATH")]
+    cache_dir: Option<PathBuf>,
+
+    /// Zero the cache hit and miss counters accumulated by earlier runs
+    #[arg(long)]
+    reset_stats: bool,
+
+    /// Refuse to start when the cache directory does not exist, e.g.
+    /// because the volume holding it is not mounted, instead of starting
+    /// with an empty cache
+    #[arg(long)]
+    require_cache_dir: bool,
+
+    /// Save the cache to disk every this many seconds while running, not
+    /// only on shutdown, skipping saves while nothing changed. Disabled if
+    /// not set
+    #[arg(long, value_name = "SECONDS")]
+    autosave_interval: Option<u64>,
+
+    /// Number of consecutive failed cache saves after which the server
+    /// warns that it runs in ephemeral mode, serving from memory only, until
+    /// a save succeeds again. 0 never switches
+    #[arg(long, value_name = "N", default_value_t = cache::DEFAULT_EPHEMERAL_AFTER_FAILURES)]
+    ephemeral_after_save_failures: u32,
+
+    /// Drop cached entries with empty or whitespace-only content when
+    /// loading the cache, so that they are fetched again
+    #[arg(long)]
+    drop_empty_on_load: bool,
+
+    /// Seconds cached documentation is served before being fetched again,
+    /// so that pages of `latest` versions pick up new releases. 0 keeps
+    /// documentation forever. With --max-cache-entries as well, documents
+    /// leave the cache when they expire or are evicted, whichever is first
+    #[arg(long, value_name = "SECONDS", default_value_t = 0)]
+    cache_ttl: u64,
+
+    /// Maximum number of documents kept in the cache; beyond it the least
+    /// recently used ones are evicted, even before they expire. Unlimited if
+    /// not set or 0
+    #[arg(long, value_name = "N")]
+    max_cache_entries: Option<usize>,
+
+    /// Memory-map cache files of at least this size instead of reading them
+    /// on start, decoding a crate's file only once it is requested. Files
+    /// must not be modified by other processes while the server runs
+    #[arg(long, value_name = "BYTES")]
+    mmap_cache_files_over: Option<u64>,
+
+    /// Text file with the instructions shown to MCP clients, replacing the
+    /// built-in ones, e.g. to describe a customized deployment
+    #[arg(long, value_name = "FILE")]
+    instructions_file: Option<PathBuf>,
+
+    /// Share of documentation fetches, between 0 and 1, that fail on purpose
+    /// for chaos testing
+    #[cfg(feature = "chaos")]
+    #[arg(long, hide = true, value_name = "RATE")]
+    chaos_rate: Option<f64>,
+
+    /// Kinds of failures injected with --chaos-rate; all kinds by default
+    #[cfg(feature = "chaos")]
+    #[arg(long, hide = true, value_enum, value_delimiter = ',')]
+    chaos_faults: Vec<chaos::Fault>,
+
+    /// JSON configuration file with additional settings, such as per-crate
+    /// default paths
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Token enabling administrative tools such as reload_config, which
+    /// re-reads the configuration file without a restart
+    #[arg(long, value_name = "TOKEN")]
+    admin_token: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Re-issue the requests of a request log written with --request-log,
+    /// filling the cache, then exit
+    ReplayLog {
+        /// Request log to replay
+        file: PathBuf,
+
+        /// Base URL of the docs.rs-compatible server to send the requests
+        /// to, docs.rs if not set
+        #[arg(long, value_name = "URL")]
+        docs_base_url: Option<String>,
+    },
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
@@ -26,19 +319,173 @@ enum ServerType {
     Stdio,
 }
 
+impl Cli {
+    /// Builds the server options from the parsed arguments, the loaded
+    /// configuration file and the instructions read from their file.
+    fn server_options(self, file_config: FileConfig, instructions: Option<String>) -> ServerOptions {
+        ServerOptions {
+            preload_from_log: self.preload_from_log,
+            preload_limit: self.preload_limit,
+            request_log: self.request_log,
+            watch_cache: self.watch_cache,
+            max_content_bytes: self.max_content_bytes,
+            serve_stale_on_outage: self.serve_stale_on_outage,
+            min_content_len: self.min_content_len,
+            not_found_ttl: std::time::Duration::from_secs(self.not_found_ttl),
+            cache_load_retries: self.cache_load_retries,
+            cache_load_concurrency: self.cache_load_concurrency,
+            retry_jitter: self.retry_jitter,
+            cache_layout: self.cache_layout,
+            cache_backend: self.cache_backend,
+            cache_dir: self.cache_dir,
+            metadata_cache_dir: self.metadata_cache_dir,
+            metadata_ttl: std::time::Duration::from_secs(self.metadata_ttl),
+            versions_ttl: std::time::Duration::from_secs(self.versions_ttl),
+            batch_timeout: self.batch_timeout.map(std::time::Duration::from_secs),
+            max_concurrent_fetches: self.max_concurrent_fetches,
+            bundle_concurrency: self.bundle_concurrency,
+            coalesce_fetches: self.coalesce_fetches,
+            follow_deprecation: self.follow_deprecation,
+            merge_canonical_pages: self.merge_canonical_pages,
+            fetch_webhook_url: self.fetch_webhook_url,
+            min_tls_version: self.min_tls_version,
+            request_timeout: Some(self.request_timeout)
+                .filter(|&secs| secs > 0)
+                .map(std::time::Duration::from_secs),
+            doc_source: self.doc_source,
+            doc_root: self.doc_root,
+            crate_name_policy: self.crate_names,
+            status_address: self.status_address,
+            status_cache_control: CacheControlPolicy {
+                health: self.health_cache_control,
+                metrics: self.metrics_cache_control,
+            },
+            instructions,
+            normalize_whitespace: self.normalize_whitespace,
+            pin_latest_links: self.pin_latest_links,
+            reset_stats: self.reset_stats,
+            require_cache_dir: self.require_cache_dir,
+            mmap_threshold: self.mmap_cache_files_over,
+            drop_empty_on_load: self.drop_empty_on_load,
+            cache_ttl: Some(self.cache_ttl).filter(|&secs| secs > 0).map(std::time::Duration::from_secs),
+            max_cache_entries: self.max_cache_entries,
+            autosave_interval: self.autosave_interval.map(std::time::Duration::from_secs),
+            ephemeral_after_save_failures: Some(self.ephemeral_after_save_failures).filter(|&n| n > 0),
+            #[cfg(feature = "chaos")]
+            chaos: self.chaos_rate.map(|rate| std::sync::Arc::new(chaos::Chaos::new(rate, self.chaos_faults))),
+            file_config,
+            config_file: self.config,
+            admin_token: self.admin_token,
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+    let file_config = match &cli.config {
+        Some(path) => FileConfig::load(path)
+            .map_err(|e| format!("Failed to load configuration file {:?}: {}", path, e))?,
+        None => FileConfig::default(),
+    };
+    let instructions = match &cli.instructions_file {
+        Some(path) => Some(
+            std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read instructions file {:?}: {}", path, e))?
+                .trim()
+                .to_string(),
+        ),
+        None => None,
+    };
+    let command = cli.command.take();
+    let server_type = cli.server_type;
+    let address = cli.address.clone();
+    let options = cli.server_options(file_config, instructions);
+
+    if let Some(Command::ReplayLog { file, docs_base_url }) = command {
+        let summary = server::replay_request_log(&file, docs_base_url, &options).await?;
+        println!("Replayed {} requests, {} failed", summary.succeeded + summary.failed, summary.failed);
+        return Ok(());
+    }
 
-    match cli.server_type {
+    match server_type {
         ServerType::Sse => {
-            println!("Starting SSE server on {}", cli.address);
-            server::start_sse_server(&cli.address).await?;
+            println!("Starting SSE server on {}", address);
+            server::start_sse_server(&address, &options).await?;
         },
         ServerType::Stdio => {
-            server::start_stdio_server().await?;
+            server::start_stdio_server(&options).await?;
         },
     }
 
     Ok(())
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docs_parser::{DocContent, DocsRsParams};
+    use tempfile::tempdir;
+
+    fn parse(args: &[&str]) -> ServerOptions {
+        Cli::try_parse_from(std::iter::once("rdoc-mcp").chain(args.iter().copied()))
+            .unwrap()
+            .server_options(FileConfig::default(), None)
+    }
+
+    fn params(path: &str) -> DocsRsParams {
+        DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            path: path.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cache_limits_reach_the_cache() {
+        let options = parse(&[]);
+        assert_eq!(options.cache_ttl, None);
+        assert_eq!(options.max_cache_entries, None);
+
+        let dir = tempdir().unwrap();
+        let cache_dir = dir.path().to_str().unwrap();
+        let options = parse(&["--cache-ttl", "60", "--max-cache-entries", "2", "--cache-dir", cache_dir]);
+        assert_eq!(options.cache_ttl, Some(std::time::Duration::from_secs(60)));
+        let (cache, _, _) = server::prepare(&options).await.unwrap();
+        for path in ["a.html", "b.html", "c.html"] {
+            cache.insert(params(path).into(), DocContent { content: path.to_string(), source_url: None }).await;
+        }
+        assert_eq!(cache.entry_count().await, 2);
+        assert!(cache.get(&params("a.html").into()).await.is_none());
+
+        // A TTL of 0 never expires entries, also with the file backend
+        let dir = tempdir().unwrap();
+        let cache_dir = dir.path().to_str().unwrap();
+        let options = parse(&["--cache-ttl", "0", "--cache-backend", "file", "--cache-dir", cache_dir]);
+        assert_eq!(options.cache_ttl, None);
+        let (cache, _, _) = server::prepare(&options).await.unwrap();
+        cache.insert(params("a.html").into(), DocContent { content: "a".to_string(), source_url: None }).await;
+        assert!(cache.get(&params("a.html").into()).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_local_doc_source_needs_a_root_directory() {
+        assert!(Cli::try_parse_from(["rdoc-mcp", "--doc-source", "local"]).is_err());
+
+        let dir = tempdir().unwrap();
+        let cache_dir = dir.path().join("cache");
+        let cache_dir = cache_dir.to_str().unwrap();
+        let doc_root = dir.path().to_str().unwrap();
+        let options = parse(&["--doc-source", "local", "--doc-root", doc_root, "--cache-dir", cache_dir]);
+        let (_, config, _) = server::prepare(&options).await.unwrap();
+        assert_eq!(config.doc_root.as_deref(), Some(dir.path()));
+
+        // The root is only used by the local source
+        let options = parse(&["--doc-root", doc_root, "--cache-dir", cache_dir]);
+        let (_, config, _) = server::prepare(&options).await.unwrap();
+        assert_eq!(config.doc_root, None);
+
+        let missing = dir.path().join("missing");
+        let options = parse(&["--doc-source", "local", "--doc-root", missing.to_str().unwrap(), "--cache-dir", cache_dir]);
+        assert!(server::prepare(&options).await.is_err());
+    }
+}
@@ -25,20 +25,353 @@
 
 use rmcp::model::{Implementation, ListPromptsResult, PaginatedRequestParam, ProtocolVersion, ServerCapabilities};
 use rmcp::service::RequestContext;
-use rmcp::{RoleServer, Error as McpError, ServerHandler, model::ServerInfo, tool};
-use rmcp::{schemars, model::{IntoContents, Content}};
+use rmcp::{RoleServer, Error as McpError, ServerHandler, model::{CallToolResult, ServerInfo}, tool};
+use rmcp::model::{IntoContents, Content, ResourceContents};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::Arc;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use futures::StreamExt;
+use tokio::sync::{RwLock, Semaphore};
 
-use crate::cache::{Cache, InMemoryCache};
-use crate::docs_parser::{DocsRsClient, DocsRsParams, DocContent, DocsFetchError};
+use crate::access_log::AccessLog;
+use crate::cache::{unix_now, Cache, CacheUsage, InMemoryCache};
+use crate::cache_key::CacheKey;
+use crate::circuit_breaker::CircuitBreaker;
+use crate::config::FileConfig;
+use crate::deadline::Deadline;
+use crate::doc_source::{DocSource, LocalDocSource};
+use crate::docs_parser::{AssociatedTypes, AutoTraits, BlanketImpls, Boilerplate, Breadcrumb, ConstructionHints, BuildStatus, CRATES_IO_URL, Constants, DEFAULT_REQUEST_TIMEOUT, CrateFullMetadata, CrateNamePolicy, CrateVersionMetadata, is_latest, LATEST_VERSION, normalize_doc_path, pin_latest_links, CrateDocs, CrateTags, Deprecation, DocLink, DocsRsClient, PublishedVersion, DocsRsParams, RenderedSource, source_page_path, DocContent, DocsFetchError, ExampleFiles, OutputFormat, Implementors, InherentImpls, ItemSource, PageSections, ParsedDoc, SourceFile, Submodules, TlsVersion, TraitMethod, TypeAlias};
+use crate::example_syntax::{self, SyntaxError};
+use crate::features::FeatureGraph;
+use crate::request_log::RequestLog;
+use crate::search_index::{CrateIndex, CrateItems, ItemKind, MAX_SEARCH_HITS, ResolvedPath, SearchIndex, SearchResults};
+use crate::webhook::FetchWebhook;
+
+/// Path prefix marking cache entries that hold derived tool results rather
+/// than rendered documentation pages. Real rustdoc paths never start with it.
+const DERIVED_PATH_PREFIX: &str = "@";
+
+/// Builds the cache key under which the path a redirect stub at `params`,
+/// or a merged page's canonical link, points to is stored.
+fn redirect_cache_key(params: &DocsRsParams) -> CacheKey {
+    derived_cache_key(&params.crate_name, &params.version, &format!("redirect:{}", params.path))
+}
+
+/// Builds the cache key of the content of the page `params` in `format`.
+fn document_cache_key(params: &DocsRsParams, format: OutputFormat) -> CacheKey {
+    match format.key_name() {
+        Some(name) => CacheKey::from(params).with_format(name),
+        None => CacheKey::from(params),
+    }
+}
+
+/// Orders crate versions from the newest: `latest` first, then versions by
+/// semver precedence, then versions that are not semver, by name.
+fn newest_version_first(a: &str, b: &str) -> std::cmp::Ordering {
+    let rank = |version: &str| (!is_latest(version), semver::Version::parse(version).ok().map(std::cmp::Reverse));
+    match (rank(a), rank(b)) {
+        ((a_latest, Some(a_semver)), (b_latest, Some(b_semver))) => a_latest.cmp(&b_latest).then(a_semver.cmp(&b_semver)),
+        ((a_latest, a_semver), (b_latest, b_semver)) => a_latest
+            .cmp(&b_latest)
+            .then(b_semver.is_some().cmp(&a_semver.is_some()))
+            .then(b.cmp(a)),
+    }
+}
+
+/// Builds the cache key under which a derived tool result is stored.
+fn derived_cache_key(crate_name: &str, version: &str, kind: &str) -> CacheKey {
+    CacheKey::new(DocsRsParams {
+        crate_name: crate_name.to_string(),
+        version: version.to_string(),
+        path: format!("{}{}", DERIVED_PATH_PREFIX, kind),
+    })
+}
+
+/// Default time crate metadata, such as crates.io tags, is served from the
+/// cache before being refetched.
+pub const DEFAULT_METADATA_TTL: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+/// Default time the list of published versions of a crate is served from
+/// the cache before being refetched; shorter than other metadata, since new
+/// versions are published more often.
+pub const DEFAULT_VERSIONS_TTL: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+
+/// Maximum number of item pages fetched at once by `list_constants`.
+const CONSTANT_PAGE_CONCURRENCY: usize = 8;
+
+/// Default maximum number of item pages fetched at once by
+/// `fetch_crate_bundle`, kept low to go easy on the documentation server.
+pub const DEFAULT_BUNDLE_CONCURRENCY: usize = 4;
+
+/// Number of bundled pages between progress messages of `fetch_crate_bundle`.
+const BUNDLE_PROGRESS_INTERVAL: usize = 25;
+
+/// Number of items bundled by `fetch_crate_bundle` when no cap is given.
+const DEFAULT_BUNDLE_ITEMS: usize = 50;
+
+/// Upper bound for the `max_items` cap of `fetch_crate_bundle`.
+const MAX_BUNDLE_ITEMS: usize = 500;
+
+/// Bundle size above which `fetch_crate_bundle` adds a size warning.
+const BUNDLE_WARN_BYTES: usize = 1024 * 1024;
+
+/// Number of linked items summarized by `fetch_with_context` when no cap
+/// is given.
+const DEFAULT_CONTEXT_LINKS: usize = 10;
+
+/// Upper bound for the `max_links` cap of `fetch_with_context`.
+const MAX_CONTEXT_LINKS: usize = 50;
+
+/// Default time a page that was not found is remembered as missing.
+pub const DEFAULT_NOT_FOUND_TTL: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// Settings of the configuration file applied by `reload_config`.
+const RELOADABLE_SETTINGS: &[&str] = &["default_paths", "boilerplate", "denied_crates"];
+
+/// Settings that are only read on start; `reload_config` reports them as
+/// requiring a restart.
+const RESTART_ONLY_SETTINGS: &[&str] = &[
+    "server_type", "address", "cache_layout", "watch_cache", "preload_from_log", "admin_token",
+    "cache_ttl", "not_found_ttl", "metadata_ttl", "versions_ttl", "max_concurrent_fetches",
+    "request_timeout",
+];
+
+/// Crates documented on docs.rs that are not published on crates.io.
+const SYSROOT_CRATES: &[&str] = &["std", "core", "alloc", "proc_macro", "test"];
+
+/// Normalizes a crate name as typed by a user: surrounding whitespace is
+/// removed and the name is lowercased, since crate names are case-insensitive.
+fn normalize_crate_name(crate_name: &str) -> String {
+    crate_name.trim().to_lowercase()
+}
+
+/// Returns the key identifying a crate however its name is spelled: the
+/// normalized name with `_` replaced by `-`, since crates.io treats both
+/// separators as equivalent.
+fn crate_name_key(crate_name: &str) -> String {
+    normalize_crate_name(crate_name).replace('_', "-")
+}
+
+/// Maximum number of confirmed crate names a [`DocFetcher`] remembers.
+const MAX_REMEMBERED_CRATE_NAMES: usize = 10_000;
+
+/// Time a confirmed crate name is remembered before it is confirmed again.
+const CRATE_NAME_TTL: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+/// How long a failure to resolve `latest` for a crate is remembered, so that
+/// requests meanwhile fetch the `latest` page without asking again.
+const LATEST_RESOLUTION_RETRY: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Absolute upper bound for the size of a single `fetch_document` response,
+/// applied to per-call `max_bytes` overrides.
+pub const MAX_CONTENT_BYTES_CEILING: usize = 1024 * 1024;
+
+/// Shortens `content` to at most `max_bytes` bytes, marker included.
+///
+/// The cut is made at the last line break (or, failing that, whitespace)
+/// before the limit so words and lines are not split, and never inside a
+/// UTF-8 character. A marker stating how many bytes were omitted is
+/// appended, unless the limit is too small to hold it, in which case the
+/// content is only cut. Content within the limit is returned unchanged.
+fn truncate_content(content: &str, max_bytes: usize) -> String {
+    if content.len() <= max_bytes {
+        return content.to_string();
+    }
+    let char_boundary = |mut cut: usize| {
+        while !content.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        cut
+    };
+    let marker = |omitted: usize| format!("\n\n[... truncated {} bytes ...]", omitted);
+    // Reserve room for the marker, sized for the worst case of omitting everything
+    let Some(budget) = max_bytes.checked_sub(marker(content.len()).len()) else {
+        return content[..char_boundary(max_bytes)].to_string();
+    };
+
+    let cut = char_boundary(budget);
+    let head = &content[..cut];
+    let boundary = head
+        .rfind('\n')
+        .or_else(|| head.rfind(char::is_whitespace))
+        .filter(|&i| i > 0)
+        .unwrap_or(cut);
+
+    let kept = content[..boundary].trim_end();
+    format!("{}{}", kept, marker(content.len() - kept.len()))
+}
+
+/// Shortens a [`ParsedDoc`] serialized as JSON to at most `max_bytes`
+/// bytes, keeping it valid JSON: examples and then methods are dropped from
+/// the end, and the description is truncated last, with
+/// [`truncate_content`]. Content that is not such a document is truncated
+/// as text.
+fn truncate_structured(content: &str, max_bytes: usize) -> String {
+    if content.len() <= max_bytes {
+        return content.to_string();
+    }
+    let Ok(mut doc) = serde_json::from_str::<ParsedDoc>(content) else {
+        return truncate_content(content, max_bytes);
+    };
+    let size = |doc: &ParsedDoc| serde_json::to_string(doc).map_or(0, |json| json.len());
+    while size(&doc) > max_bytes && doc.examples.pop().is_some() {}
+    while size(&doc) > max_bytes && doc.methods.pop().is_some() {}
+    // Escaping makes the serialized description longer than its text
+    while let Some(description) = &doc.description {
+        let excess = size(&doc).saturating_sub(max_bytes);
+        if excess == 0 {
+            break;
+        }
+        let truncated = truncate_content(description, description.len().saturating_sub(excess));
+        doc.description = (truncated.len() < description.len()).then_some(truncated);
+    }
+    serde_json::to_string(&doc).unwrap_or_default()
+}
+
+/// URI of JSON documents whose source URL is unknown, such as documents
+/// cached before source URLs were recorded.
+const STRUCTURED_DOCUMENT_URI: &str = "rustdoc://document.json";
+
+/// Average number of characters per token assumed by [`estimate_tokens`].
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Estimates how many tokens `content` takes up in a model's context.
+///
+/// This is a cheap heuristic of one token per four characters, rounded up;
+/// actual counts depend on the model's tokenizer.
+fn estimate_tokens(content: &str) -> usize {
+    content.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Serializes a structured tool result as a pretty-printed JSON text content.
+fn json_contents<T: Serialize>(value: &T) -> Vec<Content> {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => vec![Content::text(json)],
+        Err(e) => vec![Content::text(format!("Failed to serialize result: {}", e))],
+    }
+}
 
 /// Implements conversion from DocContent to MCP Contents.
 impl IntoContents for DocContent {
     fn into_contents(self) -> Vec<Content> {
-        vec![Content::text(self.content)]
+        let mut contents = vec![Content::text(self.content)];
+        if let Some(source_url) = self.source_url {
+            contents.push(Content::text(format!("Source: {}", source_url)));
+        }
+        contents
+    }
+}
+
+/// Response of [`DocFetcher::fetch_document`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DocumentResponse {
+    /// The documentation content, truncated to the size limit; empty when
+    /// `not_modified` is set
+    pub content: String,
+    /// Estimated token count of `content`, if requested
+    pub estimated_tokens: Option<usize>,
+    /// Hex-encoded SHA-256 hash of the content, if requested
+    pub content_hash: Option<String>,
+    /// Whether the content matches the hash the client already has, in
+    /// which case it is left out
+    pub not_modified: bool,
+    /// Path of the page the content is from, if the requested path is a
+    /// redirect stub pointing there
+    pub canonical_path: Option<String>,
+    /// Signature, description, methods and examples of the page, if requested
+    pub structure: Option<ParsedDoc>,
+    /// URL the content was fetched from, if known
+    pub source_url: Option<String>,
+    /// Format of `content`
+    pub format: OutputFormat,
+}
+
+/// Metadata returned alongside the content of a [`DocumentResponse`].
+#[derive(Serialize)]
+struct DocumentMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    estimated_tokens: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_hash: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    not_modified: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    canonical_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    structure: Option<ParsedDoc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_url: Option<String>,
+}
+
+/// Implements conversion from DocumentResponse to MCP Contents.
+///
+/// The content comes first; requested metadata, the page structure and the
+/// canonical path of a followed redirect follow as a separate JSON text
+/// content. A not modified
+/// response only carries a short notice instead of the content.
+impl IntoContents for DocumentResponse {
+    fn into_contents(self) -> Vec<Content> {
+        let mut contents = vec![match (self.not_modified, self.format) {
+            (true, _) => Content::text("Not modified: the content matches the given hash."),
+            (false, OutputFormat::Json) => Content::resource(ResourceContents::TextResourceContents {
+                uri: self.source_url.clone().unwrap_or_else(|| STRUCTURED_DOCUMENT_URI.to_string()),
+                mime_type: Some("application/json".to_string()),
+                text: self.content,
+            }),
+            (false, _) => Content::text(self.content),
+        }];
+        if self.estimated_tokens.is_some()
+            || self.content_hash.is_some()
+            || self.canonical_path.is_some()
+            || self.structure.is_some()
+            || self.source_url.is_some()
+        {
+            contents.extend(json_contents(&DocumentMetadata {
+                estimated_tokens: self.estimated_tokens,
+                content_hash: self.content_hash,
+                not_modified: self.not_modified,
+                canonical_path: self.canonical_path,
+                structure: self.structure,
+                source_url: self.source_url,
+            }));
+        }
+        contents
     }
 }
 
+/// Returns a line-based unified diff from `left` to `right`, with `context`
+/// unchanged lines around each change. The labels name the two sides in the
+/// diff header.
+fn unified_diff(left_label: &str, left: &str, right_label: &str, right: &str, context: usize) -> String {
+    similar::TextDiff::from_lines(left, right)
+        .unified_diff()
+        .context_radius(context)
+        .header(left_label, right_label)
+        .to_string()
+}
+
+/// Returns the hex-encoded SHA-256 hash of `content`, used by clients to
+/// revalidate documents they cached.
+fn content_hash(content: &str) -> String {
+    use sha2::{Digest, Sha256};
+
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Returns whether `given` is the administrative token `expected`. The
+/// SHA-256 digests of both are compared, so that the time taken does not
+/// reveal how much of the token was guessed right.
+fn admin_token_matches(expected: &str, given: &str) -> bool {
+    use sha2::{Digest, Sha256};
+
+    Sha256::digest(expected.as_bytes()) == Sha256::digest(given.as_bytes())
+}
+
 /// Implements conversion from DocsFetchError to MCP Contents.
 impl IntoContents for DocsFetchError {
     fn into_contents(self) -> Vec<Content> {
@@ -46,199 +379,5754 @@ impl IntoContents for DocsFetchError {
     }
 }
 
-/// Main struct responsible for fetching and caching Rust documentation.
-/// 
-/// `DocFetcher` provides functionality to fetch documentation from docs.rs
-/// and caches the results in memory for faster subsequent access.
-#[derive(Clone)]
-pub struct DocFetcher {
-    /// In-memory cache for storing fetched documentation
-    cache: Arc<InMemoryCache>,
+/// Implements conversion from CrateIndex to MCP Contents.
+impl IntoContents for CrateIndex {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
 }
 
-#[tool(tool_box)]
-impl DocFetcher {
-    /// Creates a new `DocFetcher` instance with the provided cache.
-    ///
-    /// # Arguments
-    /// * `cache` - Arc-wrapped InMemoryCache instance for storing documentation
-    pub fn new(cache: Arc<InMemoryCache>) -> Self {
-        Self { cache }
+/// Implements conversion from CrateItems to MCP Contents.
+impl IntoContents for CrateItems {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
     }
+}
 
-    /// Checks if a document with the given parameters exists in the cache.
-    ///
-    /// # Arguments
-    /// * `params` - Parameters specifying the document to check for
-    ///
-    /// # Returns
-    /// `true` if the document is cached, `false` otherwise
-    #[allow(dead_code)]
-    pub async fn is_cached(&self, params: &DocsRsParams) -> bool {
-        self.cache.contains_key(params).await
+/// Implements conversion from SearchResults to MCP Contents.
+impl IntoContents for SearchResults {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
     }
+}
 
-    /// Clears all cached documentation.
-    #[allow(dead_code)]
-    pub async fn clear_cache(&self) {
-        self.cache.clear().await;
-        tracing::info!("Document cache cleared.");
+/// Implements conversion from ResolvedPath to MCP Contents.
+impl IntoContents for ResolvedPath {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
     }
+}
 
-    /// Fetches documentation for a Rust crate from docs.rs.
-    ///
-    /// This function will first check the cache for the requested documentation.
-    /// If not found, it will fetch from docs.rs and cache the result.
-    ///
-    /// # Arguments
-    /// * `crate_name` - Name of the crate to fetch documentation for
-    /// * `version` - Version of the crate (e.g., "1.0.0")
-    /// * `path` - Path to the specific documentation page
-    ///
-    /// # Returns
-    /// * `Ok(DocContent)` - The fetched documentation content
-    /// * `Err(DocsFetchError)` - If fetching fails
-    #[tool(description = "Fetch Rust documentation of a specific crate and version. When you encounter an api issue, you can use this tool to fetch the documentation for reference.")]
-    async fn fetch_document(
-        &self,
-        #[tool(param)]
-        #[schemars(description = "Name of the crate to fetch documentation for")]
-        crate_name: String,
+/// Implements conversion from TypeAlias to MCP Contents.
+impl IntoContents for TypeAlias {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
 
-        #[tool(param)]
-        #[schemars(description = "Version of crate, e.g. 1.0.0. If not specified, the latest version will be used.")]
-        version: String,
+/// Outcome of fetching one page in [`DocFetcher::fetch_crate_paths`].
+#[derive(Debug, Clone, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PathResult {
+    /// The page's documentation content
+    Content(String),
+    /// Why the page could not be fetched
+    Error(String),
+}
 
-        #[tool(param)]
-        #[schemars(description = "Path to the specific documentation page (e.g., 'std/vec/struct.Vec.html'). If not specified, the document of the crate will be returned")]
-        path: String,
-    ) -> Result<DocContent, DocsFetchError> {
-        let params = DocsRsParams {
-            crate_name,
-            version,
-            path,
-        };
+/// Pages of one crate fetched together by [`DocFetcher::fetch_crate_paths`].
+#[derive(Debug, Clone, Serialize, Eq, PartialEq)]
+pub struct CratePaths {
+    /// Canonical name of the crate
+    pub crate_name: String,
+    /// Version the pages were fetched for, with `latest` resolved when possible
+    pub version: String,
+    /// Result of every requested path that fit into `max_total_bytes`
+    pub documents: BTreeMap<String, PathResult>,
+    /// Whether paths were left out to stay within `max_total_bytes`
+    pub truncated: bool,
+    /// Paths left out, in request order
+    pub omitted: Vec<String>,
+}
 
-        // Check cache first
-        if let Some(cached_content) = self.cache.get(&params).await {
-            tracing::info!("Cache hit for {:?}", params);
-            return Ok(cached_content);
-        }
-        
-        tracing::info!("Cache miss for {:?}. Fetching...", params);
-        let client = DocsRsClient::new();
-        match client.fetch_docs(params.clone()).await {
-            Ok(doc_content) => {
-                // Store in cache
-                self.cache.insert(params, doc_content.clone()).await;
-                Ok(doc_content)
-            },
-            Err(err) => Err(err),
-        }
+/// Implements conversion from CratePaths to MCP Contents.
+impl IntoContents for CratePaths {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
     }
 }
 
-#[tool(tool_box)]
-impl ServerHandler for DocFetcher {
-    fn get_info(&self) -> ServerInfo {
-        ServerInfo {
-            protocol_version: ProtocolVersion::default(),
-            capabilities: ServerCapabilities::builder()
-                .enable_tools()  // We only need tools capability
-                .build(),
-            server_info: Implementation::from_build_env(),
-            instructions: Some(
-                "This server provides access to Rust documentation from docs.rs. \
-                Use the 'fetch_document' tool to retrieve documentation for any crate. \
-                Specify the crate name, version, and path to the documentation page you want to fetch. \
-                Results are cached for better performance.".to_string()
-            ),
+/// Client-supplied cap on the combined size of the results of a batch.
+///
+/// Results are admitted in order until the first one that would exceed the
+/// cap; it and every later result are rejected.
+#[derive(Debug, Default)]
+struct ByteBudget {
+    /// Cap in bytes, `None` for no cap
+    limit: Option<usize>,
+    /// Bytes admitted so far
+    used: usize,
+    /// Whether a result has been rejected
+    exhausted: bool,
+}
+
+impl ByteBudget {
+    fn new(limit: Option<usize>) -> Self {
+        Self { limit, ..Self::default() }
+    }
+
+    /// Admits a result of `bytes` bytes if it still fits.
+    fn admit(&mut self, bytes: usize) -> bool {
+        if self.exhausted || self.limit.is_some_and(|limit| self.used + bytes > limit) {
+            self.exhausted = true;
+            return false;
         }
+        self.used += bytes;
+        true
     }
+}
 
-    async fn list_prompts(
-        &self,
-        _request: PaginatedRequestParam,
-        _: RequestContext<RoleServer>,
-    ) -> Result<ListPromptsResult, McpError> {
-        // We don't use prompts in this implementation
-        Ok(ListPromptsResult {
-            next_cursor: None,
-            prompts: vec![],
-        })
+/// A documentation example checked by [`DocFetcher::validate_example`].
+#[derive(Debug, Clone, Serialize, Eq, PartialEq)]
+pub struct ExampleValidation {
+    /// Documentation path of the page the example is from
+    pub path: String,
+    /// Position of the example among the examples of the page
+    pub index: usize,
+    /// Number of Rust examples on the page
+    pub total_examples: usize,
+    /// Visible code of the example
+    pub code: String,
+    /// Whether the example is syntactically valid Rust
+    pub valid: bool,
+    /// Why the example does not parse, if it is invalid
+    pub error: Option<SyntaxError>,
+}
+
+/// Implements conversion from ExampleValidation to MCP Contents.
+impl IntoContents for ExampleValidation {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use rmcp::model::{ClientCapabilities, ClientInfo, Implementation};
-    use rmcp::{ServiceExt, model::CallToolRequestParam, transport::SseTransport};
-    use rmcp::transport::sse_server::SseServer;
-    use tempfile::tempdir;
-    use std::time::Instant;
-    use std::fs;
+/// Published versions of a crate satisfying a requirement, see
+/// [`DocFetcher::matching_versions`].
+#[derive(Debug, Clone, Serialize, Eq, PartialEq)]
+pub struct MatchingVersions {
+    /// Canonical name of the crate
+    pub crate_name: String,
+    /// The requirement, as parsed
+    pub requirement: String,
+    /// Matching versions, newest first; yanked versions are left out
+    pub versions: Vec<String>,
+}
 
-    fn setup_test_fetcher() -> (DocFetcher, Arc<InMemoryCache>) {
-        let temp_dir = tempdir().unwrap();
-        let cache_dir = temp_dir.path().to_path_buf();
-        fs::create_dir_all(&cache_dir).unwrap(); 
+/// Implements conversion from MatchingVersions to MCP Contents.
+impl IntoContents for MatchingVersions {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
 
-        let cache = Arc::new(InMemoryCache::new(cache_dir));
-        let fetcher = DocFetcher::new(cache.clone());
-        (fetcher, cache)
+/// A published version of a crate, as listed by [`DocFetcher::list_versions`].
+#[derive(Debug, Clone, Serialize, Eq, PartialEq)]
+pub struct CrateVersion {
+    /// Version number, e.g. `1.2.3`
+    pub version: String,
+    /// Whether the version was yanked
+    pub yanked: bool,
+    /// Whether this is the newest version that is neither yanked nor a
+    /// prerelease
+    pub latest_stable: bool,
+}
+
+/// All published versions of a crate, see [`DocFetcher::list_versions`].
+#[derive(Debug, Clone, Serialize, Eq, PartialEq)]
+pub struct CrateVersions {
+    /// Canonical name of the crate
+    pub crate_name: String,
+    /// Newest version that is neither yanked nor a prerelease, if any
+    pub latest_stable: Option<String>,
+    /// Versions, newest first
+    pub versions: Vec<CrateVersion>,
+}
+
+/// Implements conversion from CrateVersions to MCP Contents.
+impl IntoContents for CrateVersions {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
     }
+}
 
-    #[tokio::test]
-    async fn test_fetch_document() {
-        let (doc_fetcher, _cache) = setup_test_fetcher();
-        let result = doc_fetcher.fetch_document(
-            "rand".to_string(),
-            "0.9.0".to_string(),
-            "rand/trait.Rng.html".to_string(),
-        ).await.unwrap();
+/// Differences between the documentation of two items, computed by
+/// [`DocFetcher::cross_crate_diff`].
+#[derive(Debug, Clone, Serialize, Eq, PartialEq)]
+pub struct ItemDiff {
+    /// The first item, with its crate name canonicalized
+    pub left: DocsRsParams,
+    /// The second item, with its crate name canonicalized
+    pub right: DocsRsParams,
+    /// Whether the first item has no documentation page
+    pub left_missing: bool,
+    /// Whether the second item has no documentation page
+    pub right_missing: bool,
+    /// Whether both items have the same documentation text
+    pub identical: bool,
+    /// Unified diff from the first item's text to the second's; a missing
+    /// item counts as empty
+    pub diff: String,
+}
 
-        assert!(!result.content.is_empty());
-        assert!(result.content.contains("User-level interface for RNGs"));
+/// Implements conversion from ItemDiff to MCP Contents.
+impl IntoContents for ItemDiff {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
     }
-    
-    #[tokio::test]
-    async fn test_sse_server() {
-        let addr = "127.0.0.1:8081"; // Let OS choose port
-        let temp_cache_dir = tempdir().unwrap().path().to_path_buf();
-        let server_cache = Arc::new(InMemoryCache::new(temp_cache_dir));
+}
 
-        let service_cache_clone = server_cache.clone();
-        let server = SseServer::serve(addr.parse().unwrap()).await.unwrap();
-        let port = server.config.bind.port();
-        let ct = server.with_service(move || DocFetcher::new(service_cache_clone.clone()));
-        
-        let addr = format!("http://127.0.0.1:{}/sse", port);
-        let transport = SseTransport::start(&addr).await.unwrap();
+/// Default number of unchanged lines shown around each change of a diff.
+const DEFAULT_DIFF_CONTEXT: usize = 3;
 
-        let client_info = ClientInfo {
-            protocol_version: Default::default(),
-            capabilities: ClientCapabilities::default(),
-            client_info: Implementation {
-                name: "test sse client".to_string(),
-                version: "0.0.1".to_string(),
-            },
-        };
-        let client = client_info.serve(transport).await.unwrap();
+/// Items of a crate concatenated by [`DocFetcher::fetch_crate_bundle`].
+#[derive(Debug, Clone, Serialize, serde::Deserialize, Eq, PartialEq)]
+pub struct CrateBundle {
+    /// Documentation of every bundled item, each preceded by a separator
+    /// line naming it
+    pub content: String,
+    /// What the bundle covers
+    pub info: BundleInfo,
+}
 
-        let result = client.call_tool(CallToolRequestParam {
-            name: "fetch_document".into(),
-            arguments: serde_json::json!({
-                "crate_name": "rand",
-                "version": "0.9.0",
-                "path": "rand/trait.Rng.html",
-            }).as_object().cloned(),
-        }).await.unwrap();
+/// Description of a [`CrateBundle`].
+#[derive(Debug, Clone, Serialize, serde::Deserialize, Eq, PartialEq)]
+pub struct BundleInfo {
+    /// Canonical name of the crate
+    pub crate_name: String,
+    /// Version the pages were fetched for, with `latest` resolved when possible
+    pub version: String,
+    /// Position of the first bundled item among all items of the crate
+    pub start: usize,
+    /// Number of bundled items
+    pub items: usize,
+    /// Number of items of the crate, including the crate root
+    pub total_items: usize,
+    /// Value of `start` continuing after this bundle, `None` at the end
+    pub next_start: Option<usize>,
+    /// Size of `content` in bytes
+    pub size_bytes: usize,
+    /// Warning about the size of the bundle, if it is large
+    pub warning: Option<String>,
+    /// Whether items were left out to stay within `max_total_bytes`
+    #[serde(default)]
+    pub truncated: bool,
+    /// Paths of the items left out, in bundle order; `next_start` continues
+    /// with the first of them
+    #[serde(default)]
+    pub omitted: Vec<String>,
+}
 
-        ct.cancel();
+/// Implements conversion from CrateBundle to MCP Contents.
+///
+/// The content comes first; the description follows as a separate JSON
+/// text content.
+impl IntoContents for CrateBundle {
+    fn into_contents(self) -> Vec<Content> {
+        let mut contents = vec![Content::text(self.content)];
+        contents.extend(json_contents(&self.info));
+        contents
+    }
+}
 
-        assert!(!result.content.is_empty());
-        assert!(result.content.iter().any(|c| c.as_text().unwrap().text.contains("User-level interface for RNGs")));
+/// Page fetched by [`DocFetcher::fetch_with_context`] together with the
+/// items it links to.
+#[derive(Debug, Clone, Serialize, serde::Deserialize, Eq, PartialEq)]
+pub struct ContextualDocument {
+    /// Documentation of the page, truncated to the size limit
+    pub content: String,
+    /// Summaries of the items the page links to
+    pub context: DocContext,
+}
+
+/// Items referenced by a [`ContextualDocument`].
+#[derive(Debug, Clone, Serialize, serde::Deserialize, Eq, PartialEq)]
+pub struct DocContext {
+    /// Linked items of the same crate, in the order the page links to them
+    pub glossary: Vec<GlossaryEntry>,
+    /// Number of further linked items left out because of the cap
+    pub omitted_links: usize,
+}
+
+/// An item linked from a page with its one-line summary.
+#[derive(Debug, Clone, Serialize, serde::Deserialize, Eq, PartialEq)]
+pub struct GlossaryEntry {
+    /// Text of the link, e.g. `MutexGuard`
+    pub name: String,
+    /// Documentation path of the item
+    pub path: String,
+    /// First paragraph of the item's documentation, `None` if it has none
+    /// or its page could not be fetched
+    pub summary: Option<String>,
+}
+
+/// Implements conversion from ContextualDocument to MCP Contents.
+///
+/// The content comes first; the glossary follows as a separate JSON
+/// content.
+impl IntoContents for ContextualDocument {
+    fn into_contents(self) -> Vec<Content> {
+        let mut contents = vec![Content::text(self.content)];
+        contents.extend(json_contents(&self.context));
+        contents
+    }
+}
+
+/// Implements conversion from CrateTags to MCP Contents.
+impl IntoContents for CrateTags {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+/// Implements conversion from CrateFullMetadata to MCP Contents.
+impl IntoContents for CrateFullMetadata {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+impl IntoContents for CrateVersionMetadata {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+/// A derived cache entry that expires, stored as JSON with its fetch time.
+#[derive(Serialize, serde::Deserialize)]
+struct Timestamped<T> {
+    /// Seconds since the Unix epoch at which the value was fetched
+    fetched_at: u64,
+    value: T,
+}
+
+/// Implements conversion from BlanketImpls to MCP Contents.
+impl IntoContents for BlanketImpls {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+/// Implements conversion from InherentImpls to MCP Contents.
+impl IntoContents for InherentImpls {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+/// Implements conversion from ConstructionHints to MCP Contents.
+impl IntoContents for ConstructionHints {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+/// Implements conversion from BuildStatus to MCP Contents.
+impl IntoContents for BuildStatus {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+/// Implements conversion from AutoTraits to MCP Contents.
+impl IntoContents for AutoTraits {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+/// Implements conversion from PageSections to MCP Contents.
+impl IntoContents for PageSections {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+/// Implements conversion from ExampleFiles to MCP Contents.
+impl IntoContents for ExampleFiles {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+/// Implements conversion from SourceFile to MCP Contents.
+impl IntoContents for SourceFile {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+/// Implements conversion from AssociatedTypes to MCP Contents.
+impl IntoContents for AssociatedTypes {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+/// Implements conversion from TraitMethod to MCP Contents.
+impl IntoContents for TraitMethod {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+/// Implements conversion from CrateDocs to MCP Contents.
+impl IntoContents for CrateDocs {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+/// Implements conversion from Submodules to MCP Contents.
+impl IntoContents for Submodules {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+/// Implements conversion from Constants to MCP Contents.
+impl IntoContents for Constants {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+/// Implements conversion from ConfigReload to MCP Contents.
+impl IntoContents for ConfigReload {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+/// Implements conversion from ItemSource to MCP Contents.
+impl IntoContents for ItemSource {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+/// Implements conversion from RenderedSource to MCP Contents.
+impl IntoContents for RenderedSource {
+    fn into_contents(self) -> Vec<Content> {
+        vec![Content::text(self.code)]
+    }
+}
+
+/// Implements conversion from Breadcrumb to MCP Contents.
+impl IntoContents for Breadcrumb {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+/// Implements conversion from FeatureGraph to MCP Contents.
+impl IntoContents for FeatureGraph {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+/// An upstream fetch that concurrent requests for the same page wait on.
+type InFlightFetch = Shared<BoxFuture<'static, Result<DocContent, DocsFetchError>>>;
+
+/// Upstream fetches in progress, keyed by the cache entry they fill.
+type InFlightMap = Arc<std::sync::Mutex<HashMap<CacheKey, InFlightFetch>>>;
+
+/// Unregisters an in-flight fetch when dropped by the task running it, so
+/// the entry goes away however the fetch ends, panics included.
+struct InFlightGuard {
+    in_flight: InFlightMap,
+    key: CacheKey,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        in_flight.remove(&self.key);
+    }
+}
+
+/// Runtime options shared by all [`DocFetcher`] instances of a server.
+#[derive(Debug, Clone)]
+pub struct FetcherConfig {
+    /// Base URL of the docs.rs-compatible documentation server
+    pub docs_base_url: String,
+    /// Base URL of the crates.io registry API, used to confirm crate names
+    pub crates_io_base_url: String,
+    /// Log recording every requested document, used for cache warm-up
+    pub access_log: Option<Arc<AccessLog>>,
+    /// Log recording every `fetch_document` call, replayable for debugging
+    pub request_log: Option<Arc<RequestLog>>,
+    /// Default maximum size of `fetch_document` responses, `None` for no limit
+    pub max_content_bytes: Option<usize>,
+    /// Page fetched when a request does not specify a path, keyed by
+    /// normalized crate name
+    pub default_paths: HashMap<String, String>,
+    /// Health tracking of the documentation server
+    pub circuit_breaker: Arc<CircuitBreaker>,
+    /// Permits bounding the pages fetched from the documentation server at
+    /// once, shared by all tools; `None` for no bound
+    pub fetch_limiter: Option<Arc<Semaphore>>,
+    /// Maximum number of item pages `fetch_crate_bundle` fetches at once,
+    /// within the bound of [`Self::fetch_limiter`]
+    pub bundle_concurrency: usize,
+    /// Let concurrent requests for the same page and format wait on one
+    /// fetch from the documentation server instead of each fetching it
+    pub coalesce_fetches: bool,
+    /// Serve another cached version of a page while the circuit breaker is open
+    pub serve_stale_on_outage: bool,
+    /// Minimum length in bytes of extracted content for a fetch to count as
+    /// successful; shorter content is neither returned nor cached
+    pub min_content_len: usize,
+    /// Time a page that was not found is answered as missing without asking
+    /// the documentation server again; zero disables remembering
+    pub not_found_ttl: std::time::Duration,
+    /// Instructions shown to clients instead of the built-in ones
+    pub instructions: Option<String>,
+    /// Page parts left out of extracted documentation text
+    pub boilerplate: Arc<Boilerplate>,
+    /// Normalized names of crates whose documentation is not served
+    pub denied_crates: HashSet<String>,
+    /// Configuration file re-read by `reload_config`
+    pub config_file: Option<PathBuf>,
+    /// Token required to call administrative tools; they are disabled
+    /// when not set
+    pub admin_token: Option<String>,
+    /// Collapse blank lines and strip trailing spaces in extracted
+    /// documentation text, outside code blocks
+    pub normalize_whitespace: bool,
+    /// Rewrite docs.rs links to the `latest` documentation of the fetched
+    /// crate to the concrete version fetched
+    pub pin_latest_links: bool,
+    /// Cache for crate metadata such as crates.io tags and features, kept
+    /// apart from rendered documentation; the documentation cache is used
+    /// when not set
+    pub metadata_cache: Option<Arc<InMemoryCache>>,
+    /// Time cached crate metadata is served before being refetched
+    pub metadata_ttl: std::time::Duration,
+    /// Crate names accepted in requests
+    pub crate_name_policy: CrateNamePolicy,
+    /// Time the cached list of published versions of a crate is served
+    /// before being refetched, and `latest` keeps resolving to the same
+    /// version
+    pub versions_ttl: std::time::Duration,
+    /// Overall time budget of a batch or bundle of fetches, shared by all
+    /// its pages; `None` for no limit
+    pub batch_timeout: Option<std::time::Duration>,
+    /// Append the documentation of the replacement named by a deprecated
+    /// item's notice to `fetch_document` responses
+    pub follow_deprecation: bool,
+    /// Store pages whose canonical link names another page of the crate,
+    /// such as re-exports, once under the canonical page, with the other
+    /// paths pointing to it
+    pub merge_canonical_pages: bool,
+    /// Webhook notified of every document fetched on a cache miss
+    pub fetch_webhook: Option<Arc<FetchWebhook>>,
+    /// Minimum TLS version of connections to the documentation server and
+    /// registry, `None` for the TLS backend's default
+    pub min_tls_version: Option<TlsVersion>,
+    /// Time a request to the documentation server or registry may take,
+    /// `None` to wait indefinitely
+    pub request_timeout: Option<std::time::Duration>,
+    /// Output directory of `cargo doc` documentation pages are read from
+    /// instead of the documentation server
+    pub doc_root: Option<PathBuf>,
+    /// Failure injection for chaos testing
+    #[cfg(feature = "chaos")]
+    pub chaos: Option<Arc<crate::chaos::Chaos>>,
+}
+
+impl Default for FetcherConfig {
+    fn default() -> Self {
+        Self {
+            docs_base_url: "https://docs.rs".to_string(),
+            crates_io_base_url: CRATES_IO_URL.to_string(),
+            access_log: None,
+            request_log: None,
+            max_content_bytes: None,
+            default_paths: HashMap::new(),
+            circuit_breaker: Arc::new(CircuitBreaker::default()),
+            fetch_limiter: None,
+            bundle_concurrency: DEFAULT_BUNDLE_CONCURRENCY,
+            coalesce_fetches: true,
+            serve_stale_on_outage: false,
+            min_content_len: 0,
+            not_found_ttl: DEFAULT_NOT_FOUND_TTL,
+            instructions: None,
+            boilerplate: Arc::new(Boilerplate::default()),
+            denied_crates: HashSet::new(),
+            config_file: None,
+            admin_token: None,
+            normalize_whitespace: true,
+            pin_latest_links: true,
+            metadata_cache: None,
+            metadata_ttl: DEFAULT_METADATA_TTL,
+            crate_name_policy: CrateNamePolicy::default(),
+            versions_ttl: DEFAULT_VERSIONS_TTL,
+            batch_timeout: None,
+            follow_deprecation: false,
+            merge_canonical_pages: false,
+            fetch_webhook: None,
+            min_tls_version: None,
+            request_timeout: Some(DEFAULT_REQUEST_TIMEOUT),
+            doc_root: None,
+            #[cfg(feature = "chaos")]
+            chaos: None,
+        }
+    }
+}
+
+/// Implements conversion from Implementors to MCP Contents.
+impl IntoContents for Implementors {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+impl FetcherConfig {
+    /// Applies the settings read from the configuration file, returning the
+    /// names of those that changed.
+    pub fn apply_file_config(&mut self, file_config: &FileConfig) -> Vec<String> {
+        let mut changed = Vec::new();
+        if self.default_paths != file_config.default_paths {
+            self.default_paths = file_config.default_paths.clone();
+            changed.push("default_paths".to_string());
+        }
+        if *self.boilerplate != file_config.boilerplate {
+            self.boilerplate = Arc::new(file_config.boilerplate.clone());
+            changed.push("boilerplate".to_string());
+        }
+        let denied_crates: HashSet<String> = file_config.denied_crates.iter().cloned().collect();
+        if self.denied_crates != denied_crates {
+            self.denied_crates = denied_crates;
+            changed.push("denied_crates".to_string());
+        }
+        changed
+    }
+}
+
+/// Creates a client for the documentation server of `config`.
+fn build_client(config: &FetcherConfig) -> DocsRsClient {
+    let client = DocsRsClient::new_with_base_url(&config.docs_base_url)
+        .with_crates_io_url(&config.crates_io_base_url)
+        .with_boilerplate(config.boilerplate.clone())
+        .with_normalize_whitespace(config.normalize_whitespace)
+        .with_merge_canonical(config.merge_canonical_pages)
+        .with_min_tls_version(config.min_tls_version)
+        .with_request_timeout(config.request_timeout);
+    #[cfg(feature = "chaos")]
+    let client = client.with_chaos(config.chaos.clone());
+    client
+}
+
+/// Usage of the caches, as reported by `cache_stats`.
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheReport {
+    /// The documentation cache
+    pub cache: CacheUsage,
+    /// The separate metadata cache, if one is configured
+    pub metadata_cache: Option<CacheUsage>,
+}
+
+/// Implements conversion from CacheReport to MCP Contents.
+impl IntoContents for CacheReport {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+/// Outcome of a `clear_cache` call.
+#[derive(Debug, Clone, Serialize, Eq, PartialEq)]
+pub struct CacheClear {
+    /// Crate whose entries were removed, `None` if the whole cache was cleared
+    pub crate_name: Option<String>,
+    /// Number of entries removed
+    pub cleared: usize,
+}
+
+/// Implements conversion from CacheClear to MCP Contents.
+impl IntoContents for CacheClear {
+    fn into_contents(self) -> Vec<Content> {
+        json_contents(&self)
+    }
+}
+
+/// Outcome of a `reload_config` call.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigReload {
+    /// Configuration file that was read
+    pub config_file: PathBuf,
+    /// Settings read from the file, whether they changed or not
+    pub reloaded: Vec<String>,
+    /// Settings whose new values are now in effect
+    pub changed: Vec<String>,
+    /// Settings that only take effect after a restart
+    pub requires_restart: Vec<String>,
+}
+
+/// Main struct responsible for fetching and caching Rust documentation.
+/// 
+/// `DocFetcher` provides functionality to fetch documentation from docs.rs
+/// and caches the results in memory for faster subsequent access.
+#[derive(Clone)]
+pub struct DocFetcher {
+    /// Cache for storing fetched documentation
+    cache: Arc<dyn Cache>,
+    /// Shared runtime options, replaced as a whole by `reload_config`
+    config: Arc<std::sync::RwLock<Arc<FetcherConfig>>>,
+    /// Client for the configured documentation server, shared by all
+    /// fetches so that connections are reused; rebuilt by `reload_config`
+    client: Arc<std::sync::RwLock<Arc<DocsRsClient>>>,
+    /// Canonical crate names confirmed via crates.io, with the time they
+    /// were confirmed, keyed by [`crate_name_key`]
+    crate_names: Arc<RwLock<HashMap<String, (String, std::time::Instant)>>>,
+    /// Upstream fetches in progress, shared by every tool going through [`Self::fetch`]
+    in_flight: InFlightMap,
+    /// Pages the documentation server reported missing, with the time it last did
+    not_found: Arc<std::sync::Mutex<HashMap<DocsRsParams, std::time::Instant>>>,
+    /// Concrete versions `latest` resolved to, with the time they were
+    /// resolved, keyed by crate name; `latest` itself if resolving failed
+    latest_versions: Arc<std::sync::Mutex<HashMap<String, (String, std::time::Instant)>>>,
+}
+
+#[tool(tool_box)]
+impl DocFetcher {
+    /// Creates a new `DocFetcher` instance with the provided cache.
+    ///
+    /// # Arguments
+    /// * `cache` - Arc-wrapped cache instance for storing documentation
+    #[allow(dead_code)]
+    pub fn new(cache: Arc<dyn Cache>) -> Self {
+        Self::with_config(cache, FetcherConfig::default())
+    }
+
+    /// Creates a new `DocFetcher` instance with the provided cache and options.
+    ///
+    /// # Arguments
+    /// * `cache` - Arc-wrapped cache instance for storing documentation
+    /// * `config` - Runtime options, see [`FetcherConfig`]
+    pub fn with_config(cache: Arc<dyn Cache>, config: FetcherConfig) -> Self {
+        Self {
+            cache,
+            client: Arc::new(std::sync::RwLock::new(Arc::new(build_client(&config)))),
+            config: Arc::new(std::sync::RwLock::new(Arc::new(config))),
+            crate_names: Arc::new(RwLock::new(HashMap::new())),
+            in_flight: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            not_found: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            latest_versions: Arc::new(std::sync::Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Returns the current runtime options.
+    fn config(&self) -> Arc<FetcherConfig> {
+        self.config.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    /// Returns the cache holding crate metadata.
+    fn metadata_cache(&self) -> Arc<dyn Cache> {
+        match self.config().metadata_cache.clone() {
+            Some(metadata_cache) => metadata_cache,
+            None => self.cache.clone(),
+        }
+    }
+
+    /// Returns a crate metadata entry from the metadata cache, unless it is
+    /// older than the configured metadata TTL.
+    async fn cached_metadata<T: serde::de::DeserializeOwned>(&self, key: &CacheKey) -> Option<T> {
+        self.cached_metadata_within(key, self.config().metadata_ttl).await
+    }
+
+    /// Returns a crate metadata entry from the metadata cache, unless it is
+    /// older than `ttl`.
+    async fn cached_metadata_within<T: serde::de::DeserializeOwned>(
+        &self,
+        key: &CacheKey,
+        ttl: std::time::Duration,
+    ) -> Option<T> {
+        let cached = self.metadata_cache().get(key).await?;
+        match serde_json::from_str::<Timestamped<T>>(&cached.content) {
+            Ok(entry) if unix_now().saturating_sub(entry.fetched_at) < ttl.as_secs() => {
+                tracing::info!("Cache hit for {:?}", key);
+                Some(entry.value)
+            }
+            Ok(_) => {
+                tracing::info!("Cached {:?} expired. Refetching...", key);
+                None
+            }
+            Err(e) => {
+                tracing::warn!("Discarding unreadable cached metadata {:?}: {}", key, e);
+                None
+            }
+        }
+    }
+
+    /// Returns a tool result stored as JSON under `key` by
+    /// [`Self::store_json`], discarding an unreadable entry.
+    ///
+    /// Tools store their results under a [`derived_cache_key`] for concrete
+    /// versions only, since the version `latest` refers to changes.
+    async fn cached_json<T: serde::de::DeserializeOwned>(&self, key: &CacheKey) -> Option<T> {
+        let cached = self.cache.get(key).await?;
+        match serde_json::from_str(&cached.content) {
+            Ok(value) => {
+                tracing::info!("Cache hit for {:?}", key);
+                Some(value)
+            }
+            Err(e) => {
+                tracing::warn!("Discarding unreadable cached {:?}: {}", key, e);
+                None
+            }
+        }
+    }
+
+    /// Stores a tool result as JSON under `key`, see [`Self::cached_json`].
+    async fn store_json<T: Serialize>(&self, key: CacheKey, value: &T) -> Result<(), DocsFetchError> {
+        let serialized = serde_json::to_string(value)
+            .map_err(|e| DocsFetchError::ParseError(e.to_string()))?;
+        self.cache.insert(key, DocContent { content: serialized, source_url: None }).await;
+        Ok(())
+    }
+
+    /// Stores a crate metadata entry in the metadata cache with the current time.
+    async fn store_metadata<T: Serialize>(&self, key: CacheKey, value: &T) -> Result<(), DocsFetchError> {
+        let entry = Timestamped { fetched_at: unix_now(), value };
+        let serialized = serde_json::to_string(&entry)
+            .map_err(|e| DocsFetchError::ParseError(e.to_string()))?;
+        self.metadata_cache().insert(key, DocContent { content: serialized, source_url: None }).await;
+        Ok(())
+    }
+
+    /// Returns the response size limit for a call, preferring the per-call
+    /// override over the configured default. Overrides are clamped to
+    /// [`MAX_CONTENT_BYTES_CEILING`].
+    fn content_limit(&self, max_bytes: Option<usize>) -> Option<usize> {
+        match max_bytes {
+            Some(requested) => Some(requested.min(MAX_CONTENT_BYTES_CEILING)),
+            None => self.config().max_content_bytes,
+        }
+    }
+
+    /// Returns the configured default page of a crate, looked up by the
+    /// crate name as given and by its known canonical form.
+    async fn default_path(&self, crate_name: &str) -> Option<String> {
+        let config = self.config();
+        let default_paths = &config.default_paths;
+        if default_paths.is_empty() {
+            return None;
+        }
+        let path = match default_paths.get(&normalize_crate_name(crate_name)) {
+            Some(path) => path,
+            None => default_paths.get(&self.known_crate_name(crate_name).await)?,
+        };
+        tracing::debug!("Using configured default path '{}' for crate '{}'", path, crate_name);
+        Some(path.clone())
+    }
+
+    /// Returns the client for the configured documentation server.
+    fn client(&self) -> Arc<DocsRsClient> {
+        self.client.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    /// Returns the source documentation pages missing from the cache are
+    /// fetched from: the configured `cargo doc` output, else the client.
+    fn source(&self) -> Arc<dyn DocSource> {
+        match &self.config().doc_root {
+            Some(root) => Arc::new(LocalDocSource::new(root).with_extractor(self.client())),
+            None => self.client(),
+        }
+    }
+
+    /// Returns whether pages are read from a local `cargo doc` output, so
+    /// that neither docs.rs nor crates.io is asked about crate names and
+    /// versions.
+    fn is_local(&self) -> bool {
+        self.config().doc_root.is_some()
+    }
+
+    /// Returns the client for a request only docs.rs can answer, such as
+    /// build logs, the source browser or search indexes, or
+    /// [`DocsFetchError::UnsupportedLocally`] naming `feature` if pages are
+    /// read from a local `cargo doc` output.
+    fn docs_rs_client(&self, feature: &str) -> Result<Arc<DocsRsClient>, DocsFetchError> {
+        match self.is_local() {
+            true => Err(DocsFetchError::UnsupportedLocally(feature.to_string())),
+            false => Ok(self.client()),
+        }
+    }
+
+    /// Returns the best known canonical form of a crate name without any
+    /// network access: the confirmed name if it was looked up before,
+    /// otherwise the normalized name.
+    async fn known_crate_name(&self, crate_name: &str) -> String {
+        let normalized = normalize_crate_name(crate_name);
+        self.remembered_crate_name(&normalized).await.unwrap_or(normalized)
+    }
+
+    /// Returns the canonical name confirmed for a normalized crate name, if
+    /// it was confirmed within [`CRATE_NAME_TTL`].
+    async fn remembered_crate_name(&self, normalized: &str) -> Option<String> {
+        self.crate_names
+            .read()
+            .await
+            .get(&crate_name_key(normalized))
+            .filter(|(_, confirmed_at)| confirmed_at.elapsed() < CRATE_NAME_TTL)
+            .map(|(canonical, _)| canonical.clone())
+    }
+
+    /// Remembers the canonical name confirmed for a normalized crate name.
+    ///
+    /// Names are client-supplied, so at most [`MAX_REMEMBERED_CRATE_NAMES`]
+    /// are kept: when full, expired names are dropped, and then the name
+    /// confirmed longest ago.
+    async fn remember_crate_name(&self, normalized: &str, canonical: String) {
+        let mut names = self.crate_names.write().await;
+        if names.len() >= MAX_REMEMBERED_CRATE_NAMES {
+            names.retain(|_, (_, confirmed_at)| confirmed_at.elapsed() < CRATE_NAME_TTL);
+        }
+        if names.len() >= MAX_REMEMBERED_CRATE_NAMES {
+            let oldest = names.iter().min_by_key(|(_, (_, confirmed_at))| *confirmed_at).map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                names.remove(&oldest);
+            }
+        }
+        names.insert(crate_name_key(normalized), (canonical, std::time::Instant::now()));
+    }
+
+    /// Returns the normalized form of a crate name, or
+    /// [`DocsFetchError::Denied`] if the crate is on the deny list in any
+    /// spelling.
+    fn allowed_crate_name(&self, crate_name: &str) -> Result<String, DocsFetchError> {
+        let normalized = normalize_crate_name(crate_name);
+        let key = crate_name_key(&normalized);
+        if self.config().denied_crates.iter().any(|denied| crate_name_key(denied) == key) {
+            return Err(DocsFetchError::Denied(normalized));
+        }
+        Ok(normalized)
+    }
+
+    /// Returns the canonical form of a crate name, confirming it via
+    /// crates.io the first time it is seen.
+    ///
+    /// crates.io resolves names case-insensitively and treats `-` and `_` as
+    /// equivalent, so e.g. `Tokio_Util` becomes `tokio-util`. Confirmed names
+    /// and names crates.io does not know are remembered for all spellings of
+    /// the name; when crates.io cannot be reached, the name is not accepted
+    /// by the configured [`CrateNamePolicy`], or pages are read from a local
+    /// `cargo doc` output, the normalized name is used for this call only.
+    /// Crates on the deny list are refused before anything is looked up, so
+    /// no tool serves them, not even from the cache.
+    async fn canonical_crate_name(&self, crate_name: &str) -> Result<String, DocsFetchError> {
+        let normalized = self.allowed_crate_name(crate_name)?;
+        if let Some(canonical) = self.remembered_crate_name(&normalized).await {
+            return Ok(canonical);
+        }
+        if SYSROOT_CRATES.contains(&normalized.as_str())
+            || self.is_local()
+            || self.config().crate_name_policy.check(&normalized).is_err()
+        {
+            return Ok(normalized);
+        }
+
+        let canonical = match self.client().resolve_crate_name(&normalized).await {
+            Ok(canonical) => canonical,
+            Err(DocsFetchError::DocsNotFound) => normalized.clone(),
+            Err(e) => {
+                tracing::warn!("Failed to confirm crate name '{}' via crates.io: {}", normalized, e);
+                return Ok(normalized);
+            }
+        };
+        if canonical != crate_name {
+            tracing::debug!("Canonicalized crate name '{}' to '{}'", crate_name, canonical);
+        }
+        self.remember_crate_name(&normalized, canonical.clone()).await;
+        Ok(canonical)
+    }
+
+    /// Returns the canonical crate name and the concrete version for a
+    /// request, resolving `latest` (or an empty version) via crates.io.
+    ///
+    /// Both are read from a single crates.io lookup. If the crate is not
+    /// published there or crates.io cannot be reached, `latest` is kept and
+    /// left to docs.rs to resolve; with a local `cargo doc` output it is kept
+    /// without asking. Crates on the deny list are refused like in
+    /// [`Self::canonical_crate_name`].
+    async fn resolve_crate_version(&self, crate_name: &str, version: &str) -> Result<(String, String), DocsFetchError> {
+        let version = version.trim();
+        if !(version.is_empty() || version == "latest") {
+            return Ok((self.canonical_crate_name(crate_name).await?, version.to_string()));
+        }
+        let normalized = self.allowed_crate_name(crate_name)?;
+        if SYSROOT_CRATES.contains(&normalized.as_str())
+            || self.is_local()
+            || self.config().crate_name_policy.check(&normalized).is_err()
+        {
+            return Ok((normalized, "latest".to_string()));
+        }
+
+        match self.client().crate_metadata(&normalized).await {
+            Ok(metadata) => {
+                let version = metadata.latest_version().unwrap_or("latest").to_string();
+                tracing::debug!("Resolved '{}' latest to {} {}", crate_name, metadata.name, version);
+                self.remember_crate_name(&normalized, metadata.name.clone()).await;
+                Ok((metadata.name, version))
+            }
+            Err(DocsFetchError::DocsNotFound) => {
+                self.remember_crate_name(&normalized, normalized.clone()).await;
+                Ok((normalized, "latest".to_string()))
+            }
+            Err(e) => {
+                tracing::warn!("Failed to resolve latest version of '{}' via crates.io: {}", normalized, e);
+                Ok((self.known_crate_name(&normalized).await, "latest".to_string()))
+            }
+        }
+    }
+
+    /// Checks if a document with the given parameters exists in the cache.
+    ///
+    /// # Arguments
+    /// * `params` - Parameters specifying the document to check for
+    ///
+    /// # Returns
+    /// `true` if the document is cached, `false` otherwise
+    #[allow(dead_code)]
+    pub async fn is_cached(&self, params: &DocsRsParams) -> bool {
+        self.cache.contains_key(&params.into()).await
+    }
+
+    /// Fetches documentation for a Rust crate from docs.rs.
+    ///
+    /// This function will first check the cache for the requested documentation.
+    /// If not found, it will fetch from docs.rs and cache the result. Redirect
+    /// stubs are followed, and the path of the page they lead to is reported
+    /// as `canonical_path`.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate to fetch documentation for
+    /// * `version` - Version of the crate (e.g., "1.0.0")
+    /// * `path` - Path to the specific documentation page, normalized with
+    ///   [`normalize_doc_path`]
+    /// * `max_bytes` - Optional response size limit overriding the configured default
+    /// * `estimate_tokens` - Whether to include the estimated token count of the content
+    /// * `include_hash` - Whether to include the SHA-256 hash of the content
+    /// * `if_none_match` - Hash of a copy the client has; if it matches the
+    ///   content, a compact not modified response is returned instead
+    /// * `include_structure` - Whether to include the page's signature,
+    ///   description, methods and examples as separate fields
+    /// * `format` - Format of the content, plain text by default
+    ///
+    /// # Returns
+    /// * `Ok(DocumentResponse)` - The fetched documentation content
+    /// * `Err(DocsFetchError)` - If fetching fails
+    // Tool parameters are flat arguments, one per field of the request
+    #[allow(clippy::too_many_arguments)]
+    #[tool(description = "Fetch Rust documentation of a specific crate and version. When you encounter an api issue, you can use this tool to fetch the documentation for reference.")]
+    async fn fetch_document(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate to fetch documentation for")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0. If not specified, the latest version will be used.")]
+        version: String,
+
+        #[tool(param)]
+        #[serde(default)]
+        #[schemars(description = "Path to the specific documentation page (e.g., 'std/vec/struct.Vec.html'). Module paths such as 'std/vec' and Rust paths such as 'vec::Vec' are mapped to a page on a best-effort basis. If not specified, the document of the crate will be returned")]
+        path: String,
+
+        #[tool(param)]
+        #[schemars(description = "Maximum size of the returned content in bytes. Longer content is truncated with a marker. If not specified, the server default is used.")]
+        max_bytes: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Whether to include the estimated token count of the returned content, as JSON metadata after the content. Defaults to false.")]
+        estimate_tokens: Option<bool>,
+
+        #[tool(param)]
+        #[schemars(description = "Whether to include the SHA-256 hash of the returned content as 'content_hash' in the JSON metadata, for caching on the client. Defaults to false.")]
+        include_hash: Option<bool>,
+
+        #[tool(param)]
+        #[schemars(description = "'content_hash' of a copy of this document you already have. If the content is unchanged, only a short 'not modified' notice is returned instead of the content.")]
+        if_none_match: Option<String>,
+
+        #[tool(param)]
+        #[schemars(description = "Whether to include the page split into 'signature', 'description', 'methods' and 'examples' as 'structure' in the JSON metadata. Defaults to false.")]
+        include_structure: Option<bool>,
+
+        #[tool(param)]
+        #[schemars(description = "Format of the returned content: 'plain_text' (default), 'markdown', which keeps headings, code blocks, lists and links, or 'json', the page's 'signature', 'description', 'methods' and 'examples' as a JSON resource.")]
+        format: Option<OutputFormat>,
+    ) -> Result<DocumentResponse, DocsFetchError> {
+        let path = match path.trim() {
+            "" => self.default_path(&crate_name).await.unwrap_or(path),
+            _ => path,
+        };
+        let path = normalize_doc_path(&crate_name, &path);
+        let params = DocsRsParams {
+            crate_name,
+            version,
+            path,
+        };
+
+        if let Some(access_log) = &self.config().access_log {
+            access_log.record(&params);
+        }
+        if let Some(request_log) = &self.config().request_log {
+            request_log.record(&params);
+        }
+        let format = format.unwrap_or_default();
+        let mut doc = self.fetch_as(params.clone(), format).await?;
+        let canonical_path = self.redirect_target(&params).await;
+        // The replacement is appended as text, which JSON cannot take
+        if self.config().follow_deprecation && format != OutputFormat::Json {
+            self.append_replacement(&mut doc, params.clone()).await;
+        }
+        if let Some(limit) = self.content_limit(max_bytes) {
+            doc.content = match format {
+                OutputFormat::Json => truncate_structured(&doc.content, limit),
+                _ => truncate_content(&doc.content, limit),
+            };
+        }
+        let hash = (include_hash.unwrap_or(false) || if_none_match.is_some())
+            .then(|| content_hash(&doc.content));
+        if let (Some(hash), Some(known)) = (&hash, &if_none_match)
+            && known.trim().trim_matches('"').eq_ignore_ascii_case(hash)
+        {
+            return Ok(DocumentResponse {
+                content: String::new(),
+                estimated_tokens: None,
+                content_hash: Some(hash.clone()),
+                not_modified: true,
+                canonical_path,
+                structure: None,
+                source_url: doc.source_url,
+                format,
+            });
+        }
+        let estimated_tokens = estimate_tokens
+            .unwrap_or(false)
+            .then(|| self::estimate_tokens(&doc.content));
+        let structure = match include_structure.unwrap_or(false) {
+            true => {
+                let path = canonical_path.clone().unwrap_or_else(|| params.path.clone());
+                Some(self.parsed_doc(&params.crate_name, &params.version, &path).await?)
+            }
+            false => None,
+        };
+        Ok(DocumentResponse {
+            content: doc.content,
+            estimated_tokens,
+            content_hash: hash,
+            not_modified: false,
+            canonical_path,
+            structure,
+            source_url: doc.source_url,
+            format,
+        })
+    }
+
+    /// Returns the page at `path` split into a [`ParsedDoc`].
+    async fn parsed_doc(&self, crate_name: &str, version: &str, path: &str) -> Result<ParsedDoc, DocsFetchError> {
+        let (crate_name, version) = self.resolve_crate_version(crate_name, version).await?;
+        let key = derived_cache_key(&crate_name, &version, &format!("structure:{}", path));
+        if let Some(parsed) = self.cached_json(&key).await {
+            return Ok(parsed);
+        }
+
+        let params = DocsRsParams { crate_name, version, path: path.to_string() };
+        let client = self.client();
+        let html = self.page_html(&params).await?;
+        let parsed = client.parse_html_content(&html);
+        if params.version != "latest" {
+            self.store_json(key, &parsed).await?;
+        }
+        Ok(parsed)
+    }
+
+    /// Returns the links of the page at `path` to other items of its crate.
+    async fn doc_links(&self, crate_name: &str, version: &str, path: &str) -> Result<Vec<DocLink>, DocsFetchError> {
+        let key = derived_cache_key(crate_name, version, &format!("links:{}", path));
+        if let Some(links) = self.cached_json(&key).await {
+            return Ok(links);
+        }
+
+        let params = DocsRsParams { crate_name: crate_name.to_string(), version: version.to_string(), path: path.to_string() };
+        let client = self.client();
+        let html = self.page_html(&params).await?;
+        let links = client.parse_doc_links(&html, path);
+        if !is_latest(version) {
+            self.store_json(key, &links).await?;
+        }
+        Ok(links)
+    }
+
+    /// Returns the path of the page the redirect stub at `params` pointed to
+    /// when it was fetched, or `None` if the page is no stub. With canonical
+    /// pages merged, the page a canonical link named is returned as well.
+    async fn redirect_target(&self, params: &DocsRsParams) -> Option<String> {
+        let crate_name = self.known_crate_name(&params.crate_name).await;
+        let version = match is_latest(&params.version) {
+            true => self.latest_version(&crate_name).await,
+            false => params.version.clone(),
+        };
+        let params = DocsRsParams { crate_name, version, path: params.path.clone() };
+        self.cache.get(&redirect_cache_key(&params)).await.map(|target| target.content)
+    }
+
+    /// Appends the documentation of the item that the deprecation notice of
+    /// the page at `params` names as its replacement, after a marker line.
+    ///
+    /// Only one step is followed, so a deprecated replacement does not lead
+    /// further. Pages without a notice or replacement are left unchanged.
+    async fn append_replacement(&self, doc: &mut DocContent, mut params: DocsRsParams) {
+        let Ok(crate_name) = self.canonical_crate_name(&params.crate_name).await else {
+            return;
+        };
+        params.crate_name = crate_name;
+        let Some(deprecation) = self.deprecation(&params).await else {
+            return;
+        };
+        let Some(replacement) = deprecation.replacement else {
+            return;
+        };
+        let replacement_params = DocsRsParams { path: replacement.clone(), ..params };
+        match self.fetch(replacement_params).await {
+            Ok(replacement_doc) => {
+                doc.content = format!(
+                    "{}\n\n===== Deprecated; replacement {} follows =====\n\n{}",
+                    doc.content.trim_end(),
+                    replacement,
+                    replacement_doc.content.trim(),
+                );
+            }
+            Err(e) => tracing::warn!("Failed to fetch replacement {} of a deprecated item: {}", replacement, e),
+        }
+    }
+
+    /// Returns the deprecation notice of the page at `params`, if it has one.
+    ///
+    /// Notices of concrete versions are cached. Failing to read the page
+    /// counts as having no notice.
+    async fn deprecation(&self, params: &DocsRsParams) -> Option<Deprecation> {
+        let key = derived_cache_key(&params.crate_name, &params.version, &format!("deprecation:{}", params.path));
+        if let Some(deprecation) = self.cached_json(&key).await {
+            return deprecation;
+        }
+
+        let client = self.client();
+        let html = match self.page_html(params).await {
+            Ok(html) => html,
+            Err(e) => {
+                tracing::debug!("Cannot check {:?} for a deprecation notice: {}", params, e);
+                return None;
+            }
+        };
+        let deprecation = client.parse_deprecation(&html, &params.path);
+        if params.version != "latest" {
+            self.store_json(key, &deprecation).await.ok();
+        }
+        deprecation
+    }
+
+    /// Returns the documentation for `params`, from the cache if possible.
+    ///
+    /// The crate name is canonicalized first, so differently cased or
+    /// separated spellings of a crate share cache entries, and a `latest` or
+    /// empty version is resolved to the concrete version, so that it shares
+    /// cache entries with requests naming that version. On a cache miss
+    /// the documentation is fetched from the configured server and stored
+    /// in the cache. Concurrent misses for the same page, from any tool,
+    /// share a single upstream request. Crates on the deny list, in any
+    /// spelling, and names not accepted by the configured
+    /// [`CrateNamePolicy`] are refused.
+    pub async fn fetch(&self, params: DocsRsParams) -> Result<DocContent, DocsFetchError> {
+        self.fetch_as(params, OutputFormat::PlainText).await
+    }
+
+    /// Returns the documentation for `params` like [`Self::fetch`], in
+    /// `format`. Each format is cached separately.
+    pub async fn fetch_as(&self, mut params: DocsRsParams, format: OutputFormat) -> Result<DocContent, DocsFetchError> {
+        let normalized = self.allowed_crate_name(&params.crate_name)?;
+        self.config().crate_name_policy.check(&normalized)?;
+
+        // Check cache first, confirming the crate name only if needed
+        params.crate_name = self.known_crate_name(&params.crate_name).await;
+        params.path = normalize_doc_path(&params.crate_name, &params.path);
+        if is_latest(&params.version) {
+            params.version = self.latest_version(&params.crate_name).await;
+        }
+        if let Some(cached_content) = self.cached_document(&params, format).await {
+            tracing::info!("Cache hit for {:?}", params);
+            return Ok(cached_content);
+        }
+        let canonical = self.canonical_crate_name(&params.crate_name).await?;
+        if canonical != params.crate_name {
+            params.crate_name = canonical;
+            if let Some(cached_content) = self.cached_document(&params, format).await {
+                tracing::info!("Cache hit for {:?}", params);
+                return Ok(cached_content);
+            }
+        }
+
+        if !self.config().coalesce_fetches {
+            return self.fetch_upstream(params, format).await;
+        }
+
+        let key = document_cache_key(&params, format);
+        let fetcher = self.clone();
+        let upstream = params.clone();
+        self.coalesced(key, &params, async move { fetcher.fetch_upstream(upstream, format).await }).await
+    }
+
+    /// Runs `fetch` for the page at `params`, unless a fetch registered
+    /// under `key` is already in flight, in which case its result is shared.
+    async fn coalesced(
+        &self,
+        key: CacheKey,
+        params: &DocsRsParams,
+        fetch: impl Future<Output = Result<DocContent, DocsFetchError>> + Send + 'static,
+    ) -> Result<DocContent, DocsFetchError> {
+        let in_flight = {
+            let mut in_flight = self.in_flight.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            match in_flight.get(&key) {
+                Some(fetch) => {
+                    tracing::info!("Joining in-flight fetch of {:?}", params);
+                    fetch.clone()
+                }
+                None => {
+                    // The fetch runs in its own task so it completes, caches its
+                    // result and unregisters itself even if every waiter gives up.
+                    // The task cannot unregister before the entry is inserted, as
+                    // the map stays locked until then.
+                    let guard = InFlightGuard { in_flight: self.in_flight.clone(), key: key.clone() };
+                    let task = tokio::spawn(async move {
+                        let result = fetch.await;
+                        drop(guard);
+                        result
+                    });
+                    let fetch = async move {
+                        task.await.unwrap_or_else(|e| {
+                            tracing::error!("Documentation fetch task failed: {}", e);
+                            Err(DocsFetchError::Internal(e.to_string()))
+                        })
+                    }.boxed().shared();
+                    in_flight.insert(key, fetch.clone());
+                    fetch
+                }
+            }
+        };
+        in_flight.await
+    }
+
+    /// Returns the cached content of the page at `params` in `format`.
+    ///
+    /// When canonical pages are merged, a page stored under the canonical
+    /// page its path was found to lead to is returned as well.
+    async fn cached_document(&self, params: &DocsRsParams, format: OutputFormat) -> Option<DocContent> {
+        if let Some(content) = self.cache.get(&document_cache_key(params, format)).await {
+            return Some(content);
+        }
+        if !self.config().merge_canonical_pages {
+            return None;
+        }
+        let target = self.cache.get(&redirect_cache_key(params)).await?;
+        let canonical = DocsRsParams { path: target.content, ..params.clone() };
+        self.cache.get(&document_cache_key(&canonical, format)).await
+    }
+
+    /// Returns the concrete version docs.rs serves as `latest` for a crate,
+    /// remembering it for the configured versions TTL. This is the only
+    /// place pages are resolved from `latest`; the client fetches the
+    /// version it is given.
+    ///
+    /// If it cannot be resolved, as for crates of the standard library,
+    /// which are not published on crates.io, `latest` is returned and left
+    /// to the documentation server. A local `cargo doc` output holds a
+    /// single version of each crate, so `latest` is kept for it without
+    /// asking. A failure is remembered for
+    /// [`LATEST_RESOLUTION_RETRY`], or the versions TTL if shorter.
+    async fn latest_version(&self, crate_name: &str) -> String {
+        if SYSROOT_CRATES.contains(&crate_name) || self.is_local() {
+            return LATEST_VERSION.to_string();
+        }
+        let versions_ttl = self.config().versions_ttl;
+        let lock = || self.latest_versions.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some((version, resolved_at)) = lock().get(crate_name) {
+            let ttl = match is_latest(version) {
+                true => LATEST_RESOLUTION_RETRY.min(versions_ttl),
+                false => versions_ttl,
+            };
+            if resolved_at.elapsed() < ttl {
+                return version.clone();
+            }
+        }
+        let version = match self.client().resolve_latest_version(crate_name).await {
+            Ok(version) => {
+                tracing::debug!("Resolved '{}' latest to {}", crate_name, version);
+                version
+            }
+            Err(e) => {
+                tracing::warn!("Failed to resolve latest version of '{}': {}", crate_name, e);
+                LATEST_VERSION.to_string()
+            }
+        };
+        lock().insert(crate_name.to_string(), (version.clone(), std::time::Instant::now()));
+        version
+    }
+
+    /// Rewrites links to the `latest` documentation of the crate of `params`
+    /// in `content` to the concrete version the content is from, resolving
+    /// `latest` via crates.io. Cached copies of `latest` pages thereby keep
+    /// pointing at the version they show; the content is left unchanged if
+    /// the version cannot be resolved.
+    async fn pin_latest_links(&self, params: &DocsRsParams, content: String) -> String {
+        if !content.contains("/latest") {
+            return content;
+        }
+        let version = match params.version.as_str() {
+            "" | "latest" => match self.resolve_crate_version(&params.crate_name, "latest").await {
+                Ok((_, version)) => version,
+                Err(_) => return content,
+            },
+            version => version.to_string(),
+        };
+        if version == "latest" {
+            return content;
+        }
+        pin_latest_links(&content, &params.crate_name, &version)
+    }
+
+    /// Returns the documentation for `params` like [`Self::fetch`], giving up
+    /// with [`DocsFetchError::Timeout`] once `deadline` passes.
+    ///
+    /// An abandoned fetch keeps running in the background, so its result
+    /// is still cached for later requests.
+    pub async fn fetch_within(&self, params: DocsRsParams, deadline: &Deadline) -> Result<DocContent, DocsFetchError> {
+        deadline.run(self.fetch(params)).await
+    }
+
+    /// Returns the raw HTML of the page at `params`, for tools parsing parts
+    /// of the page other than its documentation.
+    ///
+    /// The crate name and version of `params` must already be resolved, see
+    /// [`Self::resolve_crate_version`]. The HTML is not cached, as tools
+    /// cache what they parse from it, but the download goes the way of
+    /// [`Self::fetch_upstream`]: concurrent requests for the same page share
+    /// it, pages reported missing are remembered, and the circuit breaker
+    /// and fetch limit apply.
+    async fn page_html(&self, params: &DocsRsParams) -> Result<String, DocsFetchError> {
+        let fetcher = self.clone();
+        let upstream = params.clone();
+        let fetch = async move { fetcher.fetch_html_upstream(upstream).await };
+        let html = match self.config().coalesce_fetches {
+            true => {
+                let key = derived_cache_key(&params.crate_name, &params.version, &format!("html:{}", params.path));
+                self.coalesced(key, params, fetch).await?
+            }
+            false => fetch.await?,
+        };
+        Ok(html.content)
+    }
+
+    /// Downloads the raw HTML of the page at `params`, honoring the circuit
+    /// breaker and fetch limit like [`Self::fetch_upstream`].
+    async fn fetch_html_upstream(&self, params: DocsRsParams) -> Result<DocContent, DocsFetchError> {
+        let config = self.config();
+        if self.not_found_since(&params).is_some_and(|reported_at| reported_at.elapsed() < config.not_found_ttl) {
+            tracing::debug!("{:?} is known to be missing.", params);
+            return Err(DocsFetchError::DocsNotFound);
+        }
+        let breaker = &config.circuit_breaker;
+        if !breaker.allow_request() {
+            tracing::info!("Not fetching {:?} while documentation server is unavailable.", params);
+            return Err(DocsFetchError::ServiceUnavailable);
+        }
+
+        tracing::info!("Fetching HTML of {:?}...", params);
+        let permit = match &config.fetch_limiter {
+            Some(limiter) => limiter.acquire().await.ok(),
+            None => None,
+        };
+        let fetched = self.source().fetch_html(&params).await;
+        drop(permit);
+        match fetched {
+            Ok(html) => {
+                breaker.record_success();
+                self.lock_not_found().remove(&params);
+                Ok(DocContent { content: html, source_url: None })
+            }
+            Err(err) if err.is_retryable() => {
+                breaker.record_failure();
+                Err(err)
+            }
+            Err(err) => {
+                breaker.record_success();
+                if matches!(err, DocsFetchError::DocsNotFound) {
+                    self.remember_not_found(params);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Returns what `parse` reads from the raw HTML of the page at `path`,
+    /// for tools that need more than the page's documentation.
+    ///
+    /// The crate version is resolved first and results of concrete versions
+    /// are cached under `kind`, so that repeated calls do not fetch the page
+    /// again.
+    async fn parsed_page<T, F>(&self, crate_name: &str, version: &str, path: &str, kind: &str, parse: F) -> Result<T, DocsFetchError>
+    where
+        T: Serialize + serde::de::DeserializeOwned,
+        F: FnOnce(&DocsRsClient, &str, &str) -> Result<T, DocsFetchError>,
+    {
+        let (crate_name, version) = self.resolve_crate_version(crate_name, version).await?;
+        let path = path.trim_start_matches('/').to_string();
+        let key = derived_cache_key(&crate_name, &version, &format!("{}:{}", kind, path));
+        if let Some(parsed) = self.cached_json(&key).await {
+            return Ok(parsed);
+        }
+
+        let params = DocsRsParams { crate_name, version, path };
+        let html = self.page_html(&params).await?;
+        let parsed = parse(&self.client(), &html, &params.path)?;
+        if !is_latest(&params.version) {
+            self.store_json(key, &parsed).await?;
+        }
+        Ok(parsed)
+    }
+
+    /// Fetches a page missing from the cache from the documentation source
+    /// in `format` and caches it, honoring the circuit breaker.
+    async fn fetch_upstream(&self, params: DocsRsParams, format: OutputFormat) -> Result<DocContent, DocsFetchError> {
+        let config = self.config();
+        let reported_missing = self.not_found_since(&params);
+        if reported_missing.is_some_and(|reported_at| reported_at.elapsed() < config.not_found_ttl) {
+            tracing::debug!("{:?} is known to be missing.", params);
+            return Err(DocsFetchError::DocsNotFound);
+        }
+        // Every request let through reports its outcome to the breaker, as
+        // it may be the trial request of a half-open breaker
+        let breaker = &config.circuit_breaker;
+        if !breaker.allow_request() {
+            tracing::info!("Cache miss for {:?} while documentation server is unavailable.", params);
+            return self.serve_stale(&params, format, DocsFetchError::ServiceUnavailable).await;
+        }
+
+        let source = self.source();
+        if reported_missing.is_some() {
+            // Revalidate with a cheap HEAD; only download the page once it exists
+            if let Ok(false) = source.page_exists(&params).await {
+                tracing::debug!("{:?} is still missing.", params);
+                breaker.record_success();
+                self.remember_not_found(params);
+                return Err(DocsFetchError::DocsNotFound);
+            }
+            self.lock_not_found().remove(&params);
+        }
+
+        tracing::info!("Cache miss for {:?}. Fetching...", params);
+        let permit = match &config.fetch_limiter {
+            Some(limiter) => limiter.acquire().await.ok(),
+            None => None,
+        };
+        let fetched = source.fetch_following_redirects(params.clone(), format).await;
+        drop(permit);
+        match fetched {
+            Ok((mut doc_content, canonical_path)) => {
+                breaker.record_success();
+                if config.pin_latest_links {
+                    doc_content.content = self.pin_latest_links(&params, doc_content.content).await;
+                }
+                let len = doc_content.content.trim().len();
+                if len < config.min_content_len {
+                    tracing::warn!("Rejecting {:?}: extracted content is only {} bytes.", params, len);
+                    return Err(DocsFetchError::ParseError(format!(
+                        "extracted content is only {} bytes, below the minimum of {}",
+                        len, config.min_content_len
+                    )));
+                }
+                if let Some(webhook) = &config.fetch_webhook {
+                    webhook.notify(&params, &content_hash(&doc_content.content), doc_content.content.len());
+                }
+                // Store in cache, along with where a redirect stub or canonical
+                // link led; merged pages are stored under the page led to only
+                let mut stored = params.clone();
+                if let Some(canonical_path) = canonical_path {
+                    self.cache.insert(redirect_cache_key(&params), DocContent { content: canonical_path.clone(), source_url: None }).await;
+                    if config.merge_canonical_pages {
+                        stored.path = canonical_path;
+                    }
+                }
+                self.cache.insert(document_cache_key(&stored, format), doc_content.clone()).await;
+                Ok(doc_content)
+            },
+            Err(err) if err.is_retryable() => {
+                breaker.record_failure();
+                if breaker.is_open() {
+                    return self.serve_stale(&params, format, err).await;
+                }
+                Err(err)
+            }
+            Err(err) => {
+                breaker.record_success();
+                if matches!(err, DocsFetchError::DocsNotFound) {
+                    self.remember_not_found(params);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    fn lock_not_found(&self) -> std::sync::MutexGuard<'_, HashMap<DocsRsParams, std::time::Instant>> {
+        self.not_found.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Returns when the documentation server last reported `params` missing.
+    fn not_found_since(&self, params: &DocsRsParams) -> Option<std::time::Instant> {
+        self.lock_not_found().get(params).copied()
+    }
+
+    /// Remembers that the documentation server just reported `params` missing.
+    fn remember_not_found(&self, params: DocsRsParams) {
+        if !self.config().not_found_ttl.is_zero() {
+            self.lock_not_found().insert(params, std::time::Instant::now());
+        }
+    }
+
+    /// Falls back to a cached copy of the same page when serving stale
+    /// content is enabled, returning `err` otherwise. The content is
+    /// prefixed with a note naming the version.
+    ///
+    /// The requested version is looked up first, then the other cached
+    /// versions of the crate from the newest, each by its key, so the same
+    /// copy is served every time without scanning the whole cache. Entries
+    /// past the cache's time to live are served too.
+    async fn serve_stale(&self, params: &DocsRsParams, format: OutputFormat, err: DocsFetchError) -> Result<DocContent, DocsFetchError> {
+        if !self.config().serve_stale_on_outage {
+            return Err(err);
+        }
+        // Only content extracted the same way, from a version of the page
+        let wanted = document_cache_key(params, format);
+        let mut versions = self.cache.cached_versions(wanted.crate_name()).await;
+        versions.retain(|version| *version != params.version);
+        versions.sort_by(|a, b| newest_version_first(a, b));
+        let mut stale = None;
+        for version in std::iter::once(params.version.clone()).chain(versions) {
+            let mut key = wanted.clone();
+            key.params.version = version;
+            if let Some(doc) = self.cache.get_stale(&key).await {
+                stale = Some((key, doc));
+                break;
+            }
+        }
+
+        match stale {
+            Some((key, doc)) => {
+                tracing::warn!("Serving stale {:?} for {:?} due to upstream outage.", key, params);
+                Ok(DocContent {
+                    content: format!(
+                        "[Served stale from cache (version {}) due to upstream outage]\n\n{}",
+                        key.params.version, doc.content
+                    ),
+                    source_url: doc.source_url,
+                })
+            }
+            None => Err(err),
+        }
+    }
+
+    /// Fetches a documentation page together with a glossary of the items
+    /// of the same crate its documentation links to.
+    ///
+    /// The linked pages are fetched through the cache, at most
+    /// [`FetcherConfig::bundle_concurrency`] at once. A linked page that
+    /// cannot be fetched is listed without a summary.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `path` - Path to the documentation page
+    /// * `max_links` - Maximum number of linked items to summarize, [`DEFAULT_CONTEXT_LINKS`] by default
+    #[tool(description = "Fetch a documentation page together with a glossary of the items of the same crate its documentation links to, each with its one-line summary. Use this instead of following the links of a page one by one. Summarizes at most 'max_links' linked items (default 10, at most 50).")]
+    async fn fetch_with_context(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[schemars(description = "Path to the documentation page, e.g. 'tokio/sync/struct.Mutex.html'")]
+        path: String,
+
+        #[tool(param)]
+        #[schemars(description = "Maximum number of linked items to summarize, default 10, at most 50")]
+        max_links: Option<usize>,
+    ) -> Result<ContextualDocument, DocsFetchError> {
+        let (crate_name, version) = self.resolve_crate_version(&crate_name, &version).await?;
+        let max_links = max_links.unwrap_or(DEFAULT_CONTEXT_LINKS).min(MAX_CONTEXT_LINKS);
+        let params = DocsRsParams { crate_name: crate_name.clone(), version: version.clone(), path };
+        let mut doc = self.fetch(params.clone()).await?;
+        if let Some(limit) = self.content_limit(None) {
+            doc.content = truncate_content(&doc.content, limit);
+        }
+
+        let mut links = self.doc_links(&crate_name, &version, &params.path).await?;
+        let omitted_links = links.len().saturating_sub(max_links);
+        links.truncate(max_links);
+        let glossary = futures::stream::iter(links)
+            .map(|link| async {
+                let summary = match self.parsed_doc(&crate_name, &version, &link.path).await {
+                    Ok(parsed) => parsed.summary().map(str::to_string),
+                    Err(e) => {
+                        tracing::warn!("Failed to summarize {} of {} {}: {}", link.path, crate_name, version, e);
+                        None
+                    }
+                };
+                GlossaryEntry { name: link.name, path: link.path, summary }
+            })
+            .buffered(self.config().bundle_concurrency.max(1))
+            .collect()
+            .await;
+
+        Ok(ContextualDocument {
+            content: doc.content,
+            context: DocContext { glossary, omitted_links },
+        })
+    }
+
+    /// Fetches several documentation pages of one crate concurrently.
+    ///
+    /// The crate name and version are resolved once for all paths, and the
+    /// pages are fetched through the cache like [`Self::fetch_document`].
+    /// A failing path does not fail the call; its error is reported in place
+    /// of its content. All pages share one overall time budget, see
+    /// [`FetcherConfig::batch_timeout`]; pages still missing when it runs
+    /// out are reported as timed out. With `max_total_bytes`, results are
+    /// added in request order until the next one would exceed it; that path
+    /// and all later ones are reported as omitted.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate to fetch documentation for
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `paths` - Paths of the documentation pages
+    /// * `max_total_bytes` - Cap on the combined size of the results
+    #[tool(description = "Fetch several documentation pages of one crate in a single call. Returns a map from each path to its content or error. Prefer this over repeated fetch_document calls for the same crate. Set 'max_total_bytes' to cap the combined size; paths that do not fit are listed as omitted.")]
+    async fn fetch_crate_paths(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate to fetch documentation for")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[schemars(description = "Paths of the documentation pages, e.g. ['tokio/sync/struct.Mutex.html', 'tokio/sync/struct.RwLock.html']")]
+        paths: Vec<String>,
+
+        #[tool(param)]
+        #[schemars(description = "Maximum combined size of the results in bytes; results are added in request order until the next would exceed it. No cap by default")]
+        max_total_bytes: Option<usize>,
+    ) -> Result<CratePaths, DocsFetchError> {
+        let deadline = Deadline::after(self.config().batch_timeout);
+        let (crate_name, version) = self.resolve_crate_version(&crate_name, &version).await?;
+        let limit = self.content_limit(None);
+
+        let fetches = paths.into_iter().map(|path| {
+            let params = DocsRsParams {
+                crate_name: crate_name.clone(),
+                version: version.clone(),
+                path,
+            };
+            async move {
+                if let Some(access_log) = &self.config().access_log {
+                    access_log.record(&params);
+                }
+                let result = match self.fetch_within(params.clone(), &deadline).await {
+                    Ok(doc) => PathResult::Content(match limit {
+                        Some(limit) => truncate_content(&doc.content, limit),
+                        None => doc.content,
+                    }),
+                    Err(e) => PathResult::Error(e.to_string()),
+                };
+                (params.path, result)
+            }
+        });
+        let mut budget = ByteBudget::new(max_total_bytes);
+        let mut documents = BTreeMap::new();
+        let mut omitted = Vec::new();
+        for (path, result) in futures::future::join_all(fetches).await {
+            let (PathResult::Content(text) | PathResult::Error(text)) = &result;
+            if budget.admit(text.len()) {
+                documents.insert(path, result);
+            } else {
+                omitted.push(path);
+            }
+        }
+
+        Ok(CratePaths { crate_name, version, documents, truncated: !omitted.is_empty(), omitted })
+    }
+
+    /// Concatenates the documentation of a crate's items into one document.
+    ///
+    /// Items are taken from the crate's search index in a stable order: the
+    /// crate root first, then every item by path, so that bundles are
+    /// reproducible. A page reachable as several items, e.g. through
+    /// re-exports, is only bundled once, under its first path. At most
+    /// `max_items` pages are fetched per call through the cache,
+    /// [`FetcherConfig::bundle_concurrency`] at a time and within the bound
+    /// of [`FetcherConfig::fetch_limiter`]; a call with
+    /// `start` set to the returned `next_start` continues where the previous
+    /// one stopped. Pages that fail are noted in place of their content.
+    /// All pages share one overall time budget, see
+    /// [`FetcherConfig::batch_timeout`]. With `max_total_bytes`, items are
+    /// added in order until the next one would exceed it, and no further
+    /// pages are fetched; `next_start` then points at the first item left
+    /// out. Bundles of concrete versions are cached unless a page timed out.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `max_items` - Maximum number of items to bundle, [`DEFAULT_BUNDLE_ITEMS`] by default
+    /// * `start` - Position of the first item to bundle, 0 by default
+    /// * `max_total_bytes` - Cap on the size of the bundled content
+    #[tool(description = "Fetch the documentation of all items of a crate concatenated into a single document, with a separator line before each item. Expensive and potentially very large: bundles at most 'max_items' items per call (default 50, at most 500) and at most 'max_total_bytes' bytes if set; continue with 'start' set to the returned 'next_start'.")]
+    async fn fetch_crate_bundle(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[schemars(description = "Maximum number of items to bundle, default 50, at most 500")]
+        max_items: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Position of the first item to bundle, from 'next_start' of a previous call; default 0")]
+        start: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Maximum size of the bundled content in bytes; items are added in order until the next would exceed it. No cap by default")]
+        max_total_bytes: Option<usize>,
+    ) -> Result<CrateBundle, DocsFetchError> {
+        let deadline = Deadline::after(self.config().batch_timeout);
+        let (crate_name, version) = self.resolve_crate_version(&crate_name, &version).await?;
+        let max_items = max_items.unwrap_or(DEFAULT_BUNDLE_ITEMS).clamp(1, MAX_BUNDLE_ITEMS);
+        let start = start.unwrap_or(0);
+        let kind = match max_total_bytes {
+            Some(cap) => format!("bundle:{}:{}:{}", start, max_items, cap),
+            None => format!("bundle:{}:{}", start, max_items),
+        };
+        let key = derived_cache_key(&crate_name, &version, &kind);
+        if let Some(bundle) = self.cached_json(&key).await {
+            return Ok(bundle);
+        }
+
+        let index = deadline.run(self.search_index(&crate_name, &version)).await?;
+        let ident = crate_name.replace('-', "_");
+        let mut items: Vec<(String, String)> = index.items
+            .into_iter()
+            .map(|item| (item.path, item.doc_path))
+            .collect();
+        items.sort();
+        items.insert(0, (ident.clone(), format!("{}/index.html", ident)));
+        let mut seen = HashSet::new();
+        items.retain(|(_, doc_path)| seen.insert(doc_path.clone()));
+        let total_items = items.len();
+        let end = start.saturating_add(max_items).min(total_items);
+        let selected = items.get(start..end).unwrap_or_default().to_vec();
+        let selected_items = selected.len();
+        let selected_paths: Vec<String> = selected.iter().map(|(path, _)| path.clone()).collect();
+
+        let mut bundled = 0;
+        let mut budget = ByteBudget::new(max_total_bytes);
+        let results: Vec<(String, bool)> = futures::stream::iter(selected)
+            .map(|(path, doc_path)| {
+                let params = DocsRsParams { crate_name: crate_name.clone(), version: version.clone(), path: doc_path };
+                async move {
+                    let (body, timed_out) = match self.fetch_within(params.clone(), &deadline).await {
+                        Ok(doc) => (doc.content, false),
+                        Err(e) => (format!("[Failed to fetch: {}]", e), matches!(e, DocsFetchError::Timeout)),
+                    };
+                    (format!("===== {} ({}) =====\n\n{}\n", path, params.path, body.trim()), timed_out)
+                }
+            })
+            .buffered(self.config().bundle_concurrency.max(1))
+            .inspect(|_| {
+                bundled += 1;
+                if bundled % BUNDLE_PROGRESS_INTERVAL == 0 || bundled == selected_items {
+                    tracing::info!("Bundled {}/{} pages of {} {}", bundled, selected_items, crate_name, version);
+                }
+            })
+            // Counting the newline joining the sections keeps the content within the cap
+            .take_while(|(section, _)| futures::future::ready(budget.admit(section.len() + 1)))
+            .collect()
+            .await;
+        let timed_out = results.iter().any(|(_, timed_out)| *timed_out);
+        let sections: Vec<String> = results.into_iter().map(|(section, _)| section).collect();
+        let omitted = selected_paths[sections.len()..].to_vec();
+        let end = if omitted.is_empty() { end } else { start + sections.len() };
+
+        let content = sections.join("\n");
+        let size_bytes = content.len();
+        let warning = (size_bytes > BUNDLE_WARN_BYTES).then(|| {
+            tracing::warn!("Bundle of {} {} is {} bytes.", crate_name, version, size_bytes);
+            format!("The bundle is {} bytes, which may exceed a model's context; consider a smaller 'max_items'.", size_bytes)
+        });
+        let bundle = CrateBundle {
+            content,
+            info: BundleInfo {
+                items: sections.len(),
+                next_start: (end < total_items).then_some(end),
+                start,
+                total_items,
+                size_bytes,
+                warning,
+                truncated: !omitted.is_empty(),
+                omitted,
+                crate_name,
+                version,
+            },
+        };
+        if bundle.info.version != "latest" && !timed_out {
+            self.store_json(key, &bundle).await?;
+        }
+        Ok(bundle)
+    }
+
+    /// Returns an example from the documentation of a page together with
+    /// the result of checking its syntax.
+    ///
+    /// The check only parses the code, see [`example_syntax::check`]; the
+    /// example is neither compiled nor run.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `path` - Path to the documentation page, the crate root if empty
+    /// * `index` - Position of the example on the page, 0 (the first) by default
+    #[tool(description = "Return an example from a documentation page (the first example of the crate root by default) with a flag telling whether it is syntactically valid Rust, and the location of the parse error if not. Only parses the code; it is not compiled or run.")]
+    async fn validate_example(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[serde(default)]
+        #[schemars(description = "Path to the documentation page, e.g. 'tokio/sync/struct.Mutex.html'. If not specified, the crate root is used")]
+        path: String,
+
+        #[tool(param)]
+        #[schemars(description = "Position of the example on the page, starting at 0; default 0")]
+        index: Option<usize>,
+    ) -> Result<ExampleValidation, DocsFetchError> {
+        let crate_name = self.canonical_crate_name(&crate_name).await?;
+        let path = match path.trim() {
+            "" => match self.default_path(&crate_name).await {
+                Some(path) => path,
+                None => format!("{}/index.html", crate_name.replace('-', "_")),
+            },
+            path => path.to_string(),
+        };
+        let examples: Vec<String> = self
+            .parsed_page(&crate_name, &version, &path, "examples", |client, html, _| Ok(client.parse_examples(html)))
+            .await?;
+
+        let index = index.unwrap_or(0);
+        let code = examples.get(index).cloned().ok_or_else(|| {
+            DocsFetchError::ParseError(format!("the page has {} examples, none at position {}", examples.len(), index))
+        })?;
+        let error = example_syntax::check(&code).err();
+        Ok(ExampleValidation {
+            path,
+            index,
+            total_examples: examples.len(),
+            code,
+            valid: error.is_none(),
+            error,
+        })
+    }
+
+    /// Compares the documentation of two items, usually of different crates,
+    /// e.g. to migrate from one crate to a similar one.
+    ///
+    /// Both pages are fetched through the cache. An item without a
+    /// documentation page is diffed as empty text and flagged as missing.
+    ///
+    /// # Arguments
+    /// * `left` - Crate, version and path of the first item
+    /// * `right` - Crate, version and path of the second item
+    /// * `context_lines` - Unchanged lines around each change, [`DEFAULT_DIFF_CONTEXT`] by default
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocsFetchError::DocsNotFound`] if neither item exists, or
+    /// the error of a fetch that failed for another reason.
+    #[tool(description = "Compare the documentation of two items, typically from different crates (e.g. 'reqwest' vs 'isahc' when migrating), as a unified diff of their text. Each item is given as {crate_name, version, path}. An item that does not exist is diffed as empty and flagged as missing.")]
+    async fn cross_crate_diff(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "First item: crate name, version and documentation path")]
+        left: DocsRsParams,
+
+        #[tool(param)]
+        #[schemars(description = "Second item: crate name, version and documentation path")]
+        right: DocsRsParams,
+
+        #[tool(param)]
+        #[schemars(description = "Number of unchanged lines shown around each change, default 3")]
+        context_lines: Option<usize>,
+    ) -> Result<ItemDiff, DocsFetchError> {
+        let (left_crate, right_crate) = tokio::join!(
+            self.canonical_crate_name(&left.crate_name),
+            self.canonical_crate_name(&right.crate_name),
+        );
+        let left = DocsRsParams { crate_name: left_crate?, ..left };
+        let right = DocsRsParams { crate_name: right_crate?, ..right };
+        let (left_doc, right_doc) = tokio::join!(self.fetch(left.clone()), self.fetch(right.clone()));
+
+        let content = |result: Result<DocContent, DocsFetchError>| match result {
+            Ok(doc) => Ok(Some(doc.content)),
+            Err(DocsFetchError::DocsNotFound) => Ok(None),
+            Err(e) => Err(e),
+        };
+        let (left_content, right_content) = match (content(left_doc)?, content(right_doc)?) {
+            (None, None) => return Err(DocsFetchError::DocsNotFound),
+            contents => contents,
+        };
+
+        let label = |params: &DocsRsParams| format!("{} {} {}", params.crate_name, params.version, params.path);
+        let left_text = left_content.as_deref().unwrap_or_default();
+        let right_text = right_content.as_deref().unwrap_or_default();
+        let diff = unified_diff(
+            &label(&left),
+            left_text,
+            &label(&right),
+            right_text,
+            context_lines.unwrap_or(DEFAULT_DIFF_CONTEXT),
+        );
+        Ok(ItemDiff {
+            identical: left_content == right_content,
+            left_missing: left_content.is_none(),
+            right_missing: right_content.is_none(),
+            diff,
+            left,
+            right,
+        })
+    }
+
+    /// Fetches a documentation page split into its logical sections.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `path` - Path to the documentation page
+    #[tool(description = "Fetch a documentation page split into named sections (Declaration, Summary, Examples, Fields, Implementations, Trait Implementations, ...) in page order. Use this to read only the parts of a page you need.")]
+    async fn fetch_sections(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[schemars(description = "Path to the documentation page, e.g. 'tokio/sync/struct.Mutex.html'")]
+        path: String,
+    ) -> Result<PageSections, DocsFetchError> {
+        self.parsed_page(&crate_name, &version, &path, "sections", |client, html, path| client.parse_sections(html, path)).await
+    }
+
+    /// Lists all items of a crate grouped by kind.
+    ///
+    /// The items are read from the crate's rustdoc search index in a single
+    /// request. The parsed index is cached so that repeated calls, and other
+    /// index-based tools, do not fetch it again.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate to index
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    #[tool(description = "List all items of a crate (modules, structs, enums, traits, functions, macros, type aliases) grouped by kind, with their paths. Use this to get a full map of a crate in one call.")]
+    async fn crate_index(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate to index")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+    ) -> Result<CrateIndex, DocsFetchError> {
+        let (crate_name, version) = self.resolve_crate_version(&crate_name, &version).await?;
+        let index = self.search_index(&crate_name, &version).await?;
+        Ok(CrateIndex::from_index(&crate_name, &version, &index))
+    }
+
+    /// Searches the items of a crate by name or path.
+    ///
+    /// The query is matched against the crate's rustdoc search index, see
+    /// [`SearchIndex::search`]. Results are cached per query for concrete
+    /// versions.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate to search
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `query` - Text to look for in item names and paths
+    #[tool(description = "Search the items of a crate by name or path (e.g. 'mutex' or 'sync::Mutex') and get the best matches with their kinds and documentation page paths, to pass to fetch_document. Use this to discover the right page of an item.")]
+    async fn search_crate(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate to search")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[schemars(description = "Text to look for in item names and paths, e.g. 'mutex'")]
+        query: String,
+    ) -> Result<SearchResults, DocsFetchError> {
+        let (crate_name, version) = self.resolve_crate_version(&crate_name, &version).await?;
+        let query = query.trim().to_string();
+        let key = derived_cache_key(&crate_name, &version, &format!("search:{}", query.to_lowercase()));
+        if let Some(results) = self.cached_json(&key).await {
+            return Ok(results);
+        }
+
+        let mut items = self.search_index(&crate_name, &version).await?.search(&query);
+        let truncated = items.len() > MAX_SEARCH_HITS;
+        items.truncate(MAX_SEARCH_HITS);
+        let results = SearchResults { crate_name, version, query, items, truncated };
+        if results.version != "latest" {
+            self.store_json(key, &results).await?;
+        }
+        Ok(results)
+    }
+
+    /// Lists the items of one kind of a crate, e.g. only its traits.
+    ///
+    /// The items are read from the cached search index like those of
+    /// [`Self::crate_index`].
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate to list
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `kind` - Kind of items to list: struct, trait, fn, enum, macro, mod or type
+    ///
+    /// # Errors
+    ///
+    /// Fails with an invalid params error if `kind` is not a known kind.
+    #[tool(description = "List only the items of one kind of a crate (struct, trait, fn, enum, macro, mod or type) with their paths. Cheaper than crate_index when only one kind of item is of interest.")]
+    async fn list_crate_items(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate to list")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[schemars(description = "Kind of items to list: struct, trait, fn, enum, macro, mod or type")]
+        kind: String,
+    ) -> Result<CallToolResult, McpError> {
+        let Some(item_kind) = ItemKind::from_filter(&kind) else {
+            return Err(McpError::invalid_params(
+                format!("unknown item kind '{}', expected struct, trait, fn, enum, macro, mod or type", kind),
+                None,
+            ));
+        };
+        let (crate_name, version) = match self.resolve_crate_version(&crate_name, &version).await {
+            Ok(resolved) => resolved,
+            Err(e) => return Ok(CallToolResult::error(e.into_contents())),
+        };
+        Ok(match self.search_index(&crate_name, &version).await {
+            Ok(index) => {
+                let items = index.items_of_kind(item_kind);
+                CallToolResult::success(CrateItems { crate_name, version, kind: item_kind, items }.into_contents())
+            }
+            Err(e) => CallToolResult::error(e.into_contents()),
+        })
+    }
+
+    /// Resolves an item name to the documentation paths of the matching items.
+    ///
+    /// The name is looked up in the crate's cached search index, so this
+    /// costs at most one request. Every item whose path ends with the name is
+    /// returned, so that the caller can pick among items sharing a name.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate to search
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `name` - Bare item name or path suffix (e.g., "HashMap" or "sync::Mutex")
+    #[tool(description = "Resolve a bare item name (e.g. 'HashMap') or path suffix (e.g. 'sync::Mutex') to the full paths and documentation page paths of the matching items of a crate. Returns all candidates when several items share the name. Use this when you know an item's name but not where it lives.")]
+    async fn resolve_path(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate to search")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[schemars(description = "Bare item name or path suffix, e.g. 'HashMap' or 'sync::Mutex'")]
+        name: String,
+    ) -> Result<ResolvedPath, DocsFetchError> {
+        let (crate_name, version) = self.resolve_crate_version(&crate_name, &version).await?;
+        let index = self.search_index(&crate_name, &version).await?;
+        let candidates = index.resolve(&name);
+        Ok(ResolvedPath { crate_name, version, name, candidates })
+    }
+
+    /// Lists the types implementing a trait.
+    ///
+    /// Fetches the trait's documentation page and parses its "Implementors"
+    /// section. Implementors from other crates, which rustdoc loads with a
+    /// script at view time, are not included; the script location is
+    /// reported instead.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate defining the trait
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `path` - Path to the trait page (e.g., 'rand/trait.Rng.html')
+    #[tool(description = "List the types implementing a trait, parsed from the trait's documentation page. Use this to discover which concrete types satisfy a trait.")]
+    async fn implementors(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate defining the trait")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[schemars(description = "Path to the trait page, e.g. 'rand/trait.Rng.html'")]
+        path: String,
+    ) -> Result<Implementors, DocsFetchError> {
+        self.parsed_page(&crate_name, &version, &path, "implementors", |client, html, _| Ok(client.parse_implementors(html))).await
+    }
+
+    /// Lists the auto traits (`Send`, `Sync`, `Unpin`, ...) a type implements
+    /// or explicitly does not implement.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate defining the type
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `path` - Path to the type page (e.g., 'alloc/rc/struct.Rc.html')
+    #[tool(description = "List the auto trait implementations (Send, Sync, Unpin, UnwindSafe, ...) of a type, parsed from its documentation page. Negative impls such as !Send are marked, as are impls that only hold under a where clause.")]
+    async fn auto_traits(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate defining the type")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[schemars(description = "Path to the type page, e.g. 'alloc/rc/struct.Rc.html'")]
+        path: String,
+    ) -> Result<AutoTraits, DocsFetchError> {
+        self.parsed_page(&crate_name, &version, &path, "auto-traits", |client, html, _| Ok(client.parse_auto_traits(html))).await
+    }
+
+    /// Returns the status of the latest docs.rs build of a crate version.
+    ///
+    /// Not cached, as builds are retried and rebuilt.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    #[tool(description = "Get the status of the docs.rs documentation build of a crate version: success, failed or in_progress, with a link to the build logs and an excerpt of the errors of a failed build. Use it to find out why fetch_document finds no documentation.")]
+    async fn build_status(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+    ) -> Result<BuildStatus, DocsFetchError> {
+        let crate_name = self.canonical_crate_name(&crate_name).await?;
+        self.docs_rs_client("Build status")?.fetch_build_status(&crate_name, &version).await
+    }
+
+    /// Lists the blanket implementations (`impl<T> Trait for T`) that apply
+    /// to a type.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate defining the type
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `path` - Path to the type page (e.g., 'alloc/rc/struct.Rc.html')
+    #[tool(description = "List the blanket implementations (impl<T> Trait for T where ..., e.g. From, Into, Any, Borrow) of a type, parsed from the Blanket Implementations section of its documentation page, as impl signatures.")]
+    async fn blanket_impls(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate defining the type")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[schemars(description = "Path to the type page, e.g. 'alloc/rc/struct.Rc.html'")]
+        path: String,
+    ) -> Result<BlanketImpls, DocsFetchError> {
+        self.parsed_page(&crate_name, &version, &path, "blanket-impls", |client, html, _| Ok(client.parse_blanket_impls(html))).await
+    }
+
+    /// Lists the inherent impl blocks of a type with the methods of each.
+    ///
+    /// Each block is reported separately with its generic parameters and
+    /// where clause, so that methods only available under some bounds can
+    /// be told apart from those always available.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate defining the type
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `path` - Path to the type page (e.g., 'alloc/vec/struct.Vec.html')
+    #[tool(description = "List the inherent impl blocks of a type, each with its generic parameters, where clause and the signatures of its methods, parsed from the Implementations section of its documentation page. Use this to see which methods are available under which bounds.")]
+    async fn inherent_impls(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate defining the type")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[schemars(description = "Path to the type page, e.g. 'alloc/vec/struct.Vec.html'")]
+        path: String,
+    ) -> Result<InherentImpls, DocsFetchError> {
+        self.parsed_page(&crate_name, &version, &path, "inherent-impls", |client, html, _| Ok(client.parse_inherent_impls(html))).await
+    }
+
+    /// Lists the functions of a type that look like ways to construct it.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate defining the type
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `path` - Path to the type page (e.g., 'reqwest/struct.Client.html')
+    #[tool(description = "Answer 'how do I make one of these?': list a type's candidate construction entry points (associated functions named new, default, builder, from, new_*, from_* or with_*, including Default and From impls) with their signatures, parsed from its documentation page. This is a name-based heuristic.")]
+    async fn construction_hint(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate defining the type")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[schemars(description = "Path to the type page, e.g. 'reqwest/struct.Client.html'")]
+        path: String,
+    ) -> Result<ConstructionHints, DocsFetchError> {
+        self.parsed_page(&crate_name, &version, &path, "construction-hints", |client, html, path| Ok(client.parse_construction_hints(html, path))).await
+    }
+
+    /// Resolves a type alias to the type it expands to.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate defining the alias
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `path` - Path to the alias page (e.g., 'std/io/type.Result.html')
+    #[tool(description = "Fetch a type alias page and return the alias declaration, the aliased type and its path, and the expanded definition. Use this to see through type alias indirection.")]
+    async fn fetch_type_alias(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate defining the type alias")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[schemars(description = "Path to the type alias page, e.g. 'std/io/type.Result.html'")]
+        path: String,
+    ) -> Result<TypeAlias, DocsFetchError> {
+        self.parsed_page(&crate_name, &version, &path, "type-alias", |client, html, _| client.parse_type_alias(html)).await
+    }
+
+    /// Returns the navigation trail of an item, from the crate root down to
+    /// the item, with the documentation path of every segment.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate defining the item
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `path` - Path to the item page (e.g., 'tokio/sync/struct.Mutex.html')
+    #[tool(description = "Return the breadcrumb of an item page (crate > modules > item) with the documentation path of every segment. Use this to navigate to the modules enclosing an item.")]
+    async fn breadcrumb(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate defining the item")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[schemars(description = "Path to the item page, e.g. 'tokio/sync/struct.Mutex.html'")]
+        path: String,
+    ) -> Result<Breadcrumb, DocsFetchError> {
+        self.parsed_page(&crate_name, &version, &path, "breadcrumb", |client, html, path| client.parse_breadcrumb(html, path)).await
+    }
+
+    /// Returns the source code of a single item, found by following the
+    /// source link on its documentation page to the line range it points to.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate defining the item
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `path` - Path to the item page (e.g., 'tokio/sync/struct.Mutex.html')
+    #[tool(description = "Return the source code of a single item (struct, function, trait, ...) by following the source link on its documentation page. Use this to see exactly how an item is implemented.")]
+    async fn fetch_item_source(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate defining the item")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[schemars(description = "Path to the item page, e.g. 'tokio/sync/struct.Mutex.html'")]
+        path: String,
+    ) -> Result<ItemSource, DocsFetchError> {
+        let (crate_name, version) = self.resolve_crate_version(&crate_name, &version).await?;
+        let path = path.trim_start_matches('/').to_string();
+        let key = derived_cache_key(&crate_name, &version, &format!("item-source:{}", path));
+        if let Some(source) = self.cached_json(&key).await {
+            return Ok(source);
+        }
+
+        let client = self.client();
+        let params = DocsRsParams { crate_name, version, path };
+        let html = self.page_html(&params).await?;
+        let link = client.parse_source_link(&html, &params.path)?;
+
+        let source_params = DocsRsParams { path: link.path, ..params.clone() };
+        let source_html = self.page_html(&source_params).await?;
+        let range = link.start_line.zip(link.end_line);
+        let (start_line, end_line, code) = client.parse_source_lines(&source_html, range)?;
+        let source = ItemSource {
+            item_path: params.path,
+            source_path: source_params.path,
+            start_line,
+            end_line,
+            code,
+        };
+        if !is_latest(&params.version) {
+            self.store_json(key, &source).await?;
+        }
+        Ok(source)
+    }
+
+    /// Returns a whole source file as rendered by rustdoc's source view, as
+    /// plain text without line numbers.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `path` - Source page path (e.g., 'src/tokio/sync/mutex.rs.html') or
+    ///   file path relative to the crate root (e.g., 'src/sync/mutex.rs')
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`DocsFetchError::DocsNotFound`] if rustdoc rendered no
+    /// source page for the file.
+    #[tool(description = "Return the full source code of a file of a crate as plain text without line numbers, read from the docs.rs source view. Accepts a source page path such as 'src/tokio/sync/mutex.rs.html' or a file path relative to the crate root such as 'src/sync/mutex.rs'. Use this when the implementation matters, not just the documentation.")]
+    async fn fetch_source(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[schemars(description = "Source page path, e.g. 'src/tokio/sync/mutex.rs.html', or file path relative to the crate root, e.g. 'src/sync/mutex.rs'")]
+        path: String,
+    ) -> Result<RenderedSource, DocsFetchError> {
+        let (crate_name, version) = self.resolve_crate_version(&crate_name, &version).await?;
+        let source_path = source_page_path(&crate_name, &path);
+        let key = derived_cache_key(&crate_name, &version, &format!("source:{}", source_path));
+        if let Some(source) = self.cached_json(&key).await {
+            return Ok(source);
+        }
+
+        let params = DocsRsParams { crate_name, version, path: source_path };
+        let html = self.page_html(&params).await?;
+        let source = self.client().parse_rendered_source(&html, params.path)?;
+        if params.version != "latest" {
+            self.store_json(key, &source).await?;
+        }
+        Ok(source)
+    }
+
+    /// Lists the files of a crate's `examples/` directory, which rustdoc does
+    /// not render but docs.rs's source browser shows.
+    ///
+    /// Crates that publish no examples get an empty list. Results are
+    /// cached for concrete versions.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    #[tool(description = "List the files of a crate's published examples/ directory, which are not part of the rendered docs. Read one with fetch_example_file. Returns an empty list if the crate publishes no examples.")]
+    async fn list_examples_files(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+    ) -> Result<ExampleFiles, DocsFetchError> {
+        let (crate_name, version) = self.resolve_crate_version(&crate_name, &version).await?;
+        let key = derived_cache_key(&crate_name, &version, "examples");
+        if let Some(files) = self.cached_json(&key).await {
+            return Ok(files);
+        }
+
+        let entries = match self.docs_rs_client("Listing example files")?.fetch_source_listing(&crate_name, &version, "examples").await {
+            Ok(entries) => entries,
+            Err(DocsFetchError::DocsNotFound) => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        let files = ExampleFiles { crate_name, version, entries };
+        if files.version != "latest" {
+            self.store_json(key, &files).await?;
+        }
+        Ok(files)
+    }
+
+    /// Returns a file of a crate's `examples/` directory.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `path` - Path of the file, relative to the crate root or to `examples/`
+    #[tool(description = "Return the contents of a file from a crate's published examples/ directory, as listed by list_examples_files.")]
+    async fn fetch_example_file(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[schemars(description = "Path of the example file, e.g. 'examples/chat.rs'")]
+        path: String,
+    ) -> Result<SourceFile, DocsFetchError> {
+        let (crate_name, version) = self.resolve_crate_version(&crate_name, &version).await?;
+        let path = path.trim_start_matches('/');
+        let path = if path.starts_with("examples/") { path.to_string() } else { format!("examples/{}", path) };
+        let key = derived_cache_key(&crate_name, &version, &format!("example-file:{}", path));
+        if let Some(file) = self.cached_json(&key).await {
+            return Ok(file);
+        }
+
+        let file = self.docs_rs_client("Fetching example files")?.fetch_source_file(&crate_name, &version, &path).await?;
+        if version != "latest" {
+            self.store_json(key, &file).await?;
+        }
+        Ok(file)
+    }
+
+    /// Returns the associated types a trait declares, with their bounds.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `path` - Path to the trait page
+    #[tool(description = "Return the associated types declared by a trait, mapping each name to its full declaration including bounds and where clauses (e.g. 'type Item: Clone + Debug'). Use this for generic code that relies on associated type bounds.")]
+    async fn trait_associated_types(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[schemars(description = "Path to the trait page, e.g. 'std/iter/trait.Iterator.html'")]
+        path: String,
+    ) -> Result<AssociatedTypes, DocsFetchError> {
+        let (crate_name, version) = self.resolve_crate_version(&crate_name, &version).await?;
+        let path = path.trim_start_matches('/').to_string();
+        let key = derived_cache_key(&crate_name, &version, &format!("associated-types:{}", path));
+        if let Some(types) = self.cached_json(&key).await {
+            return Ok(types);
+        }
+
+        let client = self.client();
+        let params = DocsRsParams { crate_name, version, path };
+        let html = self.page_html(&params).await?;
+        let types = client.parse_associated_types(&html, &params.path);
+        if params.version != "latest" {
+            self.store_json(key, &types).await?;
+        }
+        Ok(types)
+    }
+
+    /// Returns the signature, documentation and kind of one method of a
+    /// trait, with the source of its default implementation if it has one.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `path` - Path to the trait page
+    /// * `method` - Name of the method
+    #[tool(description = "Return the details of one method of a trait: its signature, whether implementors must define it (required) or it has a default implementation (provided), its documentation, and for provided methods a link to the source of the default implementation, which fetch_document can open.")]
+    async fn trait_method_detail(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[schemars(description = "Path to the trait page, e.g. 'std/io/trait.Read.html'")]
+        path: String,
+
+        #[tool(param)]
+        #[schemars(description = "Name of the method, e.g. 'read_to_end'")]
+        method: String,
+    ) -> Result<TraitMethod, DocsFetchError> {
+        let (crate_name, version) = self.resolve_crate_version(&crate_name, &version).await?;
+        let path = path.trim_start_matches('/').to_string();
+        let method = method.trim().to_string();
+        let key = derived_cache_key(&crate_name, &version, &format!("trait-method:{}#{}", path, method));
+        if let Some(detail) = self.cached_json(&key).await {
+            return Ok(detail);
+        }
+
+        let client = self.client();
+        let params = DocsRsParams { crate_name, version, path };
+        let html = self.page_html(&params).await?;
+        let detail = client.parse_trait_method(&html, &params.path, &method)?;
+        if params.version != "latest" {
+            self.store_json(key, &detail).await?;
+        }
+        Ok(detail)
+    }
+
+    /// Returns the immediate sub-modules of a module with their summaries.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `path` - Path to a module page
+    #[tool(description = "List only the immediate sub-modules of a module page (e.g. 'tokio/index.html') with their paths and summaries. Lighter than crate_index; use it to drill down a crate step by step.")]
+    async fn list_submodules(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[schemars(description = "Path to a module page, e.g. 'tokio/index.html' or 'tokio/net/index.html'")]
+        path: String,
+    ) -> Result<Submodules, DocsFetchError> {
+        let (crate_name, version) = self.resolve_crate_version(&crate_name, &version).await?;
+        let path = path.trim_start_matches('/').to_string();
+        let key = derived_cache_key(&crate_name, &version, &format!("submodules:{}", path));
+        if let Some(submodules) = self.cached_json(&key).await {
+            return Ok(submodules);
+        }
+
+        let client = self.client();
+        let params = DocsRsParams { crate_name, version, path };
+        let html = self.page_html(&params).await?;
+        let submodules = client.parse_submodules(&html, &params.path)?;
+        if params.version != "latest" {
+            self.store_json(key, &submodules).await?;
+        }
+        Ok(submodules)
+    }
+
+    /// Returns the crate-level documentation of a crate as markdown.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    #[tool(description = "Get only the crate-level documentation of a crate (its //! docs at the top of the crate root page) as markdown, without the listing of its items. Use it to read a crate's own overview and guides.")]
+    async fn crate_docs(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+    ) -> Result<CrateDocs, DocsFetchError> {
+        let (crate_name, version) = self.resolve_crate_version(&crate_name, &version).await?;
+        let key = derived_cache_key(&crate_name, &version, "crate_docs");
+        if let Some(docs) = self.cached_json(&key).await {
+            return Ok(docs);
+        }
+
+        let client = self.client();
+        let path = format!("{}/index.html", crate_name.replace('-', "_"));
+        let params = DocsRsParams { crate_name, version, path };
+        let html = self.page_html(&params).await?;
+        let docs = CrateDocs {
+            markdown: client.parse_crate_docs(&html),
+            crate_name: params.crate_name,
+            version: params.version,
+        };
+        if docs.version != "latest" {
+            self.store_json(key, &docs).await?;
+        }
+        Ok(docs)
+    }
+
+    /// Returns the constants and statics of a module, or the associated
+    /// constants of a type or trait, with their types and values.
+    ///
+    /// Module pages do not show declarations, so the page of every listed
+    /// item is fetched as well; items whose page cannot be fetched are
+    /// returned without type and value.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    /// * `path` - Path to a module, type or trait page
+    #[tool(description = "List the public constants and statics of a module page (e.g. 'tokio/index.html'), or the associated constants of a type or trait page, with their types and values.")]
+    async fn list_constants(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+
+        #[tool(param)]
+        #[schemars(description = "Path to a module, type or trait page, e.g. 'std/f64/consts/index.html' or 'std/primitive.u32.html'")]
+        path: String,
+    ) -> Result<Constants, DocsFetchError> {
+        let (crate_name, version) = self.resolve_crate_version(&crate_name, &version).await?;
+        let path = path.trim_start_matches('/').to_string();
+        let key = derived_cache_key(&crate_name, &version, &format!("constants:{}", path));
+        if let Some(constants) = self.cached_json(&key).await {
+            return Ok(constants);
+        }
+
+        let client = self.client();
+        let params = DocsRsParams { crate_name, version, path };
+        let html = self.page_html(&params).await?;
+        let mut constants = client.parse_constants(&html, &params.path)?;
+
+        let pages: Vec<Option<DocsRsParams>> = constants.constants
+            .iter()
+            .map(|item| item.declaration.is_none().then(|| DocsRsParams { path: item.path.clone(), ..params.clone() }))
+            .collect();
+        let declarations: Vec<Option<String>> = futures::stream::iter(pages)
+            .map(|page| {
+                let client = client.clone();
+                async move {
+                    let page = page?;
+                    match self.page_html(&page).await {
+                        Ok(html) => client.parse_item_declaration(&html),
+                        Err(e) => {
+                            tracing::warn!("Failed to fetch declaration of {:?}: {}", page, e);
+                            None
+                        }
+                    }
+                }
+            })
+            .buffered(CONSTANT_PAGE_CONCURRENCY)
+            .collect()
+            .await;
+        for (item, declaration) in constants.constants.iter_mut().zip(declarations) {
+            if let Some(declaration) = declaration {
+                item.set_declaration(declaration);
+            }
+        }
+
+        if params.version != "latest" {
+            self.store_json(key, &constants).await?;
+        }
+        Ok(constants)
+    }
+
+    /// Re-reads the configuration file and applies its settings to the
+    /// running server without dropping the cache or client sessions.
+    ///
+    /// Only the settings of the configuration file are reloaded, see
+    /// [`RELOADABLE_SETTINGS`]; command line flags such as TTLs and fetch
+    /// limits keep their values until a restart.
+    ///
+    /// Requires the token configured with `--admin-token`; without one the
+    /// tool is disabled.
+    ///
+    /// # Arguments
+    /// * `admin_token` - Administrative token configured on the server
+    #[tool(description = "Administrative: re-read the server's configuration file and apply its default_paths, boilerplate and denied_crates settings without a restart. Other settings, such as TTLs and fetch limits, are not reloaded and are listed as requiring a restart. Requires the server's admin token.")]
+    async fn reload_config(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Administrative token configured on the server")]
+        admin_token: String,
+    ) -> Result<ConfigReload, String> {
+        let config = self.config();
+        match &config.admin_token {
+            None => return Err("Administrative tools are disabled on this server".to_string()),
+            Some(token) if !admin_token_matches(token, &admin_token) => return Err("Invalid admin token".to_string()),
+            Some(_) => {}
+        }
+        let Some(config_file) = config.config_file.clone() else {
+            return Err("The server was started without a configuration file".to_string());
+        };
+        let file_config = FileConfig::load(&config_file)
+            .map_err(|e| format!("Failed to load configuration file {:?}: {}", config_file, e))?;
+
+        let changed = {
+            let mut current = self.config.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let mut updated = FetcherConfig::clone(&current);
+            let changed = updated.apply_file_config(&file_config);
+            if changed.iter().any(|setting| setting == "boilerplate") {
+                *self.client.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Arc::new(build_client(&updated));
+            }
+            *current = Arc::new(updated);
+            changed
+        };
+        tracing::info!("Reloaded configuration from {:?}, changed: {:?}", config_file, changed);
+        Ok(ConfigReload {
+            config_file,
+            reloaded: RELOADABLE_SETTINGS.iter().map(|s| s.to_string()).collect(),
+            changed,
+            requires_restart: RESTART_ONLY_SETTINGS.iter().map(|s| s.to_string()).collect(),
+        })
+    }
+
+    /// Removes cached documentation, so that it is fetched again.
+    ///
+    /// Entries derived from pages, such as search indexes and bundles, are
+    /// removed along with them. A separate metadata cache is left alone, as
+    /// its entries expire on their own.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Crate whose entries to remove; all entries if not given
+    #[tool(description = "Remove cached documentation so that it is fetched again from docs.rs, e.g. after a crate version was re-published or its docs rebuilt. Clears only the entries of 'crate_name' if given, otherwise the whole cache. Returns the number of entries removed.")]
+    pub async fn clear_cache(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Crate whose cached documentation to remove. If not specified, the whole cache is cleared.")]
+        crate_name: Option<String>,
+    ) -> CacheClear {
+        let crate_name = match crate_name.as_deref().map(str::trim) {
+            Some(name) if !name.is_empty() => Some(self.known_crate_name(name).await),
+            _ => None,
+        };
+        let cleared = match &crate_name {
+            Some(crate_name) => self.cache.clear_crate(crate_name).await,
+            None => self.cache.clear().await,
+        };
+        match &crate_name {
+            Some(crate_name) => tracing::info!("Cleared {} cached entries of crate {}.", cleared, crate_name),
+            None => tracing::info!("Document cache cleared, {} entries removed.", cleared),
+        }
+        CacheClear { crate_name, cleared }
+    }
+
+    /// Reports the lookup, hit, miss and insert counters, the entry count
+    /// and the stored size of the caches.
+    #[tool(description = "Report the lookup, hit, miss and insert counters, the number of entries and the approximate stored size in bytes of the documentation cache and, if separate, the metadata cache. Use the hit ratio to judge how well the cache works.")]
+    async fn cache_stats(&self) -> CacheReport {
+        let metadata_cache = match &self.config().metadata_cache {
+            Some(metadata_cache) => Some(CacheUsage::of(metadata_cache.as_ref()).await),
+            None => None,
+        };
+        CacheReport { cache: CacheUsage::of(self.cache.as_ref()).await, metadata_cache }
+    }
+
+    /// Returns the categories and keywords a crate declares on crates.io.
+    ///
+    /// Results are kept in the metadata cache for the configured metadata
+    /// TTL, since tags can change with every release.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate
+    #[tool(description = "Return the categories and keywords a crate declares on crates.io. Use this to classify a crate or to find related crates.")]
+    async fn crate_tags(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate")]
+        crate_name: String,
+    ) -> Result<CrateTags, DocsFetchError> {
+        let crate_name = self.canonical_crate_name(&crate_name).await?;
+        let key = derived_cache_key(&crate_name, "latest", "tags");
+        if let Some(tags) = self.cached_metadata(&key).await {
+            return Ok(tags);
+        }
+
+        let tags = self.client().fetch_tags(&crate_name).await?;
+        self.store_metadata(key, &tags).await?;
+        Ok(tags)
+    }
+
+    /// Returns everything crates.io reports about a crate, read from a
+    /// single response of its crate endpoint.
+    ///
+    /// The crate name is confirmed by the same response instead of a lookup
+    /// of its own. Results are kept in the metadata cache for the configured
+    /// metadata TTL, and the tags and versions they hold are cached for
+    /// `crate_tags` and the version tools as well.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate
+    #[tool(description = "Return a crate's crates.io metadata in one call: description, license, homepage and repository links, downloads, categories, keywords, all published versions and a summary of who published them. Prefer this over several separate metadata calls when gathering an overview of a crate.")]
+    async fn crate_full_metadata(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate")]
+        crate_name: String,
+    ) -> Result<CrateFullMetadata, DocsFetchError> {
+        let normalized = self.allowed_crate_name(&crate_name)?;
+        self.config().crate_name_policy.check(&normalized)?;
+        let known = self.known_crate_name(&normalized).await;
+        let key = derived_cache_key(&known, "latest", "full-metadata");
+        if let Some(metadata) = self.cached_metadata(&key).await {
+            return Ok(metadata);
+        }
+
+        let metadata = self.client().fetch_full_metadata(&known).await?;
+        let crate_name = metadata.crate_name.clone();
+        self.remember_crate_name(&normalized, crate_name.clone()).await;
+        self.store_metadata(derived_cache_key(&crate_name, "latest", "full-metadata"), &metadata).await?;
+        self.store_metadata(derived_cache_key(&crate_name, "latest", "tags"), &metadata.tags()).await?;
+        self.store_metadata(derived_cache_key(&crate_name, "latest", "versions"), &metadata.versions).await?;
+        Ok(metadata)
+    }
+
+    /// Returns the description, license, links, downloads and features of a
+    /// crate version, read from a single response of the crates.io crate
+    /// endpoint.
+    ///
+    /// The crate name is confirmed by the same response instead of a lookup
+    /// of its own. Results are kept in the metadata cache for the configured
+    /// metadata TTL, keyed by crate and requested version.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate
+    /// * `version` - Version of the crate, the latest one if not given
+    #[tool(description = "Return a crate version's description, license, homepage and repository links, total downloads and feature flags from crates.io. Use this to decide whether a crate fits before reading its API documentation.")]
+    async fn get_crate_metadata(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of the crate, e.g. 1.0.0. The latest stable version if not specified")]
+        version: Option<String>,
+    ) -> Result<CrateVersionMetadata, DocsFetchError> {
+        let normalized = self.allowed_crate_name(&crate_name)?;
+        self.config().crate_name_policy.check(&normalized)?;
+        let known = self.known_crate_name(&normalized).await;
+        let version = version.map(|version| version.trim().to_string()).filter(|version| !is_latest(version));
+        let version_key = version.as_deref().unwrap_or(LATEST_VERSION);
+        let key = derived_cache_key(&known, version_key, "crate-metadata");
+        if let Some(metadata) = self.cached_metadata(&key).await {
+            return Ok(metadata);
+        }
+
+        let metadata = self.client().fetch_crate_metadata(&known, version.as_deref()).await?;
+        self.remember_crate_name(&normalized, metadata.crate_name.clone()).await;
+        self.store_metadata(derived_cache_key(&metadata.crate_name, version_key, "crate-metadata"), &metadata).await?;
+        Ok(metadata)
+    }
+
+    /// Returns the published versions of a crate matching a semver
+    /// requirement, e.g. `^1.2`, newest first.
+    ///
+    /// Prerelease versions only match requirements that name a prerelease,
+    /// following Cargo's rules, and yanked versions are left out. The list
+    /// of versions is kept in the metadata cache for the configured versions
+    /// TTL.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate
+    /// * `requirement` - Version requirement in Cargo syntax
+    ///
+    /// # Errors
+    ///
+    /// Fails with an invalid params error if `requirement` cannot be parsed.
+    #[tool(description = "List the published versions of a crate that satisfy a Cargo version requirement such as '^1.2', '~0.4' or '>=1, <3', newest first. Use this to pick a concrete version to fetch documentation for.")]
+    async fn matching_versions(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version requirement in Cargo syntax, e.g. '^1.2'")]
+        requirement: String,
+    ) -> Result<CallToolResult, McpError> {
+        let requirement = semver::VersionReq::parse(requirement.trim()).map_err(|e| {
+            McpError::invalid_params(format!("invalid version requirement '{}': {}", requirement, e), None)
+        })?;
+        Ok(match self.find_matching_versions(&crate_name, &requirement).await {
+            Ok(versions) => CallToolResult::success(versions.into_contents()),
+            Err(e) => CallToolResult::error(e.into_contents()),
+        })
+    }
+
+    /// Returns the published versions of a crate matching `requirement`,
+    /// newest first, as described for [`Self::matching_versions`].
+    pub async fn find_matching_versions(
+        &self,
+        crate_name: &str,
+        requirement: &semver::VersionReq,
+    ) -> Result<MatchingVersions, DocsFetchError> {
+        let crate_name = self.canonical_crate_name(crate_name).await?;
+        let published = self.published_versions(&crate_name).await?;
+
+        let mut versions: Vec<semver::Version> = published
+            .iter()
+            .filter(|version| !version.yanked)
+            .filter_map(|version| semver::Version::parse(&version.num).ok())
+            .filter(|version| requirement.matches(version))
+            .collect();
+        versions.sort_by(|a, b| b.cmp(a));
+        Ok(MatchingVersions {
+            crate_name,
+            requirement: requirement.to_string(),
+            versions: versions.iter().map(ToString::to_string).collect(),
+        })
+    }
+
+    /// Lists all published versions of a crate, newest first, flagging
+    /// yanked versions and the latest stable one.
+    ///
+    /// Versions are ordered by semver precedence; versions that are not
+    /// valid semver, which crates.io no longer accepts, come last.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate
+    #[tool(description = "List all published versions of a crate, newest first, flagging yanked versions and the latest stable version. Use this to pick a valid version before fetching documentation.")]
+    async fn list_versions(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate")]
+        crate_name: String,
+    ) -> Result<CrateVersions, DocsFetchError> {
+        let crate_name = self.canonical_crate_name(&crate_name).await?;
+        let published = self.published_versions(&crate_name).await?;
+
+        let mut parsed: Vec<(Option<semver::Version>, PublishedVersion)> = published
+            .into_iter()
+            .map(|version| (semver::Version::parse(&version.num).ok(), version))
+            .collect();
+        parsed.sort_by(|(a, _), (b, _)| b.cmp(a));
+        let latest_stable = parsed
+            .iter()
+            .find(|(semver, version)| !version.yanked && semver.as_ref().is_some_and(|v| v.pre.is_empty()))
+            .map(|(_, version)| version.num.clone());
+        let versions = parsed
+            .into_iter()
+            .map(|(_, version)| CrateVersion {
+                latest_stable: latest_stable.as_deref() == Some(version.num.as_str()),
+                version: version.num,
+                yanked: version.yanked,
+            })
+            .collect();
+        Ok(CrateVersions { crate_name, latest_stable, versions })
+    }
+
+    /// Returns the published versions of a crate as listed by crates.io,
+    /// kept in the metadata cache for the configured versions TTL.
+    async fn published_versions(&self, crate_name: &str) -> Result<Vec<PublishedVersion>, DocsFetchError> {
+        let key = derived_cache_key(crate_name, "latest", "versions");
+        if let Some(published) = self.cached_metadata_within(&key, self.config().versions_ttl).await {
+            return Ok(published);
+        }
+        let published = self.client().fetch_versions(crate_name).await?;
+        self.store_metadata(key, &published).await?;
+        Ok(published)
+    }
+
+    /// Describes which features and optional dependencies each feature of a
+    /// crate enables.
+    ///
+    /// The `[features]` table is read from crates.io. Results for concrete
+    /// versions are kept in the metadata cache for the configured metadata
+    /// TTL.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Name of the crate
+    /// * `version` - Version of the crate (e.g., "1.0.0" or "latest")
+    #[tool(description = "Return the feature dependency graph of a crate: for each feature, the features it enables directly and transitively, the optional dependencies it pulls in, and the dependency features it enables. Use this before enabling features to understand their interactions.")]
+    async fn feature_graph(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the crate")]
+        crate_name: String,
+
+        #[tool(param)]
+        #[schemars(description = "Version of crate, e.g. 1.0.0, or 'latest'")]
+        version: String,
+    ) -> Result<FeatureGraph, DocsFetchError> {
+        let (crate_name, version) = self.resolve_crate_version(&crate_name, &version).await?;
+        let key = derived_cache_key(&crate_name, &version, "features");
+        if let Some(features) = self.cached_metadata(&key).await {
+            return Ok(FeatureGraph::from_features(&crate_name, &version, &features));
+        }
+
+        let features = self.client().fetch_features(&crate_name, &version).await?;
+        if version != "latest" {
+            self.store_metadata(key, &features).await?;
+        }
+        Ok(FeatureGraph::from_features(&crate_name, &version, &features))
+    }
+
+    /// Returns the parsed search index of a crate, from the cache if possible.
+    ///
+    /// Callers resolve `latest` first; the index of an unresolved `latest`
+    /// is not cached, as it changes with every release.
+    async fn search_index(&self, crate_name: &str, version: &str) -> Result<SearchIndex, DocsFetchError> {
+        let key = derived_cache_key(crate_name, version, "search-index");
+        if let Some(index) = self.cached_json(&key).await {
+            return Ok(index);
+        }
+
+        tracing::info!("Cache miss for {:?}. Fetching...", key);
+        let index = self.docs_rs_client("Searching the crate index")?.fetch_search_index(crate_name, version).await?;
+        if !is_latest(version) {
+            self.store_json(key, &index).await?;
+        }
+        Ok(index)
+    }
+}
+
+/// Appended to the instructions while the cache cannot be saved.
+const EPHEMERAL_CACHE_WARNING: &str = " Warning: the documentation cache cannot be saved \
+    and only lives in memory; documents fetched now will be fetched again after a restart.";
+
+impl DocFetcher {
+    /// Appends a warning to `instructions` if the cache is in ephemeral mode.
+    fn with_cache_warning(&self, mut instructions: String) -> String {
+        if self.cache.is_ephemeral() {
+            instructions.push_str(EPHEMERAL_CACHE_WARNING);
+        }
+        instructions
+    }
+}
+
+#[tool(tool_box)]
+impl ServerHandler for DocFetcher {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            protocol_version: ProtocolVersion::default(),
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()  // We only need tools capability
+                .build(),
+            server_info: Implementation::from_build_env(),
+            instructions: Some(self.with_cache_warning(match &self.config().instructions {
+                Some(instructions) => instructions.clone(),
+                None => "This server provides access to Rust documentation from docs.rs. \
+                Use the 'fetch_document' tool to retrieve documentation for any crate. \
+                Specify the crate name, version, and path to the documentation page you want to fetch. \
+                Set 'estimate_tokens' to also get the approximate token count of the returned content. \
+                Use the 'fetch_sections' tool to get a page split into named sections. \
+                Use the 'fetch_crate_paths' tool to fetch several pages of one crate at once. \
+                Use the 'fetch_crate_bundle' tool to get the documentation of a whole crate as one document. \
+                Use the 'crate_index' tool to list a crate's items and their paths. \
+                Use the 'search_crate' tool to find the documentation path of an item by searching a crate. \
+                Use the 'list_crate_items' tool to list only the items of one kind, e.g. the traits of a crate. \
+                Use the 'resolve_path' tool to find the documentation path of an item you only know by name. \
+                Use the 'breadcrumb' tool to find the modules enclosing an item. \
+                Use the 'list_submodules' tool to list only the sub-modules of a module. \
+                Use the 'crate_docs' tool to read only a crate's own crate-level documentation as markdown. \
+                Use the 'list_constants' tool to see the constants and statics of a module or type with their values. \
+                Use the 'fetch_item_source' tool to read the source code of a single item. \
+                Use the 'fetch_source' tool to read a whole source file of a crate. \
+                Use the 'cross_crate_diff' tool to compare the documentation of similar items of two crates. \
+                Use the 'build_status' tool to find out why the documentation of a crate version is missing. \
+                Use the 'list_versions' tool to list all published versions of a crate, flagging yanked ones and the latest stable one. \
+                Use the 'matching_versions' tool to find the published versions satisfying a version requirement such as '^1.2'. \
+                Use the 'validate_example' tool to check that an example from the docs is syntactically valid before relying on it. \
+                Use the 'list_examples_files' and 'fetch_example_file' tools to read a crate's examples/ directory. \
+                Use the 'crate_tags' tool to get a crate's crates.io categories and keywords. \
+                Use the 'crate_full_metadata' tool to get a crate's description, license, links, downloads, tags, versions and publishers in one call. \
+                Use the 'get_crate_metadata' tool to get the description, license, links and feature flags of one version of a crate. \
+                Use the 'feature_graph' tool to see what each feature of a crate enables. \
+                Use the 'implementors' tool to find the types implementing a trait. \
+                Use the 'trait_associated_types' tool to see the bounds of a trait's associated types. \
+                Use the 'auto_traits' tool to check whether a type is Send, Sync or Unpin. \
+                Use the 'inherent_impls' tool to see a type's methods grouped by impl block with the bounds under which they are available. \
+                Use the 'blanket_impls' tool to list the blanket implementations that apply to a type. \
+                Use the 'construction_hint' tool to find the constructors, builders and From impls of a type. \
+                Use the 'fetch_with_context' tool to read a page together with the summaries of the items it links to. \
+                Use the 'trait_method_detail' tool to see whether a trait method is required or provided, with its docs and default implementation. \
+                Use the 'clear_cache' tool to drop stale cached documentation, of one crate or of all crates. \
+                Use the 'cache_stats' tool to see how often the cache answered requests and how much it stores. \
+                Use the 'fetch_type_alias' tool to see what a type alias expands to. \
+                Results are cached for better performance.".to_string(),
+            })),
+        }
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: PaginatedRequestParam,
+        _: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, McpError> {
+        // We don't use prompts in this implementation
+        Ok(ListPromptsResult {
+            next_cursor: None,
+            prompts: vec![],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docs_parser::{BuildState, Publisher};
+    use crate::search_index::{IndexItem, ItemKind};
+    use rmcp::model::{ClientCapabilities, ClientInfo, Implementation};
+    use rmcp::{ServiceExt, model::CallToolRequestParam, transport::SseTransport};
+    use rmcp::transport::sse_server::SseServer;
+    use tempfile::tempdir;
+    use std::time::Instant;
+    use std::fs;
+
+    /// Builds a fetcher with `config` reading documentation from the mocked
+    /// `docs` server and crate names from the mocked `registry`, which
+    /// confirms `crate_name` as is.
+    fn mocked_fetcher(
+        crate_name: &str,
+        docs: &mockito::ServerGuard,
+        registry: &mut mockito::ServerGuard,
+        config: FetcherConfig,
+    ) -> DocFetcher {
+        registry.mock("GET", format!("/api/v1/crates/{}", crate_name).as_str())
+            .with_status(200)
+            .with_body(format!(r#"{{"crate":{{"name":"{}"}}}}"#, crate_name))
+            .create();
+        fetcher_against(docs, registry, config)
+    }
+
+    /// Builds a fetcher with `config` and an empty cache, reading from the
+    /// mocked `docs` and `registry` servers.
+    fn fetcher_against(docs: &mockito::ServerGuard, registry: &mockito::ServerGuard, config: FetcherConfig) -> DocFetcher {
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let config = FetcherConfig {
+            docs_base_url: docs.url(),
+            crates_io_base_url: registry.url(),
+            ..config
+        };
+        DocFetcher::with_config(cache, config)
+    }
+
+    /// Builds a fetcher for the `demo` crate like [`mocked_fetcher`], with
+    /// the default settings.
+    fn demo_fetcher(docs: &mockito::ServerGuard, registry: &mut mockito::ServerGuard) -> DocFetcher {
+        mocked_fetcher("demo", docs, registry, FetcherConfig::default())
+    }
+
+    fn setup_test_fetcher() -> (DocFetcher, Arc<InMemoryCache>) {
+        let temp_dir = tempdir().unwrap();
+        let cache_dir = temp_dir.path().to_path_buf();
+        fs::create_dir_all(&cache_dir).unwrap(); 
+
+        let cache = Arc::new(InMemoryCache::new(cache_dir));
+        let fetcher = DocFetcher::new(cache.clone());
+        (fetcher, cache)
+    }
+
+    #[tokio::test]
+    async fn test_fetch_document() {
+        let (doc_fetcher, _cache) = setup_test_fetcher();
+        let result = doc_fetcher.fetch_document(
+            "rand".to_string(),
+            "0.9.0".to_string(),
+            "rand/trait.Rng.html".to_string(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        ).await.unwrap();
+
+        assert!(!result.content.is_empty());
+        assert!(result.content.contains("User-level interface for RNGs"));
+    }
+    
+    #[tokio::test]
+    async fn test_sse_server() {
+        let addr = "127.0.0.1:8081"; // Let OS choose port
+        let temp_cache_dir = tempdir().unwrap().path().to_path_buf();
+        let server_cache = Arc::new(InMemoryCache::new(temp_cache_dir));
+
+        let service_cache_clone = server_cache.clone();
+        let server = SseServer::serve(addr.parse().unwrap()).await.unwrap();
+        let port = server.config.bind.port();
+        let ct = server.with_service(move || DocFetcher::new(service_cache_clone.clone()));
+        
+        let addr = format!("http://127.0.0.1:{}/sse", port);
+        let transport = SseTransport::start(&addr).await.unwrap();
+
+        let client_info = ClientInfo {
+            protocol_version: Default::default(),
+            capabilities: ClientCapabilities::default(),
+            client_info: Implementation {
+                name: "test sse client".to_string(),
+                version: "0.0.1".to_string(),
+            },
+        };
+        let client = client_info.serve(transport).await.unwrap();
+
+        let result = client.call_tool(CallToolRequestParam {
+            name: "fetch_document".into(),
+            arguments: serde_json::json!({
+                "crate_name": "rand",
+                "version": "0.9.0",
+                "path": "rand/trait.Rng.html",
+            }).as_object().cloned(),
+        }).await.unwrap();
+
+        ct.cancel();
+
+        assert!(!result.content.is_empty());
+        assert!(result.content.iter().any(|c| c.as_text().unwrap().text.contains("User-level interface for RNGs")));
+    }
+
+    #[tokio::test]
+    async fn test_crate_index_uses_cached_search_index() {
+        let (doc_fetcher, cache) = setup_test_fetcher();
+        let index = SearchIndex {
+            items: vec![IndexItem {
+                name: "Widget".to_string(),
+                kind: ItemKind::Struct,
+                path: "demo::Widget".to_string(),
+                doc_path: "demo/struct.Widget.html".to_string(),
+            }],
+        };
+        cache.insert(
+            derived_cache_key("demo", "1.0.0", "search-index"),
+            DocContent { content: serde_json::to_string(&index).unwrap(), source_url: None },
+        ).await;
+
+        let result = doc_fetcher.crate_index("demo".to_string(), "1.0.0".to_string()).await.unwrap();
+        assert_eq!(result.structs, index.items);
+        assert!(result.traits.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_crate_index_resolves_latest_before_caching() {
+        const INDEX_PAGE: &str = r#"<html><body><div id="rustdoc-vars" data-root-path="../" data-search-index-js="../search-index.js"></div></body></html>"#;
+        const INDEX: &str = r#"var searchIndex = new Map(JSON.parse('[["demo",{"t":"F","n":["build"],"q":[[0,"demo"]]}]]'));"#;
+        let mut docs = mockito::Server::new_async().await;
+        let concrete = docs.mock("GET", "/demo/1.2.0/search-index.js").with_status(200).with_body(INDEX).expect(1).create();
+        docs.mock("GET", "/demo/1.2.0/demo/index.html").with_status(200).with_body(INDEX_PAGE).create();
+        let mut registry = mockito::Server::new_async().await;
+        registry.mock("GET", "/api/v1/crates/demo")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"demo","max_stable_version":"1.2.0"}}"#)
+            .create();
+        let fetcher = fetcher_against(&docs, &registry, FetcherConfig::default());
+
+        for _ in 0..2 {
+            let index = fetcher.crate_index("demo".to_string(), "latest".to_string()).await.unwrap();
+            assert_eq!(index.version, "1.2.0");
+        }
+        concrete.assert();
+        assert!(fetcher.cache.contains_key(&derived_cache_key("demo", "1.2.0", "search-index")).await);
+
+        // An unresolved `latest` is fetched again rather than cached
+        let mut docs = mockito::Server::new_async().await;
+        let latest = docs.mock("GET", "/demo/latest/search-index.js").with_status(200).with_body(INDEX).expect(2).create();
+        docs.mock("GET", "/demo/latest/demo/index.html").with_status(200).with_body(INDEX_PAGE).create();
+        let mut registry = mockito::Server::new_async().await;
+        registry.mock("GET", "/api/v1/crates/demo").with_status(500).create();
+        let fetcher = fetcher_against(&docs, &registry, FetcherConfig::default());
+        for _ in 0..2 {
+            let candidates = fetcher.resolve_path("demo".to_string(), "latest".to_string(), "build".to_string()).await.unwrap();
+            assert_eq!(candidates.version, "latest");
+        }
+        latest.assert();
+        assert!(!fetcher.cache.contains_key(&derived_cache_key("demo", "latest", "search-index")).await);
+    }
+
+    #[tokio::test]
+    async fn test_page_tools_go_through_shared_fetch_path() {
+        let mut docs = mockito::Server::new_async().await;
+        let page = docs.mock("GET", "/demo/1.2.0/demo/trait.Handler.html")
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper"><h2 id="implementors">Implementors</h2></div>"#)
+            .expect(1)
+            .create();
+        let outage = docs.mock("GET", "/demo/1.2.0/demo/struct.Server.html")
+            .with_status(503)
+            .expect(1)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+        registry.mock("GET", "/api/v1/crates/demo")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"demo","max_stable_version":"1.2.0"}}"#)
+            .create();
+        let fetcher = fetcher_against(&docs, &registry, FetcherConfig {
+            circuit_breaker: Arc::new(CircuitBreaker::new(1, std::time::Duration::from_secs(60))),
+            ..FetcherConfig::default()
+        });
+
+        // `latest` is resolved and the parsed result cached for the version
+        for _ in 0..2 {
+            fetcher.implementors("Demo".to_string(), "latest".to_string(), "demo/trait.Handler.html".to_string()).await.unwrap();
+        }
+        page.assert();
+        assert!(fetcher.cache.contains_key(&derived_cache_key("demo", "1.2.0", "implementors:demo/trait.Handler.html")).await);
+
+        // The failing page opens the breaker, so the second call is not sent upstream
+        let path = "demo/struct.Server.html";
+        assert!(fetcher.auto_traits("demo".to_string(), "1.2.0".to_string(), path.to_string()).await.is_err());
+        let blocked = fetcher.inherent_impls("demo".to_string(), "1.2.0".to_string(), path.to_string()).await;
+        assert!(matches!(blocked, Err(DocsFetchError::ServiceUnavailable)), "got {:?}", blocked);
+        outage.assert();
+    }
+
+    #[tokio::test]
+    async fn test_search_crate_caches_results_per_query() {
+        let (doc_fetcher, cache) = setup_test_fetcher();
+        let index = SearchIndex {
+            items: vec![
+                IndexItem {
+                    name: "MutexGuard".to_string(),
+                    kind: ItemKind::Struct,
+                    path: "demo::sync::MutexGuard".to_string(),
+                    doc_path: "demo/sync/struct.MutexGuard.html".to_string(),
+                },
+                IndexItem {
+                    name: "Mutex".to_string(),
+                    kind: ItemKind::Struct,
+                    path: "demo::sync::Mutex".to_string(),
+                    doc_path: "demo/sync/struct.Mutex.html".to_string(),
+                },
+            ],
+        };
+        let index_key = derived_cache_key("demo", "1.0.0", "search-index");
+        cache.insert(index_key.clone(), DocContent { content: serde_json::to_string(&index).unwrap(), source_url: None }).await;
+
+        let results = doc_fetcher.search_crate("demo".to_string(), "1.0.0".to_string(), "mutex".to_string()).await.unwrap();
+        let doc_paths: Vec<&str> = results.items.iter().map(|item| item.doc_path.as_str()).collect();
+        assert_eq!(doc_paths, ["demo/sync/struct.Mutex.html", "demo/sync/struct.MutexGuard.html"]);
+        assert!(!results.truncated);
+
+        // The results are served from the cache even without the index
+        let results_key = derived_cache_key("demo", "1.0.0", "search:mutex");
+        let stored = cache.get(&results_key).await.expect("search results not cached");
+        cache.clear().await;
+        cache.insert(results_key, stored).await;
+        let cached = doc_fetcher.search_crate("demo".to_string(), "1.0.0".to_string(), " Mutex ".to_string()).await.unwrap();
+        assert_eq!(cached.items, results.items);
+    }
+
+    #[tokio::test]
+    async fn test_list_crate_items_filters_by_kind() {
+        let (doc_fetcher, cache) = setup_test_fetcher();
+        let item = |name: &str, kind: ItemKind, path: &str| IndexItem {
+            name: name.to_string(),
+            kind,
+            path: path.to_string(),
+            doc_path: String::new(),
+        };
+        let index = SearchIndex {
+            items: vec![
+                item("Widget", ItemKind::Struct, "demo::Widget"),
+                item("Render", ItemKind::Trait, "demo::Render"),
+                item("build", ItemKind::Function, "demo::build"),
+                item("Layout", ItemKind::Trait, "demo::layout::Layout"),
+            ],
+        };
+        cache.insert(
+            derived_cache_key("demo", "1.0.0", "search-index"),
+            DocContent { content: serde_json::to_string(&index).unwrap(), source_url: None },
+        ).await;
+
+        let result = doc_fetcher.list_crate_items("demo".to_string(), "1.0.0".to_string(), "trait".to_string()).await.unwrap();
+        let text = result.content[0].as_text().unwrap().text.clone();
+        let items: CrateItems = serde_json::from_str(&text).unwrap();
+        assert_eq!(items.kind, ItemKind::Trait);
+        assert_eq!(items.items, [index.items[1].clone(), index.items[3].clone()]);
+
+        let invalid = doc_fetcher.list_crate_items("demo".to_string(), "1.0.0".to_string(), "method".to_string()).await;
+        assert_eq!(invalid.unwrap_err().code, rmcp::model::ErrorCode::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_path_lists_candidates() {
+        let (doc_fetcher, cache) = setup_test_fetcher();
+        let item = |name: &str, path: &str, doc_path: &str| IndexItem {
+            name: name.to_string(),
+            kind: ItemKind::Struct,
+            path: path.to_string(),
+            doc_path: doc_path.to_string(),
+        };
+        let index = SearchIndex {
+            items: vec![
+                item("Error", "demo::io::Error", "demo/io/struct.Error.html"),
+                item("Widget", "demo::Widget", "demo/struct.Widget.html"),
+                item("Error", "demo::Error", "demo/struct.Error.html"),
+            ],
+        };
+        cache.insert(
+            derived_cache_key("demo", "1.0.0", "search-index"),
+            DocContent { content: serde_json::to_string(&index).unwrap(), source_url: None },
+        ).await;
+
+        let result = doc_fetcher.resolve_path("demo".to_string(), "1.0.0".to_string(), "Error".to_string()).await.unwrap();
+        let doc_paths: Vec<&str> = result.candidates.iter().map(|c| c.doc_path.as_str()).collect();
+        assert_eq!(doc_paths, ["demo/struct.Error.html", "demo/io/struct.Error.html"]);
+
+        let result = doc_fetcher.resolve_path("demo".to_string(), "1.0.0".to_string(), "widget".to_string()).await.unwrap();
+        assert_eq!(result.candidates, [index.items[1].clone()]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_webhook_notified_on_cache_miss() {
+        let mut docs = mockito::Server::new_async().await;
+        docs.mock("GET", "/demo/1.0.0/demo/index.html")
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper">Demo docs</div>"#)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+        registry.mock("GET", "/api/v1/crates/demo")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"demo"}}"#)
+            .create();
+        let mut receiver = mockito::Server::new_async().await;
+        let hook = receiver.mock("POST", "/hook")
+            .match_body(mockito::Matcher::Json(serde_json::json!({
+                "crate_name": "demo",
+                "version": "1.0.0",
+                "path": "demo/index.html",
+                "content_hash": content_hash("Demo docs"),
+                "content_bytes": 9,
+            })))
+            .with_status(204)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let config = FetcherConfig {
+            docs_base_url: docs.url(),
+            crates_io_base_url: registry.url(),
+            fetch_webhook: Some(Arc::new(FetchWebhook::new(format!("{}/hook", receiver.url())))),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache, config);
+        let params = DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            path: "demo/index.html".to_string(),
+        };
+
+        // The second fetch is a cache hit and sends no notification
+        assert_eq!(fetcher.fetch(params.clone()).await.unwrap().content, "Demo docs");
+        assert_eq!(fetcher.fetch(params).await.unwrap().content, "Demo docs");
+
+        let started = std::time::Instant::now();
+        while !hook.matched_async().await && started.elapsed() < std::time::Duration::from_secs(5) {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        hook.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_latest_links_pinned_to_fetched_version() {
+        let mut docs = mockito::Server::new_async().await;
+        docs.mock("GET", "/demo/1.4.2/demo/index.html")
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper"><p>See https://docs.rs/demo/latest/demo/struct.Config.html,
+                the sources at https://docs.rs/crate/demo/latest/source/ and https://docs.rs/serde/latest/serde/.</p></div>"#)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+        registry.mock("GET", "/api/v1/crates/demo")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"demo","max_stable_version":"1.4.2","newest_version":"1.5.0-rc.1"}}"#)
+            .create();
+
+        let fetcher = fetcher_against(&docs, &registry, FetcherConfig::default());
+        let params = DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: "latest".to_string(),
+            path: "demo/index.html".to_string(),
+        };
+
+        let content = fetcher.fetch(params).await.unwrap().content;
+        assert!(content.contains("https://docs.rs/demo/1.4.2/demo/struct.Config.html"), "got {}", content);
+        assert!(content.contains("https://docs.rs/crate/demo/1.4.2/source/"));
+        assert!(content.contains("https://docs.rs/serde/latest/serde/"));
+        assert!(!content.contains("docs.rs/demo/latest"));
+    }
+
+    #[tokio::test]
+    async fn test_latest_shares_cache_entries_with_resolved_version() {
+        let mut docs = mockito::Server::new_async().await;
+        let redirect = docs.mock("GET", "/demo/latest/")
+            .with_status(302)
+            .with_header("location", "/demo/1.2.3/demo/")
+            .expect(1)
+            .create();
+        let page = docs.mock("GET", "/demo/1.2.3/demo/index.html")
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper">Demo 1.2.3</div>"#)
+            .expect(1)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+        registry.mock("GET", "/api/v1/crates/demo")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"demo"}}"#)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let config = FetcherConfig {
+            docs_base_url: docs.url(),
+            crates_io_base_url: registry.url(),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache.clone(), config);
+        let params = |version: &str| DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: version.to_string(),
+            path: "demo/index.html".to_string(),
+        };
+
+        for version in ["latest", "", "1.2.3"] {
+            assert_eq!(fetcher.fetch(params(version)).await.unwrap().content, "Demo 1.2.3");
+        }
+        redirect.assert();
+        page.assert();
+        assert!(cache.contains_key(&CacheKey::from(&params("1.2.3"))).await);
+        assert!(!cache.contains_key(&CacheKey::from(&params("latest"))).await);
+    }
+
+    #[tokio::test]
+    async fn test_failed_latest_resolution_is_remembered() {
+        let mut docs = mockito::Server::new_async().await;
+        // No redirect to a concrete version, and crates.io fails
+        let redirect = docs.mock("GET", "/demo/latest/")
+            .with_status(200)
+            .expect(1)
+            .create();
+        let pages = docs.mock("GET", mockito::Matcher::Regex(r"^/demo/latest/demo/.+\.html$".to_string()))
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper">Demo latest</div>"#)
+            .expect(2)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+        registry.mock("GET", "/api/v1/crates/demo").with_status(500).create();
+
+        let fetcher = fetcher_against(&docs, &registry, FetcherConfig::default());
+        for path in ["demo/index.html", "demo/struct.Other.html"] {
+            let params = DocsRsParams { crate_name: "demo".to_string(), version: "latest".to_string(), path: path.to_string() };
+            assert_eq!(fetcher.fetch(params).await.unwrap().content, "Demo latest");
+        }
+        redirect.assert();
+        pages.assert();
+    }
+
+    #[tokio::test]
+    async fn test_local_source_serves_page_tools_without_network() {
+        let mut docs = mockito::Server::new_async().await;
+        let remote_docs = docs.mock("GET", mockito::Matcher::Any).expect(0).create();
+        let mut registry = mockito::Server::new_async().await;
+        let remote_registry = registry.mock("GET", mockito::Matcher::Any).expect(0).create();
+
+        let doc_root = tempdir().unwrap();
+        std::fs::create_dir_all(doc_root.path().join("demo")).unwrap();
+        std::fs::write(
+            doc_root.path().join("demo/struct.Widget.html"),
+            r#"<html><body><section id="main-content"><h1>Struct Widget</h1>
+            <details class="toggle top-doc" open><div class="docblock"><p>A widget.</p></div></details>
+            </section></body></html>"#,
+        ).unwrap();
+        let fetcher = fetcher_against(&docs, &registry, FetcherConfig {
+            doc_root: Some(doc_root.path().to_path_buf()),
+            ..FetcherConfig::default()
+        });
+
+        let page = fetcher.fetch_sections("demo".to_string(), "latest".to_string(), "demo/struct.Widget.html".to_string()).await.unwrap();
+        assert!(page.sections.iter().any(|section| section.content.contains("A widget.")), "{:?}", page);
+        let params = DocsRsParams { crate_name: "demo".to_string(), version: "latest".to_string(), path: "demo/struct.Widget.html".to_string() };
+        assert!(fetcher.fetch(params).await.unwrap().content.contains("A widget."));
+
+        let status = fetcher.build_status("demo".to_string(), "1.0.0".to_string()).await;
+        assert!(matches!(status, Err(DocsFetchError::UnsupportedLocally(_))), "got {:?}", status);
+        let index = fetcher.crate_index("demo".to_string(), "latest".to_string()).await;
+        assert!(matches!(index, Err(DocsFetchError::UnsupportedLocally(_))), "got {:?}", index);
+        remote_docs.assert();
+        remote_registry.assert();
+    }
+
+    #[tokio::test]
+    async fn test_cross_crate_diff() {
+        let mut docs = mockito::Server::new_async().await;
+        docs.mock("GET", "/alpha/1.0.0/alpha/struct.Client.html")
+            .with_status(200)
+            .with_body("<div id=\"rustdoc_body_wrapper\"><p>An HTTP client.</p>\n<p>Sends requests.</p>\n<p>Supports cookies.</p></div>")
+            .create();
+        docs.mock("GET", "/beta/2.0.0/beta/struct.Client.html")
+            .with_status(200)
+            .with_body("<div id=\"rustdoc_body_wrapper\"><p>An HTTP client.</p>\n<p>Sends requests.</p>\n<p>Supports HTTP/2.</p></div>")
+            .create();
+        docs.mock("GET", "/beta/2.0.0/beta/struct.Missing.html")
+            .with_status(404)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+        for name in ["alpha", "beta"] {
+            registry.mock("GET", format!("/api/v1/crates/{}", name).as_str())
+                .with_status(200)
+                .with_body(format!(r#"{{"crate":{{"name":"{}"}}}}"#, name))
+                .create();
+        }
+
+        let fetcher = fetcher_against(&docs, &registry, FetcherConfig::default());
+        let item = |crate_name: &str, version: &str, path: &str| DocsRsParams {
+            crate_name: crate_name.to_string(),
+            version: version.to_string(),
+            path: path.to_string(),
+        };
+
+        let diff = fetcher.cross_crate_diff(
+            item("alpha", "1.0.0", "alpha/struct.Client.html"),
+            item("beta", "2.0.0", "beta/struct.Client.html"),
+            None,
+        ).await.unwrap();
+        assert!(!diff.identical && !diff.left_missing && !diff.right_missing);
+        assert!(diff.diff.starts_with("--- alpha 1.0.0 alpha/struct.Client.html\n+++ beta 2.0.0 beta/struct.Client.html\n"));
+        let has_line = |diff: &str, marker: char, text: &str| {
+            diff.lines().skip(2).any(|line| line.strip_prefix(marker).is_some_and(|line| line.trim() == text))
+        };
+        assert!(has_line(&diff.diff, '-', "Supports cookies."));
+        assert!(has_line(&diff.diff, '+', "Supports HTTP/2."));
+        assert!(has_line(&diff.diff, ' ', "Sends requests."));
+
+        let missing = fetcher.cross_crate_diff(
+            item("alpha", "1.0.0", "alpha/struct.Client.html"),
+            item("beta", "2.0.0", "beta/struct.Missing.html"),
+            None,
+        ).await.unwrap();
+        assert!(missing.right_missing && !missing.left_missing);
+        assert!(has_line(&missing.diff, '-', "An HTTP client."));
+    }
+
+    #[tokio::test]
+    async fn test_build_status_of_failed_build() {
+        let mut docs = mockito::Server::new_async().await;
+        docs.mock("GET", "/crate/demo/1.0.0/builds.json")
+            .with_status(200)
+            .with_body(r#"[
+                {"id":42,"rustc_version":"rustc 1.80.0-nightly (2024-05-01)","docsrs_version":"docsrs 0.6.0","build_status":"failure","build_time":"2024-05-02T10:00:00Z","errors":"error[E0433]: failed to resolve: use of undeclared crate `sys`\nerror: could not document `demo`"},
+                {"id":41,"rustc_version":"rustc 1.79.0-nightly","build_status":"success","build_time":"2024-04-01T10:00:00Z"}
+            ]"#)
+            .create();
+        docs.mock("GET", "/crate/demo/9.9.9/builds.json")
+            .with_status(404)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+
+        let fetcher = demo_fetcher(&docs, &mut registry);
+
+        let status = fetcher.build_status("demo".to_string(), "1.0.0".to_string()).await.unwrap();
+        assert_eq!(status.status, BuildState::Failed);
+        assert_eq!(status.build_id, Some(42));
+        assert_eq!(status.rustc_version.as_deref(), Some("rustc 1.80.0-nightly (2024-05-01)"));
+        assert_eq!(status.log_url, Some(format!("{}/crate/demo/1.0.0/builds/42", docs.url())));
+        assert!(status.log_excerpt.unwrap().ends_with("error: could not document `demo`"));
+
+        let missing = fetcher.build_status("demo".to_string(), "9.9.9".to_string()).await;
+        assert!(matches!(missing, Err(DocsFetchError::DocsNotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_matching_versions() {
+        let mut registry = mockito::Server::new_async().await;
+        registry.mock("GET", "/api/v1/crates/demo")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"demo"}}"#)
+            .create();
+        registry.mock("GET", "/api/v1/crates/demo/versions")
+            .with_status(200)
+            .with_body(r#"{"versions":[
+                {"num":"2.0.0","yanked":false},
+                {"num":"2.0.0-rc.1","yanked":false},
+                {"num":"1.3.0-beta.2","yanked":false},
+                {"num":"1.2.1","yanked":true},
+                {"num":"1.10.0","yanked":false},
+                {"num":"1.2.0","yanked":false},
+                {"num":"1.1.9","yanked":false}
+            ]}"#)
+            .expect(1)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let config = FetcherConfig {
+            crates_io_base_url: registry.url(),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache, config);
+        let matching = |req: &str| {
+            let fetcher = fetcher.clone();
+            let req = semver::VersionReq::parse(req).unwrap();
+            async move { fetcher.find_matching_versions("demo", &req).await.unwrap().versions }
+        };
+
+        // Prereleases and yanked versions are left out, newest first
+        assert_eq!(matching("^1.2").await, vec!["1.10.0", "1.2.0"]);
+        assert_eq!(matching("=1.1.9").await, vec!["1.1.9"]);
+        // A prerelease requirement matches prereleases of the same version
+        assert_eq!(matching(">=2.0.0-rc.1").await, vec!["2.0.0", "2.0.0-rc.1"]);
+        assert_eq!(matching("^3").await, Vec::<String>::new());
+
+        let invalid = fetcher.matching_versions("demo".to_string(), "not a version".to_string()).await;
+        assert_eq!(invalid.unwrap_err().code, rmcp::model::ErrorCode::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_list_versions_flags_latest_stable_and_yanked() {
+        let mut registry = mockito::Server::new_async().await;
+        registry.mock("GET", "/api/v1/crates/demo")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"demo"}}"#)
+            .create();
+        let listing = registry.mock("GET", "/api/v1/crates/demo/versions")
+            .with_status(200)
+            .with_body(r#"{"versions":[
+                {"num":"1.3.0","yanked":true},
+                {"num":"2.0.0-rc.1","yanked":false},
+                {"num":"1.10.0","yanked":false},
+                {"num":"1.2.0","yanked":false}
+            ]}"#)
+            .expect(2)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let config = FetcherConfig {
+            crates_io_base_url: registry.url(),
+            versions_ttl: std::time::Duration::ZERO,
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache, config);
+
+        let versions = fetcher.list_versions("demo".to_string()).await.unwrap();
+        assert_eq!(versions.latest_stable.as_deref(), Some("1.10.0"));
+        let listed: Vec<_> = versions.versions.iter()
+            .map(|v| (v.version.as_str(), v.yanked, v.latest_stable))
+            .collect();
+        assert_eq!(listed, vec![
+            ("2.0.0-rc.1", false, false),
+            ("1.10.0", false, true),
+            ("1.3.0", true, false),
+            ("1.2.0", false, false),
+        ]);
+
+        // With an expired list, new releases are picked up
+        fetcher.list_versions("demo".to_string()).await.unwrap();
+        listing.assert();
+    }
+
+    #[tokio::test]
+    async fn test_validate_example() {
+        let mut docs = mockito::Server::new_async().await;
+        docs.mock("GET", "/demo/1.0.0/demo/index.html")
+            .with_status(200)
+            .with_body(r##"<section id="main-content"><details class="toggle top-doc"><div class="docblock">
+                <p>Usage:</p>
+                <div class="example-wrap"><pre class="rust rust-example-rendered"><code><span class="kw">let </span>v = demo::parse(<span class="string">"1"</span>)<span class="question-mark">?</span>;
+<span class="macro">assert_eq!</span>(v, <span class="number">1</span>);</code></pre></div>
+                <div class="example-wrap"><pre class="language-text"><code>not rust</code></pre></div>
+                <div class="example-wrap"><pre class="rust rust-example-rendered"><code><span class="kw">let </span>v = demo::parse(<span class="string">"1"</span>)<span class="question-mark">?</span>;
+<span class="kw">let </span>= v + <span class="number">1</span>;</code></pre></div>
+                </div></details></section>"##)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+
+        let fetcher = demo_fetcher(&docs, &mut registry);
+
+        let valid = fetcher.validate_example("demo".to_string(), "1.0.0".to_string(), String::new(), None).await.unwrap();
+        assert_eq!(valid.path, "demo/index.html");
+        assert_eq!(valid.total_examples, 2);
+        assert!(valid.code.starts_with("let v = demo::parse(\"1\")?;"));
+        assert!(valid.valid);
+        assert_eq!(valid.error, None);
+
+        let malformed = fetcher.validate_example("demo".to_string(), "1.0.0".to_string(), String::new(), Some(1)).await.unwrap();
+        assert!(!malformed.valid);
+        assert_eq!(malformed.error.unwrap().line, 2);
+
+        let missing = fetcher.validate_example("demo".to_string(), "1.0.0".to_string(), String::new(), Some(2)).await;
+        assert!(matches!(missing, Err(DocsFetchError::ParseError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_document_if_none_match() {
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let fetcher = DocFetcher::with_config(cache.clone(), FetcherConfig::default());
+        let params = DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            path: "demo/index.html".to_string(),
+        };
+        cache.insert(CacheKey::from(&params), DocContent { content: "Demo docs".to_string(), source_url: None }).await;
+        let fetch = |if_none_match: Option<String>| {
+            fetcher.fetch_document(params.crate_name.clone(), params.version.clone(), params.path.clone(), None, None, Some(true), if_none_match, None, None)
+        };
+
+        let full = fetch(None).await.unwrap();
+        let hash = full.content_hash.clone().unwrap();
+        assert_eq!(hash.len(), 64);
+        assert_eq!(full.content, "Demo docs");
+        assert!(!full.not_modified);
+
+        // Matching hash: compact response without the content
+        let unchanged = fetch(Some(hash.clone())).await.unwrap();
+        assert!(unchanged.not_modified);
+        assert!(unchanged.content.is_empty());
+        let contents = unchanged.into_contents();
+        assert!(!contents[0].as_text().unwrap().text.contains("Demo docs"));
+        let metadata: serde_json::Value = serde_json::from_str(&contents[1].as_text().unwrap().text).unwrap();
+        assert_eq!(metadata["not_modified"], true);
+        assert_eq!(metadata["content_hash"], hash.as_str());
+
+        // Stale hash: full content with the current hash
+        let changed = fetch(Some("0".repeat(64))).await.unwrap();
+        assert!(!changed.not_modified);
+        assert_eq!(changed.content, "Demo docs");
+        assert_eq!(changed.content_hash, Some(hash));
+    }
+
+    #[tokio::test]
+    async fn test_ephemeral_cache_keeps_serving_with_warning() {
+        let temp_dir = tempdir().unwrap();
+        let cache_dir = temp_dir.path().join("cache");
+        std::fs::write(&cache_dir, "").unwrap();
+        let cache = Arc::new(InMemoryCache::new(cache_dir));
+        let fetcher = DocFetcher::with_config(cache.clone(), FetcherConfig::default());
+        let params = DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            path: "demo/index.html".to_string(),
+        };
+        cache.insert(CacheKey::from(&params), DocContent { content: "Demo docs".to_string(), source_url: None }).await;
+        assert!(!fetcher.get_info().instructions.unwrap().contains(EPHEMERAL_CACHE_WARNING));
+
+        for _ in 0..crate::cache::DEFAULT_EPHEMERAL_AFTER_FAILURES {
+            assert!(cache.save().await.is_err());
+        }
+        assert!(cache.is_ephemeral());
+        assert!(fetcher.get_info().instructions.unwrap().ends_with(EPHEMERAL_CACHE_WARNING));
+
+        let response = fetcher.fetch_document(params.crate_name, params.version, params.path, None, None, None, None, None, None).await.unwrap();
+        assert_eq!(response.content, "Demo docs");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_document_as_json() {
+        let mut docs = mockito::Server::new_async().await;
+        let page = docs.mock("GET", "/tokio/1.40.0/tokio/sync/struct.Mutex.html")
+            .with_status(200)
+            .with_body(include_str!("../tests/fixtures/struct.Mutex.html"))
+            .expect(1)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+        let fetcher = mocked_fetcher("tokio", &docs, &mut registry, FetcherConfig::default());
+        let fetch = |max_bytes| {
+            fetcher.fetch_document(
+                "tokio".to_string(),
+                "1.40.0".to_string(),
+                "tokio/sync/struct.Mutex.html".to_string(),
+                max_bytes,
+                None,
+                None,
+                None,
+                None,
+                Some(OutputFormat::Json),
+            )
+        };
+
+        let contents = fetch(None).await.unwrap().into_contents();
+        let Some(ResourceContents::TextResourceContents { uri, mime_type, text }) = contents[0].as_resource().map(|r| &r.resource) else {
+            panic!("expected a text resource, got {:?}", contents[0]);
+        };
+        assert_eq!(mime_type.as_deref(), Some("application/json"));
+        assert!(uri.ends_with("/tokio/1.40.0/tokio/sync/struct.Mutex.html"), "{}", uri);
+        let doc: ParsedDoc = serde_json::from_str(text).unwrap();
+        assert!(doc.signature.as_deref().unwrap().starts_with("pub struct Mutex<T"), "{:?}", doc.signature);
+        assert!(doc.description.as_deref().is_some_and(|description| !description.is_empty()));
+        assert_eq!(doc.methods.len(), 4);
+        assert_eq!(doc.examples.len(), 1);
+
+        // Cached, and shortened to the size limit as valid JSON
+        let limited = fetch(Some(300)).await.unwrap();
+        assert!(limited.content.len() <= 300, "{} bytes", limited.content.len());
+        let shortened: ParsedDoc = serde_json::from_str(&limited.content).unwrap();
+        assert_eq!(shortened.signature, doc.signature);
+        assert!(shortened.examples.is_empty());
+        page.assert();
+    }
+
+    #[tokio::test]
+    async fn test_fetch_document_includes_cached_structure() {
+        let mut docs = mockito::Server::new_async().await;
+        let page = docs.mock("GET", "/tokio/1.40.0/tokio/sync/struct.Mutex.html")
+            .with_status(200)
+            .with_body(include_str!("../tests/fixtures/struct.Mutex.html"))
+            .expect(2)
+            .create();
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let config = FetcherConfig {
+            docs_base_url: docs.url(),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache, config);
+        let fetch = |include_structure| {
+            fetcher.fetch_document(
+                "tokio".to_string(),
+                "1.40.0".to_string(),
+                "tokio/sync/struct.Mutex.html".to_string(),
+                None,
+                None,
+                None,
+                None,
+                include_structure,
+                None,
+            )
+        };
+
+        let plain = fetch(None).await.unwrap();
+        assert_eq!(plain.structure, None);
+        let contents = plain.into_contents();
+        let metadata: serde_json::Value = serde_json::from_str(&contents[1].as_text().unwrap().text).unwrap();
+        assert!(metadata.get("structure").is_none(), "{}", metadata);
+
+        let structured = fetch(Some(true)).await.unwrap();
+        let structure = structured.structure.clone().unwrap();
+        assert!(structure.signature.unwrap().starts_with("pub struct Mutex<T"));
+        assert_eq!(structure.methods.len(), 4);
+        assert_eq!(structure.examples.len(), 1);
+        let contents = structured.into_contents();
+        let metadata: serde_json::Value = serde_json::from_str(&contents[1].as_text().unwrap().text).unwrap();
+        assert_eq!(metadata["structure"]["methods"][2], "pub async fn lock(&self) -> MutexGuard<'_, T>");
+
+        // The structure is cached along with the page
+        assert!(fetch(Some(true)).await.unwrap().structure.is_some());
+        page.assert();
+    }
+
+    #[tokio::test]
+    async fn test_clear_cache_scoped_to_crate() {
+        let (fetcher, cache) = setup_test_fetcher();
+        let params = |crate_name: &str| DocsRsParams {
+            crate_name: crate_name.to_string(),
+            version: "1.0.0".to_string(),
+            path: format!("{}/index.html", crate_name),
+        };
+        for crate_name in ["serde", "tokio"] {
+            cache.insert(params(crate_name).into(), DocContent { content: format!("{} docs", crate_name), source_url: None }).await;
+        }
+
+        let cleared = fetcher.clear_cache(Some("Serde".to_string())).await;
+        assert_eq!(cleared, CacheClear { crate_name: Some("serde".to_string()), cleared: 1 });
+        assert!(!fetcher.is_cached(&params("serde")).await);
+        assert!(fetcher.is_cached(&params("tokio")).await);
+        let contents = cleared.into_contents();
+        let summary: serde_json::Value = serde_json::from_str(&contents[0].as_text().unwrap().text).unwrap();
+        assert_eq!(summary["cleared"], 1);
+
+        assert_eq!(fetcher.clear_cache(None).await, CacheClear { crate_name: None, cleared: 1 });
+        assert!(!fetcher.is_cached(&params("tokio")).await);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_with_context_summarizes_linked_items() {
+        let mut docs = mockito::Server::new_async().await;
+        let page = docs.mock("GET", "/tokio/1.40.0/tokio/sync/struct.Mutex.html")
+            .with_status(200)
+            .with_body(include_str!("../tests/fixtures/struct.Mutex.html"))
+            .expect(2)
+            .create();
+        let guard = docs.mock("GET", "/tokio/1.40.0/tokio/sync/struct.MutexGuard.html")
+            .with_status(200)
+            .with_body(r#"<section id="main-content"><details class="toggle top-doc" open><div class="docblock">
+                <p>A handle to a held <code>Mutex</code>.</p><p>Dropping it unlocks the mutex.</p></div></details></section>"#)
+            .expect(1)
+            .create();
+        docs.mock("GET", "/tokio/1.40.0/tokio/sync/struct.TryLockError.html")
+            .with_status(404)
+            .create();
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let config = FetcherConfig {
+            docs_base_url: docs.url(),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache, config);
+        let fetch = |max_links| {
+            fetcher.fetch_with_context(
+                "tokio".to_string(),
+                "1.40.0".to_string(),
+                "tokio/sync/struct.Mutex.html".to_string(),
+                max_links,
+            )
+        };
+
+        let document = fetch(None).await.unwrap();
+        assert!(document.content.contains("An asynchronous"));
+        assert_eq!(document.context.omitted_links, 0);
+        assert_eq!(document.context.glossary, [
+            GlossaryEntry {
+                name: "MutexGuard".to_string(),
+                path: "tokio/sync/struct.MutexGuard.html".to_string(),
+                summary: Some("A handle to a held `Mutex`.".to_string()),
+            },
+            GlossaryEntry {
+                name: "TryLockError".to_string(),
+                path: "tokio/sync/struct.TryLockError.html".to_string(),
+                summary: None,
+            },
+        ]);
+        let contents = document.into_contents();
+        let context: serde_json::Value = serde_json::from_str(&contents[1].as_text().unwrap().text).unwrap();
+        assert_eq!(context["glossary"][0]["name"], "MutexGuard");
+
+        // Links and summaries are cached; the cap leaves out later links
+        let capped = fetch(Some(1)).await.unwrap();
+        assert_eq!(capped.context.glossary.len(), 1);
+        assert_eq!(capped.context.omitted_links, 1);
+        page.assert();
+        guard.assert();
+    }
+
+    #[tokio::test]
+    async fn test_fetch_document_follows_deprecation() {
+        let mut docs = mockito::Server::new_async().await;
+        docs.mock("GET", "/demo/1.0.0/demo/fn.old_api.html")
+            .with_status(200)
+            .with_body(r##"<section id="main-content" class="content">
+                <div class="main-heading"><h1>Function <span class="fn">old_api</span></h1></div>
+                <span class="item-info"><div class="stab deprecated"><span class="emoji">👎</span><span>Deprecated since 1.2.0: use <a href="fn.new_api.html"><code>new_api</code></a> instead</span></div></span>
+                <details class="toggle top-doc"><div class="docblock"><p>Does the old thing.</p></div></details></section>"##)
+            .create();
+        docs.mock("GET", "/demo/1.0.0/demo/fn.new_api.html")
+            .with_status(200)
+            .with_body(r##"<section id="main-content" class="content"><p>Does the new thing.</p></section>"##)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+
+        let fetcher = mocked_fetcher("demo", &docs, &mut registry, FetcherConfig {
+            follow_deprecation: true,
+            ..FetcherConfig::default()
+        });
+
+        let doc = fetcher
+            .fetch_document("demo".to_string(), "1.0.0".to_string(), "demo/fn.old_api.html".to_string(), None, None, None, None, None, None)
+            .await
+            .unwrap();
+        let (old, new) = doc.content
+            .split_once("===== Deprecated; replacement demo/fn.new_api.html follows =====")
+            .unwrap();
+        assert!(old.contains("Does the old thing."));
+        assert!(new.contains("Does the new thing."));
+    }
+
+    #[tokio::test]
+    async fn test_batch_shares_overall_time_budget() {
+        let mut docs = mockito::Server::new_async().await;
+        docs.mock("GET", "/demo/1.0.0/demo/struct.Fast.html")
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper">Fast docs</div>"#)
+            .create();
+        docs.mock("GET", "/demo/1.0.0/demo/struct.Slow.html")
+            .with_status(200)
+            .with_chunked_body(|w| {
+                std::thread::sleep(std::time::Duration::from_millis(1000));
+                w.write_all(br#"<div id="rustdoc_body_wrapper">Slow docs</div>"#)
+            })
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+
+        let fetcher = mocked_fetcher("demo", &docs, &mut registry, FetcherConfig {
+            batch_timeout: Some(std::time::Duration::from_millis(400)),
+            ..FetcherConfig::default()
+        });
+        let fast = "demo/struct.Fast.html".to_string();
+        let slow = "demo/struct.Slow.html".to_string();
+
+        let started = std::time::Instant::now();
+        let result = fetcher
+            .fetch_crate_paths("demo".to_string(), "1.0.0".to_string(), vec![fast.clone(), slow.clone()], None)
+            .await
+            .unwrap();
+
+        assert!(started.elapsed() < std::time::Duration::from_millis(900));
+        assert_eq!(result.documents[&fast], PathResult::Content("Fast docs".to_string()));
+        assert_eq!(result.documents[&slow], PathResult::Error(DocsFetchError::Timeout.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_list_examples_files_without_examples() {
+        let mut docs = mockito::Server::new_async().await;
+        docs.mock("GET", "/crate/demo/1.0.0/source/examples/")
+            .with_status(404)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+
+        let fetcher = demo_fetcher(&docs, &mut registry);
+
+        let files = fetcher.list_examples_files("demo".to_string(), "1.0.0".to_string()).await.unwrap();
+        assert!(files.entries.is_empty());
+        assert_eq!(files.version, "1.0.0");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_source_strips_line_numbers_and_caches() {
+        let mut docs = mockito::Server::new_async().await;
+        let page = docs.mock("GET", "/demo-io/1.0.0/src/demo_io/read.rs.html")
+            .with_status(200)
+            .with_body(r##"<div class="example-wrap"><div data-nosnippet><pre class="src-line-numbers"><a href="#1" id="1">1</a>
+<a href="#2" id="2">2</a>
+<a href="#3" id="3">3</a></pre></div><pre class="rust"><code><span class="kw">pub trait </span>AsyncRead {
+    <span class="kw">fn </span>poll_read(<span class="kw-2">&amp;mut </span><span class="self">self</span>);
+}</code></pre></div>"##)
+            .expect(1)
+            .create();
+        docs.mock("GET", "/demo-io/1.0.0/src/demo_io/missing.rs.html")
+            .with_status(404)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+
+        let fetcher = mocked_fetcher("demo-io", &docs, &mut registry, FetcherConfig::default());
+
+        let source = fetcher.fetch_source("demo-io".to_string(), "1.0.0".to_string(), "src/read.rs".to_string()).await.unwrap();
+        assert_eq!(source.source_path, "src/demo_io/read.rs.html");
+        assert_eq!(source.lines, 3);
+        assert_eq!(source.code, "pub trait AsyncRead {\n    fn poll_read(&mut self);\n}");
+
+        // The page path names the same cached file
+        let cached = fetcher.fetch_source("demo-io".to_string(), "1.0.0".to_string(), "src/demo_io/read.rs.html".to_string()).await.unwrap();
+        assert_eq!(cached, source);
+        page.assert();
+
+        let missing = fetcher.fetch_source("demo-io".to_string(), "1.0.0".to_string(), "src/missing.rs".to_string()).await;
+        assert!(matches!(missing, Err(DocsFetchError::DocsNotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_crate_bundle_concatenates_items_in_stable_order() {
+        let mut docs = mockito::Server::new_async().await;
+        for (path, body) in [
+            ("demo/index.html", "Demo crate"),
+            ("demo/struct.Alpha.html", "Alpha docs"),
+            ("demo/fn.beta.html", "beta docs"),
+            ("demo/io/index.html", "IO module"),
+        ] {
+            docs.mock("GET", format!("/demo/1.0.0/{}", path).as_str())
+                .with_status(200)
+                .with_body(format!(r#"<div id="rustdoc_body_wrapper">{}</div>"#, body))
+                .expect(1)
+                .create();
+        }
+        let mut registry = mockito::Server::new_async().await;
+        registry.mock("GET", "/api/v1/crates/demo")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"demo"}}"#)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let item = |name: &str, kind, doc_path: &str| IndexItem {
+            name: name.to_string(),
+            kind,
+            path: format!("demo::{}", name),
+            doc_path: doc_path.to_string(),
+        };
+        let index = SearchIndex {
+            items: vec![
+                item("io", ItemKind::Module, "demo/io/index.html"),
+                item("beta", ItemKind::Function, "demo/fn.beta.html"),
+                item("Alpha", ItemKind::Struct, "demo/struct.Alpha.html"),
+            ],
+        };
+        cache.insert(
+            derived_cache_key("demo", "1.0.0", "search-index"),
+            DocContent { content: serde_json::to_string(&index).unwrap(), source_url: None },
+        ).await;
+        let config = FetcherConfig {
+            docs_base_url: docs.url(),
+            crates_io_base_url: registry.url(),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache, config);
+
+        let first = fetcher.fetch_crate_bundle("demo".to_string(), "1.0.0".to_string(), Some(2), None, None).await.unwrap();
+        assert_eq!(first.content, "===== demo (demo/index.html) =====\n\nDemo crate\n\n===== demo::Alpha (demo/struct.Alpha.html) =====\n\nAlpha docs\n");
+        assert_eq!((first.info.items, first.info.total_items, first.info.next_start), (2, 4, Some(2)));
+
+        let rest = fetcher.fetch_crate_bundle("demo".to_string(), "1.0.0".to_string(), Some(2), first.info.next_start, None).await.unwrap();
+        assert_eq!(rest.content, "===== demo::beta (demo/fn.beta.html) =====\n\nbeta docs\n\n===== demo::io (demo/io/index.html) =====\n\nIO module\n");
+        assert_eq!(rest.info.next_start, None);
+
+        // The whole crate at once is assembled from cached pages, in the same order
+        let all = fetcher.fetch_crate_bundle("demo".to_string(), "1.0.0".to_string(), None, None, None).await.unwrap();
+        assert_eq!(all.content, format!("{}\n{}", first.content, rest.content));
+        assert_eq!(all.info.warning, None);
+        assert!(!all.info.truncated);
+
+        // A byte cap stops the bundle early and continues at the first omitted item
+        let capped = fetcher.fetch_crate_bundle("demo".to_string(), "1.0.0".to_string(), None, None, Some(first.content.len() + 1)).await.unwrap();
+        assert_eq!(capped.content, first.content);
+        assert!(capped.info.truncated);
+        assert_eq!(capped.info.omitted, ["demo::beta", "demo::io"]);
+        assert_eq!(capped.info.next_start, Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_crate_paths_stops_at_byte_budget() {
+        let mut docs = mockito::Server::new_async().await;
+        for (name, size) in [("A", 400), ("B", 400), ("C", 400), ("D", 100)] {
+            docs.mock("GET", format!("/demo/1.0.0/demo/struct.{}.html", name).as_str())
+                .with_status(200)
+                .with_body(format!(r#"<div id="rustdoc_body_wrapper">{}</div>"#, name.repeat(size)))
+                .create();
+        }
+        let mut registry = mockito::Server::new_async().await;
+
+        let fetcher = demo_fetcher(&docs, &mut registry);
+
+        // Requested out of name order; D would still fit but comes after C
+        let paths = ["demo/struct.B.html", "demo/struct.A.html", "demo/struct.C.html", "demo/struct.D.html"];
+        let result = fetcher.fetch_crate_paths(
+            "demo".to_string(),
+            "1.0.0".to_string(),
+            paths.iter().map(|p| p.to_string()).collect(),
+            Some(1000),
+        ).await.unwrap();
+
+        let included: Vec<_> = result.documents.keys().map(String::as_str).collect();
+        assert_eq!(included, ["demo/struct.A.html", "demo/struct.B.html"]);
+        assert!(result.truncated);
+        assert_eq!(result.omitted, ["demo/struct.C.html", "demo/struct.D.html"]);
+
+        let uncapped = fetcher.fetch_crate_paths(
+            "demo".to_string(),
+            "1.0.0".to_string(),
+            paths.iter().map(|p| p.to_string()).collect(),
+            None,
+        ).await.unwrap();
+        assert_eq!(uncapped.documents.len(), 4);
+        assert!(!uncapped.truncated && uncapped.omitted.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_crate_bundle_crawls_deduplicated_and_bounded() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let most_in_flight = Arc::new(AtomicUsize::new(0));
+        let mut docs = mockito::Server::new_async().await;
+        let pages = ["demo/index.html", "demo/struct.Alpha.html", "demo/fn.beta.html", "demo/io/index.html", "demo/io/fn.read.html"];
+        let mocks: Vec<_> = pages.iter().map(|path| {
+            let (in_flight, most_in_flight) = (in_flight.clone(), most_in_flight.clone());
+            let body = format!(r#"<div id="rustdoc_body_wrapper">{} docs</div>"#, path);
+            docs.mock("GET", format!("/demo/1.0.0/{}", path).as_str())
+                .with_status(200)
+                .with_chunked_body(move |w| {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    most_in_flight.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    w.write_all(body.as_bytes())
+                })
+                .expect(1)
+                .create()
+        }).collect();
+        let mut registry = mockito::Server::new_async().await;
+        registry.mock("GET", "/api/v1/crates/demo")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"demo"}}"#)
+            .create();
+
+        let item = |path: &str, kind, doc_path: &str| IndexItem {
+            name: path.rsplit("::").next().unwrap().to_string(),
+            kind,
+            path: path.to_string(),
+            doc_path: doc_path.to_string(),
+        };
+        // Alpha is re-exported at the root of `io` and its page linked twice
+        let index = SearchIndex {
+            items: vec![
+                item("demo::io::read", ItemKind::Function, "demo/io/fn.read.html"),
+                item("demo::io::Alpha", ItemKind::Struct, "demo/struct.Alpha.html"),
+                item("demo::io", ItemKind::Module, "demo/io/index.html"),
+                item("demo::beta", ItemKind::Function, "demo/fn.beta.html"),
+                item("demo::Alpha", ItemKind::Struct, "demo/struct.Alpha.html"),
+                item("demo::Alpha", ItemKind::Struct, "demo/struct.Alpha.html"),
+            ],
+        };
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        cache.insert(
+            derived_cache_key("demo", "1.0.0", "search-index"),
+            DocContent { content: serde_json::to_string(&index).unwrap(), source_url: None },
+        ).await;
+        let config = FetcherConfig {
+            docs_base_url: docs.url(),
+            crates_io_base_url: registry.url(),
+            bundle_concurrency: 4,
+            fetch_limiter: Some(Arc::new(Semaphore::new(2))),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache, config);
+
+        let first = fetcher.fetch_crate_bundle("demo".to_string(), "1.0.0".to_string(), None, None, None).await.unwrap();
+        let headers: Vec<_> = first.content.lines().filter(|line| line.starts_with("=====")).collect();
+        assert_eq!(headers, [
+            "===== demo (demo/index.html) =====",
+            "===== demo::Alpha (demo/struct.Alpha.html) =====",
+            "===== demo::beta (demo/fn.beta.html) =====",
+            "===== demo::io (demo/io/index.html) =====",
+            "===== demo::io::read (demo/io/fn.read.html) =====",
+        ]);
+        assert_eq!(first.info.total_items, pages.len());
+        for mock in &mocks {
+            mock.assert();
+        }
+        assert!(most_in_flight.load(Ordering::SeqCst) <= 2);
+    }
+
+    async fn fetch_with_limit(fetcher: &DocFetcher, cache: &InMemoryCache, max_bytes: Option<usize>) -> String {
+        let params = DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            path: "demo/index.html".to_string(),
+        };
+        let line = "word ".repeat(19) + "\n";
+        cache.insert(CacheKey::from(&params), DocContent { content: line.repeat(40_000), source_url: None }).await;
+        fetcher.fetch_document(params.crate_name, params.version, params.path, max_bytes, None, None, None, None, None)
+            .await
+            .unwrap()
+            .content
+    }
+
+    #[tokio::test]
+    async fn test_fetch_document_max_bytes_override() {
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let config = FetcherConfig { max_content_bytes: Some(10_000), ..FetcherConfig::default() };
+        let fetcher = DocFetcher::with_config(cache.clone(), config);
+
+        let default = fetch_with_limit(&fetcher, &cache, None).await;
+        assert!(default.len() <= 10_000 && default.len() > 9_000);
+        assert!(default.ends_with("bytes ...]"));
+
+        let below = fetch_with_limit(&fetcher, &cache, Some(500)).await;
+        assert!(below.len() <= 500);
+        assert!(below.starts_with("word word"));
+        assert!(below.contains("word\n\n[... truncated "), "cut should fall on a line boundary: {:?}", below);
+
+        let above = fetch_with_limit(&fetcher, &cache, Some(50_000)).await;
+        assert!(above.len() <= 50_000 && above.len() > 10_000);
+
+        let clamped = fetch_with_limit(&fetcher, &cache, Some(usize::MAX)).await;
+        assert!(clamped.len() <= MAX_CONTENT_BYTES_CEILING && clamped.len() > 50_000);
+        assert!(clamped.ends_with("bytes ...]"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_document_estimates_tokens_on_request() {
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let fetcher = DocFetcher::with_config(cache.clone(), FetcherConfig::default());
+        let fetch = |path: &str, content: String, estimate_tokens: Option<bool>| {
+            let (cache, fetcher) = (cache.clone(), fetcher.clone());
+            let path = path.to_string();
+            async move {
+                let params = DocsRsParams {
+                    crate_name: "demo".to_string(),
+                    version: "1.0.0".to_string(),
+                    path: path.clone(),
+                };
+                cache.insert(params.into(), DocContent { content, source_url: None }).await;
+                fetcher.fetch_document("demo".to_string(), "1.0.0".to_string(), path, None, estimate_tokens, None, None, None, None)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let short = fetch("demo/short.html", "word ".repeat(100), Some(true)).await;
+        let long = fetch("demo/long.html", "word ".repeat(1000), Some(true)).await;
+        assert_eq!(short.estimated_tokens, Some(125));
+        assert_eq!(long.estimated_tokens, Some(1250));
+
+        let contents = long.into_contents();
+        assert_eq!(contents.len(), 2);
+        let metadata: serde_json::Value = serde_json::from_str(&contents[1].as_text().unwrap().text).unwrap();
+        assert_eq!(metadata["estimated_tokens"], 1250);
+
+        let plain = fetch("demo/plain.html", "word ".repeat(100), None).await;
+        assert_eq!(plain.estimated_tokens, None);
+        assert_eq!(plain.into_contents().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_crate_name_variants_share_cache_entry() {
+        let mut docs = mockito::Server::new_async().await;
+        let page = docs.mock("GET", "/serde/1.0.0/serde/index.html")
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper">Serde docs</div>"#)
+            .expect(1)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+        let lookup = registry.mock("GET", "/api/v1/crates/serde")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"serde"}}"#)
+            .expect(1)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let config = FetcherConfig {
+            docs_base_url: docs.url(),
+            crates_io_base_url: registry.url(),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache.clone(), config);
+
+        for name in ["Serde", "serde", " SERDE "] {
+            let doc = fetcher.fetch_document(name.to_string(), "1.0.0".to_string(), "serde/index.html".to_string(), None, None, None, None, None, None)
+                .await
+                .unwrap();
+            assert_eq!(doc.content, "Serde docs");
+        }
+
+        page.assert();
+        lookup.assert();
+        assert_eq!(cache.entries().count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_crate_name_separators_canonicalized_via_crates_io() {
+        let mut docs = mockito::Server::new_async().await;
+        let page = docs.mock("GET", "/tokio-util/0.7.0/tokio_util/index.html")
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper">tokio-util docs</div>"#)
+            .expect(1)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+        let lookup = registry.mock("GET", "/api/v1/crates/tokio_util")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"tokio-util"}}"#)
+            .create();
+        registry.mock("GET", "/api/v1/crates/tokio-util")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"tokio-util"}}"#)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let config = FetcherConfig {
+            docs_base_url: docs.url(),
+            crates_io_base_url: registry.url(),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache.clone(), config);
+
+        for name in ["Tokio_Util", "tokio-util"] {
+            fetcher.fetch_document(name.to_string(), "0.7.0".to_string(), "tokio_util/index.html".to_string(), None, None, None, None, None, None)
+                .await
+                .unwrap();
+        }
+
+        page.assert();
+        lookup.assert();
+        let keys: Vec<String> = cache.entries().map(|(key, _)| key.params.crate_name).collect().await;
+        assert_eq!(keys, ["tokio-util"]);
+    }
+
+    #[tokio::test]
+    async fn test_remembered_crate_names_are_bounded() {
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let fetcher = DocFetcher::with_config(cache, FetcherConfig::default());
+
+        for i in 0..=MAX_REMEMBERED_CRATE_NAMES {
+            fetcher.remember_crate_name(&format!("crate-{}", i), format!("crate-{}", i)).await;
+        }
+        assert_eq!(fetcher.crate_names.read().await.len(), MAX_REMEMBERED_CRATE_NAMES);
+        assert_eq!(fetcher.remembered_crate_name("crate-0").await, None, "oldest name should be dropped");
+        assert_eq!(fetcher.remembered_crate_name("crate_1").await.as_deref(), Some("crate-1"));
+
+        // Names are confirmed again once their TTL has passed
+        if let Some(confirmed_at) = std::time::Instant::now().checked_sub(CRATE_NAME_TTL) {
+            fetcher.crate_names.write().await.insert("tokio-util".to_string(), ("tokio-util".to_string(), confirmed_at));
+            assert_eq!(fetcher.remembered_crate_name("tokio_util").await, None);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_document_uses_configured_default_path() {
+        let mut docs = mockito::Server::new_async().await;
+        let prelude = docs.mock("GET", "/tokio/1.0.0/tokio/prelude/index.html")
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper">Tokio prelude</div>"#)
+            .expect(1)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+        registry.mock("GET", "/api/v1/crates/tokio")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"tokio"}}"#)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let config = FetcherConfig {
+            docs_base_url: docs.url(),
+            crates_io_base_url: registry.url(),
+            default_paths: HashMap::from([("tokio".to_string(), "tokio/prelude/index.html".to_string())]),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache.clone(), config);
+
+        let doc = fetcher.fetch_document("Tokio".to_string(), "1.0.0".to_string(), String::new(), None, None, None, None, None, None)
+            .await
+            .unwrap();
+
+        prelude.assert();
+        assert_eq!(doc.content, "Tokio prelude");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_document_follows_redirect_stub() {
+        let mut docs = mockito::Server::new_async().await;
+        let stub = docs.mock("GET", "/demo/1.0.0/demo/struct.Old.html")
+            .with_status(200)
+            .with_body(r#"<html><head><meta http-equiv="refresh" content="0;URL=../demo/struct.New.html"></head>
+                <body><p>Redirecting to <a href="../demo/struct.New.html">../demo/struct.New.html</a>...</p></body></html>"#)
+            .expect(1)
+            .create();
+        docs.mock("GET", "/demo/1.0.0/demo/struct.New.html")
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper">New docs</div>"#)
+            .expect(1)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+        registry.mock("GET", "/api/v1/crates/demo")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"demo"}}"#)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let config = FetcherConfig {
+            docs_base_url: docs.url(),
+            crates_io_base_url: registry.url(),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache, config);
+
+        // The canonical path is also reported when the content comes from the cache
+        for _ in 0..2 {
+            let doc = fetcher.fetch_document("demo".to_string(), "1.0.0".to_string(), "demo/struct.Old.html".to_string(), None, None, None, None, None, None)
+                .await
+                .unwrap();
+            assert_eq!(doc.content, "New docs");
+            assert_eq!(doc.canonical_path.as_deref(), Some("demo/struct.New.html"));
+            let contents = doc.into_contents();
+            let metadata: serde_json::Value = serde_json::from_str(&contents[1].as_text().unwrap().text).unwrap();
+            assert_eq!(metadata["canonical_path"], "demo/struct.New.html");
+        }
+        stub.assert();
+    }
+
+    #[tokio::test]
+    async fn test_source_url_follows_redirects_and_is_cached() {
+        let mut docs = mockito::Server::new_async().await;
+        docs.mock("GET", "/demo/1.0.0/demo/struct.Old.html")
+            .with_status(302)
+            .with_header("Location", "/demo/1.0.0/demo/struct.New.html")
+            .create();
+        docs.mock("GET", "/demo/1.0.0/demo/struct.New.html")
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper">New docs</div>"#)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+        registry.mock("GET", "/api/v1/crates/demo")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"demo"}}"#)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let config = FetcherConfig {
+            docs_base_url: docs.url(),
+            crates_io_base_url: registry.url(),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache.clone(), config);
+        let params = DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            path: "demo/struct.Old.html".to_string(),
+        };
+        let doc = fetcher.fetch_document(params.crate_name.clone(), params.version.clone(), params.path.clone(), None, None, None, None, None, None)
+            .await
+            .unwrap();
+        let source_url = format!("{}/demo/1.0.0/demo/struct.New.html", docs.url());
+        assert_eq!(doc.content, "New docs");
+        assert_eq!(doc.source_url.as_deref(), Some(source_url.as_str()));
+        let contents = doc.into_contents();
+        let metadata: serde_json::Value = serde_json::from_str(&contents[1].as_text().unwrap().text).unwrap();
+        assert_eq!(metadata["source_url"], source_url.as_str());
+
+        cache.save().await.unwrap();
+        let restarted = InMemoryCache::new(temp_dir.path().to_path_buf());
+        restarted.load().await.unwrap();
+        let cached = restarted.get(&CacheKey::from(&params)).await.unwrap();
+        assert_eq!(cached.source_url.as_deref(), Some(source_url.as_str()));
+
+        // Entries cached before the URL was recorded still load
+        let legacy: DocContent = serde_json::from_str(r#"{"content":"Old docs"}"#).unwrap();
+        assert_eq!(legacy.source_url, None);
+        assert_eq!(legacy.into_contents().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_document_formats_cached_separately() {
+        let mut docs = mockito::Server::new_async().await;
+        let page = docs.mock("GET", "/demo/1.0.0/demo/fn.run.html")
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper"><h2>Examples</h2><pre class="rust"><code>demo::run();</code></pre></div>"#)
+            .expect(2)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+
+        let fetcher = demo_fetcher(&docs, &mut registry);
+
+        for _ in 0..2 {
+            let plain = fetcher.fetch_document("demo".to_string(), "1.0.0".to_string(), "demo/fn.run.html".to_string(), None, None, None, None, None, None)
+                .await
+                .unwrap();
+            assert!(!plain.content.contains('#'), "got {:?}", plain.content);
+            let markdown = fetcher.fetch_document("demo".to_string(), "1.0.0".to_string(), "demo/fn.run.html".to_string(), None, None, None, None, None, Some(OutputFormat::Markdown))
+                .await
+                .unwrap();
+            assert_eq!(markdown.content, "## Examples\n\n```rust\ndemo::run();\n```");
+        }
+        page.assert();
+    }
+
+    #[tokio::test]
+    async fn test_merge_canonical_pages_shares_one_entry() {
+        let mut docs = mockito::Server::new_async().await;
+        let reexport = docs.mock("GET", "/demo/1.0.0/demo/struct.Sender.html")
+            .with_status(200)
+            .with_body(include_str!("../tests/fixtures/struct.Sender.html"))
+            .expect(1)
+            .create();
+        let canonical = docs.mock("GET", "/demo/1.0.0/demo/channel/struct.Sender.html")
+            .with_status(200)
+            .with_body(include_str!("../tests/fixtures/struct.Sender.html"))
+            .expect(0)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+        registry.mock("GET", "/api/v1/crates/demo")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"demo"}}"#)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let config = FetcherConfig {
+            docs_base_url: docs.url(),
+            crates_io_base_url: registry.url(),
+            merge_canonical_pages: true,
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache.clone(), config);
+        let params = |path: &str| DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            path: path.to_string(),
+        };
+
+        let first = fetcher.fetch_document("demo".to_string(), "1.0.0".to_string(), "demo/struct.Sender.html".to_string(), None, None, None, None, None, None)
+            .await
+            .unwrap();
+        assert!(first.content.contains("The sending half of a channel."));
+        assert_eq!(first.canonical_path.as_deref(), Some("demo/channel/struct.Sender.html"));
+        for path in ["demo/channel/struct.Sender.html", "demo/struct.Sender.html"] {
+            let doc = fetcher.fetch(params(path)).await.unwrap();
+            assert_eq!(doc.content, first.content);
+        }
+        reexport.assert();
+        canonical.assert();
+
+        // One document, plus the alias of the re-export
+        assert!(cache.get(&params("demo/struct.Sender.html").into()).await.is_none());
+        assert!(cache.get(&params("demo/channel/struct.Sender.html").into()).await.is_some());
+        assert_eq!(cache.entry_count().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_reload_config_applies_deny_list() {
+        let mut docs = mockito::Server::new_async().await;
+        let page = docs.mock("GET", "/demo/1.0.0/demo/index.html")
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper">Demo docs</div>"#)
+            .expect(1)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+        registry.mock("GET", "/api/v1/crates/demo")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"demo"}}"#)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let config_file = temp_dir.path().join("config.json");
+        fs::write(&config_file, "{}").unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().join("cache")));
+        let config = FetcherConfig {
+            docs_base_url: docs.url(),
+            crates_io_base_url: registry.url(),
+            config_file: Some(config_file.clone()),
+            admin_token: Some("secret".to_string()),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache, config);
+        let params = DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            path: "demo/index.html".to_string(),
+        };
+        assert_eq!(fetcher.fetch(params.clone()).await.unwrap().content, "Demo docs");
+
+        fs::write(&config_file, r#"{"denied_crates": ["Demo"]}"#).unwrap();
+        assert!(fetcher.reload_config("wrong".to_string()).await.is_err());
+        assert!(fetcher.fetch(params.clone()).await.is_ok());
+
+        let reload = fetcher.reload_config("secret".to_string()).await.unwrap();
+        assert_eq!(reload.changed, vec!["denied_crates"]);
+        assert_eq!(reload.reloaded, vec!["default_paths", "boilerplate", "denied_crates"]);
+        assert!(reload.requires_restart.iter().any(|setting| setting == "address"));
+        assert!(reload.requires_restart.iter().any(|setting| setting == "cache_ttl"));
+        // Denied even though the page is cached
+        assert!(matches!(fetcher.fetch(params).await, Err(DocsFetchError::Denied(name)) if name == "demo"));
+        page.assert();
+    }
+
+    #[tokio::test]
+    async fn test_derived_tools_refuse_denied_crates() {
+        let mut docs = mockito::Server::new_async().await;
+        let page = docs.mock("GET", mockito::Matcher::Any).expect(0).create();
+        let mut registry = mockito::Server::new_async().await;
+        let lookup = registry.mock("GET", mockito::Matcher::Any).expect(0).create();
+
+        let fetcher = fetcher_against(&docs, &registry, FetcherConfig {
+            denied_crates: HashSet::from(["demo".to_string()]),
+            ..FetcherConfig::default()
+        });
+        let key = derived_cache_key("demo", "1.0.0", "constants:demo/index.html");
+        fetcher.store_json(key, &Constants { path: "demo/index.html".to_string(), constants: Vec::new() }).await.unwrap();
+
+        // Denied in any spelling, even though the result is cached
+        let constants = fetcher.list_constants("Demo".to_string(), "1.0.0".to_string(), "demo/index.html".to_string()).await;
+        assert!(matches!(constants, Err(DocsFetchError::Denied(name)) if name == "demo"));
+        let docs = fetcher.crate_docs("demo".to_string(), "latest".to_string()).await;
+        assert!(matches!(docs, Err(DocsFetchError::Denied(_))));
+        let metadata = fetcher.get_crate_metadata("demo".to_string(), None).await;
+        assert!(matches!(metadata, Err(DocsFetchError::Denied(_))));
+        page.assert();
+        lookup.assert();
+    }
+
+    #[tokio::test]
+    async fn test_client_is_shared_until_boilerplate_changes() {
+        let mut docs = mockito::Server::new_async().await;
+        for name in ["a", "b"] {
+            docs.mock("GET", format!("/demo/1.0.0/demo/struct.{}.html", name).as_str())
+                .with_status(200)
+                .with_body(r#"<div id="rustdoc_body_wrapper">Demo docs<aside class="ad">Sponsored</aside></div>"#)
+                .create();
+        }
+        let mut registry = mockito::Server::new_async().await;
+        registry.mock("GET", "/api/v1/crates/demo")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"demo"}}"#)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let config_file = temp_dir.path().join("config.json");
+        fs::write(&config_file, "{}").unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().join("cache")));
+        let config = FetcherConfig {
+            docs_base_url: docs.url(),
+            crates_io_base_url: registry.url(),
+            config_file: Some(config_file.clone()),
+            admin_token: Some("secret".to_string()),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache, config);
+        let params = |name: &str| DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            path: format!("demo/struct.{}.html", name),
+        };
+
+        let client = fetcher.client();
+        assert_eq!(fetcher.fetch(params("a")).await.unwrap().content, "Demo docs Sponsored");
+        fs::write(&config_file, r#"{"denied_crates": ["internal"]}"#).unwrap();
+        fetcher.reload_config("secret".to_string()).await.unwrap();
+        assert!(Arc::ptr_eq(&client, &fetcher.client()));
+
+        fs::write(&config_file, r#"{"boilerplate": {"selectors": ["aside"]}}"#).unwrap();
+        fetcher.reload_config("secret".to_string()).await.unwrap();
+        assert!(!Arc::ptr_eq(&client, &fetcher.client()));
+        assert_eq!(fetcher.fetch(params("b")).await.unwrap().content, "Demo docs");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_handles_edge_case_crate_names() {
+        let mut registry = mockito::Server::new_async().await;
+        registry.mock("GET", "/api/v1/crates/async")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"async"}}"#)
+            .create();
+        let lookup = registry.mock("GET", "/api/v1/crates/tokio_util")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"tokio-util"}}"#)
+            .expect(1)
+            .create();
+        registry.mock("GET", "/api/v1/crates/foo.bar")
+            .with_status(404)
+            .create();
+        let mut docs = mockito::Server::new_async().await;
+        docs.mock("GET", "/async/1.0.0/async/index.html")
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper">Async docs</div>"#)
+            .create();
+        let page = docs.mock("GET", "/tokio-util/1.0.0/tokio_util/index.html")
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper">Tokio util docs</div>"#)
+            .expect(1)
+            .create();
+        docs.mock("GET", "/foo.bar/1.0.0/foo/index.html")
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper">Dotted docs</div>"#)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let fetcher_with = |crate_name_policy: CrateNamePolicy| {
+            let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+            let config = FetcherConfig {
+                docs_base_url: docs.url(),
+                crates_io_base_url: registry.url(),
+                crate_name_policy,
+                ..FetcherConfig::default()
+            };
+            DocFetcher::with_config(cache, config)
+        };
+        let params = |crate_name: &str, path: &str| DocsRsParams {
+            crate_name: crate_name.to_string(),
+            version: "1.0.0".to_string(),
+            path: path.to_string(),
+        };
+        let fetcher = fetcher_with(CrateNamePolicy::CratesIo);
+
+        // A keyword is a valid crate name and URL segment
+        let doc = fetcher.fetch(params("async", "async/index.html")).await.unwrap();
+        assert_eq!(doc.content, "Async docs");
+
+        // Separator variants share the confirmed name and the cached page
+        for spelling in ["tokio_util", "Tokio-Util", "tokio-util"] {
+            let doc = fetcher.fetch(params(spelling, "tokio_util/index.html")).await.unwrap();
+            assert_eq!(doc.content, "Tokio util docs");
+        }
+        lookup.assert();
+        page.assert();
+
+        // Dotted names are only accepted when permitted, paths never
+        let dotted = fetcher.fetch(params("foo.bar", "foo/index.html")).await;
+        assert!(matches!(dotted, Err(DocsFetchError::InvalidCrateName(name)) if name == "foo.bar"));
+        let permissive = fetcher_with(CrateNamePolicy::Permissive);
+        let doc = permissive.fetch(params("foo.bar", "foo/index.html")).await.unwrap();
+        assert_eq!(doc.content, "Dotted docs");
+        let escaping = permissive.fetch(params("..", "foo/index.html")).await;
+        assert!(matches!(escaping, Err(DocsFetchError::InvalidCrateName(_))));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_crate_paths_resolves_version_once() {
+        let mut registry = mockito::Server::new_async().await;
+        let metadata = registry.mock("GET", "/api/v1/crates/demo")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"demo","max_stable_version":"1.2.3","newest_version":"1.3.0-rc.1"}}"#)
+            .expect(1)
+            .create();
+        let mut docs = mockito::Server::new_async().await;
+        for name in ["Alpha", "Beta"] {
+            docs.mock("GET", format!("/demo/1.2.3/demo/struct.{}.html", name).as_str())
+                .with_status(200)
+                .with_body(format!(r#"<div id="rustdoc_body_wrapper">{} docs</div>"#, name))
+                .expect(1)
+                .create();
+        }
+        docs.mock("GET", "/demo/1.2.3/demo/struct.Missing.html")
+            .with_status(404)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let config = FetcherConfig {
+            docs_base_url: docs.url(),
+            crates_io_base_url: registry.url(),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache.clone(), config);
+
+        let paths = ["demo/struct.Alpha.html", "demo/struct.Beta.html", "demo/struct.Missing.html"];
+        let result = fetcher.fetch_crate_paths(
+            "Demo".to_string(),
+            "latest".to_string(),
+            paths.iter().map(|p| p.to_string()).collect(),
+            None,
+        ).await.unwrap();
+
+        metadata.assert();
+        assert_eq!(result.crate_name, "demo");
+        assert_eq!(result.version, "1.2.3");
+        assert_eq!(result.documents[paths[0]], PathResult::Content("Alpha docs".to_string()));
+        assert_eq!(result.documents[paths[1]], PathResult::Content("Beta docs".to_string()));
+        assert!(matches!(result.documents[paths[2]], PathResult::Error(_)));
+        assert!(fetcher.is_cached(&DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: "1.2.3".to_string(),
+            path: paths[0].to_string(),
+        }).await);
+    }
+
+    #[tokio::test]
+    async fn test_serves_stale_when_breaker_open() {
+        let mut docs = mockito::Server::new_async().await;
+        let upstream = docs.mock("GET", "/std/1.2.0/std/vec/struct.Vec.html")
+            .with_status(503)
+            .expect(1)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let stale_key = DocsRsParams {
+            crate_name: "std".to_string(),
+            version: "1.1.0".to_string(),
+            path: "std/vec/struct.Vec.html".to_string(),
+        };
+        cache.insert(stale_key.into(), DocContent { content: "Old Vec docs".to_string(), source_url: None }).await;
+        let config = FetcherConfig {
+            docs_base_url: docs.url(),
+            circuit_breaker: Arc::new(CircuitBreaker::new(1, std::time::Duration::from_secs(60))),
+            serve_stale_on_outage: true,
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache.clone(), config);
+        let params = DocsRsParams {
+            crate_name: "std".to_string(),
+            version: "1.2.0".to_string(),
+            path: "std/vec/struct.Vec.html".to_string(),
+        };
+
+        // The failing request opens the breaker, the second is not sent upstream
+        for _ in 0..2 {
+            let doc = fetcher.fetch(params.clone()).await.unwrap();
+            assert!(doc.content.starts_with("[Served stale from cache (version 1.1.0) due to upstream outage]"));
+            assert!(doc.content.ends_with("Old Vec docs"));
+        }
+        upstream.assert();
+        assert!(!fetcher.is_cached(&params).await, "stale content is not cached under the requested version");
+
+        let missing = DocsRsParams { path: "std/index.html".to_string(), ..params };
+        assert!(matches!(fetcher.fetch(missing).await, Err(DocsFetchError::ServiceUnavailable)));
+    }
+
+    #[tokio::test]
+    async fn test_serves_expired_entry_while_breaker_open() {
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()).with_ttl(Some(std::time::Duration::from_secs(1))));
+        let params = DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            path: "demo/index.html".to_string(),
+        };
+        cache.insert(params.clone().into(), DocContent { content: "Demo docs".to_string(), source_url: None }).await;
+        tokio::time::sleep(std::time::Duration::from_millis(2100)).await;
+        assert!(!cache.contains_key(&params.clone().into()).await, "entry should have expired");
+
+        let config = FetcherConfig {
+            circuit_breaker: Arc::new(CircuitBreaker::new(1, std::time::Duration::from_secs(60))),
+            serve_stale_on_outage: true,
+            ..FetcherConfig::default()
+        };
+        config.circuit_breaker.record_failure();
+        let fetcher = DocFetcher::with_config(cache, config);
+
+        let doc = fetcher.fetch(params).await.unwrap();
+        assert!(doc.content.starts_with("[Served stale from cache (version 1.0.0)"), "{}", doc.content);
+        assert!(doc.content.ends_with("Demo docs"));
+    }
+
+    #[tokio::test]
+    async fn test_serves_newest_stale_version() {
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        for version in ["0.9.0", "1.10.0", "1.9.0", "not-semver"] {
+            let key = DocsRsParams {
+                crate_name: "demo".to_string(),
+                version: version.to_string(),
+                path: "demo/index.html".to_string(),
+            };
+            cache.insert(key.into(), DocContent { content: format!("Docs {}", version), source_url: None }).await;
+        }
+        let config = FetcherConfig {
+            circuit_breaker: Arc::new(CircuitBreaker::new(1, std::time::Duration::from_secs(60))),
+            serve_stale_on_outage: true,
+            ..FetcherConfig::default()
+        };
+        config.circuit_breaker.record_failure();
+        let fetcher = DocFetcher::with_config(cache, config);
+
+        for _ in 0..5 {
+            let doc = fetcher.fetch(DocsRsParams {
+                crate_name: "demo".to_string(),
+                version: "2.0.0".to_string(),
+                path: "demo/index.html".to_string(),
+            }).await.unwrap();
+            assert!(doc.content.starts_with("[Served stale from cache (version 1.10.0)"), "{}", doc.content);
+        }
+
+        let mut versions = vec!["1.9.0", "latest", "beta", "1.10.0", "1.10.0-rc.1", "alpha"];
+        versions.sort_by(|a, b| newest_version_first(a, b));
+        assert_eq!(versions, ["latest", "1.10.0", "1.10.0-rc.1", "1.9.0", "beta", "alpha"]);
+    }
+
+    #[tokio::test]
+    async fn test_missing_page_revalidated_with_head_after_ttl() {
+        let mut docs = mockito::Server::new_async().await;
+        let path = "/demo/1.0.0/demo/struct.New.html";
+        let missing = docs.mock("GET", path).with_status(404).expect(1).create();
+
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let ttl = std::time::Duration::from_millis(50);
+        let config = FetcherConfig {
+            docs_base_url: docs.url(),
+            not_found_ttl: ttl,
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache.clone(), config);
+        let params = DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            path: "demo/struct.New.html".to_string(),
+        };
+
+        // Within the TTL the missing page is not requested again
+        for _ in 0..2 {
+            assert!(matches!(fetcher.fetch(params.clone()).await, Err(DocsFetchError::DocsNotFound)));
+        }
+        missing.assert();
+        missing.remove();
+
+        // After the TTL a HEAD finds it still missing, without a GET
+        tokio::time::sleep(ttl).await;
+        let still_missing = docs.mock("HEAD", path).with_status(404).expect(1).create();
+        assert!(matches!(fetcher.fetch(params.clone()).await, Err(DocsFetchError::DocsNotFound)));
+        still_missing.assert();
+        still_missing.remove();
+
+        // Once built, the HEAD succeeds and the page is downloaded and cached
+        tokio::time::sleep(ttl).await;
+        let exists = docs.mock("HEAD", path).with_status(200).expect(1).create();
+        let page = docs.mock("GET", path)
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper">New docs</div>"#)
+            .expect(1)
+            .create();
+        assert_eq!(fetcher.fetch(params.clone()).await.unwrap().content, "New docs");
+        exists.assert();
+        page.assert();
+        assert!(fetcher.is_cached(&params).await);
+        assert!(fetcher.not_found_since(&params).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_rejects_content_below_minimum_length() {
+        let mut docs = mockito::Server::new_async().await;
+        docs.mock("GET", "/demo/1.0.0/demo/index.html")
+            .with_status(200)
+            .with_body(r#"<html><body><div id="rustdoc_body_wrapper"> ok </div></body></html>"#)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let config = FetcherConfig {
+            docs_base_url: docs.url(),
+            min_content_len: 16,
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache.clone(), config);
+        let params = DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            path: "demo/index.html".to_string(),
+        };
+
+        let result = fetcher.fetch(params.clone()).await;
+        assert!(matches!(result, Err(DocsFetchError::ParseError(_))));
+        assert!(!fetcher.is_cached(&params).await);
+    }
+
+    #[tokio::test]
+    async fn test_single_and_batch_fetches_share_upstream_request() {
+        let mut docs = mockito::Server::new_async().await;
+        let page = docs.mock("GET", "/demo/1.0.0/demo/struct.Shared.html")
+            .with_status(200)
+            .with_chunked_body(|w| {
+                // Keep the request in flight long enough for both callers to join
+                std::thread::sleep(std::time::Duration::from_millis(200));
+                w.write_all(br#"<div id="rustdoc_body_wrapper">Shared docs</div>"#)
+            })
+            .expect(1)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+        registry.mock("GET", "/api/v1/crates/demo")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"demo"}}"#)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let config = FetcherConfig {
+            docs_base_url: docs.url(),
+            crates_io_base_url: registry.url(),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache.clone(), config);
+        let path = "demo/struct.Shared.html".to_string();
+
+        let (single, batch) = tokio::join!(
+            fetcher.fetch_document("demo".to_string(), "1.0.0".to_string(), path.clone(), None, None, None, None, None, None),
+            fetcher.fetch_crate_paths("demo".to_string(), "1.0.0".to_string(), vec![path.clone()], None),
+        );
+
+        page.assert();
+        assert_eq!(single.unwrap().content, "Shared docs");
+        assert_eq!(batch.unwrap().documents[&path], PathResult::Content("Shared docs".to_string()));
+        assert!(fetcher.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_info_uses_configured_instructions() {
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+
+        let default = DocFetcher::new(cache.clone()).get_info();
+        assert!(default.instructions.unwrap().contains("fetch_document"));
+
+        let config = FetcherConfig {
+            instructions: Some("Docs come from the internal mirror; only approved crates are available.".to_string()),
+            ..FetcherConfig::default()
+        };
+        let info = DocFetcher::with_config(cache, config).get_info();
+        assert_eq!(
+            info.instructions.as_deref(),
+            Some("Docs come from the internal mirror; only approved crates are available.")
+        );
+    }
+
+
+    /// Gate holding back mocked responses until it is opened.
+    type ResponseGate = Arc<(std::sync::Mutex<bool>, std::sync::Condvar)>;
+
+    /// Returns a mock response body that is only sent once `gate` is
+    /// opened, or after a few seconds so a broken test cannot hang.
+    fn gated_body(gate: &ResponseGate, body: &'static [u8]) -> impl Fn(&mockito::Request) -> Vec<u8> + Send + Sync + 'static {
+        let gate = gate.clone();
+        move |_| {
+            let (open, opened) = &*gate;
+            let open = open.lock().unwrap();
+            drop(opened.wait_timeout_while(open, std::time::Duration::from_secs(5), |open| !*open).unwrap());
+            body.to_vec()
+        }
+    }
+
+    /// Opens `gate` once `callers` callers wait on the in-flight fetch of a
+    /// page, so that none of them can miss it.
+    async fn open_when_joined(fetcher: &DocFetcher, callers: usize, gate: &ResponseGate) {
+        loop {
+            // The map holds one handle of the fetch, each caller another
+            let joined = fetcher.in_flight.lock().unwrap().values()
+                .any(|fetch| fetch.strong_count().is_some_and(|count| count > callers));
+            if joined {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+        }
+        let (open, opened) = &**gate;
+        *open.lock().unwrap() = true;
+        opened.notify_all();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_fetch_documents_share_one_request() {
+        let mut docs = mockito::Server::new_async().await;
+        let mut registry = mockito::Server::new_async().await;
+        let fetcher = demo_fetcher(&docs, &mut registry);
+        let gate = ResponseGate::default();
+        let page = docs.mock("GET", "/demo/1.0.0/demo/struct.Popular.html")
+            .with_status(200)
+            .with_body_from_request(gated_body(&gate, br#"<div id="rustdoc_body_wrapper">Popular docs</div>"#))
+            .expect(1)
+            .create();
+
+        let requests = (0..16).map(|_| {
+            fetcher.fetch_document("demo".to_string(), "1.0.0".to_string(), "demo/struct.Popular.html".to_string(), None, None, None, None, None, None)
+        });
+        let (results, ()) = tokio::join!(futures::future::join_all(requests), open_when_joined(&fetcher, 16, &gate));
+
+        page.assert();
+        for result in results {
+            assert_eq!(result.unwrap().content, "Popular docs");
+        }
+        assert!(fetcher.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_fetches_are_not_shared_when_coalescing_is_off() {
+        let mut docs = mockito::Server::new_async().await;
+        let mut registry = mockito::Server::new_async().await;
+        let fetcher = demo_fetcher(&docs, &mut registry);
+        let mut config = (*fetcher.config()).clone();
+        config.coalesce_fetches = false;
+        *fetcher.config.write().unwrap() = Arc::new(config);
+        let page = docs.mock("GET", "/demo/1.0.0/demo/struct.Popular.html")
+            .with_status(200)
+            .with_chunked_body(|w| {
+                std::thread::sleep(std::time::Duration::from_millis(200));
+                w.write_all(br#"<div id="rustdoc_body_wrapper">Popular docs</div>"#)
+            })
+            .expect(2)
+            .create();
+
+        let requests = (0..2).map(|_| {
+            fetcher.fetch_document("demo".to_string(), "1.0.0".to_string(), "demo/struct.Popular.html".to_string(), None, None, None, None, None, None)
+        });
+        let results = futures::future::join_all(requests).await;
+
+        page.assert();
+        for result in results {
+            assert_eq!(result.unwrap().content, "Popular docs");
+        }
+        assert!(fetcher.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_coalesced_fetch_does_not_poison_later_requests() {
+        let mut docs = mockito::Server::new_async().await;
+        let mut registry = mockito::Server::new_async().await;
+        let fetcher = demo_fetcher(&docs, &mut registry);
+        let params = DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            path: "demo/struct.Flaky.html".to_string(),
+        };
+
+        let gate = ResponseGate::default();
+        let failing = docs.mock("GET", "/demo/1.0.0/demo/struct.Flaky.html")
+            .with_status(503)
+            .with_body_from_request(gated_body(&gate, b"busy"))
+            .expect(1)
+            .create();
+        let (first, second, ()) = tokio::join!(
+            fetcher.fetch(params.clone()),
+            fetcher.fetch(params.clone()),
+            open_when_joined(&fetcher, 2, &gate),
+        );
+        assert!(matches!(first, Err(DocsFetchError::ServiceUnavailable)));
+        assert!(matches!(second, Err(DocsFetchError::ServiceUnavailable)));
+        failing.assert();
+        failing.remove();
+        assert!(fetcher.in_flight.lock().unwrap().is_empty());
+
+        let page = docs.mock("GET", "/demo/1.0.0/demo/struct.Flaky.html")
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper">Flaky docs</div>"#)
+            .expect(1)
+            .create();
+        assert_eq!(fetcher.fetch(params).await.unwrap().content, "Flaky docs");
+        page.assert();
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_fetch_still_completes_and_unregisters() {
+        let mut docs = mockito::Server::new_async().await;
+        let mut registry = mockito::Server::new_async().await;
+        let fetcher = demo_fetcher(&docs, &mut registry);
+        let params = DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            path: "demo/struct.Slow.html".to_string(),
+        };
+        let page = docs.mock("GET", "/demo/1.0.0/demo/struct.Slow.html")
+            .with_status(200)
+            .with_chunked_body(|w| {
+                std::thread::sleep(std::time::Duration::from_millis(200));
+                w.write_all(br#"<div id="rustdoc_body_wrapper">Slow docs</div>"#)
+            })
+            .expect(1)
+            .create();
+
+        // The only waiter gives up once the fetch is under way
+        let waiter = tokio::spawn({
+            let (fetcher, params) = (fetcher.clone(), params.clone());
+            async move { fetcher.fetch(params).await }
+        });
+        let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(5);
+        while fetcher.in_flight.lock().unwrap().is_empty() {
+            assert!(tokio::time::Instant::now() < deadline, "fetch was never registered");
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        waiter.abort();
+        assert!(waiter.await.unwrap_err().is_cancelled());
+
+        while !fetcher.in_flight.lock().unwrap().is_empty() {
+            assert!(tokio::time::Instant::now() < deadline, "abandoned fetch was never unregistered");
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert!(fetcher.is_cached(&params).await);
+        page.assert();
+    }
+
+    #[tokio::test]
+    async fn test_crate_tags_refetched_after_ttl() {
+        let mut registry = mockito::Server::new_async().await;
+        let lookup = registry.mock("GET", "/api/v1/crates/demo")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"demo","categories":["parsing"],"keywords":["json"]}}"#)
+            .expect(2)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let config = FetcherConfig {
+            crates_io_base_url: registry.url(),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache.clone(), config);
+
+        // One lookup confirms the crate name, the next fetches the tags
+        let tags = fetcher.crate_tags("demo".to_string()).await.unwrap();
+        assert_eq!(tags.categories, vec!["parsing"]);
+        assert_eq!(fetcher.crate_tags("demo".to_string()).await.unwrap(), tags);
+        lookup.assert();
+
+        // An expired entry is refetched
+        let key = derived_cache_key("demo", "latest", "tags");
+        let expired = Timestamped { fetched_at: 0, value: &CrateTags::default() };
+        cache.insert(key, DocContent { content: serde_json::to_string(&expired).unwrap(), source_url: None }).await;
+        let refetch = registry.mock("GET", "/api/v1/crates/demo")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"demo","keywords":["serde"]}}"#)
+            .expect(1)
+            .create();
+        let tags = fetcher.crate_tags("demo".to_string()).await.unwrap();
+        assert_eq!(tags.keywords, vec!["serde"]);
+        assert!(tags.categories.is_empty());
+        refetch.assert();
+    }
+
+    #[tokio::test]
+    async fn test_crate_full_metadata_from_one_response() {
+        let mut registry = mockito::Server::new_async().await;
+        let lookup = registry.mock("GET", "/api/v1/crates/demo_json")
+            .with_status(200)
+            .with_body(r#"{
+                "categories": [{"id": "parsing", "category": "Parsing tools", "slug": "parsing"}],
+                "crate": {
+                    "id": "demo-json", "name": "demo-json",
+                    "description": "  A fast JSON parser.\n",
+                    "homepage": null, "repository": "https://github.com/demo/demo-json",
+                    "documentation": null,
+                    "downloads": 123456, "recent_downloads": 7890,
+                    "max_version": "2.0.0-beta.1", "max_stable_version": "1.1.0", "newest_version": "2.0.0-beta.1",
+                    "categories": ["parsing"], "keywords": ["json", "serde"]
+                },
+                "keywords": [{"id": "json", "keyword": "json"}, {"id": "serde", "keyword": "serde"}],
+                "versions": [
+                    {"id": 4, "num": "2.0.0-beta.1", "yanked": false, "license": "MIT OR Apache-2.0",
+                     "published_by": {"id": 2, "login": "bob", "name": null}},
+                    {"id": 3, "num": "1.1.0", "yanked": false, "license": "MIT",
+                     "published_by": {"id": 1, "login": "alice", "name": "Alice"}},
+                    {"id": 2, "num": "1.0.1", "yanked": true, "license": "MIT",
+                     "published_by": {"id": 1, "login": "alice", "name": "Alice"}},
+                    {"id": 1, "num": "1.0.0", "yanked": false, "license": "MIT", "published_by": null}
+                ]
+            }"#)
+            .expect(1)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let config = FetcherConfig {
+            crates_io_base_url: registry.url(),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache, config);
+
+        let metadata = fetcher.crate_full_metadata("Demo_JSON".to_string()).await.unwrap();
+        assert_eq!(metadata.crate_name, "demo-json");
+        assert_eq!(metadata.description.as_deref(), Some("A fast JSON parser."));
+        assert_eq!(metadata.license.as_deref(), Some("MIT"), "license of the latest stable version");
+        assert_eq!(metadata.repository.as_deref(), Some("https://github.com/demo/demo-json"));
+        assert_eq!(metadata.homepage, None);
+        assert_eq!((metadata.downloads, metadata.recent_downloads), (123456, Some(7890)));
+        assert_eq!(metadata.max_stable_version.as_deref(), Some("1.1.0"));
+        assert_eq!(metadata.newest_version.as_deref(), Some("2.0.0-beta.1"));
+        assert_eq!(metadata.categories, ["parsing"]);
+        assert_eq!(metadata.keywords, ["json", "serde"]);
+        let versions: Vec<_> = metadata.versions.iter().map(|v| (v.num.as_str(), v.yanked)).collect();
+        assert_eq!(versions, [("2.0.0-beta.1", false), ("1.1.0", false), ("1.0.1", true), ("1.0.0", false)]);
+        assert_eq!(metadata.publishers, [
+            Publisher { login: "alice".to_string(), name: Some("Alice".to_string()), versions: 2 },
+            Publisher { login: "bob".to_string(), name: None, versions: 1 },
+        ]);
+
+        // Cached together, including the tags and versions for other tools
+        assert_eq!(fetcher.crate_full_metadata("demo-json".to_string()).await.unwrap(), metadata);
+        assert_eq!(fetcher.crate_tags("demo_json".to_string()).await.unwrap(), metadata.tags());
+        assert_eq!(fetcher.published_versions("demo-json").await.unwrap(), metadata.versions);
+        lookup.assert();
+    }
+
+    #[tokio::test]
+    async fn test_get_crate_metadata_per_version() {
+        let mut registry = mockito::Server::new_async().await;
+        // crates.io answers for both spellings of the name
+        let lookup = registry.mock("GET", mockito::Matcher::Regex("^/api/v1/crates/demo[-_]json$".to_string()))
+            .with_status(200)
+            .with_body(r#"{
+                "crate": {
+                    "id": "demo-json", "name": "demo-json",
+                    "description": "A fast JSON parser.\n",
+                    "homepage": "https://demo-json.rs", "repository": "https://github.com/demo/demo-json",
+                    "downloads": 123456, "max_stable_version": "1.1.0", "newest_version": "2.0.0-beta.1"
+                },
+                "versions": [
+                    {"id": 3, "num": "2.0.0-beta.1", "yanked": false, "license": "MIT OR Apache-2.0",
+                     "features": {"default": ["std"], "std": [], "simd": []}},
+                    {"id": 2, "num": "1.1.0", "yanked": false, "license": "MIT OR Apache-2.0",
+                     "features": {"default": ["std"], "std": [], "serde": ["dep:serde"]}},
+                    {"id": 1, "num": "1.0.0", "yanked": false, "license": "MIT"}
+                ]
+            }"#)
+            .expect(2)
+            .create();
+        registry.mock("GET", "/api/v1/crates/missing")
+            .with_status(404)
+            .with_body(r#"{"errors":[{"detail":"crate `missing` does not exist"}]}"#)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let config = FetcherConfig {
+            crates_io_base_url: registry.url(),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache, config);
+
+        let latest = fetcher.get_crate_metadata("Demo_JSON".to_string(), None).await.unwrap();
+        assert_eq!(latest, CrateVersionMetadata {
+            crate_name: "demo-json".to_string(),
+            version: "1.1.0".to_string(),
+            description: Some("A fast JSON parser.".to_string()),
+            license: Some("MIT OR Apache-2.0".to_string()),
+            homepage: Some("https://demo-json.rs".to_string()),
+            repository: Some("https://github.com/demo/demo-json".to_string()),
+            downloads: 123456,
+            features: BTreeMap::from([
+                ("default".to_string(), vec!["std".to_string()]),
+                ("serde".to_string(), vec!["dep:serde".to_string()]),
+                ("std".to_string(), vec![]),
+            ]),
+        });
+        let contents = latest.clone().into_contents();
+        let json: serde_json::Value = serde_json::from_str(&contents[0].as_text().unwrap().text).unwrap();
+        assert_eq!(json["features"]["serde"][0], "dep:serde");
+
+        // Each version is cached on its own
+        assert_eq!(fetcher.get_crate_metadata("demo-json".to_string(), Some("latest".to_string())).await.unwrap(), latest);
+        // Padded versions are fetched and cached trimmed
+        let old = fetcher.get_crate_metadata("demo-json".to_string(), Some(" 1.0.0 ".to_string())).await.unwrap();
+        assert_eq!((old.version.as_str(), old.license.as_deref()), ("1.0.0", Some("MIT")));
+        assert!(old.features.is_empty());
+        assert_eq!(fetcher.get_crate_metadata("demo-json".to_string(), Some("1.0.0".to_string())).await.unwrap(), old);
+        lookup.assert();
+
+        let unknown_version = fetcher.get_crate_metadata("demo-json".to_string(), Some("9.9.9".to_string())).await;
+        assert!(matches!(unknown_version, Err(DocsFetchError::DocsNotFound)), "got {:?}", unknown_version);
+        let missing = fetcher.get_crate_metadata("missing".to_string(), None).await;
+        assert!(matches!(missing, Err(DocsFetchError::DocsNotFound)), "got {:?}", missing);
+    }
+
+    #[tokio::test]
+    async fn test_feature_graph_cached_per_version() {
+        let mut registry = mockito::Server::new_async().await;
+        let features = registry.mock("GET", "/api/v1/crates/demo/1.0.0")
+            .with_status(200)
+            .with_body(r#"{"version":{"features":{"default":["std"],"std":["alloc"],"alloc":[]}}}"#)
+            .expect(1)
+            .create();
+        registry.mock("GET", "/api/v1/crates/demo")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"demo"}}"#)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().to_path_buf()));
+        let config = FetcherConfig {
+            crates_io_base_url: registry.url(),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache.clone(), config);
+
+        for _ in 0..2 {
+            let graph = fetcher.feature_graph("demo".to_string(), "1.0.0".to_string()).await.unwrap();
+            assert_eq!(graph.features["default"].transitive, vec!["alloc", "std"]);
+        }
+        features.assert();
+    }
+
+    #[tokio::test]
+    async fn test_metadata_cache_ttl_independent_of_content() {
+        let mut docs = mockito::Server::new_async().await;
+        let page = docs.mock("GET", "/demo/1.0.0/demo/index.html")
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper">Demo docs</div>"#)
+            .expect(1)
+            .create();
+        let mut registry = mockito::Server::new_async().await;
+        let lookup = registry.mock("GET", "/api/v1/crates/demo")
+            .with_status(200)
+            .with_body(r#"{"crate":{"name":"demo","categories":["parsing"]}}"#)
+            .expect(3)
+            .create();
+
+        let temp_dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(temp_dir.path().join("docs")));
+        let metadata_cache = Arc::new(InMemoryCache::new(temp_dir.path().join("metadata")));
+        let config = FetcherConfig {
+            docs_base_url: docs.url(),
+            crates_io_base_url: registry.url(),
+            metadata_cache: Some(metadata_cache.clone()),
+            metadata_ttl: std::time::Duration::ZERO,
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache.clone(), config);
+
+        // One lookup confirms the crate name, then every call refetches the
+        // expired tags while the page is served from the content cache
+        for _ in 0..2 {
+            let doc = fetcher.fetch_document("demo".to_string(), "1.0.0".to_string(), "demo/index.html".to_string(), None, None, None, None, None, None)
+                .await
+                .unwrap();
+            assert_eq!(doc.content, "Demo docs");
+            assert_eq!(fetcher.crate_tags("demo".to_string()).await.unwrap().categories, vec!["parsing"]);
+        }
+        page.assert();
+        lookup.assert();
+
+        let key = derived_cache_key("demo", "latest", "tags");
+        assert!(metadata_cache.contains_key(&key).await);
+        assert!(!cache.contains_key(&key).await);
+    }
+
+    #[test]
+    fn test_truncate_content_respects_char_boundaries() {
+        let content = "é".repeat(100);
+        let truncated = truncate_content(&content, 60);
+        assert!(truncated.len() <= 60);
+        assert!(truncated.starts_with('é'));
+        assert_eq!(truncate_content("short", 60), "short");
+    }
+
+    #[test]
+    fn test_truncate_content_below_marker_size() {
+        let content = "é".repeat(100);
+        for max_bytes in [0, 1, 5, 20] {
+            let truncated = truncate_content(&content, max_bytes);
+            assert!(truncated.len() <= max_bytes, "{} bytes for a limit of {}", truncated.len(), max_bytes);
+            assert!(content.starts_with(&truncated));
+        }
     }
 
     #[tokio::test]
@@ -248,7 +6136,7 @@ mod tests {
         let version = "1.0".to_string();
         let path = "serde/index.html".to_string();
 
-        doc_fetcher.clear_cache().await;
+        doc_fetcher.clear_cache(None).await;
 
         // First fetch - should be a cache miss
         println!("First fetch attempt (expect cache miss)...");
@@ -257,6 +6145,12 @@ mod tests {
             crate_name.clone(),
             version.clone(),
             path.clone(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         ).await.unwrap();
         let duration1 = start1.elapsed();
         println!("First fetch took: {:?}", duration1);
@@ -268,6 +6162,12 @@ mod tests {
             crate_name.clone(),
             version.clone(),
             path.clone(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         ).await.unwrap();
         let duration2 = start2.elapsed();
         println!("Second fetch took: {:?}", duration2);
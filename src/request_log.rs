@@ -0,0 +1,208 @@
+//! Persistent log of `fetch_document` requests, replayable for debugging.
+//!
+//! Every `fetch_document` call is appended as one JSON line holding the time
+//! it arrived and its [`DocsRsParams`], but not the returned content. Unlike
+//! the [access log](crate::access_log), which only ranks documents for the
+//! warm-up, the request log keeps every call in order, so that a session can
+//! be reproduced with [`replay`], e.g. against another documentation server
+//! or to warm a fresh cache.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cache::unix_now;
+use crate::docs_parser::DocsRsParams;
+use crate::mcp::DocFetcher;
+
+/// One logged request.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LoggedRequest {
+    /// Unix time in seconds the request arrived
+    pub timestamp: u64,
+    /// Parameters of the request
+    #[serde(flatten)]
+    pub params: DocsRsParams,
+}
+
+/// Append-only request log writer.
+#[derive(Debug)]
+pub struct RequestLog {
+    /// Location of the log file
+    path: PathBuf,
+    /// Open handle in append mode
+    file: Mutex<File>,
+}
+
+impl RequestLog {
+    /// Opens (or creates) the request log at `path` for appending.
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error if the file cannot be opened.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self { path, file: Mutex::new(file) })
+    }
+
+    /// Appends a request arriving now to the log.
+    ///
+    /// Failures are logged and otherwise ignored, so that a full disk never
+    /// fails the request itself.
+    pub fn record(&self, params: &DocsRsParams) {
+        let entry = LoggedRequest { timestamp: unix_now(), params: params.clone() };
+        let mut line = match serde_json::to_string(&entry) {
+            Ok(line) => line,
+            Err(e) => {
+                tracing::warn!("Failed to serialize request log entry {:?}: {}", params, e);
+                return;
+            }
+        };
+        line.push('\n');
+
+        let mut file = match self.file.lock() {
+            Ok(file) => file,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if let Err(e) = file.write_all(line.as_bytes()) {
+            tracing::warn!("Failed to append to request log {:?}: {}", self.path, e);
+        }
+    }
+}
+
+/// Reads the requests of a request log in the order they were logged.
+///
+/// Malformed lines, e.g. a line truncated by a crash, are skipped.
+///
+/// # Errors
+///
+/// Returns an IO error if the file cannot be read.
+pub fn read_requests(path: &Path) -> io::Result<Vec<LoggedRequest>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut requests = Vec::new();
+    for (line_no, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<LoggedRequest>(&line) {
+            Ok(request) => requests.push(request),
+            Err(e) => tracing::debug!("Skipping malformed request log line {}: {}", line_no + 1, e),
+        }
+    }
+    Ok(requests)
+}
+
+/// Outcome of a replay.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReplaySummary {
+    /// Requests answered successfully
+    pub succeeded: usize,
+    /// Requests that failed
+    pub failed: usize,
+}
+
+/// Re-issues the requests of a request log through `fetcher`, one after the
+/// other in their logged order.
+///
+/// Requests already answered by the fetcher's cache are not sent again.
+/// Failures are logged and do not stop the replay.
+///
+/// # Errors
+///
+/// Returns an IO error if the log cannot be read.
+pub async fn replay(fetcher: &DocFetcher, path: &Path) -> io::Result<ReplaySummary> {
+    let mut summary = ReplaySummary::default();
+    for request in read_requests(path)? {
+        match fetcher.fetch(request.params.clone()).await {
+            Ok(_) => summary.succeeded += 1,
+            Err(e) => {
+                tracing::warn!("Failed to replay {:?}: {}", request.params, e);
+                summary.failed += 1;
+            }
+        }
+    }
+    tracing::info!(
+        "Replayed request log {:?}: {} succeeded, {} failed.",
+        path,
+        summary.succeeded,
+        summary.failed
+    );
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::{Cache, InMemoryCache};
+    use crate::mcp::FetcherConfig;
+    use mockito::Server;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    fn params(path: &str) -> DocsRsParams {
+        DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            path: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_read_requests_keeps_order_and_skips_malformed_lines() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("requests.log");
+        let log = RequestLog::open(&path).unwrap();
+        log.record(&params("b.html"));
+        log.record(&params("a.html"));
+        log.record(&params("b.html"));
+        drop(log);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"timestamp\":1,\"crate_na\n").unwrap();
+
+        let requests = read_requests(&path).unwrap();
+        let paths: Vec<_> = requests.iter().map(|r| r.params.path.as_str()).collect();
+        assert_eq!(paths, ["b.html", "a.html", "b.html"]);
+        assert!(requests.iter().all(|r| r.timestamp > 0));
+    }
+
+    #[tokio::test]
+    async fn test_replay_populates_fresh_cache() {
+        let dir = tempdir().unwrap();
+        let log_path = dir.path().join("requests.log");
+        {
+            let log = RequestLog::open(&log_path).unwrap();
+            log.record(&params("demo/struct.Found.html"));
+            log.record(&params("demo/struct.Missing.html"));
+            log.record(&params("demo/struct.Found.html"));
+        }
+
+        let mut server = Server::new_async().await;
+        let found = server.mock("GET", "/demo/1.0.0/demo/struct.Found.html")
+            .with_status(200)
+            .with_body(r#"<div id="rustdoc_body_wrapper">Found docs</div>"#)
+            .expect(1)
+            .create();
+        server.mock("GET", "/demo/1.0.0/demo/struct.Missing.html")
+            .with_status(404)
+            .create();
+
+        let cache = Arc::new(InMemoryCache::new(dir.path().join("cache")));
+        let config = FetcherConfig {
+            docs_base_url: server.url(),
+            ..FetcherConfig::default()
+        };
+        let fetcher = DocFetcher::with_config(cache.clone(), config);
+
+        let summary = replay(&fetcher, &log_path).await.unwrap();
+
+        found.assert();
+        assert_eq!(summary, ReplaySummary { succeeded: 2, failed: 1 });
+        let content = cache.get(&params("demo/struct.Found.html").into()).await.unwrap();
+        assert!(content.content.contains("Found docs"));
+    }
+}
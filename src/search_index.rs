@@ -0,0 +1,537 @@
+//! Parsing of rustdoc's `search-index.js` into a structured item listing.
+//!
+//! rustdoc ships every crate's documentation with a JavaScript search index
+//! describing all documented items. Reading that single file is far cheaper
+//! than crawling module pages one by one, so this module turns it into a flat
+//! list of [`IndexItem`]s and groups them into a [`CrateIndex`].
+//!
+//! The index format is not stable across rustdoc releases. The parser accepts
+//! the shapes emitted by recent rustdoc versions:
+//! - `var searchIndex = new Map(JSON.parse('[["crate",{...}]]'));`
+//! - `var searchIndex = JSON.parse('{"crate":{...}}');`
+//!
+//! Within a crate entry, `n` holds item names, `t` their kinds (either a string
+//! with one letter per item, `'A'` being kind `0`, or an array of integers) and
+//! `q` the module paths (either sparse `[index, path]` pairs that apply until the
+//! next pair, or a dense array where an empty string repeats the previous path).
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::docs_parser::DocsFetchError;
+
+/// Kind of an item listed in the search index.
+///
+/// Only the kinds that have their own documentation page and are useful for
+/// navigation are represented; associated items (methods, fields, variants)
+/// are skipped while parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    Module,
+    Struct,
+    Enum,
+    Trait,
+    Function,
+    Macro,
+    TypeAlias,
+}
+
+impl ItemKind {
+    /// Maps rustdoc's numeric `ItemType` discriminant to an [`ItemKind`].
+    fn from_rustdoc_code(code: u64) -> Option<Self> {
+        match code {
+            0 => Some(Self::Module),
+            3 => Some(Self::Struct),
+            4 => Some(Self::Enum),
+            5 => Some(Self::Function),
+            6 => Some(Self::TypeAlias),
+            8 => Some(Self::Trait),
+            14 => Some(Self::Macro),
+            _ => None,
+        }
+    }
+
+    /// Parses a kind filter, accepting the short names `mod`, `struct`,
+    /// `enum`, `trait`, `fn`, `macro` and `type` as well as the serialized
+    /// names of the kinds, in any case.
+    pub fn from_filter(filter: &str) -> Option<Self> {
+        match filter.trim().to_ascii_lowercase().as_str() {
+            "mod" | "module" => Some(Self::Module),
+            "struct" => Some(Self::Struct),
+            "enum" => Some(Self::Enum),
+            "trait" => Some(Self::Trait),
+            "fn" | "function" => Some(Self::Function),
+            "macro" => Some(Self::Macro),
+            "type" | "type_alias" => Some(Self::TypeAlias),
+            _ => None,
+        }
+    }
+
+    /// Returns the file name prefix rustdoc uses for pages of this kind.
+    fn page_prefix(self) -> &'static str {
+        match self {
+            Self::Module => "",
+            Self::Struct => "struct.",
+            Self::Enum => "enum.",
+            Self::Trait => "trait.",
+            Self::Function => "fn.",
+            Self::Macro => "macro.",
+            Self::TypeAlias => "type.",
+        }
+    }
+}
+
+/// A single documented item found in the search index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexItem {
+    /// Item name, e.g. `HashMap`
+    pub name: String,
+    /// Kind of the item
+    pub kind: ItemKind,
+    /// Fully qualified Rust path, e.g. `std::collections::HashMap`
+    pub path: String,
+    /// Documentation page path relative to the crate version root,
+    /// e.g. `std/collections/struct.HashMap.html`
+    pub doc_path: String,
+}
+
+impl IndexItem {
+    fn new(name: &str, kind: ItemKind, module_path: &str) -> Self {
+        let module_dir = module_path.replace("::", "/");
+        let doc_path = match kind {
+            ItemKind::Module => format!("{}/{}/index.html", module_dir, name),
+            _ => format!("{}/{}{}.html", module_dir, kind.page_prefix(), name),
+        };
+        Self {
+            name: name.to_string(),
+            kind,
+            path: format!("{}::{}", module_path, name),
+            doc_path,
+        }
+    }
+}
+
+/// All navigable items of a crate as listed in its search index.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchIndex {
+    /// Items in index order
+    pub items: Vec<IndexItem>,
+}
+
+/// A crate's items grouped by kind, sorted by path within each group.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrateIndex {
+    pub crate_name: String,
+    pub version: String,
+    pub modules: Vec<IndexItem>,
+    pub structs: Vec<IndexItem>,
+    pub enums: Vec<IndexItem>,
+    pub traits: Vec<IndexItem>,
+    pub functions: Vec<IndexItem>,
+    pub macros: Vec<IndexItem>,
+    pub type_aliases: Vec<IndexItem>,
+}
+
+impl CrateIndex {
+    /// Groups the items of a search index into per-kind buckets.
+    pub fn from_index(crate_name: &str, version: &str, index: &SearchIndex) -> Self {
+        let mut grouped = Self {
+            crate_name: crate_name.to_string(),
+            version: version.to_string(),
+            ..Self::default()
+        };
+        for item in &index.items {
+            let bucket = match item.kind {
+                ItemKind::Module => &mut grouped.modules,
+                ItemKind::Struct => &mut grouped.structs,
+                ItemKind::Enum => &mut grouped.enums,
+                ItemKind::Trait => &mut grouped.traits,
+                ItemKind::Function => &mut grouped.functions,
+                ItemKind::Macro => &mut grouped.macros,
+                ItemKind::TypeAlias => &mut grouped.type_aliases,
+            };
+            bucket.push(item.clone());
+        }
+        for bucket in [
+            &mut grouped.modules,
+            &mut grouped.structs,
+            &mut grouped.enums,
+            &mut grouped.traits,
+            &mut grouped.functions,
+            &mut grouped.macros,
+            &mut grouped.type_aliases,
+        ] {
+            bucket.sort_by(|a, b| a.path.cmp(&b.path));
+        }
+        grouped
+    }
+}
+
+/// The items of one kind of a crate, sorted by path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrateItems {
+    pub crate_name: String,
+    pub version: String,
+    pub kind: ItemKind,
+    pub items: Vec<IndexItem>,
+}
+
+/// Maximum number of items returned by a search.
+pub const MAX_SEARCH_HITS: usize = 50;
+
+/// Items of a crate matching a search query, best matches first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub crate_name: String,
+    pub version: String,
+    pub query: String,
+    /// Matching items, at most [`MAX_SEARCH_HITS`]
+    pub items: Vec<IndexItem>,
+    /// Whether more items matched than are returned
+    pub truncated: bool,
+}
+
+/// Candidate items for a possibly partial item name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolvedPath {
+    pub crate_name: String,
+    pub version: String,
+    /// Name that was resolved, e.g. `HashMap` or `collections::HashMap`
+    pub name: String,
+    /// Matching items, shortest path first; empty if nothing matched
+    pub candidates: Vec<IndexItem>,
+}
+
+impl SearchIndex {
+    /// Returns the items of kind `kind`, sorted by path.
+    pub fn items_of_kind(&self, kind: ItemKind) -> Vec<IndexItem> {
+        let mut items: Vec<IndexItem> = self.items.iter().filter(|item| item.kind == kind).cloned().collect();
+        items.sort_by(|a, b| a.path.cmp(&b.path));
+        items
+    }
+
+    /// Returns the items matching `query`, compared case-insensitively with
+    /// item names and paths, best matches first: exact names, then names
+    /// starting with the query, names containing it and finally paths
+    /// containing it. Equally good matches are ordered by path length, so
+    /// items close to the crate root come first, then by path.
+    pub fn search(&self, query: &str) -> Vec<IndexItem> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let rank = |item: &IndexItem| {
+            let name = item.name.to_lowercase();
+            match () {
+                _ if name == query => Some(0),
+                _ if name.starts_with(&query) => Some(1),
+                _ if name.contains(&query) => Some(2),
+                _ if item.path.to_lowercase().contains(&query) => Some(3),
+                _ => None,
+            }
+        };
+        let mut hits: Vec<(u8, &IndexItem)> = self.items.iter()
+            .filter_map(|item| Some((rank(item)?, item)))
+            .collect();
+        hits.sort_by(|(a_rank, a), (b_rank, b)| {
+            a_rank.cmp(b_rank)
+                .then_with(|| a.path.len().cmp(&b.path.len()))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        hits.into_iter().map(|(_, item)| item.clone()).collect()
+    }
+
+    /// Returns the items whose path ends with `name`, which may be a bare
+    /// item name or a path suffix such as `collections::HashMap`.
+    ///
+    /// Names are matched exactly first; only if nothing matches are they
+    /// compared case-insensitively. Candidates are ordered by path length,
+    /// so items re-exported close to the crate root come first, then by path.
+    pub fn resolve(&self, name: &str) -> Vec<IndexItem> {
+        let name = name.trim().trim_start_matches("::");
+        if name.is_empty() {
+            return Vec::new();
+        }
+        let suffix = format!("::{}", name);
+        let mut candidates: Vec<IndexItem> = self.items.iter()
+            .filter(|item| item.path == name || item.path.ends_with(&suffix))
+            .cloned()
+            .collect();
+        if candidates.is_empty() {
+            let name = name.to_lowercase();
+            let suffix = suffix.to_lowercase();
+            candidates = self.items.iter()
+                .filter(|item| {
+                    let path = item.path.to_lowercase();
+                    path == name || path.ends_with(&suffix)
+                })
+                .cloned()
+                .collect();
+        }
+        candidates.sort_by(|a, b| {
+            a.path.matches("::").count().cmp(&b.path.matches("::").count())
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        candidates.dedup_by(|a, b| a.doc_path == b.doc_path);
+        candidates
+    }
+}
+
+/// Parses the contents of a `search-index.js` file for the given crate.
+///
+/// The crate name is matched with hyphens normalized to underscores, since
+/// rustdoc always uses the Rust identifier form.
+///
+/// # Errors
+///
+/// Returns [`DocsFetchError::ParseError`] if the payload cannot be located or
+/// decoded, and [`DocsFetchError::DocsNotFound`] if the crate is not present
+/// in the index.
+pub fn parse_search_index(js: &str, crate_name: &str) -> Result<SearchIndex, DocsFetchError> {
+    let payload = extract_json_payload(js)
+        .ok_or_else(|| DocsFetchError::ParseError("search index payload not found".to_string()))?;
+    let root: Value = serde_json::from_str(&payload)
+        .map_err(|e| DocsFetchError::ParseError(format!("invalid search index JSON: {}", e)))?;
+
+    let ident = crate_name.replace('-', "_");
+    let entry = match &root {
+        Value::Array(crates) => crates.iter().find_map(|pair| {
+            let pair = pair.as_array()?;
+            (pair.first()?.as_str()? == ident).then(|| pair.get(1)).flatten()
+        }),
+        Value::Object(crates) => crates.get(&ident),
+        _ => None,
+    }
+    .ok_or(DocsFetchError::DocsNotFound)?;
+
+    parse_crate_entry(entry, &ident)
+}
+
+/// Extracts the JSON text embedded in a `search-index.js` file.
+fn extract_json_payload(js: &str) -> Option<String> {
+    const MARKER: &str = "JSON.parse('";
+    if let Some(start) = js.find(MARKER) {
+        let body = &js[start + MARKER.len()..];
+        return Some(unescape_js_single_quoted(body));
+    }
+    // Plain object literal, e.g. `var searchIndex = {...};`
+    let start = js.find(['{', '['])?;
+    let end = js.rfind(['}', ']'])?;
+    (end > start).then(|| js[start..=end].to_string())
+}
+
+/// Decodes a single-quoted JavaScript string literal, starting right after
+/// its opening quote and stopping at the first unescaped closing quote.
+fn unescape_js_single_quoted(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c == '\'' {
+            break;
+        }
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            // Line continuation
+            Some('\n') => {}
+            Some('\r') => {
+                let mut lookahead = chars.clone();
+                if lookahead.next() == Some('\n') {
+                    chars = lookahead;
+                }
+            }
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Parses the per-crate object of the search index.
+fn parse_crate_entry(entry: &Value, crate_ident: &str) -> Result<SearchIndex, DocsFetchError> {
+    let names: Vec<&str> = entry
+        .get("n")
+        .and_then(Value::as_array)
+        .ok_or_else(|| DocsFetchError::ParseError("search index has no item names".to_string()))?
+        .iter()
+        .map(|n| n.as_str().unwrap_or_default())
+        .collect();
+
+    let kinds: Vec<Option<u64>> = match entry.get("t") {
+        Some(Value::String(letters)) => letters
+            .chars()
+            .map(|c| (c as u64).checked_sub('A' as u64))
+            .collect(),
+        Some(Value::Array(codes)) => codes.iter().map(Value::as_u64).collect(),
+        _ => {
+            return Err(DocsFetchError::ParseError(
+                "search index has no item kinds".to_string(),
+            ))
+        }
+    };
+
+    let module_paths = resolve_module_paths(entry.get("q"), names.len(), crate_ident);
+
+    let items = names
+        .iter()
+        .zip(kinds)
+        .zip(module_paths)
+        .filter_map(|((name, code), module_path)| {
+            let kind = ItemKind::from_rustdoc_code(code?)?;
+            (!name.is_empty()).then(|| IndexItem::new(name, kind, &module_path))
+        })
+        .collect();
+
+    Ok(SearchIndex { items })
+}
+
+/// Expands the `q` field into one module path per item.
+fn resolve_module_paths(q: Option<&Value>, len: usize, crate_ident: &str) -> Vec<String> {
+    let mut paths = vec![String::new(); len];
+    let mut current = crate_ident.to_string();
+
+    match q.and_then(Value::as_array) {
+        // Dense form: one entry per item, empty meaning "same as previous"
+        Some(entries) if entries.iter().all(Value::is_string) => {
+            for (i, slot) in paths.iter_mut().enumerate() {
+                if let Some(p) = entries.get(i).and_then(Value::as_str).filter(|p| !p.is_empty()) {
+                    current = p.to_string();
+                }
+                slot.clone_from(&current);
+            }
+        }
+        // Sparse form: `[index, path]` pairs that apply until the next pair
+        Some(entries) => {
+            let mut changes: Vec<(usize, &str)> = entries
+                .iter()
+                .filter_map(|e| {
+                    let pair = e.as_array()?;
+                    Some((pair.first()?.as_u64()? as usize, pair.get(1)?.as_str()?))
+                })
+                .collect();
+            changes.sort_by_key(|(i, _)| *i);
+            let mut changes = changes.into_iter().peekable();
+            for (i, slot) in paths.iter_mut().enumerate() {
+                while let Some((_, p)) = changes.next_if(|(at, _)| *at <= i) {
+                    current = p.to_string();
+                }
+                slot.clone_from(&current);
+            }
+        }
+        None => paths.iter_mut().for_each(|slot| slot.clone_from(&current)),
+    }
+
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_INDEX: &str = r#"var searchIndex = new Map(JSON.parse('[\
+["demo_crate",{"doc":"A demo crate","t":"AAFDEIGOLMDI","n":["de","ser","from_str","Value","Kind","Serialize","Map","json","Error","Map","Deserializer","Deserialize"],"q":[[0,"demo_crate"],[5,"demo_crate::ser"],[8,"demo_crate::de"]],"i":[0,0,0,0,0,0,0,0,0,0,0,0]}]\
+]'));
+if (typeof exports !== 'undefined') exports.searchIndex = searchIndex;"#;
+
+    fn names(items: &[IndexItem]) -> Vec<&str> {
+        items.iter().map(|i| i.path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse_search_index_groups_by_kind() {
+        let index = parse_search_index(SAMPLE_INDEX, "demo-crate").unwrap();
+        let grouped = CrateIndex::from_index("demo-crate", "1.0.0", &index);
+
+        assert_eq!(names(&grouped.modules), ["demo_crate::de", "demo_crate::ser"]);
+        assert_eq!(names(&grouped.structs), ["demo_crate::Value", "demo_crate::de::Deserializer"]);
+        assert_eq!(names(&grouped.enums), ["demo_crate::Kind"]);
+        assert_eq!(names(&grouped.traits), ["demo_crate::de::Deserialize", "demo_crate::ser::Serialize"]);
+        assert_eq!(names(&grouped.functions), ["demo_crate::from_str"]);
+        assert_eq!(names(&grouped.macros), ["demo_crate::ser::json"]);
+        assert_eq!(names(&grouped.type_aliases), ["demo_crate::ser::Map"]);
+    }
+
+    #[test]
+    fn test_parse_search_index_doc_paths() {
+        let index = parse_search_index(SAMPLE_INDEX, "demo_crate").unwrap();
+        let doc_paths: Vec<&str> = index.items.iter().map(|i| i.doc_path.as_str()).collect();
+
+        assert!(doc_paths.contains(&"demo_crate/de/index.html"));
+        assert!(doc_paths.contains(&"demo_crate/struct.Value.html"));
+        assert!(doc_paths.contains(&"demo_crate/ser/macro.json.html"));
+        assert!(doc_paths.contains(&"demo_crate/de/trait.Deserialize.html"));
+    }
+
+    #[test]
+    fn test_parse_search_index_legacy_object_form() {
+        let js = r#"var searchIndex = JSON.parse('{"legacy":{"t":[3,5,10],"n":["Thing","make","method"],"q":["legacy","",""]}}');"#;
+        let index = parse_search_index(js, "legacy").unwrap();
+
+        // The associated method (kind 10) is skipped
+        assert_eq!(names(&index.items), ["legacy::Thing", "legacy::make"]);
+    }
+
+    #[test]
+    fn test_items_of_kind() {
+        let index = parse_search_index(SAMPLE_INDEX, "demo_crate").unwrap();
+        let kind = ItemKind::from_filter("trait").unwrap();
+
+        let traits = index.items_of_kind(kind);
+        assert_eq!(names(&traits), ["demo_crate::de::Deserialize", "demo_crate::ser::Serialize"]);
+        assert!(traits.iter().all(|item| item.kind == ItemKind::Trait));
+
+        assert_eq!(ItemKind::from_filter("fn"), Some(ItemKind::Function));
+        assert_eq!(ItemKind::from_filter("Type_Alias"), Some(ItemKind::TypeAlias));
+        assert_eq!(ItemKind::from_filter("method"), None);
+    }
+
+    #[test]
+    fn test_search_ranks_matches() {
+        let index = parse_search_index(SAMPLE_INDEX, "demo_crate").unwrap();
+
+        // Exact name, then prefix, then substring of the name
+        assert_eq!(
+            names(&index.search("deserialize")),
+            ["demo_crate::de::Deserialize", "demo_crate::de::Deserializer"]
+        );
+        assert_eq!(names(&index.search("Ser")), [
+            "demo_crate::ser",
+            "demo_crate::ser::Serialize",
+            "demo_crate::de::Deserialize",
+            "demo_crate::de::Deserializer",
+            "demo_crate::ser::Map",
+            "demo_crate::ser::json",
+        ]);
+        assert_eq!(names(&index.search("ser::Map")), ["demo_crate::ser::Map"]);
+        assert!(index.search("  ").is_empty());
+    }
+
+    #[test]
+    fn test_resolve_bare_names() {
+        let index = parse_search_index(SAMPLE_INDEX, "demo_crate").unwrap();
+
+        let value = index.resolve("Value");
+        assert_eq!(names(&value), ["demo_crate::Value"]);
+        assert_eq!(value[0].doc_path, "demo_crate/struct.Value.html");
+
+        // Several items share the name; the one nearest the root comes first
+        let js = r#"var searchIndex = JSON.parse('{"dup":{"t":[0,3,3,8],"n":["de","Error","Error","Error"],"q":["dup","dup","dup::de","dup::ser"]}}');"#;
+        let dup = parse_search_index(js, "dup").unwrap();
+        assert_eq!(names(&dup.resolve("Error")), ["dup::Error", "dup::de::Error", "dup::ser::Error"]);
+        assert_eq!(names(&dup.resolve("ser::Error")), ["dup::ser::Error"]);
+
+        // Path suffixes narrow the match, and case is only ignored as a fallback
+        assert_eq!(names(&index.resolve("de::Deserializer")), ["demo_crate::de::Deserializer"]);
+        assert_eq!(names(&index.resolve("deserialize")), ["demo_crate::de::Deserialize"]);
+        assert!(index.resolve("Missing").is_empty());
+    }
+
+    #[test]
+    fn test_parse_search_index_missing_crate() {
+        let result = parse_search_index(SAMPLE_INDEX, "other");
+        assert!(matches!(result, Err(DocsFetchError::DocsNotFound)));
+    }
+}
@@ -1,15 +1,347 @@
 use rmcp::ServiceExt;
-use rmcp::transport::{stdio, sse_server::SseServer};
+use rmcp::transport::stdio;
 use tracing_subscriber::{self, layer::SubscriberExt, util::SubscriberInitExt};
 use std::sync::Arc;
-use std::path::PathBuf;
+use tokio::sync::Semaphore;
+use std::path::{Path, PathBuf};
 
-use crate::cache::{InMemoryCache, Cache};
-use crate::mcp::DocFetcher;
+use crate::access_log::{self, AccessLog};
+use crate::cache::{InMemoryCache, Cache, LoadRetry, NoopCache};
+use crate::file_cache::{CacheBackend, FileCache};
+use crate::cache_layout::CacheLayoutKind;
+use crate::circuit_breaker::{CircuitBreaker, RetryJitter};
+use crate::cache_watcher::{self, CacheWatcher};
+use crate::config::FileConfig;
+use crate::doc_source::DocSourceKind;
+use crate::docs_parser::{self, CrateNamePolicy, TlsVersion};
+use crate::mcp::{DocFetcher, FetcherConfig};
+use crate::request_log::{self, ReplaySummary, RequestLog};
+use crate::sse_front;
+use crate::status::{self, CacheControlPolicy};
+use crate::webhook::FetchWebhook;
 
 const CACHE_DIR: &str = ".cache";
 
-pub async fn start_sse_server(addr: &str) -> anyhow::Result<()> {
+/// Options controlling how the server is set up.
+#[derive(Debug, Clone, Default)]
+pub struct ServerOptions {
+    /// Access log to record requests to and to preload the cache from on start
+    pub preload_from_log: Option<PathBuf>,
+    /// Maximum number of documents preloaded from the access log
+    pub preload_limit: usize,
+    /// Log recording every `fetch_document` call for later replay
+    pub request_log: Option<PathBuf>,
+    /// Reload cache files changed in the cache directory by other instances
+    pub watch_cache: bool,
+    /// Default maximum size of documents returned to clients
+    pub max_content_bytes: Option<usize>,
+    /// Serve stale cached content while the documentation server is failing
+    pub serve_stale_on_outage: bool,
+    /// Minimum length of extracted content for a fetch to succeed
+    pub min_content_len: usize,
+    /// Time a page that was not found is remembered as missing
+    pub not_found_ttl: std::time::Duration,
+    /// Number of retries of a cache file read after a transient IO error
+    pub cache_load_retries: u32,
+    /// Maximum number of cache files loaded in parallel on start
+    pub cache_load_concurrency: usize,
+    /// On-disk layout of the cache directory
+    pub cache_layout: CacheLayoutKind,
+    /// Whether documentation is kept in memory or read from disk on demand
+    pub cache_backend: CacheBackend,
+    /// Directory the documentation cache is persisted in, `.cache` if not set
+    pub cache_dir: Option<PathBuf>,
+    /// Instructions shown to clients instead of the built-in ones
+    pub instructions: Option<String>,
+    /// Collapse blank lines and trailing spaces in extracted documentation
+    pub normalize_whitespace: bool,
+    /// Rewrite links to the `latest` docs of a crate to the fetched version
+    pub pin_latest_links: bool,
+    /// Zero the cache hit/miss counters persisted by earlier runs
+    pub reset_stats: bool,
+    /// Fail to start when the cache directory does not exist
+    pub require_cache_dir: bool,
+    /// Size from which cache files are memory-mapped and decoded lazily
+    pub mmap_threshold: Option<u64>,
+    /// Drop entries with empty content when loading the cache
+    pub drop_empty_on_load: bool,
+    /// Time cached documentation is served before being refetched
+    pub cache_ttl: Option<std::time::Duration>,
+    /// Maximum number of documents kept in the cache
+    pub max_cache_entries: Option<usize>,
+    /// Interval of cache saves while running
+    pub autosave_interval: Option<std::time::Duration>,
+    /// Consecutive failed saves after which the cache is ephemeral
+    pub ephemeral_after_save_failures: Option<u32>,
+    /// Randomization of the time requests are held back after an outage
+    pub retry_jitter: RetryJitter,
+    /// Failure injection for chaos testing
+    #[cfg(feature = "chaos")]
+    pub chaos: Option<Arc<crate::chaos::Chaos>>,
+    /// Settings read from the configuration file
+    pub file_config: FileConfig,
+    /// Configuration file, re-read by the `reload_config` tool
+    pub config_file: Option<PathBuf>,
+    /// Token required to call administrative tools
+    pub admin_token: Option<String>,
+    /// Directory of a cache for crate metadata kept apart from the
+    /// documentation cache
+    pub metadata_cache_dir: Option<PathBuf>,
+    /// Time cached crate metadata is served before being refetched
+    pub metadata_ttl: std::time::Duration,
+    /// Time the cached list of published versions is served before being
+    /// refetched
+    pub versions_ttl: std::time::Duration,
+    /// Overall time budget of a batch or bundle of fetches
+    pub batch_timeout: Option<std::time::Duration>,
+    /// Maximum number of pages fetched from the documentation server at once
+    pub max_concurrent_fetches: Option<usize>,
+    /// Maximum number of pages a bundle fetches at once
+    pub bundle_concurrency: usize,
+    /// Share one upstream fetch between concurrent requests for a page
+    pub coalesce_fetches: bool,
+    /// Append the replacement's documentation to deprecated items
+    pub follow_deprecation: bool,
+    /// Store re-exported pages once, under their canonical page
+    pub merge_canonical_pages: bool,
+    /// URL notified of every document fetched on a cache miss
+    pub fetch_webhook_url: Option<String>,
+    /// Minimum TLS version of connections to docs.rs and crates.io
+    pub min_tls_version: Option<TlsVersion>,
+    /// Time a request to docs.rs or crates.io may take, `None` to wait
+    /// indefinitely
+    pub request_timeout: Option<std::time::Duration>,
+    /// Where documentation missing from the cache is fetched from
+    pub doc_source: DocSourceKind,
+    /// Output directory of `cargo doc` read by the local source
+    pub doc_root: Option<PathBuf>,
+    /// Crate names accepted in requests
+    pub crate_name_policy: CrateNamePolicy,
+    /// Address of the health and metrics endpoints of the SSE server
+    pub status_address: Option<String>,
+    /// `Cache-Control` headers of the health and metrics endpoints
+    pub status_cache_control: CacheControlPolicy,
+}
+
+/// Loads the cache and builds the fetcher configuration shared by all sessions.
+///
+/// When an access log is configured, the most frequently accessed documents
+/// of previous sessions are preloaded in the background. When cache watching
+/// is enabled, the returned watcher must be kept alive while serving.
+///
+/// Fails if the cache directory is required but cannot be loaded, if the
+/// minimum TLS version cannot be enforced, or if the local documentation
+/// source has no readable directory.
+pub(crate) async fn prepare(options: &ServerOptions) -> anyhow::Result<(Arc<dyn Cache>, FetcherConfig, Option<CacheWatcher>)> {
+    if let Err(e) = docs_parser::check_min_tls_version(options.min_tls_version) {
+        anyhow::bail!("Cannot require TLS {:?}: {}", options.min_tls_version, e);
+    }
+    let doc_root = match (options.doc_source, &options.doc_root) {
+        (DocSourceKind::DocsRs, _) => None,
+        (DocSourceKind::Local, None) => anyhow::bail!("The local documentation source requires a documentation root"),
+        (DocSourceKind::Local, Some(root)) if !root.is_dir() => {
+            anyhow::bail!("Documentation root {} is not a directory", root.display())
+        }
+        (DocSourceKind::Local, Some(root)) => Some(root.clone()),
+    };
+    let cache_dir_path = options.cache_dir.clone().unwrap_or_else(|| PathBuf::from(CACHE_DIR));
+    let load_retry = LoadRetry { retries: options.cache_load_retries, ..LoadRetry::default() };
+    let (cache, memory_cache): (Arc<dyn Cache>, _) = match options.cache_backend {
+        CacheBackend::Memory => {
+            let cache = load_memory_cache(options, &cache_dir_path, load_retry).await?;
+            (cache.clone(), Some(cache))
+        }
+        CacheBackend::File => (load_file_cache(options, &cache_dir_path).await?, None),
+        CacheBackend::None => (Arc::new(NoopCache::new()), None),
+    };
+
+    let mut config = FetcherConfig {
+        max_content_bytes: options.max_content_bytes,
+        circuit_breaker: Arc::new(CircuitBreaker::default().with_jitter(options.retry_jitter)),
+        serve_stale_on_outage: options.serve_stale_on_outage,
+        min_content_len: options.min_content_len,
+        not_found_ttl: options.not_found_ttl,
+        instructions: options.instructions.clone(),
+        normalize_whitespace: options.normalize_whitespace,
+        pin_latest_links: options.pin_latest_links,
+        config_file: options.config_file.clone(),
+        admin_token: options.admin_token.clone(),
+        metadata_ttl: options.metadata_ttl,
+        versions_ttl: options.versions_ttl,
+        batch_timeout: options.batch_timeout,
+        fetch_limiter: options.max_concurrent_fetches.map(|permits| Arc::new(Semaphore::new(permits.max(1)))),
+        bundle_concurrency: options.bundle_concurrency,
+        coalesce_fetches: options.coalesce_fetches,
+        follow_deprecation: options.follow_deprecation,
+        merge_canonical_pages: options.merge_canonical_pages,
+        fetch_webhook: options.fetch_webhook_url.as_deref().map(|url| Arc::new(FetchWebhook::new(url))),
+        min_tls_version: options.min_tls_version,
+        request_timeout: options.request_timeout,
+        doc_root,
+        crate_name_policy: options.crate_name_policy,
+        #[cfg(feature = "chaos")]
+        chaos: options.chaos.clone(),
+        ..FetcherConfig::default()
+    };
+    config.apply_file_config(&options.file_config);
+    if let Some(metadata_dir) = &options.metadata_cache_dir {
+        let metadata_cache = Arc::new(
+            InMemoryCache::new(metadata_dir.clone())
+                .with_load_retry(load_retry)
+                .with_load_concurrency(options.cache_load_concurrency)
+                .with_layout(options.cache_layout.layout()),
+        );
+        if let Err(e) = metadata_cache.load().await {
+            tracing::error!("Failed to load metadata cache from {:?}: {}. Starting fresh.", metadata_dir, e);
+        }
+        config.metadata_cache = Some(metadata_cache);
+    }
+    if let Some(log_path) = &options.preload_from_log {
+        let fetcher = DocFetcher::with_config(cache.clone(), config.clone());
+        let preload_cache = cache.clone();
+        let preload_path = log_path.clone();
+        let limit = options.preload_limit;
+        tokio::spawn(async move {
+            access_log::preload(&fetcher, preload_cache.as_ref(), &preload_path, limit).await;
+        });
+
+        match AccessLog::open(log_path) {
+            Ok(log) => config.access_log = Some(Arc::new(log)),
+            Err(e) => tracing::error!("Failed to open access log {:?}: {}. Access logging disabled.", log_path, e),
+        }
+    }
+    if let Some(log_path) = &options.request_log {
+        match RequestLog::open(log_path) {
+            Ok(log) => config.request_log = Some(Arc::new(log)),
+            Err(e) => tracing::error!("Failed to open request log {:?}: {}. Request logging disabled.", log_path, e),
+        }
+    }
+
+    let watcher = if let Some(memory_cache) = memory_cache.filter(|_| options.watch_cache) {
+        match cache_watcher::watch_cache_dir(memory_cache, cache_watcher::DEFAULT_DEBOUNCE) {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                tracing::error!("Failed to watch cache directory {:?}: {}. Continuing without watching.", cache_dir_path, e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    Ok((cache, config, watcher))
+}
+
+/// Builds the in-memory documentation cache and loads it from `cache_dir`.
+///
+/// Fails if the cache directory is required but cannot be loaded.
+async fn load_memory_cache(options: &ServerOptions, cache_dir: &Path, load_retry: LoadRetry) -> anyhow::Result<Arc<InMemoryCache>> {
+    let cache = Arc::new(
+        InMemoryCache::new(cache_dir.to_path_buf())
+            .with_load_retry(load_retry)
+            .with_load_concurrency(options.cache_load_concurrency)
+            .with_layout(options.cache_layout.layout())
+            .with_require_dir(options.require_cache_dir)
+            .with_mmap_threshold(options.mmap_threshold)
+            .with_drop_empty_on_load(options.drop_empty_on_load)
+            .with_ttl(options.cache_ttl)
+            .with_max_entries(options.max_cache_entries)
+            .with_ephemeral_after(options.ephemeral_after_save_failures),
+    );
+    if options.mmap_threshold.is_some() && options.watch_cache {
+        tracing::warn!("Cache files are memory-mapped while watching for other instances writing them; a file rewritten while mapped can crash the server.");
+    }
+    if let Err(e) = cache.load().await {
+        if options.require_cache_dir {
+            anyhow::bail!("Failed to load required cache directory {:?}: {}", cache_dir, e);
+        }
+        tracing::error!("Failed to load cache from {:?}: {}. Starting fresh.", cache_dir, e);
+    }
+    if options.reset_stats {
+        cache.reset_stats();
+    }
+    Ok(cache)
+}
+
+/// Builds the disk-backed documentation cache below `cache_dir`. Options
+/// only affecting the in-memory cache are ignored with a warning.
+///
+/// Fails if the cache directory is required but does not exist.
+async fn load_file_cache(options: &ServerOptions, cache_dir: &Path) -> anyhow::Result<Arc<FileCache>> {
+    if options.mmap_threshold.is_some() || options.max_cache_entries.is_some() || options.watch_cache {
+        tracing::warn!("Memory-mapping, entry limits and watching only apply to the memory cache backend; ignoring them.");
+    }
+    let cache = Arc::new(
+        FileCache::new(cache_dir.to_path_buf())
+            .with_require_dir(options.require_cache_dir)
+            .with_ttl(options.cache_ttl),
+    );
+    if let Err(e) = cache.load().await {
+        if options.require_cache_dir {
+            anyhow::bail!("Failed to load required cache directory {:?}: {}", cache_dir, e);
+        }
+        tracing::error!("Failed to prepare cache directory {:?}: {}. Entries may not be persisted.", cache_dir, e);
+    }
+    Ok(cache)
+}
+
+/// Saves the documentation cache and, if separate, the metadata cache,
+/// logging failures. With `changed_only`, caches without unsaved changes
+/// are skipped.
+async fn save_caches(cache: &dyn Cache, metadata_cache: Option<&InMemoryCache>, changed_only: bool) {
+    if (!changed_only || cache.has_unsaved_changes())
+        && let Err(e) = cache.save().await
+    {
+        tracing::error!("Failed to save documentation cache: {}", e);
+    }
+    if let Some(metadata_cache) = metadata_cache
+        && (!changed_only || metadata_cache.has_unsaved_changes())
+        && let Err(e) = metadata_cache.save().await
+    {
+        tracing::error!("Failed to save cache to {:?}: {}", metadata_cache.cache_dir(), e);
+    }
+}
+
+/// Background task saving changed caches periodically.
+struct Autosave {
+    task: tokio::task::JoinHandle<()>,
+    stop: tokio::sync::oneshot::Sender<()>,
+}
+
+impl Autosave {
+    /// Stops the task, letting a save in progress finish first so that no
+    /// cache file is left half written.
+    async fn stop(self) {
+        let _ = self.stop.send(());
+        if let Err(e) = self.task.await {
+            tracing::error!("Autosave task failed: {}", e);
+        }
+    }
+}
+
+/// Saves the caches that changed every `interval` until stopped.
+fn spawn_autosave(
+    interval: Option<std::time::Duration>,
+    cache: Arc<dyn Cache>,
+    metadata_cache: Option<Arc<InMemoryCache>>,
+) -> Option<Autosave> {
+    let interval = interval.filter(|interval| !interval.is_zero())?;
+    let (stop, mut stopped) = tokio::sync::oneshot::channel();
+    let task = tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(interval);
+        // The first tick completes immediately, right after the cache was loaded
+        ticks.tick().await;
+        loop {
+            tokio::select! {
+                _ = ticks.tick() => save_caches(cache.as_ref(), metadata_cache.as_deref(), true).await,
+                _ = &mut stopped => break,
+            }
+        }
+    });
+    Some(Autosave { task, stop })
+}
+
+pub async fn start_sse_server(addr: &str, options: &ServerOptions) -> anyhow::Result<()> {
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
@@ -18,27 +350,37 @@ pub async fn start_sse_server(addr: &str) -> anyhow::Result<()> {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    let cache_dir_path = PathBuf::from(CACHE_DIR);
-    let cache = Arc::new(InMemoryCache::new(cache_dir_path.clone()));
-    if let Err(e) = cache.load().await {
-        tracing::error!("Failed to load cache from {:?}: {}. Starting fresh.", cache_dir_path, e);
-    }
+    let (cache, config, _watcher) = prepare(options).await?;
+    let metadata_cache = config.metadata_cache.clone();
+    let autosave = spawn_autosave(options.autosave_interval, cache.clone(), metadata_cache.clone());
 
-    let server_cache = cache.clone();
-    let ct = SseServer::serve(addr.parse()?) 
-        .await?
-        .with_service(move || DocFetcher::new(server_cache.clone()));
+    // All sessions share one fetcher so they also share its runtime state
+    let fetcher = DocFetcher::with_config(cache.clone(), config);
+    // The transport listens on loopback behind a front adding `/health`
+    let transport = sse_front::bind_transport().await?;
+    let transport_addr = transport.config.bind;
+    let ct = transport.with_service(move || fetcher.clone());
+    let health = status::health_router(cache.clone(), &options.status_cache_control)?;
+    let front_ct = ct.child_token();
+    let front = sse_front::router(health, transport_addr);
+    status::serve(addr, "MCP over SSE", front, async move { front_ct.cancelled().await }).await?;
+    if let Some(status_address) = &options.status_address {
+        let router = status::router(cache.clone(), metadata_cache.clone(), &options.status_cache_control)?;
+        let status_ct = ct.child_token();
+        status::serve(status_address, "health and metrics", router, async move { status_ct.cancelled().await }).await?;
+    }
 
     tokio::signal::ctrl_c().await?;
     tracing::info!("Shutdown signal received. Saving cache...");
-    if let Err(e) = cache.save().await {
-        tracing::error!("Failed to save cache to {:?}: {}", cache_dir_path, e);
+    if let Some(autosave) = autosave {
+        autosave.stop().await;
     }
+    save_caches(cache.as_ref(), metadata_cache.as_deref(), false).await;
     ct.cancel();
     Ok(())
 }
 
-pub async fn start_stdio_server() -> anyhow::Result<()> {
+pub async fn start_stdio_server(options: &ServerOptions) -> anyhow::Result<()> {
     tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env().add_directive(tracing::Level::DEBUG.into()))
         .with_writer(std::io::stderr)
@@ -47,22 +389,77 @@ pub async fn start_stdio_server() -> anyhow::Result<()> {
 
     tracing::info!("Starting MCP server");
 
-    let cache_dir_path = PathBuf::from(CACHE_DIR);
-    let cache = Arc::new(InMemoryCache::new(cache_dir_path.clone()));
-    if let Err(e) = cache.load().await {
-        tracing::error!("Failed to load cache from {:?}: {}. Starting fresh.", cache_dir_path, e);
-    }
+    let (cache, config, _watcher) = prepare(options).await?;
+    let metadata_cache = config.metadata_cache.clone();
+    let autosave = spawn_autosave(options.autosave_interval, cache.clone(), metadata_cache.clone());
 
     let service_cache = cache.clone();
-    let service = DocFetcher::new(service_cache).serve(stdio()).await.inspect_err(|e| {
+    let service = DocFetcher::with_config(service_cache, config).serve(stdio()).await.inspect_err(|e| {
         tracing::error!("serving error: {:?}", e);
     })?;
 
     service.waiting().await?;
 
     tracing::info!("Service finished. Saving cache...");
-    if let Err(e) = cache.save().await {
-        tracing::error!("Failed to save cache to {:?}: {}", cache_dir_path, e);
+    if let Some(autosave) = autosave {
+        autosave.stop().await;
     }
+    save_caches(cache.as_ref(), metadata_cache.as_deref(), false).await;
     Ok(())
 }
+
+/// Replays the requests of a request log into the cache and saves it.
+///
+/// Requests are sent to `docs_base_url` if given, otherwise to docs.rs.
+/// Replayed requests are not logged again, even when a request log is
+/// configured in `options`.
+pub async fn replay_request_log(
+    log_path: &Path,
+    docs_base_url: Option<String>,
+    options: &ServerOptions,
+) -> anyhow::Result<ReplaySummary> {
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into()))
+        .with_writer(std::io::stderr)
+        .init();
+
+    let (cache, mut config, _watcher) = prepare(options).await?;
+    config.request_log = None;
+    if let Some(docs_base_url) = docs_base_url {
+        config.docs_base_url = docs_base_url;
+    }
+    let metadata_cache = config.metadata_cache.clone();
+    let fetcher = DocFetcher::with_config(cache.clone(), config);
+    let summary = request_log::replay(&fetcher, log_path)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to read request log {:?}: {}", log_path, e))?;
+    save_caches(cache.as_ref(), metadata_cache.as_deref(), false).await;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docs_parser::{DocContent, DocsRsParams};
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_autosave_writes_changed_cache() {
+        let dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(dir.path().to_path_buf()));
+        let interval = std::time::Duration::from_millis(20);
+        let autosave = spawn_autosave(Some(interval), cache.clone(), None).unwrap();
+
+        let key = DocsRsParams {
+            crate_name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            path: "demo/index.html".to_string(),
+        };
+        cache.insert(key.into(), DocContent { content: "Demo docs".to_string(), source_url: None }).await;
+        tokio::time::sleep(interval * 10).await;
+        autosave.stop().await;
+
+        assert!(dir.path().join("demo.json").exists());
+        assert!(!cache.has_unsaved_changes());
+    }
+}
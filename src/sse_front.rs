@@ -0,0 +1,154 @@
+//! Front of the SSE server's listener.
+//!
+//! The MCP transport binds a listener of its own and keeps its routes
+//! private: rmcp 0.1.5's `SseServer` neither accepts a listener nor exposes
+//! its `Router`, so no route can be added next to `/sse`. The transport is
+//! therefore moved to a loopback port and the configured address is served
+//! by a small reverse proxy instead: `GET /health` is answered directly, for
+//! load balancer and orchestrator probes, and every other request,
+//! including the `/sse` event stream and posted messages, is forwarded to
+//! the transport unchanged.
+
+use std::net::{Ipv4Addr, SocketAddr};
+
+use axum::Router;
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::http::StatusCode;
+use axum::http::header::{CONNECTION, HOST, TRANSFER_ENCODING};
+use axum::response::{IntoResponse, Response};
+use rmcp::transport::sse_server::SseServer;
+
+/// Maximum size of a request body forwarded to the transport; MCP messages
+/// are small JSON documents.
+const MAX_FORWARDED_BODY: usize = 4 * 1024 * 1024;
+
+/// Attempts at binding the MCP transport before giving up.
+const TRANSPORT_BIND_ATTEMPTS: u32 = 5;
+
+/// Starts the MCP transport on a free loopback port.
+///
+/// The transport binds the address it is given and cannot take a listener,
+/// so a free port is found by binding and releasing it. Another process can
+/// take the port in between, in which case another free port is tried.
+///
+/// # Errors
+///
+/// Fails if no loopback port can be bound within
+/// [`TRANSPORT_BIND_ATTEMPTS`] attempts.
+pub async fn bind_transport() -> anyhow::Result<SseServer> {
+    let mut attempt = 1;
+    loop {
+        let addr = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?.local_addr()?;
+        match SseServer::serve(addr).await {
+            Ok(server) => return Ok(server),
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse && attempt < TRANSPORT_BIND_ATTEMPTS => {
+                tracing::debug!("Port {} of the MCP transport was taken, trying another: {}", addr.port(), e);
+                attempt += 1;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Transport requests are forwarded to.
+#[derive(Clone)]
+struct Upstream {
+    client: reqwest::Client,
+    /// Base URL of the transport, without trailing slash
+    base_url: String,
+}
+
+impl Upstream {
+    /// Sends `request` to the transport and streams back its response.
+    async fn forward(&self, request: Request) -> anyhow::Result<Response> {
+        let (parts, body) = request.into_parts();
+        let path = parts.uri.path_and_query().map_or("/", |path| path.as_str());
+        let body = axum::body::to_bytes(body, MAX_FORWARDED_BODY).await?;
+        let mut headers = parts.headers;
+        headers.remove(HOST);
+        let upstream = self.client
+            .request(parts.method, format!("{}{}", self.base_url, path))
+            .headers(headers)
+            .body(body)
+            .send()
+            .await?;
+
+        let mut response = Response::builder().status(upstream.status());
+        for (name, value) in upstream.headers() {
+            if name != TRANSFER_ENCODING && name != CONNECTION {
+                response = response.header(name, value);
+            }
+        }
+        // Chunks are passed on as they arrive, keeping event streams open
+        let chunks = futures::stream::unfold(Some(upstream), |upstream| async move {
+            let mut upstream = upstream?;
+            match upstream.chunk().await {
+                Ok(Some(chunk)) => Some((Ok(chunk), Some(upstream))),
+                Ok(None) => None,
+                Err(e) => Some((Err(e), None)),
+            }
+        });
+        Ok(response.body(Body::from_stream(chunks))?)
+    }
+}
+
+async fn forward(State(upstream): State<Upstream>, request: Request) -> Response {
+    match upstream.forward(request).await {
+        Ok(response) => response,
+        Err(e) => {
+            tracing::warn!("Failed to forward request to the MCP transport: {}", e);
+            (StatusCode::BAD_GATEWAY, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Builds the router of the SSE address: the routes of `health`, and
+/// forwarding of all other requests to the transport at `transport`.
+pub fn router(health: Router, transport: SocketAddr) -> Router {
+    // No timeout, as event streams stay open for the whole session, and no
+    // proxy, as the transport is only reachable on loopback
+    let client = reqwest::Client::builder().no_proxy().build().unwrap_or_default();
+    let upstream = Upstream { client, base_url: format!("http://{}", transport) };
+    health.merge(Router::new().fallback(forward).with_state(upstream))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::InMemoryCache;
+    use crate::status::{CacheControlPolicy, health_router};
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_serves_health_and_forwards_sse() {
+        let dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(dir.path().to_path_buf()));
+        let transport = bind_transport().await.unwrap();
+        let health = health_router(cache, &CacheControlPolicy::default()).unwrap();
+        let router = router(health, transport.config.bind);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let health = reqwest::get(format!("http://{}/health", addr)).await.unwrap();
+        assert_eq!(health.status(), StatusCode::OK);
+        let body: serde_json::Value = health.json().await.unwrap();
+        assert_eq!(body["status"], "ok");
+        assert!(body["uptime_secs"].is_u64());
+
+        // The SSE handshake still reaches the transport
+        let mut sse = reqwest::get(format!("http://{}/sse", addr)).await.unwrap();
+        assert_eq!(sse.status(), StatusCode::OK);
+        let event = sse.chunk().await.unwrap().unwrap();
+        let event = String::from_utf8_lossy(&event);
+        assert!(event.contains("event: endpoint"), "got {:?}", event);
+        assert!(event.contains("/message?sessionId="), "got {:?}", event);
+
+        let missing = reqwest::get(format!("http://{}/missing", addr)).await.unwrap();
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+        transport.cancel();
+    }
+}
@@ -0,0 +1,212 @@
+//! Auxiliary HTTP endpoints of the SSE server.
+//!
+//! The MCP transport owns the routes of its listener, so health and metrics
+//! are served on a listener of their own:
+//! - `GET /health` answers whether the server is up and its cache persisted
+//! - `GET /metrics` reports the hit and miss counters and sizes of the caches
+//!
+//! `/health` is also answered on the SSE address itself, by the
+//! [front](crate::sse_front) forwarding everything else to the transport.
+//!
+//! Both are read-only and cheap to compute but may be polled by many
+//! monitors at once. Each response carries a `Cache-Control` header taken
+//! from a [`CacheControlPolicy`], so that a CDN or proxy in front of the
+//! server can answer repeated polls itself for a short while.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::extract::State;
+use axum::http::HeaderValue;
+use axum::http::header::CACHE_CONTROL;
+use axum::response::Response;
+use axum::routing::get;
+use axum::{Json, Router, middleware};
+use serde::Serialize;
+
+use crate::cache::{Cache, CacheUsage, InMemoryCache};
+
+/// Default `Cache-Control` header of health responses.
+pub const DEFAULT_HEALTH_CACHE_CONTROL: &str = "public, max-age=5";
+
+/// Default `Cache-Control` header of metrics responses.
+pub const DEFAULT_METRICS_CACHE_CONTROL: &str = "public, max-age=15";
+
+/// `Cache-Control` headers of the auxiliary endpoints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheControlPolicy {
+    /// Header of `/health` responses
+    pub health: String,
+    /// Header of `/metrics` responses
+    pub metrics: String,
+}
+
+impl Default for CacheControlPolicy {
+    fn default() -> Self {
+        Self {
+            health: DEFAULT_HEALTH_CACHE_CONTROL.to_string(),
+            metrics: DEFAULT_METRICS_CACHE_CONTROL.to_string(),
+        }
+    }
+}
+
+/// Caches reported on by the endpoints.
+#[derive(Clone)]
+struct StatusState {
+    cache: Arc<dyn Cache>,
+    metadata_cache: Option<Arc<InMemoryCache>>,
+    /// When the endpoints were set up, at server start
+    started: Instant,
+}
+
+/// Body of `/health` responses.
+#[derive(Debug, Serialize)]
+struct Health {
+    status: &'static str,
+    /// Version of the server
+    version: &'static str,
+    /// Seconds since the server started
+    uptime_secs: u64,
+    /// Number of documents in the cache
+    cache_entries: usize,
+    /// Whether the cache only lives in memory because it cannot be saved
+    ephemeral_cache: bool,
+}
+
+/// Body of `/metrics` responses.
+#[derive(Debug, Serialize)]
+struct Metrics {
+    /// Counters and size of the documentation cache, the counters
+    /// including earlier runs
+    cache: CacheUsage,
+    /// Counters and size of the separate metadata cache, if any
+    metadata_cache: Option<CacheUsage>,
+}
+
+async fn health(State(state): State<StatusState>) -> Json<Health> {
+    Json(Health {
+        status: "ok",
+        version: env!("CARGO_PKG_VERSION"),
+        uptime_secs: state.started.elapsed().as_secs(),
+        cache_entries: state.cache.entry_count().await,
+        ephemeral_cache: state.cache.is_ephemeral(),
+    })
+}
+
+async fn metrics(State(state): State<StatusState>) -> Json<Metrics> {
+    let metadata_cache = match &state.metadata_cache {
+        Some(metadata_cache) => Some(CacheUsage::of(metadata_cache.as_ref()).await),
+        None => None,
+    };
+    Json(Metrics { cache: CacheUsage::of(state.cache.as_ref()).await, metadata_cache })
+}
+
+/// Parses a configured `Cache-Control` header, naming the option it was
+/// configured for in the error.
+fn header_value(value: &str, option: &str) -> anyhow::Result<HeaderValue> {
+    HeaderValue::from_str(value)
+        .map_err(|e| anyhow::anyhow!("Invalid {} header {:?}: {}", option, value, e))
+}
+
+/// Builds the router of the auxiliary endpoints.
+///
+/// # Errors
+///
+/// Fails if a header of `policy` is not a valid header value.
+pub fn router(
+    cache: Arc<dyn Cache>,
+    metadata_cache: Option<Arc<InMemoryCache>>,
+    policy: &CacheControlPolicy,
+) -> anyhow::Result<Router> {
+    let state = StatusState { cache, metadata_cache, started: Instant::now() };
+    Ok(routes(policy, true)?.with_state(state))
+}
+
+/// Builds a router of the `/health` endpoint alone.
+///
+/// # Errors
+///
+/// Fails if the health header of `policy` is not a valid header value.
+pub fn health_router(cache: Arc<dyn Cache>, policy: &CacheControlPolicy) -> anyhow::Result<Router> {
+    let state = StatusState { cache, metadata_cache: None, started: Instant::now() };
+    Ok(routes(policy, false)?.with_state(state))
+}
+
+/// Builds the routes of `/health`, and of `/metrics` if `with_metrics` is set.
+fn routes(policy: &CacheControlPolicy, with_metrics: bool) -> anyhow::Result<Router<StatusState>> {
+    let with_cache_control = |value: HeaderValue| {
+        middleware::map_response(move |mut response: Response| {
+            let value = value.clone();
+            async move {
+                response.headers_mut().insert(CACHE_CONTROL, value);
+                response
+            }
+        })
+    };
+    let health_header = header_value(&policy.health, "health Cache-Control")?;
+    let metrics_header = header_value(&policy.metrics, "metrics Cache-Control")?;
+    let router = Router::new().route("/health", get(health).route_layer(with_cache_control(health_header)));
+    Ok(match with_metrics {
+        true => router.route("/metrics", get(metrics).route_layer(with_cache_control(metrics_header))),
+        false => router,
+    })
+}
+
+/// Serves `router` on `addr` in the background until `shutdown` completes,
+/// naming what is served in the log.
+pub async fn serve(
+    addr: &str,
+    name: &str,
+    router: Router,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> anyhow::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    tracing::info!("Serving {} on {}", name, listener.local_addr()?);
+    tokio::spawn(async move {
+        let server = axum::serve(listener, router).with_graceful_shutdown(shutdown);
+        if let Err(e) = server.await {
+            tracing::error!("Status server failed: {}", e);
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_endpoints_set_cache_control() {
+        let dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(dir.path().to_path_buf()));
+        let policy = CacheControlPolicy { metrics: "no-store".to_string(), ..CacheControlPolicy::default() };
+        let router = router(cache, None, &policy).unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let health = reqwest::get(format!("http://{}/health", addr)).await.unwrap();
+        assert_eq!(health.headers()[CACHE_CONTROL], DEFAULT_HEALTH_CACHE_CONTROL);
+        let body: serde_json::Value = health.json().await.unwrap();
+        assert_eq!(body["status"], "ok");
+        assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(body["cache_entries"], 0);
+
+        let metrics = reqwest::get(format!("http://{}/metrics", addr)).await.unwrap();
+        assert_eq!(metrics.headers()[CACHE_CONTROL], "no-store");
+        let body: serde_json::Value = metrics.json().await.unwrap();
+        assert_eq!(body["cache"]["hits"], 0);
+        assert_eq!(body["cache"]["size_bytes"], 0);
+    }
+
+    #[test]
+    fn test_invalid_header_is_rejected() {
+        let dir = tempdir().unwrap();
+        let cache = Arc::new(InMemoryCache::new(dir.path().to_path_buf()));
+        let policy = CacheControlPolicy { health: "max-age=5\n".to_string(), ..CacheControlPolicy::default() };
+        assert!(router(cache, None, &policy).is_err());
+    }
+}
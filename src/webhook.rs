@@ -0,0 +1,89 @@
+//! Notification of downstream systems about newly fetched documentation.
+//!
+//! When configured, every document fetched from the documentation server
+//! because it was missing from the cache is announced with a POST request
+//! carrying its [`DocsRsParams`] and a hash of its content. Notifications
+//! are sent in the background: they never delay or fail the fetch, and a
+//! failed notification is only logged.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::Client;
+use serde::Serialize;
+use tokio::sync::Semaphore;
+
+use crate::docs_parser::DocsRsParams;
+
+/// Maximum number of notifications in flight; further ones are dropped
+/// until earlier ones complete, so a slow receiver cannot pile up tasks.
+pub const MAX_PENDING_NOTIFICATIONS: usize = 16;
+
+/// Time a notification may take before it is abandoned.
+const NOTIFICATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Body of a notification.
+#[derive(Debug, Serialize)]
+struct FetchNotification<'a> {
+    crate_name: &'a str,
+    version: &'a str,
+    path: &'a str,
+    /// Hex-encoded SHA-256 hash of the extracted content
+    content_hash: &'a str,
+    /// Size of the extracted content in bytes
+    content_bytes: usize,
+}
+
+/// Sends fetch notifications to a webhook URL.
+#[derive(Debug)]
+pub struct FetchWebhook {
+    /// URL notifications are posted to
+    url: String,
+    /// HTTP client used for all notifications
+    client: Client,
+    /// Permits bounding the notifications in flight
+    pending: Arc<Semaphore>,
+}
+
+impl FetchWebhook {
+    /// Creates a webhook posting to `url`.
+    pub fn new(url: impl Into<String>) -> Self {
+        let client = Client::builder()
+            .timeout(NOTIFICATION_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            url: url.into(),
+            client,
+            pending: Arc::new(Semaphore::new(MAX_PENDING_NOTIFICATIONS)),
+        }
+    }
+
+    /// Announces that the document `params` with `content_bytes` bytes of
+    /// content hashing to `content_hash` was fetched.
+    ///
+    /// Returns immediately; the request is sent by a spawned task. The
+    /// notification is dropped with a warning if too many are in flight.
+    pub fn notify(&self, params: &DocsRsParams, content_hash: &str, content_bytes: usize) {
+        let Ok(permit) = self.pending.clone().try_acquire_owned() else {
+            tracing::warn!("Dropping fetch notification for {:?}: too many notifications pending.", params);
+            return;
+        };
+        let body = FetchNotification {
+            crate_name: &params.crate_name,
+            version: &params.version,
+            path: &params.path,
+            content_hash,
+            content_bytes,
+        };
+        let request = self.client.post(&self.url).json(&body);
+        let params = params.clone();
+        tokio::spawn(async move {
+            match request.send().await.and_then(|response| response.error_for_status()) {
+                Ok(_) => tracing::debug!("Sent fetch notification for {:?}", params),
+                Err(e) => tracing::warn!("Failed to send fetch notification for {:?}: {}", params, e),
+            }
+            drop(permit);
+        });
+    }
+}